@@ -87,9 +87,10 @@ use libstackerdb::StackerDBChunkData;
 use clarity::vm::types::StandardPrincipalData;
 
 use crate::cli::{
-    consume_arg, consume_private_key, consume_u64, load_from_file_or_stdin, make_runner, make_tx,
-    open_home_stackerdb_session, open_replica_stackerdb_session, post_tx, split_fqn, usage,
-    wrbsite_load_code_bytes,
+    consume_arg, consume_multisig, consume_private_key, consume_u64, finish_tx,
+    load_from_file_or_stdin, make_runner, make_tx, next_nonce, open_home_stackerdb_session,
+    open_replica_stackerdb_session, origin_tx_fee, release_nonce, split_fqn, usage,
+    with_unlocked_config, wrbsite_load_code_bytes, MultisigExport,
 };
 
 use serde;
@@ -195,6 +196,8 @@ fn subcommand_bns_fast_register(
     dry_run: bool,
     privkey_opt: Option<Secp256k1PrivateKey>,
     tx_fee_opt: Option<u64>,
+    sponsored: bool,
+    multisig_opt: Option<MultisigExport>,
 ) -> Option<Txid> {
     let (name, namespace) = split_fqn(wrbsite_name).unwrap_or_else(|e| {
         eprintln!("FATAL: could not decode name: {}", &e);
@@ -211,20 +214,20 @@ fn subcommand_bns_fast_register(
         return None;
     }
 
-    let (privkey, mainnet) = with_global_config(|cfg| (cfg.private_key().clone(), cfg.mainnet()))
-        .ok_or_else(|| panic!("System is not initialized"))
-        .unwrap();
-
-    let privkey = privkey_opt.unwrap_or(privkey);
+    let mainnet = with_global_config(|cfg| cfg.mainnet()).expect("System is not initialized");
+    let privkey =
+        privkey_opt.unwrap_or_else(|| with_unlocked_config(|cfg| cfg.private_key().clone()));
 
-    let stacks_addr = StacksAddress::p2pkh(mainnet, &StacksPublicKey::from_private(&privkey));
+    let stacks_addr = match multisig_opt.as_ref() {
+        Some(multisig) => multisig.origin.address(mainnet),
+        None => StacksAddress::p2pkh(mainnet, &StacksPublicKey::from_private(&privkey)),
+    };
     let principal =
         StandardPrincipalData::new(stacks_addr.version(), stacks_addr.bytes().clone().0).unwrap();
-    let account = runner
-        .get_account(&principal.clone().into())
-        .unwrap_or_else(|e| {
-            panic!("FATAL: failed to look up account {}: {:?}", &principal, &e);
-        });
+    let nonce = next_nonce(&mut runner, &principal.clone().into()).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
 
     let bns_address = StacksAddress::new(
         runner.get_bns_contract_id().issuer.version(),
@@ -233,11 +236,13 @@ fn subcommand_bns_fast_register(
     .expect("Infallible");
 
     // go fast-register
-    let tx = make_tx(&mut runner, tx_fee_opt, |fee_rate| {
+    let tx_fee_opt = origin_tx_fee(tx_fee_opt, sponsored);
+    let multisig_origin_opt = multisig_opt.as_ref().map(|multisig| &multisig.origin);
+    let tx = make_tx(&mut runner, tx_fee_opt, multisig_origin_opt, |fee_rate| {
         make_contract_call(
             mainnet,
             &privkey,
-            account.nonce,
+            nonce,
             fee_rate,
             &bns_address,
             "BNS-V2",
@@ -257,6 +262,7 @@ fn subcommand_bns_fast_register(
         .expect("FATAL: could not make update-zonefile transaction")
     })
     .unwrap_or_else(|e| {
+        release_nonce(&principal.clone().into(), nonce);
         eprintln!(
             "FATAL: failed to generate zonefile-update transaction: {}",
             &e
@@ -264,18 +270,17 @@ fn subcommand_bns_fast_register(
         process::exit(1);
     });
 
-    if dry_run {
-        println!("{}", &to_hex(&tx.serialize_to_vec()));
-        return Some(tx.txid());
-    }
-
-    let txid = post_tx(&mut runner, &tx).unwrap_or_else(|e| {
-        wrb_debug!("{}", &to_hex(&tx.serialize_to_vec()));
-        eprintln!("FATAL: failed to post zonefile-update transaction: {}", &e);
-        process::exit(1);
-    });
-
-    Some(txid)
+    finish_tx(
+        &mut runner,
+        tx,
+        &privkey,
+        &principal.clone().into(),
+        nonce,
+        dry_run,
+        sponsored,
+        multisig_opt,
+        "name-claim-fast",
+    )
 }
 
 /// bns subcommand helper
@@ -354,7 +359,7 @@ pub fn subcommand_bns(mut argv: Vec<String>) {
     } else if cmd == "fast-register" {
         if argv.len() < 4 {
             eprintln!(
-                "Usage: {} bns {} [-k|--private-key KEY] [-f|--fee FEE] [-n|--dry-run] NAME",
+                "Usage: {} bns {} [-k|--private-key KEY] [-f|--fee FEE] [-n|--dry-run] [-s|--sponsored] [--multisig M:PUBKEY,... [--multisig-mode MODE] [-o|--export PATH]] NAME",
                 &argv[0], &cmd
             );
             process::exit(1);
@@ -368,11 +373,30 @@ pub fn subcommand_bns(mut argv: Vec<String>) {
 
         let privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
         let tx_fee_opt = consume_u64(&mut argv, &["-f", "--fee"]);
+        let sponsored = consume_arg(&mut argv, &["-s", "--sponsored"], false)
+            .map_err(|e| {
+                usage(&e);
+                unreachable!()
+            })
+            .unwrap()
+            .is_some();
+        let multisig_opt = consume_multisig(&mut argv);
+        // these transactions are printed or saved instead of posted, so there's no txid
+        let exporting = dry_run.is_some() || sponsored || multisig_opt.is_some();
         let wrbsite_name = argv[3].clone();
 
-        let txid_opt =
-            subcommand_bns_fast_register(&wrbsite_name, dry_run.is_some(), privkey_opt, tx_fee_opt);
+        let txid_opt = subcommand_bns_fast_register(
+            &wrbsite_name,
+            dry_run.is_some(),
+            privkey_opt,
+            tx_fee_opt,
+            sponsored,
+            multisig_opt,
+        );
         let Some(txid) = txid_opt else {
+            if exporting {
+                return;
+            }
             process::exit(1);
         };
         println!("{}", &txid);
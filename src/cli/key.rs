@@ -0,0 +1,240 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::process;
+
+use crate::core::identity::Identity;
+use crate::core::keystore::{
+    read_new_passphrase, read_passphrase, Keystore, KeystoreSecret, NEW_KEYSTORE_PASSPHRASE_ENV,
+};
+use crate::core::with_global_config;
+
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::chainstate::StacksPublicKey;
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::cli::{consume_arg, consume_private_key, load_from_file_or_stdin, usage};
+
+/// Encrypt `secret` into a new keystore at `path`
+fn key_store_new(secret: &KeystoreSecret, path: &str, force: bool) {
+    if !force && fs::metadata(path).is_ok() {
+        eprintln!(
+            "FATAL: {} already exists. Pass -f or --force to overwrite it.",
+            path
+        );
+        process::exit(1);
+    }
+    let passphrase = read_new_passphrase().unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    key_store(secret, path, &passphrase);
+
+    let mainnet = with_global_config(|cfg| cfg.mainnet()).expect("System is not initialized");
+    println!("{}", &key_address(&secret.private_key(), mainnet));
+    eprintln!(
+        "Saved key to {}. Set `keystore = \"{}\"` in your config file (in place of `private_key` or `mnemonic`) to use it.",
+        path, path
+    );
+}
+
+/// Encrypt `secret` with `passphrase` into a keystore at `path`, overwriting it
+fn key_store(secret: &KeystoreSecret, path: &str, passphrase: &str) {
+    let keystore = Keystore::encrypt_secret(secret, passphrase).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    keystore.store(path).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+}
+
+/// Decrypt the secret in the keystore at `path`, along with the passphrase that unlocked it
+fn key_unlock_with_passphrase(path: &str) -> (KeystoreSecret, String) {
+    let keystore = Keystore::load(path).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    let passphrase = read_passphrase(&format!("Passphrase for {}: ", path)).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    let secret = keystore.decrypt_secret(&passphrase).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    (secret, passphrase)
+}
+
+/// Decrypt the secret in the keystore at `path`
+fn key_unlock(path: &str) -> KeystoreSecret {
+    key_unlock_with_passphrase(path).0
+}
+
+fn key_address(privkey: &Secp256k1PrivateKey, mainnet: bool) -> StacksAddress {
+    StacksAddress::p2pkh(mainnet, &StacksPublicKey::from_private(privkey))
+}
+
+/// key subcommand helper
+/// Commands start at argv[2]
+pub fn subcommand_key(mut argv: Vec<String>) {
+    if argv.len() < 3 {
+        eprintln!("Usage: {} key [subcommand] [options]", &argv[0]);
+        process::exit(1);
+    }
+    let cmd = argv[2].clone();
+    let force = consume_arg(&mut argv, &["-f", "--force"], false)
+        .map_err(|e| {
+            usage(&e);
+            unreachable!()
+        })
+        .unwrap()
+        .is_some();
+
+    if cmd == "create" {
+        if argv.len() < 4 {
+            eprintln!("Usage: {} key {} [-f|--force] PATH", &argv[0], &cmd);
+            process::exit(1);
+        }
+        let identity = Identity::generate();
+        eprintln!(
+            "Write down this mnemonic and keep it safe.  It is the only backup of your identity:"
+        );
+        eprintln!("{}", &identity.mnemonic());
+        key_store_new(&KeystoreSecret::Mnemonic(identity), &argv[3], force);
+        return;
+    } else if cmd == "import" {
+        let privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
+        let mnemonic = consume_arg(&mut argv, &["-m", "--mnemonic"], false)
+            .map_err(|e| {
+                usage(&e);
+                unreachable!()
+            })
+            .unwrap()
+            .is_some();
+        if argv.len() < 4 {
+            eprintln!(
+                "Usage: {} key {} [-f|--force] [-k|--private-key KEY | -m|--mnemonic] PATH",
+                &argv[0], &cmd
+            );
+            eprintln!("With -m, the mnemonic is read from stdin.");
+            eprintln!("Without -k or -m, the identity in the config file is imported.");
+            process::exit(1);
+        }
+        let secret = if let Some(privkey) = privkey_opt {
+            KeystoreSecret::PrivateKey(privkey)
+        } else if mnemonic {
+            let phrase_bytes = load_from_file_or_stdin("-");
+            let phrase = std::str::from_utf8(&phrase_bytes).unwrap_or_else(|_| {
+                eprintln!("FATAL: mnemonic is not valid UTF-8");
+                process::exit(1);
+            });
+            KeystoreSecret::Mnemonic(Identity::from_mnemonic(phrase).unwrap_or_else(|e| {
+                eprintln!("FATAL: {}", &e);
+                process::exit(1);
+            }))
+        } else {
+            with_global_config(|cfg| {
+                if cfg.is_locked() {
+                    eprintln!("FATAL: the configured identity is already in a keystore");
+                    process::exit(1);
+                }
+                match cfg.identity() {
+                    Some(identity) => KeystoreSecret::Mnemonic(identity.clone()),
+                    None => KeystoreSecret::PrivateKey(cfg.private_key().clone()),
+                }
+            })
+            .expect("System is not initialized")
+        };
+        key_store_new(&secret, &argv[3], force);
+        return;
+    } else if cmd == "export" {
+        if argv.len() < 4 {
+            eprintln!("Usage: {} key {} PATH", &argv[0], &cmd);
+            process::exit(1);
+        }
+        match key_unlock(&argv[3]) {
+            KeystoreSecret::PrivateKey(privkey) => println!("{}", &privkey.to_hex()),
+            KeystoreSecret::Mnemonic(identity) => println!("{}", &identity.mnemonic()),
+        }
+        return;
+    } else if cmd == "addresses" {
+        let site_opt = consume_arg(&mut argv, &["--site"], true)
+            .map_err(|e| {
+                usage(&e);
+                unreachable!()
+            })
+            .unwrap();
+        if argv.len() < 4 {
+            eprintln!(
+                "Usage: {} key {} [--site NAME.NAMESPACE] PATH",
+                &argv[0], &cmd
+            );
+            process::exit(1);
+        }
+        let mainnet = with_global_config(|cfg| cfg.mainnet()).expect("System is not initialized");
+        match key_unlock(&argv[3]) {
+            KeystoreSecret::PrivateKey(privkey) => {
+                // the same key is used for everything
+                println!("identity: {}", &key_address(&privkey, mainnet));
+            }
+            KeystoreSecret::Mnemonic(identity) => {
+                println!("identity: {}", &key_address(&identity.root_key(), mainnet));
+                println!("wrbpod: {}", &key_address(&identity.wrbpod_key(), mainnet));
+                if let Some(site) = site_opt {
+                    let Some((name, namespace)) = site.split_once('.') else {
+                        eprintln!("FATAL: expected NAME.NAMESPACE, got '{}'", &site);
+                        process::exit(1);
+                    };
+                    println!(
+                        "{}: {}",
+                        &site,
+                        &key_address(&identity.site_key(name, namespace), mainnet)
+                    );
+                }
+            }
+        }
+        return;
+    } else if cmd == "change-passphrase" {
+        if argv.len() < 4 {
+            eprintln!("Usage: {} key {} PATH", &argv[0], &cmd);
+            eprintln!(
+                "The new passphrase is read from ${}, or prompted for.",
+                NEW_KEYSTORE_PASSPHRASE_ENV
+            );
+            process::exit(1);
+        }
+        let path = argv[3].clone();
+        let (secret, old_passphrase) = key_unlock_with_passphrase(&path);
+        let passphrase = read_new_passphrase().unwrap_or_else(|e| {
+            eprintln!("FATAL: {}", &e);
+            process::exit(1);
+        });
+        if passphrase == old_passphrase {
+            eprintln!("FATAL: the new passphrase is the same as the old one");
+            process::exit(1);
+        }
+        key_store(&secret, &path, &passphrase);
+        eprintln!("Changed the passphrase of {}", &path);
+        return;
+    }
+
+    eprintln!("Unrecognized `key` command '{}'", &cmd);
+    process::exit(1);
+}
@@ -25,6 +25,7 @@ use std::str::FromStr;
 use std::thread;
 use std::time::Duration;
 
+use crate::core::keystore::{read_passphrase, Keystore};
 use crate::core::Config;
 use crate::runner::bns::BNSResolver;
 use crate::runner::bns::NodeBNSResolver;
@@ -34,7 +35,10 @@ use crate::runner::site::ZonefileResourceRecord;
 use crate::runner::Error as RunnerError;
 use crate::runner::Runner;
 
+use crate::runner::nonces::NonceDB;
 use crate::runner::tx::StacksAccount;
+use crate::runner::tx::{TxStatus, TxTracker, DEFAULT_TX_CONFIRM_TIMEOUT_SECS};
+use crate::tx::multisig::{MultisigMode, MultisigOrigin, PartiallySignedTransaction};
 use crate::tx::TransactionVersion;
 use crate::tx::Txid;
 
@@ -53,6 +57,7 @@ use crate::storage::WrbpodSlices;
 use crate::storage::WrbpodSuperblock;
 
 use crate::core::globals::redirect_logfile;
+use crate::core::unlock_identity;
 use crate::core::with_global_config;
 use crate::core::with_globals;
 
@@ -61,9 +66,11 @@ use crate::runner::stackerdb::StackerDBSession;
 use crate::vm::clarity_vm::vm_execute;
 
 use crate::tx::{
-    make_contract_call, StacksTransaction, TransactionPostCondition, TransactionPostConditionMode,
+    make_contract_call, make_sponsored_tx, StacksTransaction, TransactionPostCondition,
+    TransactionPostConditionMode,
 };
 
+use clarity::vm::types::PrincipalData;
 use clarity::vm::types::QualifiedContractIdentifier;
 use clarity::vm::types::StacksAddressExtensions;
 use clarity::vm::types::TupleData;
@@ -75,6 +82,7 @@ use stacks_common::address::{
 };
 use stacks_common::types::chainstate::StacksAddress;
 use stacks_common::types::chainstate::StacksPublicKey;
+use stacks_common::util::get_epoch_time_secs;
 use stacks_common::util::hash::hex_bytes;
 use stacks_common::util::hash::to_hex;
 use stacks_common::util::hash::Hash160;
@@ -92,7 +100,9 @@ pub use crate::core::wrbsite_load;
 
 pub mod bns;
 pub mod clar;
+pub mod key;
 pub mod site;
+pub mod tx;
 pub mod wrbpod;
 
 #[cfg(test)]
@@ -100,7 +110,9 @@ pub mod tests;
 
 pub use crate::cli::bns::subcommand_bns;
 pub use crate::cli::clar::subcommand_clarity;
+pub use crate::cli::key::subcommand_key;
 pub use crate::cli::site::subcommand_site;
+pub use crate::cli::tx::subcommand_tx;
 pub use crate::cli::wrbpod::subcommand_wrbpod;
 
 pub fn consume_arg(
@@ -148,10 +160,25 @@ pub fn usage(msg: &str) {
     process::exit(1);
 }
 
+/// Run `func` on the global config, once the identity's keystore (if any) is unlocked.
+/// Commands use this to get at the identity's keys, so that they only ask for the keystore
+/// passphrase if they need it.
+pub fn with_unlocked_config<F, R>(func: F) -> R
+where
+    F: FnOnce(&Config) -> R,
+{
+    unlock_identity().unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    with_global_config(func).expect("System is not initialized")
+}
+
 /// Open a StackerDB session to the home node
 pub fn open_home_stackerdb_session(
     contract_id: QualifiedContractIdentifier,
 ) -> Result<Box<dyn StackerDBClient>, String> {
+    unlock_identity()?;
     let privkey = with_global_config(|cfg| cfg.private_key().clone())
         .ok_or("System is not initialized".to_string())?;
     let mut runner = make_runner();
@@ -171,6 +198,7 @@ pub fn open_home_stackerdb_session(
 pub fn open_replica_stackerdb_session(
     contract_id: QualifiedContractIdentifier,
 ) -> Result<Box<dyn StackerDBClient>, String> {
+    unlock_identity()?;
     let privkey = with_global_config(|cfg| cfg.private_key().clone())
         .ok_or("System is not initialized".to_string())?;
     let mut runner = make_runner();
@@ -186,6 +214,26 @@ pub fn open_replica_stackerdb_session(
     Ok(replica_stackerdb_client)
 }
 
+/// Open a StackerDB session to the replica node that can write chunks
+pub fn open_writable_replica_stackerdb_session(
+    contract_id: QualifiedContractIdentifier,
+) -> Result<Box<dyn StackerDBClient>, String> {
+    unlock_identity()?;
+    let privkey = with_global_config(|cfg| cfg.private_key().clone())
+        .ok_or("System is not initialized".to_string())?;
+    let mut runner = make_runner();
+    let replica_stackerdb_client = runner
+        .get_writable_replica_stackerdb_client(contract_id.clone(), privkey.clone())
+        .map_err(|e| {
+            format!(
+                "Failed to instantiate StackerDB client to {}: {:?}",
+                contract_id, &e
+            )
+        })?;
+
+    Ok(replica_stackerdb_client)
+}
+
 /// Get code bytes from a contract as part of a CLI command
 pub fn wrbsite_load_code_bytes(
     contract_id: &QualifiedContractIdentifier,
@@ -212,20 +260,29 @@ pub fn wrbsite_load_code_bytes(
     code_bytes_opt
 }
 
-/// Get the fee for a transaction and generate it
-pub fn make_tx<F>(
+/// Get the fee for the transaction that `tx_gen` makes: `fee_opt` if given, or the node's estimate
+/// otherwise.  `tx_gen` is only called to measure the transaction, which is never sent.
+/// If the transaction will be sent from a multisig account, then pass `multisig_opt`, so the fee
+/// is estimated for a transaction carrying the account's signatures instead of a single one.
+pub fn estimate_tx_fee<F>(
     runner: &mut Runner,
     fee_opt: Option<u64>,
-    mut tx_gen: F,
-) -> Result<StacksTransaction, String>
+    multisig_opt: Option<&MultisigOrigin>,
+    tx_gen: F,
+) -> Result<u64, String>
 where
-    F: FnMut(u64) -> StacksTransaction,
+    F: FnOnce(u64) -> StacksTransaction,
 {
     if let Some(fee) = fee_opt {
-        return Ok(tx_gen(fee));
+        return Ok(fee);
     }
 
-    let tx_no_fee = tx_gen(0);
+    let tx_no_fee = match multisig_opt {
+        Some(origin) => PartiallySignedTransaction::new(&tx_gen(0), origin.clone())
+            .and_then(|pst| pst.to_fee_estimate_transaction())
+            .map_err(|e| format!("Failed to make multisig transaction: {}", &e))?,
+        None => tx_gen(0),
+    };
     let fee_estimate = runner.get_tx_fee(&tx_no_fee).map_err(|e| match e {
         RunnerError::NoFeeEstimate => {
             "Failed to learn fee estimate from node. Please pass a fee via -f or --fee.".to_string()
@@ -241,77 +298,292 @@ where
 
     // take middle fee
     let est = fee_estimate.estimations.len() / 2;
-    let tx_with_fee = tx_gen(fee_estimate.estimations[est].fee);
-    Ok(tx_with_fee)
+    Ok(fee_estimate.estimations[est].fee)
 }
 
-/// Determine which account(s) to poll to see if a tx confirmed
-fn poll_tx_accounts(
+/// Get the fee for a transaction and generate it.
+/// See `estimate_tx_fee()` for how the fee is chosen.
+pub fn make_tx<F>(
     runner: &mut Runner,
-    tx: &StacksTransaction,
-) -> Result<(StacksAccount, Option<StacksAccount>), RunnerError> {
-    let mainnet = tx.version == TransactionVersion::Mainnet;
+    fee_opt: Option<u64>,
+    multisig_opt: Option<&MultisigOrigin>,
+    mut tx_gen: F,
+) -> Result<StacksTransaction, String>
+where
+    F: FnMut(u64) -> StacksTransaction,
+{
+    let fee = estimate_tx_fee(runner, fee_opt, multisig_opt, &mut tx_gen)?;
+    Ok(tx_gen(fee))
+}
 
-    let origin_addr = if mainnet {
-        tx.auth.origin().address_mainnet()
-    } else {
-        tx.auth.origin().address_testnet()
+/// Open the DB of nonces handed out to pending transactions
+pub fn open_nonce_db() -> Result<NonceDB, String> {
+    let path = with_global_config(|cfg| cfg.nonces_path())
+        .ok_or("System is not initialized".to_string())?;
+    NonceDB::open(&path).map_err(|e| format!("Failed to open nonce DB at {}: {:?}", &path, &e))
+}
+
+/// Get the next nonce to use for a transaction from `principal`.
+/// The nonce is set aside until the transaction is broadcast, so other transactions (including
+/// ones from other wrb processes) won't get it.  Give it back with `release_nonce()` if the
+/// transaction is never broadcast.
+pub fn next_nonce(runner: &mut Runner, principal: &PrincipalData) -> Result<u64, String> {
+    let mut nonce_db = open_nonce_db()?;
+    runner
+        .next_nonce(&mut nonce_db, principal)
+        .map_err(|e| format!("Failed to get next nonce for {}: {:?}", principal, &e))
+}
+
+/// Give back a nonce from `next_nonce()` whose transaction was never broadcast
+pub fn release_nonce(principal: &PrincipalData, nonce: u64) {
+    let res = open_nonce_db().and_then(|mut nonce_db| {
+        nonce_db
+            .release_nonce(principal, nonce)
+            .map_err(|e| format!("{:?}", &e))
+    });
+    if let Err(e) = res {
+        wrb_warn!("Failed to release nonce {} of {}: {}", nonce, principal, &e);
     }
-    .to_account_principal();
+}
 
-    let origin_account = runner.get_account(&origin_addr)?;
+/// Remember that `tx`, which someone else will finish and broadcast (its sponsor, or the
+/// cosigners of a multisig account), uses `origin`'s nonce, so that the origin's next transaction doesn't
+/// reuse it.  On a dry run, the nonce is given back instead.
+pub fn record_handed_off_tx(origin: &PrincipalData, tx: &StacksTransaction, dry_run: bool) {
+    if dry_run {
+        release_nonce(origin, tx.get_origin_nonce());
+        return;
+    }
+    let res = open_nonce_db().and_then(|mut nonce_db| {
+        nonce_db
+            .record_tx(origin, tx, get_epoch_time_secs())
+            .map_err(|e| format!("{:?}", &e))
+    });
+    if let Err(e) = res {
+        wrb_warn!(
+            "Failed to record handed-off transaction {} in nonce DB: {}",
+            &tx.txid(),
+            &e
+        );
+    }
+}
 
-    let sponsor_account = if let Some(sponsor) = tx.auth.sponsor() {
-        let sponsor_addr = if mainnet {
-            sponsor.address_mainnet()
-        } else {
-            sponsor.address_testnet()
+/// Post a transaction and wait for it to get mined.
+/// The transaction is recorded against the nonce of the account paying for it (its sponsor, if
+/// it has one) once it's broadcast.  If the broadcast fails, the nonce is given back, unless an
+/// earlier transaction is already using it.
+/// Fails if the node rejects it, if it is mined but aborted, if another transaction takes its
+/// nonce, or if it is not mined within the configured timeout.
+pub fn post_tx(runner: &mut Runner, tx: &StacksTransaction) -> Result<Txid, String> {
+    let tracker = TxTracker::new(tx);
+    let timeout = with_global_config(|cfg| cfg.tx_confirm_timeout())
+        .unwrap_or(Duration::from_secs(DEFAULT_TX_CONFIRM_TIMEOUT_SECS));
+    let (payer, payer_nonce) = tracker
+        .sponsor
+        .clone()
+        .unwrap_or((tracker.origin.clone(), tracker.origin_nonce));
+
+    let mut nonce_db = open_nonce_db()?;
+    if let Err(e) = runner.post_tx(tx) {
+        let unused = nonce_db
+            .get_pending_nonce(&payer, payer_nonce)
+            .map(|pending_opt| {
+                pending_opt
+                    .map(|pending| pending.tx.is_none())
+                    .unwrap_or(false)
+            })
+            .unwrap_or(false);
+        if unused {
+            release_nonce(&payer, payer_nonce);
         }
-        .to_account_principal();
-        Some(runner.get_account(&sponsor_addr)?)
-    } else {
-        None
+        return Err(match e {
+            RunnerError::TxRejected(reason) => {
+                format!("Node rejected transaction {}: {}", &tracker.txid, &reason)
+            }
+            e => format!("Failed to post transaction: {:?}", &e),
+        });
+    }
+    if let Err(e) = nonce_db.record_tx(&payer, tx, get_epoch_time_secs()) {
+        wrb_warn!(
+            "Failed to record transaction {} in nonce DB: {:?}",
+            &tracker.txid,
+            &e
+        );
+    }
+
+    eprint!("Sending tx {} and waiting for confirmation", &tracker.txid);
+    let status = runner
+        .wait_for_tx(&tracker, timeout, |_status| eprint!("."))
+        .map_err(|e| {
+            eprintln!();
+            format!("Failed to confirm transaction {}: {}", &tracker.txid, &e)
+        })?;
+    eprintln!();
+
+    match status {
+        TxStatus::Mined(_) => Ok(tracker.txid),
+        TxStatus::NonceConsumed => {
+            eprintln!(
+                "WARN: node does not index transactions; assuming {} was mined because its nonce was used",
+                &tracker.txid
+            );
+            Ok(tracker.txid)
+        }
+        status => Err(format!("Transaction {} {}", &tracker.txid, &status)),
+    }
+}
+
+/// A multisig account to send a transaction from, and where to save the transaction for its
+/// cosigners to sign with `wrb tx sign`
+pub struct MultisigExport {
+    pub origin: MultisigOrigin,
+    pub path: String,
+}
+
+/// Decode `--multisig M:PUBKEY,PUBKEY,...`, `--multisig-mode MODE`, and `-o|--export PATH`.
+/// The mode defaults to p2sh, and the path defaults to stdout.
+pub fn consume_multisig(argv: &mut Vec<String>) -> Option<MultisigExport> {
+    let consume = |argv: &mut Vec<String>, argnames: &[&str]| {
+        consume_arg(argv, argnames, true)
+            .map_err(|e| {
+                usage(&e);
+                unreachable!()
+            })
+            .unwrap()
+    };
+    let spec_opt = consume(argv, &["--multisig"]);
+    let mode_opt = consume(argv, &["--multisig-mode"]);
+    let path_opt = consume(argv, &["-o", "--export"]);
+    let Some(spec) = spec_opt else {
+        if mode_opt.is_some() || path_opt.is_some() {
+            usage("--multisig-mode and --export require --multisig");
+        }
+        return None;
     };
 
-    Ok((origin_account, sponsor_account))
+    let mode_str = mode_opt.unwrap_or("p2sh".to_string());
+    let mode = MultisigMode::from_name(&mode_str)
+        .ok_or_else(|| {
+            usage(&format!(
+                "Unrecognized multisig mode '{}'. Expected one of p2sh, p2wsh, p2sh-order-independent, p2wsh-order-independent",
+                &mode_str
+            ));
+            unreachable!()
+        })
+        .unwrap();
+    let origin = MultisigOrigin::parse(mode, &spec)
+        .map_err(|e| {
+            usage(&e);
+            unreachable!()
+        })
+        .unwrap();
+
+    Some(MultisigExport {
+        origin,
+        path: path_opt.unwrap_or("-".to_string()),
+    })
 }
 
-/// Post a transaction and wait for it to get confirmed
-pub fn post_tx(runner: &mut Runner, tx: &StacksTransaction) -> Result<Txid, String> {
-    let (origin_account_before, sponsor_account_before_opt) = poll_tx_accounts(runner, tx)
-        .map_err(|e| format!("Failed to query transaction accounts: {:?}", &e))?;
+/// Save a partially-signed transaction to `path`, or to stdout if `path` is "-"
+pub fn store_multisig_tx(pst: &PartiallySignedTransaction, path: &str) -> Result<(), String> {
+    let json = pst.to_json();
+    if path == "-" {
+        println!("{}", &json);
+        return Ok(());
+    }
+    fs::write(path, json.as_bytes()).map_err(|e| format!("Failed to write {}: {:?}", path, &e))
+}
 
-    runner
-        .post_tx(tx)
-        .map_err(|e| format!("Failed to post transaction: {:?}", &e))?;
+/// Load a partially-signed transaction from `path`, or from stdin if `path` is "-"
+pub fn load_multisig_tx(path: &str) -> Result<PartiallySignedTransaction, String> {
+    let bytes = load_from_file_or_stdin(path);
+    PartiallySignedTransaction::from_json(&String::from_utf8_lossy(&bytes))
+}
 
-    eprint!("Sending tx {} and waiting for confirmation", &tx.txid());
-    loop {
-        thread::sleep(Duration::from_secs(1));
+/// The fee the origin sets on a transaction.  Sponsors set their own fee, so a sponsored
+/// transaction gets none.
+pub fn origin_tx_fee(tx_fee_opt: Option<u64>, sponsored: bool) -> Option<u64> {
+    if sponsored {
+        Some(0)
+    } else {
+        tx_fee_opt
+    }
+}
 
-        let (origin_account, sponsor_account_opt) = poll_tx_accounts(runner, tx)
-            .map_err(|e| format!("Failed to query transaction accounts: {:?}", &e))?;
+/// Finish a transaction that `origin` made with `privkey`, using `nonce`.
+/// * If `sponsored`, re-sign it for a sponsor and print it, so the sponsor can pay for it.
+/// * If `multisig_opt` is given, export it so the cosigners can sign it.
+/// * If `dry_run`, print it.
+/// * Otherwise, post it and wait for it to get mined.
+///
+/// `what` describes the transaction in error messages.  The nonce is given back if the
+/// transaction won't be broadcast.  Returns the txid if the transaction was posted.
+pub fn finish_tx(
+    runner: &mut Runner,
+    tx: StacksTransaction,
+    privkey: &Secp256k1PrivateKey,
+    origin: &PrincipalData,
+    nonce: u64,
+    dry_run: bool,
+    sponsored: bool,
+    multisig_opt: Option<MultisigExport>,
+    what: &str,
+) -> Option<Txid> {
+    let tx = if sponsored {
+        make_sponsored_tx(&tx, privkey).unwrap_or_else(|e| {
+            release_nonce(origin, nonce);
+            eprintln!("FATAL: failed to make sponsored transaction: {}", &e);
+            process::exit(1);
+        })
+    } else {
+        tx
+    };
 
-        eprint!(".");
-        if origin_account.nonce == origin_account_before.nonce {
-            continue;
-        }
+    if let Some(multisig) = multisig_opt {
+        export_multisig_tx(&tx, &multisig).unwrap_or_else(|e| {
+            release_nonce(origin, nonce);
+            eprintln!("FATAL: {}", &e);
+            process::exit(1);
+        });
+        record_handed_off_tx(origin, &tx, dry_run);
+        return None;
+    }
 
-        let Some(sponsor_account_before) = sponsor_account_before_opt.as_ref() else {
-            break;
-        };
-        let Some(sponsor_account) = sponsor_account_opt.as_ref() else {
-            break;
-        };
+    if sponsored {
+        // hand off to the sponsor
+        record_handed_off_tx(origin, &tx, dry_run);
+        println!("{}", &to_hex(&tx.serialize_to_vec()));
+        return None;
+    }
 
-        if sponsor_account_before.nonce == sponsor_account.nonce {
-            continue;
-        }
+    if dry_run {
+        release_nonce(origin, nonce);
+        println!("{}", &to_hex(&tx.serialize_to_vec()));
+        return None;
+    }
+
+    let txid = post_tx(runner, &tx).unwrap_or_else(|e| {
+        wrb_debug!("{}", &to_hex(&tx.serialize_to_vec()));
+        eprintln!("FATAL: failed to post {} transaction: {}", what, &e);
+        process::exit(1);
+    });
+    Some(txid)
+}
 
-        break;
+/// Instead of posting `tx`, save it with its origin replaced by the multisig account, so the
+/// cosigners can sign it.  `tx` must already carry the multisig account's nonce.
+/// Its nonce stays reserved until `wrb tx broadcast` posts it (or the reservation times out).
+pub fn export_multisig_tx(tx: &StacksTransaction, multisig: &MultisigExport) -> Result<(), String> {
+    let pst = PartiallySignedTransaction::new(tx, multisig.origin.clone())
+        .map_err(|e| format!("Failed to make multisig transaction: {}", &e))?;
+    store_multisig_tx(&pst, &multisig.path)?;
+    if multisig.path != "-" {
+        eprintln!(
+            "Saved unsigned transaction to {}. Cosigners can sign it with `tx sign`. {}",
+            &multisig.path, &pst
+        );
     }
-    eprintln!();
-    Ok(tx.txid())
+    Ok(())
 }
 
 /// get data from stdin or a file
@@ -375,8 +647,35 @@ fn consume_u32(argv: &mut Vec<String>, argnames: &[&str]) -> Option<u32> {
     consume_int_arg::<u32>(argv, argnames)
 }
 
-/// Decode a private key
-/// TODO: expand to loading from a file or an environment variable
+/// Load a private key from `source`, which is one of:
+/// * hex
+/// * `env:NAME`, for hex in the environment variable NAME
+/// * `file:PATH`, for hex or an encrypted keystore in a file.  The keystore's passphrase is read
+/// from $WRB_KEYSTORE_PASSPHRASE, or prompted for.
+pub fn load_private_key(source: &str) -> Result<Secp256k1PrivateKey, String> {
+    if let Some(var) = source.strip_prefix("env:") {
+        let privkey_hex =
+            env::var(var).map_err(|e| format!("Failed to read ${}: {:?}", var, &e))?;
+        return Secp256k1PrivateKey::from_hex(privkey_hex.trim())
+            .map_err(|e| format!("Failed to decode private key in ${}: {}", var, &e));
+    }
+    if let Some(path) = source.strip_prefix("file:") {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read private key from {}: {:?}", path, &e))?;
+        let contents = contents.trim();
+        if contents.starts_with('{') {
+            let keystore = Keystore::from_json(contents)?;
+            let passphrase = read_passphrase(&format!("Passphrase for {}: ", path))?;
+            return keystore.decrypt(&passphrase);
+        }
+        return Secp256k1PrivateKey::from_hex(contents)
+            .map_err(|e| format!("Failed to decode private key in {}: {}", path, &e));
+    }
+    Secp256k1PrivateKey::from_hex(source)
+        .map_err(|e| format!("Failed to decode private key: {}", &e))
+}
+
+/// Decode a private key, given as hex, `env:NAME`, or `file:PATH` (see `load_private_key()`)
 pub fn consume_private_key(
     argv: &mut Vec<String>,
     argnames: &[&str],
@@ -388,7 +687,7 @@ pub fn consume_private_key(
         })
         .unwrap()
         .map(|k_str| {
-            Secp256k1PrivateKey::from_hex(&k_str)
+            load_private_key(&k_str)
                 .map_err(|e| {
                     usage(&e);
                     unreachable!();
@@ -26,8 +26,11 @@ use std::time::Duration;
 use crate::core::Config;
 use crate::runner::bns::BNSResolver;
 use crate::runner::bns::NodeBNSResolver;
+use crate::runner::pins::{SitePin, SitePinDB};
 use crate::runner::site::WrbTxtRecord;
 use crate::runner::site::WrbTxtRecordV1;
+use crate::runner::site::WrbTxtRecordV2;
+use crate::runner::site::WrbsiteManifest;
 use crate::runner::site::ZonefileResourceRecord;
 use crate::runner::Error as RunnerError;
 use crate::runner::Runner;
@@ -76,26 +79,90 @@ use stacks_common::types::chainstate::StacksPublicKey;
 use stacks_common::util::hash::hex_bytes;
 use stacks_common::util::hash::to_hex;
 use stacks_common::util::hash::Hash160;
+use stacks_common::util::hash::Sha512Trunc256Sum;
 use stacks_common::util::secp256k1::Secp256k1PrivateKey;
 
 use crate::stacks_common::codec::StacksMessageCodec;
 
+use libstackerdb::SlotMetadata;
 use libstackerdb::StackerDBChunkAckData;
 use libstackerdb::StackerDBChunkData;
+use libstackerdb::STACKERDB_MAX_CHUNK_SIZE;
 
 use crate::cli::{
-    consume_arg, consume_private_key, consume_u64, load_from_file_or_stdin, make_runner, make_tx,
-    open_home_stackerdb_session, open_replica_stackerdb_session, post_tx, split_fqn, usage,
-    wrbsite_load_code_bytes,
+    consume_arg, consume_multisig, consume_private_key, consume_u64, finish_tx,
+    load_from_file_or_stdin, make_runner, make_tx, next_nonce, open_home_stackerdb_session,
+    open_replica_stackerdb_session, open_writable_replica_stackerdb_session, origin_tx_fee,
+    release_nonce, split_fqn, usage, with_unlocked_config, wrbsite_load_code_bytes, MultisigExport,
 };
 
 use crate::cli::bns::subcommand_bns_owner;
 use crate::cli::bns::subcommand_bns_resolve;
 
+/// Parse a comma-separated list of slot IDs.
+/// The first slot holds the site code (or the manifest, if there is more than one slot).
+fn parse_slot_ids(slot_ids_str: &str) -> Vec<u32> {
+    let slot_ids: Vec<u32> = slot_ids_str
+        .split(",")
+        .map(|slot_id_str| {
+            slot_id_str.parse::<u32>().unwrap_or_else(|e| {
+                eprintln!(
+                    "FATAL: could not parse '{}' into slot ID: {:?}",
+                    slot_id_str, &e
+                );
+                process::exit(1);
+            })
+        })
+        .collect();
+
+    if slot_ids.len() == 0 {
+        eprintln!("FATAL: no slot IDs given");
+        process::exit(1);
+    }
+    slot_ids
+}
+
+/// Sign and upload a single chunk to the given slot, and return its metadata
+fn site_upload_chunk(
+    stackerdb_session: &mut dyn StackerDBClient,
+    slots_metadata: &[SlotMetadata],
+    privkey: &Secp256k1PrivateKey,
+    contract_id: &QualifiedContractIdentifier,
+    slot_id: u32,
+    data: Vec<u8>,
+) -> (StackerDBChunkAckData, SlotMetadata) {
+    let slot_version = slots_metadata
+        .get(slot_id as usize)
+        .map(|slot_md| slot_md.slot_version)
+        .unwrap_or_else(|| {
+            eprintln!(
+                "FATAL: no such StackerDB slot {} in {}",
+                slot_id, contract_id
+            );
+            process::exit(1);
+        });
+
+    let mut chunk_data = StackerDBChunkData::new(slot_id, slot_version + 1, data);
+    chunk_data.sign(privkey).unwrap_or_else(|e| {
+        eprintln!("FATAL: failed to sign chunk: {:?}", &e);
+        process::exit(1);
+    });
+    let metadata = chunk_data.get_slot_metadata();
+
+    let ack = stackerdb_session.put_chunk(chunk_data).unwrap_or_else(|e| {
+        eprintln!("FATAL: failed to upload site code chunk: {:?}", &e);
+        process::exit(1);
+    });
+
+    (ack, metadata)
+}
+
 /// site upload
+/// If more than one slot is given, then the code is split across slot_ids[1..], and a manifest
+/// listing those parts is stored in slot_ids[0].
 fn subcommand_site_upload(
     contract_id: &QualifiedContractIdentifier,
-    slot_id: u32,
+    slot_ids: &[u32],
     path_to_code: String,
 ) -> StackerDBChunkAckData {
     let code = load_from_file_or_stdin(&path_to_code);
@@ -105,8 +172,8 @@ fn subcommand_site_upload(
         process::exit(1);
     });
 
-    let mut stackerdb_session =
-        open_replica_stackerdb_session(contract_id.clone()).unwrap_or_else(|e| {
+    let mut stackerdb_session = open_writable_replica_stackerdb_session(contract_id.clone())
+        .unwrap_or_else(|e| {
             eprintln!(
                 "FATAL: failed to connect to StackerDB {} on replica node: {}",
                 contract_id, &e
@@ -124,31 +191,60 @@ fn subcommand_site_upload(
         process::exit(1);
     });
 
-    let slot_version = slots_metadata
-        .get(slot_id as usize)
-        .map(|slot_md| slot_md.slot_version)
-        .unwrap_or_else(|| {
-            eprintln!(
-                "FATAL: no such StackerDB slot {} in {}",
-                slot_id, contract_id
-            );
-            process::exit(1);
-        });
+    let privkey = with_unlocked_config(|cfg| cfg.private_key().clone());
 
-    let privkey =
-        with_global_config(|cfg| cfg.private_key().clone()).expect("System is not initialized");
+    if slot_ids.len() == 1 {
+        let (ack, _) = site_upload_chunk(
+            &mut *stackerdb_session,
+            &slots_metadata,
+            &privkey,
+            contract_id,
+            slot_ids[0],
+            code_bytes,
+        );
+        return ack;
+    }
 
-    let mut chunk_data = StackerDBChunkData::new(slot_id, slot_version + 1, code_bytes);
-    chunk_data.sign(&privkey).unwrap_or_else(|e| {
-        eprintln!("FATAL: failed to sign chunk: {:?}", &e);
-        process::exit(1);
-    });
+    // multi-chunk site
+    let parts: Vec<Vec<u8>> = code_bytes
+        .chunks(STACKERDB_MAX_CHUNK_SIZE as usize)
+        .map(|part| part.to_vec())
+        .collect();
 
-    let ack = stackerdb_session.put_chunk(chunk_data).unwrap_or_else(|e| {
-        eprintln!("FATAL: failed to upload site code chunk: {:?}", &e);
+    if parts.len() > slot_ids.len() - 1 {
+        eprintln!(
+            "FATAL: site code needs {} slots, but only {} part slots were given",
+            parts.len(),
+            slot_ids.len() - 1
+        );
         process::exit(1);
-    });
+    }
+
+    let mut parts_metadata = vec![];
+    for (part, slot_id) in parts.into_iter().zip(slot_ids[1..].iter()) {
+        let (ack, metadata) = site_upload_chunk(
+            &mut *stackerdb_session,
+            &slots_metadata,
+            &privkey,
+            contract_id,
+            *slot_id,
+            part,
+        );
+        if !ack.accepted {
+            return ack;
+        }
+        parts_metadata.push(metadata);
+    }
 
+    let manifest = WrbsiteManifest::new(parts_metadata);
+    let (ack, _) = site_upload_chunk(
+        &mut *stackerdb_session,
+        &slots_metadata,
+        &privkey,
+        contract_id,
+        slot_ids[0],
+        manifest.serialize_to_vec(),
+    );
     ack
 }
 
@@ -171,19 +267,23 @@ fn subcommand_site_download(contract_id: &QualifiedContractIdentifier, slot_id:
 }
 
 /// site subcommand publish
+/// If more than one slot is given, then slot_ids[0] holds the site's manifest.
 fn subcommand_site_publish(
     contract_id: &QualifiedContractIdentifier,
-    slot_id: u32,
+    slot_ids: &[u32],
     wrbsite_name: String,
     dry_run: bool,
     raw: bool,
     name_privkey_opt: Option<Secp256k1PrivateKey>,
     tx_fee_opt: Option<u64>,
+    sponsored: bool,
+    multisig_opt: Option<MultisigExport>,
 ) -> Option<Txid> {
     let (name, namespace) = split_fqn(&wrbsite_name).unwrap_or_else(|e| {
         eprintln!("FATAL: could not decode '{}': {}", &wrbsite_name, &e);
         process::exit(1);
     });
+    let slot_id = slot_ids[0];
 
     let mut stackerdb_session =
         open_replica_stackerdb_session(contract_id.clone()).unwrap_or_else(|e| {
@@ -232,8 +332,7 @@ fn subcommand_site_publish(
         });
 
     // reconstruct the chunk
-    let (privkey, mainnet) = with_global_config(|cfg| (cfg.private_key().clone(), cfg.mainnet()))
-        .expect("System is not initialized");
+    let (privkey, mainnet) = with_unlocked_config(|cfg| (cfg.private_key().clone(), cfg.mainnet()));
 
     let name_privkey = name_privkey_opt.unwrap_or(privkey.clone());
 
@@ -248,14 +347,17 @@ fn subcommand_site_publish(
     let mut zonefile = subcommand_bns_resolve(&wrbsite_name)
         .unwrap_or(format!("$ORIGIN {}\n\n", &wrbsite_name).as_bytes().to_vec());
 
-    let mut wrb_rr_bytes = ZonefileResourceRecord::try_from(WrbTxtRecord::V1(WrbTxtRecordV1::new(
-        contract_id.clone(),
-        metadata,
-    )))
-    .expect("FATAL: could not construct a zonefile resource record for this wrbsite")
-    .to_string()
-    .as_bytes()
-    .to_vec();
+    let wrb_rec = if slot_ids.len() == 1 {
+        WrbTxtRecord::V1(WrbTxtRecordV1::new(contract_id.clone(), metadata))
+    } else {
+        WrbTxtRecord::V2(WrbTxtRecordV2::new(contract_id.clone(), metadata))
+    };
+
+    let mut wrb_rr_bytes = ZonefileResourceRecord::try_from(wrb_rec)
+        .expect("FATAL: could not construct a zonefile resource record for this wrbsite")
+        .to_string()
+        .as_bytes()
+        .to_vec();
 
     zonefile.append(&mut "\n".as_bytes().to_vec());
     zonefile.append(&mut wrb_rr_bytes);
@@ -277,13 +379,12 @@ fn subcommand_site_publish(
         return None;
     }
 
-    let addr = StacksAddress::p2pkh(mainnet, &StacksPublicKey::from_private(&name_privkey))
-        .to_account_principal();
+    let addr = match multisig_opt.as_ref() {
+        Some(multisig) => multisig.origin.address(mainnet),
+        None => StacksAddress::p2pkh(mainnet, &StacksPublicKey::from_private(&name_privkey)),
+    }
+    .to_account_principal();
     let mut runner = make_runner();
-    let account = runner.get_account(&addr).unwrap_or_else(|e| {
-        panic!("FATAL: failed to look up account {}: {:?}", &addr, &e);
-    });
-
     let bns_address = StacksAddress::new(
         runner.get_bns_contract_id().issuer.version(),
         Hash160(runner.get_bns_contract_id().issuer.1.clone()),
@@ -305,11 +406,18 @@ fn subcommand_site_publish(
         process::exit(1);
     }
 
-    let tx = make_tx(&mut runner, tx_fee_opt, |fee_rate| {
+    let nonce = next_nonce(&mut runner, &addr).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+
+    let tx_fee_opt = origin_tx_fee(tx_fee_opt, sponsored);
+    let multisig_origin_opt = multisig_opt.as_ref().map(|multisig| &multisig.origin);
+    let tx = make_tx(&mut runner, tx_fee_opt, multisig_origin_opt, |fee_rate| {
         make_contract_call(
             mainnet,
             &name_privkey,
-            account.nonce,
+            nonce,
             fee_rate,
             &bns_address,
             "zonefile-resolver",
@@ -331,6 +439,7 @@ fn subcommand_site_publish(
         .expect("FATAL: could not make update-zonefile transaction")
     })
     .unwrap_or_else(|e| {
+        release_nonce(&addr, nonce);
         eprintln!(
             "FATAL: failed to generate zonefile-update transaction: {}",
             &e
@@ -338,13 +447,78 @@ fn subcommand_site_publish(
         process::exit(1);
     });
 
-    let txid = post_tx(&mut runner, &tx).unwrap_or_else(|e| {
-        wrb_debug!("{}", &to_hex(&tx.serialize_to_vec()));
-        eprintln!("FATAL: failed to post zonefile-update transaction: {}", &e);
+    finish_tx(
+        &mut runner,
+        tx,
+        &name_privkey,
+        &addr,
+        nonce,
+        dry_run,
+        sponsored,
+        multisig_opt,
+        "zonefile-update",
+    )
+}
+
+/// Open the DB of wrbsite publisher pins
+fn open_site_pin_db() -> SitePinDB {
+    let pins_path =
+        with_global_config(|cfg| cfg.site_pins_path()).expect("System is not initialized");
+    SitePinDB::open(&pins_path).unwrap_or_else(|e| {
+        eprintln!("FATAL: failed to open pin DB at '{}': {:?}", &pins_path, &e);
+        process::exit(1);
+    })
+}
+
+/// site subcommand pins
+fn subcommand_site_pins() -> Vec<SitePin> {
+    let pin_db = open_site_pin_db();
+    pin_db.get_all_pins().unwrap_or_else(|e| {
+        eprintln!("FATAL: failed to load pins: {:?}", &e);
+        process::exit(1);
+    })
+}
+
+/// site subcommand pin.
+/// Pins the site's code to the given hash.  The site must have been loaded before, so we know its
+/// publisher.
+fn subcommand_site_pin(wrbsite_name: &str, code_hash: Sha512Trunc256Sum) {
+    let mut pin_db = open_site_pin_db();
+    let mut pin = pin_db
+        .get_pin(wrbsite_name)
+        .unwrap_or_else(|e| {
+            eprintln!("FATAL: failed to load pin for '{}': {:?}", wrbsite_name, &e);
+            process::exit(1);
+        })
+        .unwrap_or_else(|| {
+            eprintln!(
+                "FATAL: '{}' has not been loaded yet, so its publisher is unknown",
+                wrbsite_name
+            );
+            process::exit(1);
+        });
+
+    pin.code_hash = Some(code_hash);
+    pin_db.put_pin(&pin).unwrap_or_else(|e| {
+        eprintln!(
+            "FATAL: failed to store pin for '{}': {:?}",
+            wrbsite_name, &e
+        );
         process::exit(1);
     });
+}
 
-    Some(txid)
+/// site subcommand unpin.
+/// Forgets the site's publisher and code hash, so the next load will re-pin it.
+fn subcommand_site_unpin(wrbsite_name: &str) {
+    let mut pin_db = open_site_pin_db();
+    pin_db.remove_pin(wrbsite_name).unwrap_or_else(|e| {
+        eprintln!(
+            "FATAL: failed to remove pin for '{}': {:?}",
+            wrbsite_name, &e
+        );
+        process::exit(1);
+    });
 }
 
 /// site subcommand helper
@@ -358,7 +532,7 @@ pub fn subcommand_site(mut argv: Vec<String>) {
     if cmd == "upload" {
         if argv.len() < 6 {
             eprintln!(
-                "Usage: {} site {} WRBPOD_CONTRACT_ID SLOT_ID PATH_TO_SITE_CODE",
+                "Usage: {} site {} WRBPOD_CONTRACT_ID SLOT_ID[,PART_SLOT_ID...] PATH_TO_SITE_CODE",
                 &argv[0], &cmd
             );
             process::exit(1);
@@ -369,16 +543,10 @@ pub fn subcommand_site(mut argv: Vec<String>) {
             process::exit(1);
         });
 
-        let slot_id = argv[4].parse::<u32>().unwrap_or_else(|e| {
-            eprintln!(
-                "FATAL: could not parse '{}' into slot ID: {:?}",
-                &argv[4], &e
-            );
-            process::exit(1);
-        });
+        let slot_ids = parse_slot_ids(&argv[4]);
 
         let path_to_code = &argv[5];
-        let ack = subcommand_site_upload(&contract_id, slot_id, path_to_code.clone());
+        let ack = subcommand_site_upload(&contract_id, &slot_ids, path_to_code.clone());
         if !ack.accepted {
             println!("{:?}", &ack);
             process::exit(1);
@@ -411,7 +579,7 @@ pub fn subcommand_site(mut argv: Vec<String>) {
         return;
     } else if cmd == "publish" {
         if argv.len() < 6 {
-            eprintln!("Usage: {} site {} [-n|--dry-run] [-r|--raw-hex] [-k|--name-private-key KEY] [-f|--fee FEE] WRBPOD_CONTRACT_ID SLOT_ID WRBSITE_NAME", &argv[0], &cmd);
+            eprintln!("Usage: {} site {} [-n|--dry-run] [-r|--raw-hex] [-k|--name-private-key KEY] [-f|--fee FEE] [-s|--sponsored] [--multisig M:PUBKEY,... [--multisig-mode MODE] [-o|--export PATH]] WRBPOD_CONTRACT_ID SLOT_ID[,PART_SLOT_ID...] WRBSITE_NAME", &argv[0], &cmd);
             process::exit(1);
         }
         let dry_run = consume_arg(&mut argv, &["-n", "--dry-run"], false)
@@ -430,29 +598,32 @@ pub fn subcommand_site(mut argv: Vec<String>) {
 
         let name_privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
         let tx_fee_opt = consume_u64(&mut argv, &["-f", "--fee"]);
+        let sponsored = consume_arg(&mut argv, &["-s", "--sponsored"], false)
+            .map_err(|e| {
+                usage(&e);
+                unreachable!()
+            })
+            .unwrap();
+        let multisig_opt = consume_multisig(&mut argv);
 
         let contract_id = QualifiedContractIdentifier::parse(&argv[3]).unwrap_or_else(|e| {
             eprintln!("FATAL: invalid contract ID '{}': {:?}", &argv[3], &e);
             process::exit(1);
         });
 
-        let slot_id = argv[4].parse::<u32>().unwrap_or_else(|e| {
-            eprintln!(
-                "FATAL: could not parse '{}' into slot ID: {:?}",
-                &argv[4], &e
-            );
-            process::exit(1);
-        });
+        let slot_ids = parse_slot_ids(&argv[4]);
 
         let wrbsite_name = argv[5].clone();
         let txid_opt = subcommand_site_publish(
             &contract_id,
-            slot_id,
+            &slot_ids,
             wrbsite_name,
             dry_run.is_some(),
             raw.is_some(),
             name_privkey_opt,
             tx_fee_opt,
+            sponsored.is_some(),
+            multisig_opt,
         );
         if let Some(txid) = txid_opt {
             println!("{}", &txid);
@@ -462,7 +633,7 @@ pub fn subcommand_site(mut argv: Vec<String>) {
     } else if cmd == "deploy" {
         // uplaod and publish
         if argv.len() < 7 {
-            eprintln!("Usage: {} site {} [-n|--dry-run] [-r|--raw-hex] [-k|--name-private-key KEY] [-f|--fee FEE] WRBPOD_CONTRACT_ID SLOT_ID WRBSITE_NAME PATH_TO_CODE", &argv[0], &cmd);
+            eprintln!("Usage: {} site {} [-n|--dry-run] [-r|--raw-hex] [-k|--name-private-key KEY] [-f|--fee FEE] [-s|--sponsored] [--multisig M:PUBKEY,... [--multisig-mode MODE] [-o|--export PATH]] WRBPOD_CONTRACT_ID SLOT_ID[,PART_SLOT_ID...] WRBSITE_NAME PATH_TO_CODE", &argv[0], &cmd);
             process::exit(1);
         }
         let dry_run = consume_arg(&mut argv, &["-n", "--dry-run"], false)
@@ -481,25 +652,26 @@ pub fn subcommand_site(mut argv: Vec<String>) {
 
         let name_privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
         let tx_fee_opt = consume_u64(&mut argv, &["-f", "--fee"]);
+        let sponsored = consume_arg(&mut argv, &["-s", "--sponsored"], false)
+            .map_err(|e| {
+                usage(&e);
+                unreachable!()
+            })
+            .unwrap();
+        let multisig_opt = consume_multisig(&mut argv);
 
         let contract_id = QualifiedContractIdentifier::parse(&argv[3]).unwrap_or_else(|e| {
             eprintln!("FATAL: invalid contract ID '{}': {:?}", &argv[3], &e);
             process::exit(1);
         });
 
-        let slot_id = argv[4].parse::<u32>().unwrap_or_else(|e| {
-            eprintln!(
-                "FATAL: could not parse '{}' into slot ID: {:?}",
-                &argv[4], &e
-            );
-            process::exit(1);
-        });
+        let slot_ids = parse_slot_ids(&argv[4]);
 
         let wrbsite_name = argv[5].clone();
         let path_to_code = &argv[6];
 
         if !dry_run.is_some() {
-            let ack = subcommand_site_upload(&contract_id, slot_id, path_to_code.clone());
+            let ack = subcommand_site_upload(&contract_id, &slot_ids, path_to_code.clone());
             if !ack.accepted {
                 eprintln!("{:?}", &ack);
                 process::exit(1);
@@ -507,17 +679,58 @@ pub fn subcommand_site(mut argv: Vec<String>) {
         }
         let txid_opt = subcommand_site_publish(
             &contract_id,
-            slot_id,
+            &slot_ids,
             wrbsite_name,
             dry_run.is_some(),
             raw.is_some(),
             name_privkey_opt,
             tx_fee_opt,
+            sponsored.is_some(),
+            multisig_opt,
         );
         if let Some(txid) = txid_opt {
             println!("{}", &txid);
         }
 
+        return;
+    } else if cmd == "pins" {
+        // list pinned sites
+        let pins = subcommand_site_pins();
+        for pin in pins.iter() {
+            println!(
+                "{}\t{}\t{}\t{}",
+                &pin.name,
+                &pin.contract_id,
+                &pin.signer,
+                pin.code_hash
+                    .as_ref()
+                    .map(|h| h.to_hex())
+                    .unwrap_or("-".to_string())
+            );
+        }
+        return;
+    } else if cmd == "pin" {
+        if argv.len() < 5 {
+            eprintln!("Usage: {} site {} WRBSITE_NAME CODE_HASH", &argv[0], &cmd);
+            process::exit(1);
+        }
+        let wrbsite_name = argv[3].clone();
+        let code_hash = Sha512Trunc256Sum::from_hex(&argv[4]).unwrap_or_else(|e| {
+            eprintln!(
+                "FATAL: could not parse '{}' into a code hash: {:?}",
+                &argv[4], &e
+            );
+            process::exit(1);
+        });
+        subcommand_site_pin(&wrbsite_name, code_hash);
+        return;
+    } else if cmd == "unpin" {
+        if argv.len() < 4 {
+            eprintln!("Usage: {} site {} WRBSITE_NAME", &argv[0], &cmd);
+            process::exit(1);
+        }
+        let wrbsite_name = argv[3].clone();
+        subcommand_site_unpin(&wrbsite_name);
         return;
     } else {
         usage("Unrecognized subcommand");
@@ -0,0 +1,166 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use clarity::vm::ClarityName;
+use clarity::vm::ContractName;
+use clarity::vm::Value;
+
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::cli::tx::{
+    describe_tx, parse_ft_post_condition, parse_nft_post_condition, parse_stx_post_condition,
+    post_condition_str,
+};
+use crate::tx::{make_contract_call, make_sponsored_tx, sponsor_tx, TransactionPostConditionMode};
+use crate::tx::{
+    AssetInfo, FungibleConditionCode, NonfungibleConditionCode, PostConditionPrincipal,
+    TransactionPostCondition,
+};
+
+fn asset_info() -> AssetInfo {
+    AssetInfo {
+        contract_address: StacksAddress::from_string("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF")
+            .unwrap(),
+        contract_name: ContractName::from("BNS-V2"),
+        asset_name: ClarityName::from("BNS-V2"),
+    }
+}
+
+#[test]
+fn test_parse_post_conditions() {
+    let stx_pc = parse_stx_post_condition("origin:le:1000").unwrap();
+    assert_eq!(
+        stx_pc,
+        TransactionPostCondition::STX(
+            PostConditionPrincipal::Origin,
+            FungibleConditionCode::SentLe,
+            1000
+        )
+    );
+    assert_eq!(
+        post_condition_str(&stx_pc),
+        "origin sends at most 1000 uSTX"
+    );
+
+    let ft_pc = parse_ft_post_condition(
+        "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.hello:eq:5:SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2::BNS-V2",
+    )
+    .unwrap();
+    assert_eq!(
+        ft_pc,
+        TransactionPostCondition::Fungible(
+            PostConditionPrincipal::Contract(
+                asset_info().contract_address,
+                ContractName::from("hello")
+            ),
+            asset_info(),
+            FungibleConditionCode::SentEq,
+            5
+        )
+    );
+
+    let nft_pc = parse_nft_post_condition(
+        "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF:not-sent:SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2::BNS-V2:{ a: u1, b: \"c:d\" }",
+    )
+    .unwrap();
+    let TransactionPostCondition::Nonfungible(principal, nft_asset_info, value, code) = nft_pc
+    else {
+        panic!("not an NFT post-condition");
+    };
+    assert_eq!(
+        principal,
+        PostConditionPrincipal::Standard(asset_info().contract_address)
+    );
+    assert_eq!(nft_asset_info, asset_info());
+    assert_eq!(code, NonfungibleConditionCode::NotSent);
+    let tuple = value.expect_tuple().unwrap();
+    assert_eq!(tuple.get("a").cloned().unwrap(), Value::UInt(1));
+
+    // malformed
+    assert!(parse_stx_post_condition("origin:le").is_err());
+    assert!(parse_stx_post_condition("origin:lte:1").is_err());
+    assert!(parse_stx_post_condition("origin:le:-1").is_err());
+    assert!(parse_stx_post_condition("nobody:le:1").is_err());
+    assert!(
+        parse_ft_post_condition("origin:le:1:SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF").is_err()
+    );
+    assert!(parse_nft_post_condition(
+        "origin:gone:SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2::BNS-V2:u1"
+    )
+    .is_err());
+    assert!(parse_nft_post_condition(
+        "origin:sent:SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2::BNS-V2"
+    )
+    .is_err());
+}
+
+#[test]
+fn test_describe_tx() {
+    let privk = Secp256k1PrivateKey::random();
+    let sponsor_privk = Secp256k1PrivateKey::random();
+    let tx = make_contract_call(
+        true,
+        &privk,
+        12,
+        345,
+        &asset_info().contract_address,
+        "BNS-V2",
+        "name-claim-fast",
+        &[Value::UInt(1), Value::some(Value::Int(-2)).unwrap()],
+        TransactionPostConditionMode::Deny,
+        vec![TransactionPostCondition::STX(
+            PostConditionPrincipal::Origin,
+            FungibleConditionCode::SentLe,
+            1000,
+        )],
+    )
+    .unwrap();
+
+    let desc = describe_tx(&tx);
+    assert!(desc.contains(&format!("Transaction {}", &tx.txid())));
+    assert!(desc.contains("Version: mainnet"));
+    assert!(desc.contains("Chain ID: 0x00000001"));
+    assert!(desc.contains("Auth: standard"));
+    assert!(desc.contains("Nonce: 12"));
+    assert!(desc.contains("Fee: 345"));
+    assert!(desc.contains("Post-condition mode: deny"));
+    assert!(desc.contains("origin sends at most 1000 uSTX"));
+    assert!(desc.contains(
+        "Payload: contract call SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2::name-claim-fast"
+    ));
+    assert!(desc.contains("Argument 0: u1"));
+    assert!(desc.contains("Argument 1: (some -2)"));
+    assert!(desc.ends_with("Signatures: valid"));
+
+    let origin_signed = make_sponsored_tx(&tx, &privk).unwrap();
+    let desc = describe_tx(&origin_signed);
+    assert!(desc.contains("Auth: sponsored"));
+    assert!(desc.contains("Sponsor: (not yet sponsored)"));
+    assert!(desc.ends_with("Signatures: origin signature valid; awaiting sponsor"));
+
+    let sponsored = sponsor_tx(&origin_signed, &sponsor_privk, 3, 678).unwrap();
+    let desc = describe_tx(&sponsored);
+    assert!(desc.contains("Sponsor: "));
+    assert!(desc.contains("Fee: 678"));
+    assert!(desc.ends_with("Signatures: valid"));
+
+    // tampering breaks the signature
+    let mut tampered = tx.clone();
+    tampered.post_condition_mode = TransactionPostConditionMode::Allow;
+    assert!(describe_tx(&tampered).contains("Signatures: INVALID"));
+}
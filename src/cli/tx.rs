@@ -0,0 +1,1065 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::Write;
+use std::io::stdin;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::core::with_global_config;
+use crate::runner::tx::TxStatus;
+use crate::runner::tx::TX_STATUS_POLL_INTERVAL_MS;
+use crate::tx::multisig::PartiallySignedTransaction;
+use crate::tx::Txid;
+use crate::tx::{make_contract_call, sponsor_tx, StacksTransaction};
+use crate::tx::{
+    AssetInfo, FungibleConditionCode, NonfungibleConditionCode, PostConditionPrincipal,
+    TransactionPayload, TransactionPostCondition, TransactionPostConditionMode,
+};
+use crate::tx::{
+    TransactionAnchorMode, TransactionAuth, TransactionAuthField, TransactionPublicKeyEncoding,
+    TransactionSpendingCondition, TransactionVersion,
+};
+use crate::util::DEFAULT_WRB_CLARITY_VERSION;
+use crate::vm::clarity_vm::vm_execute;
+
+use clarity::vm::types::PrincipalData;
+use clarity::vm::types::QualifiedContractIdentifier;
+use clarity::vm::types::StacksAddressExtensions;
+use clarity::vm::ClarityName;
+use clarity::vm::Value;
+
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::chainstate::StacksPublicKey;
+use stacks_common::util::hash::hex_bytes;
+use stacks_common::util::hash::to_hex;
+use stacks_common::util::hash::Hash160;
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::cli::{
+    consume_arg, consume_multisig, consume_private_key, consume_u64, estimate_tx_fee, finish_tx,
+    load_from_file_or_stdin, load_multisig_tx, make_runner, make_tx, next_nonce, open_nonce_db,
+    origin_tx_fee, post_tx, release_nonce, store_multisig_tx, usage, with_unlocked_config,
+    MultisigExport,
+};
+
+/// Options for `tx call`
+struct TxCallOptions {
+    privkey_opt: Option<Secp256k1PrivateKey>,
+    tx_fee_opt: Option<u64>,
+    dry_run: bool,
+    preview: bool,
+    sponsored: bool,
+    multisig_opt: Option<MultisigExport>,
+}
+
+/// Get the account for the given private key, or the configured one
+fn tx_account(privkey_opt: Option<Secp256k1PrivateKey>) -> (Secp256k1PrivateKey, PrincipalData) {
+    let mainnet = with_global_config(|cfg| cfg.mainnet()).expect("System is not initialized");
+    let privkey =
+        privkey_opt.unwrap_or_else(|| with_unlocked_config(|cfg| cfg.private_key().clone()));
+    let principal = StacksAddress::p2pkh(mainnet, &StacksPublicKey::from_private(&privkey))
+        .to_account_principal();
+    (privkey, principal)
+}
+
+/// tx subcommand to list an account's pending nonces, after reconciling them with the node.
+/// This rebroadcasts every pending transaction.
+fn subcommand_tx_nonces(principal: &PrincipalData) {
+    let mut runner = make_runner();
+    let mut nonce_db = open_nonce_db().unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    let chain_nonce = runner
+        .reconcile_nonces(&mut nonce_db, principal)
+        .unwrap_or_else(|e| {
+            eprintln!(
+                "FATAL: failed to reconcile nonces for {}: {}",
+                principal, &e
+            );
+            process::exit(1);
+        });
+    let pending = nonce_db.get_pending_nonces(principal).unwrap_or_else(|e| {
+        eprintln!("FATAL: failed to load nonces for {}: {:?}", principal, &e);
+        process::exit(1);
+    });
+
+    println!("{}: next confirmed nonce is {}", principal, chain_nonce);
+    for pending_nonce in pending.iter() {
+        match pending_nonce.tx.as_ref() {
+            Some(tx) => println!(
+                "{}\t{}\tfee {}",
+                pending_nonce.nonce,
+                &tx.txid(),
+                tx.get_tx_fee()
+            ),
+            None => println!("{}\t(reserved)", pending_nonce.nonce),
+        }
+    }
+}
+
+/// tx subcommand to replace a pending transaction with one that pays a higher fee
+fn subcommand_tx_replace_by_fee(
+    privkey: &Secp256k1PrivateKey,
+    principal: &PrincipalData,
+    nonce: u64,
+    tx_fee: u64,
+) -> Txid {
+    let mut runner = make_runner();
+    let nonce_db = open_nonce_db().unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    let tx = runner
+        .make_replace_by_fee_tx(&nonce_db, principal, nonce, tx_fee, privkey)
+        .unwrap_or_else(|e| {
+            eprintln!("FATAL: failed to make replacement transaction: {}", &e);
+            process::exit(1);
+        });
+
+    post_tx(&mut runner, &tx).unwrap_or_else(|e| {
+        eprintln!("FATAL: failed to post replacement transaction: {}", &e);
+        process::exit(1);
+    })
+}
+
+/// tx subcommand to report what became of a transaction.
+/// If `wait` is given, then keep asking until it is mined or `wait` passes.
+fn subcommand_tx_status(txid: &Txid, wait: Option<Duration>) -> TxStatus {
+    let mut runner = make_runner();
+    let deadline = wait.map(|wait| Instant::now() + wait);
+    loop {
+        let status = runner
+            .get_tx_status(txid)
+            .map_err(|e| {
+                eprintln!("FATAL: failed to query transaction {}: {}", txid, &e);
+                process::exit(1);
+            })
+            .unwrap();
+
+        if status.is_final() {
+            return status;
+        }
+        let Some(deadline) = deadline else {
+            return status;
+        };
+        if Instant::now() >= deadline {
+            return status;
+        }
+        thread::sleep(Duration::from_millis(TX_STATUS_POLL_INTERVAL_MS));
+    }
+}
+
+/// tx subcommand to add a cosigner's signature to a multisig transaction.
+/// The transaction is written back to `path`.
+fn subcommand_tx_sign(privkey: &Secp256k1PrivateKey, path: &str) -> PartiallySignedTransaction {
+    let mut pst = load_multisig_tx(path).unwrap_or_else(|e| {
+        eprintln!(
+            "FATAL: failed to load multisig transaction from {}: {}",
+            path, &e
+        );
+        process::exit(1);
+    });
+    pst.sign(privkey).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    store_multisig_tx(&pst, path).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    pst
+}
+
+/// tx subcommand to describe a multisig transaction and who still needs to sign it
+fn subcommand_tx_inspect(path: &str) {
+    let pst = load_multisig_tx(path).unwrap_or_else(|e| {
+        eprintln!(
+            "FATAL: failed to load multisig transaction from {}: {}",
+            path, &e
+        );
+        process::exit(1);
+    });
+    let mainnet = with_global_config(|cfg| cfg.mainnet()).expect("System is not initialized");
+
+    println!("Origin: {}", &pst.origin.address(mainnet));
+    println!("Nonce: {}", pst.tx.get_origin_nonce());
+    println!("Fee: {}", pst.tx.get_tx_fee());
+    println!("Signatures: {}", &pst);
+    for (public_key, sig) in pst.origin.public_keys.iter().zip(pst.signatures.iter()) {
+        println!(
+            "{}\t{}",
+            &public_key.to_hex(),
+            if sig.is_some() { "signed" } else { "unsigned" }
+        );
+    }
+    if pst.is_complete() {
+        match pst.to_transaction() {
+            Ok(tx) => println!("Ready to broadcast as {}", &tx.txid()),
+            Err(e) => println!("Not ready to broadcast: {}", &e),
+        }
+    }
+}
+
+/// tx subcommand to post a multisig transaction once enough cosigners have signed it
+fn subcommand_tx_broadcast(path: &str) -> Txid {
+    let pst = load_multisig_tx(path).unwrap_or_else(|e| {
+        eprintln!(
+            "FATAL: failed to load multisig transaction from {}: {}",
+            path, &e
+        );
+        process::exit(1);
+    });
+    let tx = pst.to_transaction().unwrap_or_else(|e| {
+        eprintln!("FATAL: cannot broadcast transaction with {}: {}", &pst, &e);
+        process::exit(1);
+    });
+    if tx.auth.is_sponsored() {
+        eprintln!(
+            "FATAL: transaction is sponsored. The sponsor must post it with `tx sponsor {}`",
+            path
+        );
+        process::exit(1);
+    }
+
+    let mut runner = make_runner();
+    post_tx(&mut runner, &tx).unwrap_or_else(|e| {
+        eprintln!("FATAL: failed to post multisig transaction: {}", &e);
+        process::exit(1);
+    })
+}
+
+/// Decode a post-condition principal: `origin`, a standard address, or a contract ID
+fn parse_post_condition_principal(principal_str: &str) -> Result<PostConditionPrincipal, String> {
+    if principal_str == "origin" {
+        return Ok(PostConditionPrincipal::Origin);
+    }
+    let principal = PrincipalData::parse(principal_str)
+        .map_err(|e| format!("Invalid principal '{}': {:?}", principal_str, &e))?;
+    let principal = match principal {
+        PrincipalData::Standard(sp) => PostConditionPrincipal::Standard(
+            StacksAddress::new(sp.version(), Hash160(sp.1))
+                .map_err(|e| format!("Invalid address '{}': {:?}", principal_str, &e))?,
+        ),
+        PrincipalData::Contract(contract_id) => PostConditionPrincipal::Contract(
+            StacksAddress::new(contract_id.issuer.version(), Hash160(contract_id.issuer.1))
+                .map_err(|e| format!("Invalid address '{}': {:?}", principal_str, &e))?,
+            contract_id.name,
+        ),
+    };
+    Ok(principal)
+}
+
+/// Decode an asset identifier: `ADDRESS.CONTRACT::ASSET`
+fn parse_asset_info(asset_str: &str) -> Result<AssetInfo, String> {
+    let Some((contract_id_str, asset_name_str)) = asset_str.split_once("::") else {
+        return Err(format!(
+            "Invalid asset '{}': expected ADDRESS.CONTRACT::ASSET",
+            asset_str
+        ));
+    };
+    let contract_id = QualifiedContractIdentifier::parse(contract_id_str)
+        .map_err(|e| format!("Invalid contract ID '{}': {:?}", contract_id_str, &e))?;
+    let asset_name = ClarityName::try_from(asset_name_str.to_string())
+        .map_err(|e| format!("Invalid asset name '{}': {:?}", asset_name_str, &e))?;
+    Ok(AssetInfo {
+        contract_address: StacksAddress::new(
+            contract_id.issuer.version(),
+            Hash160(contract_id.issuer.1),
+        )
+        .map_err(|e| format!("Invalid address '{}': {:?}", contract_id_str, &e))?,
+        contract_name: contract_id.name,
+        asset_name,
+    })
+}
+
+fn parse_fungible_condition_code(code_str: &str) -> Result<FungibleConditionCode, String> {
+    match code_str {
+        "eq" => Ok(FungibleConditionCode::SentEq),
+        "gt" => Ok(FungibleConditionCode::SentGt),
+        "ge" => Ok(FungibleConditionCode::SentGe),
+        "lt" => Ok(FungibleConditionCode::SentLt),
+        "le" => Ok(FungibleConditionCode::SentLe),
+        _ => Err(format!(
+            "Invalid condition '{}': expected eq, gt, ge, lt, or le",
+            code_str
+        )),
+    }
+}
+
+fn fungible_condition_code_str(code: &FungibleConditionCode) -> &'static str {
+    match code {
+        FungibleConditionCode::SentEq => "exactly",
+        FungibleConditionCode::SentGt => "more than",
+        FungibleConditionCode::SentGe => "at least",
+        FungibleConditionCode::SentLt => "less than",
+        FungibleConditionCode::SentLe => "at most",
+    }
+}
+
+/// Evaluate a Clarity expression on the command line to a value
+pub fn parse_clarity_arg(arg: &str) -> Result<Value, String> {
+    vm_execute(arg, DEFAULT_WRB_CLARITY_VERSION)
+        .map_err(|e| format!("Could not evaluate '{}': {:?}", arg, &e))?
+        .ok_or_else(|| format!("'{}' does not evaluate to a value", arg))
+}
+
+/// Decode an STX post-condition: `PRINCIPAL:CODE:AMOUNT`, where CODE is one of eq, gt, ge, lt, or
+/// le, and AMOUNT is in microSTX
+pub fn parse_stx_post_condition(pc_str: &str) -> Result<TransactionPostCondition, String> {
+    let parts: Vec<&str> = pc_str.splitn(3, ':').collect();
+    let [principal_str, code_str, amount_str] = parts[..] else {
+        return Err(format!(
+            "Invalid STX post-condition '{}': expected PRINCIPAL:CODE:AMOUNT",
+            pc_str
+        ));
+    };
+    let amount: u64 = amount_str
+        .parse()
+        .map_err(|e| format!("Invalid amount '{}': {:?}", amount_str, &e))?;
+    Ok(TransactionPostCondition::STX(
+        parse_post_condition_principal(principal_str)?,
+        parse_fungible_condition_code(code_str)?,
+        amount,
+    ))
+}
+
+/// Decode a fungible token post-condition: `PRINCIPAL:CODE:AMOUNT:ADDRESS.CONTRACT::TOKEN`
+pub fn parse_ft_post_condition(pc_str: &str) -> Result<TransactionPostCondition, String> {
+    let parts: Vec<&str> = pc_str.splitn(4, ':').collect();
+    let [principal_str, code_str, amount_str, asset_str] = parts[..] else {
+        return Err(format!(
+            "Invalid fungible token post-condition '{}': expected PRINCIPAL:CODE:AMOUNT:ADDRESS.CONTRACT::TOKEN",
+            pc_str
+        ));
+    };
+    let amount: u64 = amount_str
+        .parse()
+        .map_err(|e| format!("Invalid amount '{}': {:?}", amount_str, &e))?;
+    Ok(TransactionPostCondition::Fungible(
+        parse_post_condition_principal(principal_str)?,
+        parse_asset_info(asset_str)?,
+        parse_fungible_condition_code(code_str)?,
+        amount,
+    ))
+}
+
+/// Decode an NFT post-condition: `PRINCIPAL:CODE:ADDRESS.CONTRACT::NFT:VALUE`, where CODE is
+/// `sent` or `not-sent`, and VALUE is the Clarity value identifying the NFT
+pub fn parse_nft_post_condition(pc_str: &str) -> Result<TransactionPostCondition, String> {
+    let parts: Vec<&str> = pc_str.splitn(3, ':').collect();
+    let [principal_str, code_str, rest] = parts[..] else {
+        return Err(format!(
+            "Invalid NFT post-condition '{}': expected PRINCIPAL:CODE:ADDRESS.CONTRACT::NFT:VALUE",
+            pc_str
+        ));
+    };
+    // the asset name ends at the first ':' after the '::'
+    let value_start = rest
+        .find("::")
+        .and_then(|idx| rest[idx + 2..].find(':').map(|value_idx| idx + 2 + value_idx))
+        .ok_or_else(|| {
+            format!(
+                "Invalid NFT post-condition '{}': expected PRINCIPAL:CODE:ADDRESS.CONTRACT::NFT:VALUE",
+                pc_str
+            )
+        })?;
+    let code = match code_str {
+        "sent" => NonfungibleConditionCode::Sent,
+        "not-sent" => NonfungibleConditionCode::NotSent,
+        _ => {
+            return Err(format!(
+                "Invalid condition '{}': expected sent or not-sent",
+                code_str
+            ));
+        }
+    };
+    Ok(TransactionPostCondition::Nonfungible(
+        parse_post_condition_principal(principal_str)?,
+        parse_asset_info(&rest[..value_start])?,
+        parse_clarity_arg(&rest[value_start + 1..])?,
+        code,
+    ))
+}
+
+/// Human-readable form of a post-condition principal
+fn post_condition_principal_str(principal: &PostConditionPrincipal) -> String {
+    match principal {
+        PostConditionPrincipal::Origin => "origin".to_string(),
+        PostConditionPrincipal::Standard(addr) => addr.to_string(),
+        PostConditionPrincipal::Contract(addr, name) => format!("{}.{}", addr, name),
+    }
+}
+
+/// Human-readable form of an asset identifier
+fn asset_info_str(asset_info: &AssetInfo) -> String {
+    format!(
+        "{}.{}::{}",
+        &asset_info.contract_address, &asset_info.contract_name, &asset_info.asset_name
+    )
+}
+
+/// Human-readable form of a post-condition
+pub fn post_condition_str(pc: &TransactionPostCondition) -> String {
+    match pc {
+        TransactionPostCondition::STX(principal, code, amount) => format!(
+            "{} sends {} {} uSTX",
+            post_condition_principal_str(principal),
+            fungible_condition_code_str(code),
+            amount
+        ),
+        TransactionPostCondition::Fungible(principal, asset_info, code, amount) => format!(
+            "{} sends {} {} {}",
+            post_condition_principal_str(principal),
+            fungible_condition_code_str(code),
+            amount,
+            asset_info_str(asset_info)
+        ),
+        TransactionPostCondition::Nonfungible(principal, asset_info, value, code) => format!(
+            "{} {} {} {}",
+            post_condition_principal_str(principal),
+            match code {
+                NonfungibleConditionCode::Sent => "sends",
+                NonfungibleConditionCode::NotSent => "does not send",
+            },
+            asset_info_str(asset_info),
+            value
+        ),
+    }
+}
+
+/// Consume every instance of a repeatable post-condition option
+fn consume_post_conditions<F>(
+    argv: &mut Vec<String>,
+    argnames: &[&str],
+    parser: F,
+) -> Vec<TransactionPostCondition>
+where
+    F: Fn(&str) -> Result<TransactionPostCondition, String>,
+{
+    let mut pcs = vec![];
+    while let Some(pc_str) = consume_arg(argv, argnames, true)
+        .map_err(|e| {
+            usage(&e);
+            unreachable!()
+        })
+        .unwrap()
+    {
+        let pc = parser(&pc_str)
+            .map_err(|e| {
+                usage(&e);
+                unreachable!()
+            })
+            .unwrap();
+        pcs.push(pc);
+    }
+    pcs
+}
+
+/// Show what a contract call will do, and ask whether or not to sign and send it
+fn preview_contract_call(
+    contract_id: &QualifiedContractIdentifier,
+    function_name: &str,
+    function_args: &[Value],
+    sender: &PrincipalData,
+    nonce: u64,
+    fee: u64,
+    post_condition_mode: TransactionPostConditionMode,
+    post_conditions: &[TransactionPostCondition],
+) -> bool {
+    eprintln!("Call {}::{}", contract_id, function_name);
+    for (i, arg) in function_args.iter().enumerate() {
+        eprintln!("  argument {}: {}", i, arg);
+    }
+    eprintln!("Sender: {}", sender);
+    eprintln!("Nonce: {}", nonce);
+    eprintln!("Fee: {}", fee);
+    eprintln!(
+        "Post-condition mode: {}",
+        match post_condition_mode {
+            TransactionPostConditionMode::Allow => "allow (assets not listed below may move)",
+            TransactionPostConditionMode::Deny => "deny (only assets listed below may move)",
+        }
+    );
+    if post_conditions.is_empty() {
+        eprintln!("Post-conditions: (none)");
+    } else {
+        eprintln!("Post-conditions:");
+        for pc in post_conditions.iter() {
+            eprintln!("  {}", &post_condition_str(pc));
+        }
+    }
+
+    eprint!("Sign and send this transaction? [y/N] ");
+    let mut answer = String::new();
+    if stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    let answer = answer.trim().to_lowercase();
+    answer == "y" || answer == "yes"
+}
+
+/// tx subcommand to call a public function in a contract
+fn subcommand_tx_call(
+    contract_id: &QualifiedContractIdentifier,
+    function_name: &str,
+    function_args: &[Value],
+    post_condition_mode: TransactionPostConditionMode,
+    post_conditions: Vec<TransactionPostCondition>,
+    opts: TxCallOptions,
+) -> Option<Txid> {
+    let (privkey, _) = tx_account(opts.privkey_opt);
+    let mainnet = with_global_config(|cfg| cfg.mainnet()).expect("System is not initialized");
+    let sender = match opts.multisig_opt.as_ref() {
+        Some(multisig) => multisig.origin.address(mainnet),
+        None => StacksAddress::p2pkh(mainnet, &StacksPublicKey::from_private(&privkey)),
+    }
+    .to_account_principal();
+    let contract_addr = StacksAddress::new(
+        contract_id.issuer.version(),
+        Hash160(contract_id.issuer.1.clone()),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("FATAL: invalid contract address {}: {:?}", &contract_id, &e);
+        process::exit(1);
+    });
+
+    let mut runner = make_runner();
+    let nonce = next_nonce(&mut runner, &sender).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+
+    let tx_fee_opt = origin_tx_fee(opts.tx_fee_opt, opts.sponsored);
+    let multisig_origin_opt = opts.multisig_opt.as_ref().map(|multisig| &multisig.origin);
+    let make_call_tx = |fee_rate| {
+        make_contract_call(
+            mainnet,
+            &privkey,
+            nonce,
+            fee_rate,
+            &contract_addr,
+            contract_id.name.as_str(),
+            function_name,
+            function_args,
+            post_condition_mode,
+            post_conditions.clone(),
+        )
+        .expect("FATAL: could not make contract-call transaction")
+    };
+    let fee = estimate_tx_fee(&mut runner, tx_fee_opt, multisig_origin_opt, &make_call_tx)
+        .unwrap_or_else(|e| {
+            release_nonce(&sender, nonce);
+            eprintln!(
+                "FATAL: failed to generate contract-call transaction: {}",
+                &e
+            );
+            process::exit(1);
+        });
+
+    // let the user back out before the transaction is signed
+    if opts.preview
+        && !preview_contract_call(
+            contract_id,
+            function_name,
+            function_args,
+            &sender,
+            nonce,
+            fee,
+            post_condition_mode,
+            &post_conditions,
+        )
+    {
+        release_nonce(&sender, nonce);
+        eprintln!("Cancelled");
+        process::exit(1);
+    }
+
+    let tx = make_call_tx(fee);
+
+    finish_tx(
+        &mut runner,
+        tx,
+        &privkey,
+        &sender,
+        nonce,
+        opts.dry_run,
+        opts.sponsored,
+        opts.multisig_opt,
+        "contract-call",
+    )
+}
+
+/// Load a transaction from `path` (or stdin if `path` is "-"), either as hex or as a multisig
+/// transaction with enough signatures
+fn load_tx(path: &str) -> Result<StacksTransaction, String> {
+    let bytes = load_from_file_or_stdin(path);
+    let tx_str = String::from_utf8_lossy(&bytes);
+    let tx_str = tx_str.trim();
+    if tx_str.starts_with('{') {
+        let pst = PartiallySignedTransaction::from_json(tx_str)?;
+        return pst
+            .to_transaction()
+            .map_err(|e| format!("Cannot use transaction with {}: {}", &pst, &e));
+    }
+    let tx_bytes = hex_bytes(tx_str.strip_prefix("0x").unwrap_or(tx_str))
+        .map_err(|e| format!("Failed to decode transaction hex: {:?}", &e))?;
+    StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+        .map_err(|e| format!("Failed to decode transaction: {:?}", &e))
+}
+
+/// Decode a transaction given as hex on the command line, or stored in a file
+fn decode_tx_arg(arg: &str) -> Result<StacksTransaction, String> {
+    if let Ok(tx_bytes) = hex_bytes(arg.strip_prefix("0x").unwrap_or(arg)) {
+        return StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+            .map_err(|e| format!("Failed to decode transaction: {:?}", &e));
+    }
+    load_tx(arg)
+}
+
+fn public_key_encoding_str(encoding: &TransactionPublicKeyEncoding) -> &'static str {
+    match encoding {
+        TransactionPublicKeyEncoding::Compressed => "compressed",
+        TransactionPublicKeyEncoding::Uncompressed => "uncompressed",
+    }
+}
+
+/// Human-readable form of a spending condition
+fn describe_spending_condition(
+    out: &mut String,
+    role: &str,
+    condition: &TransactionSpendingCondition,
+    mainnet: bool,
+) {
+    let (hash_mode, fields, signatures_required) = match condition {
+        TransactionSpendingCondition::Singlesig(cond) => {
+            (format!("{:?}", &cond.hash_mode), None, None)
+        }
+        TransactionSpendingCondition::Multisig(cond) => (
+            format!("{:?} multisig", &cond.hash_mode),
+            Some(&cond.fields),
+            Some(cond.signatures_required),
+        ),
+        TransactionSpendingCondition::OrderIndependentMultisig(cond) => (
+            format!("{:?} order-independent multisig", &cond.hash_mode),
+            Some(&cond.fields),
+            Some(cond.signatures_required),
+        ),
+    };
+    let _ = writeln!(
+        out,
+        "{}: {} ({})",
+        role,
+        &condition.get_address(mainnet),
+        &hash_mode
+    );
+    let _ = writeln!(out, "  Nonce: {}", condition.nonce());
+    let _ = writeln!(out, "  Fee: {}", condition.tx_fee());
+    if let TransactionSpendingCondition::Singlesig(cond) = condition {
+        let _ = writeln!(
+            out,
+            "  Signature: {} ({})",
+            &cond.signature.to_hex(),
+            public_key_encoding_str(&cond.key_encoding)
+        );
+    }
+    if let (Some(fields), Some(signatures_required)) = (fields, signatures_required) {
+        let _ = writeln!(
+            out,
+            "  Signatures: {} of {} required",
+            condition.num_signatures(),
+            signatures_required
+        );
+        for (i, field) in fields.iter().enumerate() {
+            let _ = match field {
+                TransactionAuthField::PublicKey(public_key) => {
+                    writeln!(out, "  Field {}: public key {}", i, &public_key.to_hex())
+                }
+                TransactionAuthField::Signature(encoding, sig) => writeln!(
+                    out,
+                    "  Field {}: signature {} ({})",
+                    i,
+                    &sig.to_hex(),
+                    public_key_encoding_str(encoding)
+                ),
+            };
+        }
+    }
+}
+
+/// Human-readable form of a transaction, including whether or not its signatures are valid
+pub fn describe_tx(tx: &StacksTransaction) -> String {
+    let mainnet = tx.version == TransactionVersion::Mainnet;
+    let mut out = String::new();
+    let _ = writeln!(out, "Transaction {}", &tx.txid());
+    let _ = writeln!(
+        out,
+        "Version: {}",
+        if mainnet { "mainnet" } else { "testnet" }
+    );
+    let _ = writeln!(out, "Chain ID: 0x{:08x}", tx.chain_id);
+    let _ = writeln!(
+        out,
+        "Anchor mode: {}",
+        match tx.anchor_mode {
+            TransactionAnchorMode::OnChainOnly => "on-chain only",
+            TransactionAnchorMode::OffChainOnly => "off-chain only",
+            TransactionAnchorMode::Any => "any",
+        }
+    );
+
+    match &tx.auth {
+        TransactionAuth::Standard(origin) => {
+            let _ = writeln!(out, "Auth: standard");
+            describe_spending_condition(&mut out, "Origin", origin, mainnet);
+        }
+        TransactionAuth::Sponsored(origin, sponsor) => {
+            let _ = writeln!(out, "Auth: sponsored");
+            describe_spending_condition(&mut out, "Origin", origin, mainnet);
+            if sponsor == &TransactionSpendingCondition::new_initial_sighash() {
+                let _ = writeln!(out, "Sponsor: (not yet sponsored)");
+            } else {
+                describe_spending_condition(&mut out, "Sponsor", sponsor, mainnet);
+            }
+        }
+    }
+    let _ = writeln!(out, "Fee: {}", tx.get_tx_fee());
+
+    let _ = writeln!(
+        out,
+        "Post-condition mode: {}",
+        match tx.post_condition_mode {
+            TransactionPostConditionMode::Allow => "allow",
+            TransactionPostConditionMode::Deny => "deny",
+        }
+    );
+    if tx.post_conditions.is_empty() {
+        let _ = writeln!(out, "Post-conditions: (none)");
+    } else {
+        let _ = writeln!(out, "Post-conditions:");
+        for pc in tx.post_conditions.iter() {
+            let _ = writeln!(out, "  {}", &post_condition_str(pc));
+        }
+    }
+
+    match &tx.payload {
+        TransactionPayload::TokenTransfer(recipient, amount, memo) => {
+            let _ = writeln!(out, "Payload: token transfer");
+            let _ = writeln!(out, "  Recipient: {}", recipient);
+            let _ = writeln!(out, "  Amount: {} uSTX", amount);
+            let _ = writeln!(out, "  Memo: {}", &to_hex(&memo.0));
+        }
+        TransactionPayload::ContractCall(call) => {
+            let _ = writeln!(
+                out,
+                "Payload: contract call {}.{}::{}",
+                &call.address, &call.contract_name, &call.function_name
+            );
+            for (i, arg) in call.function_args.iter().enumerate() {
+                let _ = writeln!(out, "  Argument {}: {}", i, arg);
+            }
+        }
+        TransactionPayload::SmartContract(contract, version_opt) => {
+            let _ = writeln!(
+                out,
+                "Payload: smart contract {}{}",
+                &contract.name,
+                version_opt
+                    .map(|version| format!(" ({})", version))
+                    .unwrap_or("".to_string())
+            );
+            let _ = writeln!(out, "{}", &contract.code_body);
+        }
+    }
+
+    let sponsor_pending = match &tx.auth {
+        TransactionAuth::Sponsored(_, sponsor) => {
+            sponsor == &TransactionSpendingCondition::new_initial_sighash()
+        }
+        TransactionAuth::Standard(_) => false,
+    };
+    let verification = if sponsor_pending {
+        tx.verify_origin()
+            .map(|_| "origin signature valid; awaiting sponsor".to_string())
+    } else {
+        tx.verify().map(|_| "valid".to_string())
+    };
+    let _ = write!(
+        out,
+        "Signatures: {}",
+        verification.unwrap_or_else(|e| format!("INVALID ({})", &e))
+    );
+    out
+}
+
+/// tx subcommand to pay the fee for someone else's sponsored transaction, and post it
+fn subcommand_tx_sponsor(
+    privkey: &Secp256k1PrivateKey,
+    principal: &PrincipalData,
+    path: &str,
+    tx_fee_opt: Option<u64>,
+) -> Txid {
+    let tx = load_tx(path).unwrap_or_else(|e| {
+        eprintln!("FATAL: failed to load transaction from {}: {}", path, &e);
+        process::exit(1);
+    });
+    if !tx.auth.is_sponsored() {
+        eprintln!("FATAL: transaction {} is not sponsored", &tx.txid());
+        process::exit(1);
+    }
+    if let Err(e) = tx.verify_origin() {
+        eprintln!(
+            "FATAL: transaction {} has a bad origin signature: {}",
+            &tx.txid(),
+            &e
+        );
+        process::exit(1);
+    }
+
+    let mut runner = make_runner();
+    let nonce = next_nonce(&mut runner, principal).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
+    let sponsored_tx = make_tx(&mut runner, tx_fee_opt, None, |fee_rate| {
+        sponsor_tx(&tx, privkey, nonce, fee_rate).expect("FATAL: could not sponsor transaction")
+    })
+    .unwrap_or_else(|e| {
+        release_nonce(principal, nonce);
+        eprintln!("FATAL: failed to generate sponsored transaction: {}", &e);
+        process::exit(1);
+    });
+
+    post_tx(&mut runner, &sponsored_tx).unwrap_or_else(|e| {
+        eprintln!("FATAL: failed to post sponsored transaction: {}", &e);
+        process::exit(1);
+    })
+}
+
+pub fn subcommand_tx(mut argv: Vec<String>) {
+    if argv.len() < 3 {
+        eprintln!("Usage: {} tx [subcommand] [options]", &argv[0]);
+        process::exit(1);
+    }
+    let cmd = argv[2].clone();
+    if cmd == "status" {
+        let wait_secs = consume_u64(&mut argv, &["-w", "--wait"]);
+        if argv.len() < 4 {
+            eprintln!("Usage: {} tx {} [-w|--wait SECONDS] TXID", &argv[0], &cmd);
+            process::exit(1);
+        }
+        let txid_str = argv[3].clone();
+        let txid = Txid::from_hex(txid_str.strip_prefix("0x").unwrap_or(&txid_str))
+            .map_err(|e| {
+                eprintln!("FATAL: could not decode txid '{}': {:?}", &txid_str, &e);
+                process::exit(1);
+            })
+            .unwrap();
+
+        let status = subcommand_tx_status(&txid, wait_secs.map(Duration::from_secs));
+        println!("{}", &status);
+        match status {
+            TxStatus::Mined(_) => {
+                return;
+            }
+            _ => {
+                process::exit(1);
+            }
+        }
+    } else if cmd == "nonces" {
+        let privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
+        let principal = if argv.len() >= 4 {
+            PrincipalData::parse(&argv[3])
+                .map_err(|e| {
+                    eprintln!("FATAL: could not decode address '{}': {:?}", &argv[3], &e);
+                    process::exit(1);
+                })
+                .unwrap()
+        } else {
+            tx_account(privkey_opt).1
+        };
+        subcommand_tx_nonces(&principal);
+        return;
+    } else if cmd == "replace-by-fee" {
+        let privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
+        let tx_fee_opt = consume_u64(&mut argv, &["-f", "--fee"]);
+        if argv.len() < 4 {
+            eprintln!(
+                "Usage: {} tx {} [-k|--private-key KEY] -f|--fee FEE NONCE",
+                &argv[0], &cmd
+            );
+            process::exit(1);
+        }
+        let Some(tx_fee) = tx_fee_opt else {
+            eprintln!("FATAL: a new fee is required (pass -f or --fee)");
+            process::exit(1);
+        };
+        let nonce: u64 = argv[3]
+            .parse()
+            .map_err(|e| {
+                eprintln!("FATAL: could not decode nonce '{}': {:?}", &argv[3], &e);
+                process::exit(1);
+            })
+            .unwrap();
+
+        let (privkey, principal) = tx_account(privkey_opt);
+        let txid = subcommand_tx_replace_by_fee(&privkey, &principal, nonce, tx_fee);
+        println!("{}", &txid);
+        return;
+    } else if cmd == "sign" {
+        let privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
+        if argv.len() < 4 {
+            eprintln!(
+                "Usage: {} tx {} [-k|--private-key KEY] PATH",
+                &argv[0], &cmd
+            );
+            process::exit(1);
+        }
+        let (privkey, _) = tx_account(privkey_opt);
+        let pst = subcommand_tx_sign(&privkey, &argv[3]);
+        if argv[3] != "-" {
+            eprintln!("{}", &pst);
+        }
+        return;
+    } else if cmd == "inspect" {
+        if argv.len() < 4 {
+            eprintln!("Usage: {} tx {} PATH", &argv[0], &cmd);
+            process::exit(1);
+        }
+        subcommand_tx_inspect(&argv[3]);
+        return;
+    } else if cmd == "broadcast" {
+        if argv.len() < 4 {
+            eprintln!("Usage: {} tx {} PATH", &argv[0], &cmd);
+            process::exit(1);
+        }
+        let txid = subcommand_tx_broadcast(&argv[3]);
+        println!("{}", &txid);
+        return;
+    } else if cmd == "call" {
+        let flag = |argv: &mut Vec<String>, argnames: &[&str]| {
+            consume_arg(argv, argnames, false)
+                .map_err(|e| {
+                    usage(&e);
+                    unreachable!()
+                })
+                .unwrap()
+                .is_some()
+        };
+        let dry_run = flag(&mut argv, &["-n", "--dry-run"]);
+        let preview = flag(&mut argv, &["-p", "--preview"]);
+        let sponsored = flag(&mut argv, &["-s", "--sponsored"]);
+        let allow = flag(&mut argv, &["--allow"]);
+        let deny = flag(&mut argv, &["--deny"]);
+        if allow && deny {
+            usage("--allow and --deny are mutually exclusive");
+        }
+        let privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
+        let tx_fee_opt = consume_u64(&mut argv, &["-f", "--fee"]);
+        let multisig_opt = consume_multisig(&mut argv);
+        let mut post_conditions =
+            consume_post_conditions(&mut argv, &["--stx"], parse_stx_post_condition);
+        post_conditions.append(&mut consume_post_conditions(
+            &mut argv,
+            &["--ft"],
+            parse_ft_post_condition,
+        ));
+        post_conditions.append(&mut consume_post_conditions(
+            &mut argv,
+            &["--nft"],
+            parse_nft_post_condition,
+        ));
+
+        if argv.len() < 5 {
+            eprintln!("Usage: {} tx {} [-k|--private-key KEY] [-f|--fee FEE] [-n|--dry-run] [-p|--preview] [-s|--sponsored] [--multisig M:PUBKEY,... [--multisig-mode MODE] [-o|--export PATH]] [--allow|--deny] [--stx PRINCIPAL:CODE:AMOUNT]... [--ft PRINCIPAL:CODE:AMOUNT:ADDRESS.CONTRACT::TOKEN]... [--nft PRINCIPAL:sent|not-sent:ADDRESS.CONTRACT::NFT:VALUE]... CONTRACT_ID FUNCTION [ARG...]", &argv[0], &cmd);
+            eprintln!("PRINCIPAL is `origin`, an address, or a contract ID.  CODE is one of eq, gt, ge, lt, or le.");
+            eprintln!("Each ARG and NFT VALUE is a Clarity expression, such as u1 or 'SP000000000000000000002Q6VF78");
+            process::exit(1);
+        }
+        let contract_id = QualifiedContractIdentifier::parse(&argv[3]).unwrap_or_else(|e| {
+            eprintln!("FATAL: invalid contract ID '{}': {:?}", &argv[3], &e);
+            process::exit(1);
+        });
+        let function_name = argv[4].clone();
+        let function_args: Vec<Value> = argv[5..]
+            .iter()
+            .map(|arg| {
+                parse_clarity_arg(arg).unwrap_or_else(|e| {
+                    eprintln!("FATAL: {}", &e);
+                    process::exit(1);
+                })
+            })
+            .collect();
+
+        // deny is the safe default
+        let post_condition_mode = if allow {
+            TransactionPostConditionMode::Allow
+        } else {
+            TransactionPostConditionMode::Deny
+        };
+        let txid_opt = subcommand_tx_call(
+            &contract_id,
+            &function_name,
+            &function_args,
+            post_condition_mode,
+            post_conditions,
+            TxCallOptions {
+                privkey_opt,
+                tx_fee_opt,
+                dry_run,
+                preview,
+                sponsored,
+                multisig_opt,
+            },
+        );
+        if let Some(txid) = txid_opt {
+            println!("{}", &txid);
+        }
+        return;
+    } else if cmd == "decode" {
+        if argv.len() < 4 {
+            eprintln!("Usage: {} tx {} HEX|PATH", &argv[0], &cmd);
+            process::exit(1);
+        }
+        let tx = decode_tx_arg(&argv[3]).unwrap_or_else(|e| {
+            eprintln!("FATAL: {}", &e);
+            process::exit(1);
+        });
+        println!("{}", &describe_tx(&tx));
+        return;
+    } else if cmd == "sponsor" {
+        let privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
+        let tx_fee_opt = consume_u64(&mut argv, &["-f", "--fee"]);
+        if argv.len() < 4 {
+            eprintln!(
+                "Usage: {} tx {} [-k|--private-key KEY] [-f|--fee FEE] PATH",
+                &argv[0], &cmd
+            );
+            process::exit(1);
+        }
+        let (privkey, principal) = tx_account(privkey_opt);
+        let txid = subcommand_tx_sponsor(&privkey, &principal, &argv[3], tx_fee_opt);
+        println!("{}", &txid);
+        return;
+    }
+
+    eprintln!("Unrecognized `tx` command '{}'", &cmd);
+    process::exit(1);
+}
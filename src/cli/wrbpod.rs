@@ -53,6 +53,7 @@ use crate::storage::WrbpodSlices;
 use crate::storage::WrbpodSuperblock;
 
 use crate::core::globals::redirect_logfile;
+use crate::core::unlock_identity;
 use crate::core::with_global_config;
 use crate::core::with_globals;
 
@@ -88,9 +89,10 @@ use libstackerdb::StackerDBChunkAckData;
 use libstackerdb::StackerDBChunkData;
 
 use crate::cli::{
-    consume_arg, consume_private_key, consume_u64, load_from_file_or_stdin, load_wrbsite_source,
-    make_runner, make_tx, open_home_stackerdb_session, open_replica_stackerdb_session, post_tx,
-    split_fqn, usage, wrbsite_load_code_bytes,
+    consume_arg, consume_multisig, consume_private_key, consume_u64, finish_tx,
+    load_from_file_or_stdin, load_wrbsite_source, make_runner, make_tx, next_nonce,
+    open_home_stackerdb_session, open_replica_stackerdb_session, origin_tx_fee, release_nonce,
+    split_fqn, usage, with_unlocked_config, wrbsite_load_code_bytes, MultisigExport,
 };
 
 fn make_wrbpod_code(num_slots: u16, chunk_size: u32, write_freq: u32) -> String {
@@ -133,9 +135,10 @@ fn wrbpod_format_session(wrbpod_addr: &WrbpodAddress) -> Result<(), String> {
         return Ok(());
     }
 
-    // is this an owned wrbpod? only true if the client's identity private key matches the target
+    // is this an owned wrbpod? only true if the client's wrbpod private key matches the target
     // contract.
-    let privkey = with_global_config(|cfg| cfg.private_key().clone())
+    unlock_identity()?;
+    let privkey = with_global_config(|cfg| cfg.wrbpod_private_key())
         .ok_or("System is not initialized".to_string())?;
 
     // go set up the wrbpod session
@@ -150,7 +153,7 @@ fn wrbpod_format_session(wrbpod_addr: &WrbpodAddress) -> Result<(), String> {
         })?;
 
     let replica_stackerdb_client = runner
-        .get_replica_stackerdb_client(wrbpod_addr.contract.clone(), privkey.clone())
+        .get_writable_replica_stackerdb_client(wrbpod_addr.contract.clone(), privkey.clone())
         .map_err(|e| {
             format!(
                 "Failed to instantiate StackerDB client to {}: {:?}",
@@ -190,9 +193,10 @@ pub fn wrbpod_open_session(wrbpod_addr: &WrbpodAddress) -> Result<(), String> {
         return Ok(());
     }
 
-    // is this an owned wrbpod? only true if the client's identity private key matches the target
+    // is this an owned wrbpod? only true if the client's wrbpod private key matches the target
     // contract.
-    let privkey = with_global_config(|cfg| cfg.private_key().clone())
+    unlock_identity()?;
+    let privkey = with_global_config(|cfg| cfg.wrbpod_private_key())
         .ok_or("System is not initialized".to_string())?;
 
     // go set up the wrbpod session
@@ -207,7 +211,7 @@ pub fn wrbpod_open_session(wrbpod_addr: &WrbpodAddress) -> Result<(), String> {
         })?;
 
     let replica_stackerdb_client = runner
-        .get_replica_stackerdb_client(wrbpod_addr.contract.clone(), privkey.clone())
+        .get_writable_replica_stackerdb_client(wrbpod_addr.contract.clone(), privkey.clone())
         .map_err(|e| {
             format!(
                 "Failed to instantiate StackerDB client to {}: {:?}",
@@ -313,7 +317,7 @@ fn wrbpod_alloc_slots(
 ) -> bool {
     let (bytes, _) = load_wrbsite_source(&app_name, wrbsite_data_source_opt)
         .map_err(|e| {
-            usage(&e);
+            usage(&e.to_string());
             unreachable!()
         })
         .unwrap();
@@ -557,29 +561,35 @@ fn wrbpod_deploy(
     dry_run: bool,
     tx_fee_opt: Option<u64>,
     privkey_opt: Option<Secp256k1PrivateKey>,
+    sponsored: bool,
+    multisig_opt: Option<MultisigExport>,
 ) -> Option<Txid> {
-    let (privkey, mainnet) = with_global_config(|cfg| (cfg.private_key().clone(), cfg.mainnet()))
-        .expect("System is not initialized");
-
-    let privkey = privkey_opt.unwrap_or(privkey);
+    // the wrbpod is owned by the wrbpod key, so it deploys the wrbpod by default
+    let mainnet = with_global_config(|cfg| cfg.mainnet()).expect("System is not initialized");
+    let privkey =
+        privkey_opt.unwrap_or_else(|| with_unlocked_config(|cfg| cfg.wrbpod_private_key()));
 
     let code = make_wrbpod_code(num_slots, slot_size, write_freq);
     let mut runner = make_runner();
 
-    let stacks_addr = StacksAddress::p2pkh(mainnet, &StacksPublicKey::from_private(&privkey));
+    let stacks_addr = match multisig_opt.as_ref() {
+        Some(multisig) => multisig.origin.address(mainnet),
+        None => StacksAddress::p2pkh(mainnet, &StacksPublicKey::from_private(&privkey)),
+    };
     let principal =
         StandardPrincipalData::new(stacks_addr.version(), stacks_addr.bytes().clone().0).unwrap();
-    let account = runner
-        .get_account(&principal.clone().into())
-        .unwrap_or_else(|e| {
-            panic!("FATAL: failed to look up account {}: {:?}", &principal, &e);
-        });
+    let nonce = next_nonce(&mut runner, &principal.clone().into()).unwrap_or_else(|e| {
+        eprintln!("FATAL: {}", &e);
+        process::exit(1);
+    });
 
-    let tx = make_tx(&mut runner, tx_fee_opt, |fee_rate| {
+    let tx_fee_opt = origin_tx_fee(tx_fee_opt, sponsored);
+    let multisig_origin_opt = multisig_opt.as_ref().map(|multisig| &multisig.origin);
+    let tx = make_tx(&mut runner, tx_fee_opt, multisig_origin_opt, |fee_rate| {
         make_contract_publish(
             mainnet,
             &privkey,
-            account.nonce,
+            nonce,
             fee_rate,
             name,
             &code,
@@ -589,22 +599,22 @@ fn wrbpod_deploy(
         .expect("FATAL: could not make wrbpod transaction")
     })
     .unwrap_or_else(|e| {
+        release_nonce(&principal.clone().into(), nonce);
         eprintln!("FATAL: failed to generate wrbpod transaction: {}", &e);
         process::exit(1);
     });
 
-    if dry_run {
-        println!("{}", &to_hex(&tx.serialize_to_vec()));
-        return None;
-    }
-
-    let txid = post_tx(&mut runner, &tx).unwrap_or_else(|e| {
-        wrb_debug!("{}", &to_hex(&tx.serialize_to_vec()));
-        eprintln!("FATAL: failed to post wrbpod transaction: {}", &e);
-        process::exit(1);
-    });
-
-    Some(txid)
+    finish_tx(
+        &mut runner,
+        tx,
+        &privkey,
+        &principal.clone().into(),
+        nonce,
+        dry_run,
+        sponsored,
+        multisig_opt,
+        "wrbpod",
+    )
 }
 
 /// Instantiate a mocked stackerdb
@@ -938,7 +948,7 @@ pub fn subcommand_wrbpod(mut argv: Vec<String>, wrbsite_data_source_opt: Option<
         return;
     } else if cmd == "deploy" {
         if argv.len() < 6 {
-            eprintln!("Usage: {} wrbpod {} [-n|--dry-run] [-k|--private-key KEY] [-f|--fee FEE] CONTRACT_NAME SLOT_SIZE NUM_SLOTS [WRITE_FREQ]", &argv[0], &cmd);
+            eprintln!("Usage: {} wrbpod {} [-n|--dry-run] [-k|--private-key KEY] [-f|--fee FEE] [-s|--sponsored] [--multisig M:PUBKEY,... [--multisig-mode MODE] [-o|--export PATH]] CONTRACT_NAME SLOT_SIZE NUM_SLOTS [WRITE_FREQ]", &argv[0], &cmd);
             process::exit(1);
         }
         let dry_run = consume_arg(&mut argv, &["-n", "--dry-run"], false)
@@ -950,6 +960,16 @@ pub fn subcommand_wrbpod(mut argv: Vec<String>, wrbsite_data_source_opt: Option<
 
         let privkey_opt = consume_private_key(&mut argv, &["-k", "--private-key"]);
         let tx_fee_opt = consume_u64(&mut argv, &["-f", "--fee"]);
+        let sponsored = consume_arg(&mut argv, &["-s", "--sponsored"], false)
+            .map_err(|e| {
+                usage(&e);
+                unreachable!()
+            })
+            .unwrap()
+            .is_some();
+        let multisig_opt = consume_multisig(&mut argv);
+        // these transactions are printed or saved instead of posted, so there's no txid
+        let exporting = dry_run.is_some() || sponsored || multisig_opt.is_some();
 
         let contract_name = argv[3].clone();
         let num_slots = argv[4]
@@ -982,8 +1002,13 @@ pub fn subcommand_wrbpod(mut argv: Vec<String>, wrbsite_data_source_opt: Option<
             dry_run.is_some(),
             tx_fee_opt,
             privkey_opt,
+            sponsored,
+            multisig_opt,
         );
         let Some(txid) = txid_opt else {
+            if exporting {
+                return;
+            }
             eprintln!("Contract already exists");
             process::exit(1);
         };
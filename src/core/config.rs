@@ -16,17 +16,29 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use clarity::vm::types::QualifiedContractIdentifier;
+use stacks_common::util::hash::Sha512Trunc256Sum;
 use stacks_common::util::secp256k1::Secp256k1PrivateKey;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use serde::Deserialize;
 use serde::Serialize;
 use toml;
 
+use crate::core::identity::Identity;
+use crate::core::keystore::{Keystore, KeystoreSecret, DEFAULT_SCRYPT_LOG_N};
+use crate::runner::http::{
+    RPCTimeouts, DEFAULT_CONNECT_TIMEOUT_MS, DEFAULT_READ_TIMEOUT_MS, DEFAULT_RPC_MAX_RETRIES,
+    DEFAULT_RPC_RETRY_BACKOFF_MS,
+};
+use crate::runner::proxy::ProxyConfig;
+use crate::runner::tx::DEFAULT_TX_CONFIRM_TIMEOUT_SECS;
+use crate::runner::{DEFAULT_MAX_PARALLEL_REPLICAS, DEFAULT_REPLICA_TIMEOUT_MS};
 use crate::storage::WrbpodAddress;
+use crate::ui::scanline::ColorDepth;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Config {
@@ -36,21 +48,124 @@ pub struct Config {
     node_host: String,
     /// node port
     node_port: u16,
-    /// identity key
-    private_key: Secp256k1PrivateKey,
+    /// nodes to fail over to, in order of preference
+    fallback_nodes: Vec<(String, u16)>,
+    /// the user's identities.  The first one is the default identity.
+    identities: Vec<ConfigIdentity>,
+    /// index into `identities` of the identity we're running as
+    active_identity: usize,
     /// location where we store Wrb DBs
     /// (relative or absolute)
     storage: String,
     /// location of the debug file
     debug_path: String,
-    /// our wrbpod
-    wrbpod: WrbpodAddress,
     /// Path to mocked stackerdb databases
     mock_stackerdb_paths: HashMap<QualifiedContractIdentifier, String>,
+    /// wrbsite names pinned to specific code hashes
+    pinned_sites: HashMap<String, Sha512Trunc256Sum>,
+    /// how long to wait for replicas to serve a wrbsite chunk
+    replica_timeout_ms: u64,
+    /// how many replicas to query at once for a wrbsite chunk
+    max_parallel_replicas: u64,
+    /// socket timeouts and retry policy for node RPC
+    rpc_timeouts: RPCTimeouts,
+    /// proxy for all outbound connections
+    proxy: Option<ProxyConfig>,
+    /// how long to wait for a broadcast transaction to be mined
+    tx_confirm_timeout_secs: u64,
+    /// how many colors the terminal can draw.  None means detect it from the environment.
+    color_depth: Option<ColorDepth>,
     /// Path from which we loaded this
     __path: String,
 }
 
+/// Name of the identity given by the top-level `private_key`, `mnemonic`, or `keystore`
+pub const DEFAULT_IDENTITY_NAME: &str = "default";
+
+/// One of the user's personas: its keys, and the wrbpod it uses
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIdentity {
+    /// name of this identity, for `--identity`
+    name: String,
+    /// root identity key.  None if it's in a keystore that hasn't been unlocked yet.
+    private_key: Option<Secp256k1PrivateKey>,
+    /// mnemonic from which the identity key and its wrbpod and per-site keys are derived.
+    /// None if the identity is a lone private key, or is in a locked keystore.
+    mnemonic: Option<Identity>,
+    /// encrypted keystore holding the identity, if it's not given inline
+    keystore: Option<String>,
+    /// this identity's wrbpod
+    wrbpod: WrbpodAddress,
+}
+
+impl ConfigIdentity {
+    fn from_parts(
+        name: String,
+        private_key: Option<String>,
+        mnemonic: Option<String>,
+        keystore: Option<String>,
+        wrbpod: &str,
+    ) -> Result<Self, String> {
+        let wrbpod = WrbpodAddress::parse(wrbpod).ok_or_else(|| {
+            format!(
+                "Failed to decode '{}': expected <addr>.<name>/<slot-id>",
+                wrbpod
+            )
+        })?;
+
+        let (private_key, mnemonic) = match (private_key, mnemonic, keystore.as_ref()) {
+            (Some(private_key_hex), None, None) => (
+                Some(
+                    Secp256k1PrivateKey::from_hex(&private_key_hex)
+                        .map_err(|e| format!("Failed to parse `private_key`: {:?}", &e))?,
+                ),
+                None,
+            ),
+            (None, Some(phrase), None) => {
+                let identity = Identity::from_mnemonic(&phrase)
+                    .map_err(|e| format!("Failed to parse `mnemonic`: {}", &e))?;
+                (Some(identity.root_key()), Some(identity))
+            }
+            // loaded by unlock_keystore()
+            (None, None, Some(_)) => (None, None),
+            (None, None, None) => {
+                return Err("One of `private_key`, `mnemonic`, or `keystore` must be given".into());
+            }
+            _ => {
+                return Err(
+                    "Only one of `private_key`, `mnemonic`, and `keystore` may be given".into(),
+                );
+            }
+        };
+
+        Ok(Self {
+            name,
+            private_key,
+            mnemonic,
+            keystore,
+            wrbpod,
+        })
+    }
+
+    /// Encode as (private_key, mnemonic, keystore, wrbpod) config file fields.
+    /// Only one of the first three is set.
+    fn into_parts(self) -> (Option<String>, Option<String>, Option<String>, String) {
+        let wrbpod = self.wrbpod.to_string();
+        if self.keystore.is_some() {
+            return (None, None, self.keystore, wrbpod);
+        }
+        if let Some(identity) = self.mnemonic {
+            return (None, Some(identity.mnemonic()), None, wrbpod);
+        }
+        (
+            self.private_key.map(|privk| privk.to_hex()),
+            None,
+            None,
+            wrbpod,
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ConfigFileMockStackerDB {
     /// address to mock
@@ -59,6 +174,37 @@ pub struct ConfigFileMockStackerDB {
     path: String,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ConfigFileNode {
+    /// node host
+    host: String,
+    /// node port
+    port: u16,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigFilePinnedSite {
+    /// fully-qualified name of the wrbsite
+    name: String,
+    /// hex-encoded hash of the wrbsite's StackerDB chunk (or its manifest chunk, if it spans
+    /// several chunks)
+    code_hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigFileIdentity {
+    /// name of the identity, for `--identity`
+    name: String,
+    /// identity key, as plaintext hex.  Prefer `keystore`.
+    private_key: Option<String>,
+    /// identity mnemonic, as plaintext BIP39 words.  Prefer `keystore`.
+    mnemonic: Option<String>,
+    /// path to an encrypted keystore holding the identity
+    keystore: Option<String>,
+    /// this identity's wrbpod, encoded as <address>.<name>/<slot-id>
+    wrbpod: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ConfigFile {
     /// mainnet or testnet
@@ -67,8 +213,14 @@ pub struct ConfigFile {
     node_host: String,
     /// node port
     node_port: u16,
-    /// identity key
-    private_key: String,
+    /// nodes to fail over to if the main node is unreachable, in order of preference
+    fallback_node: Option<Vec<ConfigFileNode>>,
+    /// identity key, as plaintext hex.  Prefer `keystore`.
+    private_key: Option<String>,
+    /// identity mnemonic, as plaintext BIP39 words.  Prefer `keystore`.
+    mnemonic: Option<String>,
+    /// path to an encrypted keystore holding the identity (created with `wrb key create`)
+    keystore: Option<String>,
     /// location where we store Wrb DBs
     /// (relative or absolute)
     storage: Option<String>,
@@ -76,8 +228,33 @@ pub struct ConfigFile {
     debug_path: Option<String>,
     /// our wrbpod, encoded as <address>.<name>/<slot-id>
     wrbpod: String,
+    /// other identities, besides the one given above
+    identity: Option<Vec<ConfigFileIdentity>>,
     /// Path to mocked stackerdb databases
     mocked_stackerdb: Option<Vec<ConfigFileMockStackerDB>>,
+    /// wrbsites pinned to specific code hashes
+    pinned_site: Option<Vec<ConfigFilePinnedSite>>,
+    /// how long to wait for replicas to serve a wrbsite chunk, in milliseconds
+    replica_timeout_ms: Option<u64>,
+    /// how many replicas to query at once for a wrbsite chunk
+    max_parallel_replicas: Option<u64>,
+    /// how long to wait for a connection to a node, in milliseconds
+    connect_timeout_ms: Option<u64>,
+    /// how long to wait on a read from or write to a node, in milliseconds
+    read_timeout_ms: Option<u64>,
+    /// how many times to retry idempotent node RPCs
+    rpc_max_retries: Option<u32>,
+    /// how long to wait before the first RPC retry, in milliseconds.  Doubles on each retry.
+    rpc_retry_backoff_ms: Option<u64>,
+    /// proxy for all outbound connections, as a URL: `socks5://host:port` (resolve host names
+    /// locally), `socks5h://host:port` (resolve host names at the proxy), or `http://host:port`
+    /// (HTTP CONNECT)
+    proxy: Option<String>,
+    /// how long to wait for a broadcast transaction to be mined, in seconds
+    tx_confirm_timeout_secs: Option<u64>,
+    /// how many colors the terminal can draw: `truecolor`, `256`, `16`, or `mono`.  If not
+    /// given, it's guessed from `NO_COLOR`, `COLORTERM`, and `TERM`.
+    color_depth: Option<String>,
 }
 
 impl ConfigFile {
@@ -90,12 +267,29 @@ impl ConfigFile {
 impl TryFrom<ConfigFile> for Config {
     type Error = String;
     fn try_from(config_file: ConfigFile) -> Result<Self, Self::Error> {
-        let default_wrbpod = WrbpodAddress::parse(&config_file.wrbpod).ok_or_else(|| {
-            format!(
-                "Failed to decode '{}': expected <addr>.<name>/<slot-id>",
-                &config_file.wrbpod
-            )
-        })?;
+        let mut identities = vec![ConfigIdentity::from_parts(
+            DEFAULT_IDENTITY_NAME.into(),
+            config_file.private_key,
+            config_file.mnemonic,
+            config_file.keystore,
+            &config_file.wrbpod,
+        )?];
+        for identity in config_file.identity.unwrap_or_default().into_iter() {
+            if identities.iter().any(|other| other.name == identity.name) {
+                return Err(format!("Duplicate identity '{}'", &identity.name));
+            }
+            let name = identity.name.clone();
+            identities.push(
+                ConfigIdentity::from_parts(
+                    identity.name,
+                    identity.private_key,
+                    identity.mnemonic,
+                    identity.keystore,
+                    &identity.wrbpod,
+                )
+                .map_err(|e| format!("Invalid identity '{}': {}", &name, &e))?,
+            );
+        }
 
         let mut mock_stackerdb_paths: HashMap<QualifiedContractIdentifier, String> = HashMap::new();
         if let Some(mocked_stackerdbs) = config_file.mocked_stackerdb {
@@ -111,16 +305,73 @@ impl TryFrom<ConfigFile> for Config {
             }
         }
 
+        let mut pinned_sites = HashMap::new();
+        if let Some(pinned_site_list) = config_file.pinned_site {
+            for pinned_site in pinned_site_list.iter() {
+                let code_hash =
+                    Sha512Trunc256Sum::from_hex(&pinned_site.code_hash).map_err(|e| {
+                        format!(
+                            "Failed to decode code hash '{}' for '{}': {:?}",
+                            &pinned_site.code_hash, &pinned_site.name, &e
+                        )
+                    })?;
+                pinned_sites.insert(pinned_site.name.clone(), code_hash);
+            }
+        }
+
         Ok(Config {
             mainnet: config_file.mainnet,
             node_host: config_file.node_host,
             node_port: config_file.node_port,
-            private_key: Secp256k1PrivateKey::from_hex(&config_file.private_key)
-                .map_err(|e| format!("Failed to parse `private_key`: {:?}", &e))?,
+            fallback_nodes: config_file
+                .fallback_node
+                .unwrap_or_default()
+                .into_iter()
+                .map(|node| (node.host, node.port))
+                .collect(),
+            identities,
+            active_identity: 0,
             storage: config_file.storage.unwrap_or("./db".into()),
             debug_path: config_file.debug_path.unwrap_or("./debug.log".into()),
-            wrbpod: default_wrbpod,
             mock_stackerdb_paths,
+            pinned_sites,
+            replica_timeout_ms: config_file
+                .replica_timeout_ms
+                .unwrap_or(DEFAULT_REPLICA_TIMEOUT_MS),
+            max_parallel_replicas: config_file
+                .max_parallel_replicas
+                .unwrap_or(DEFAULT_MAX_PARALLEL_REPLICAS as u64),
+            rpc_timeouts: RPCTimeouts {
+                connect_timeout: Duration::from_millis(
+                    config_file
+                        .connect_timeout_ms
+                        .unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS),
+                ),
+                read_timeout: Duration::from_millis(
+                    config_file
+                        .read_timeout_ms
+                        .unwrap_or(DEFAULT_READ_TIMEOUT_MS),
+                ),
+                max_retries: config_file
+                    .rpc_max_retries
+                    .unwrap_or(DEFAULT_RPC_MAX_RETRIES),
+                retry_backoff: Duration::from_millis(
+                    config_file
+                        .rpc_retry_backoff_ms
+                        .unwrap_or(DEFAULT_RPC_RETRY_BACKOFF_MS),
+                ),
+            },
+            proxy: config_file
+                .proxy
+                .map(|proxy_url| ProxyConfig::parse(&proxy_url))
+                .transpose()?,
+            tx_confirm_timeout_secs: config_file
+                .tx_confirm_timeout_secs
+                .unwrap_or(DEFAULT_TX_CONFIRM_TIMEOUT_SECS),
+            color_depth: config_file
+                .color_depth
+                .map(|depth| ColorDepth::parse(&depth))
+                .transpose()?,
             __path: "".into(),
         })
     }
@@ -128,14 +379,42 @@ impl TryFrom<ConfigFile> for Config {
 
 impl From<Config> for ConfigFile {
     fn from(config: Config) -> Self {
+        let mut identities = config.identities.into_iter();
+        let (private_key, mnemonic, keystore, wrbpod) = identities
+            .next()
+            .expect("FATAL: no default identity")
+            .into_parts();
+        let other_identities = identities
+            .map(|identity| {
+                let name = identity.name.clone();
+                let (private_key, mnemonic, keystore, wrbpod) = identity.into_parts();
+                ConfigFileIdentity {
+                    name,
+                    private_key,
+                    mnemonic,
+                    keystore,
+                    wrbpod,
+                }
+            })
+            .collect();
         Self {
             mainnet: config.mainnet,
             node_host: config.node_host.clone(),
             node_port: config.node_port,
-            private_key: config.private_key.to_hex(),
+            fallback_node: Some(
+                config
+                    .fallback_nodes
+                    .into_iter()
+                    .map(|(host, port)| ConfigFileNode { host, port })
+                    .collect(),
+            ),
+            private_key,
+            mnemonic,
+            keystore,
             storage: Some(config.storage),
             debug_path: Some(config.debug_path),
-            wrbpod: config.wrbpod.to_string(),
+            wrbpod,
+            identity: Some(other_identities),
             mocked_stackerdb: Some(
                 config
                     .mock_stackerdb_paths
@@ -146,27 +425,61 @@ impl From<Config> for ConfigFile {
                     })
                     .collect(),
             ),
+            pinned_site: Some(
+                config
+                    .pinned_sites
+                    .into_iter()
+                    .map(|(name, code_hash)| ConfigFilePinnedSite {
+                        name,
+                        code_hash: code_hash.to_hex(),
+                    })
+                    .collect(),
+            ),
+            replica_timeout_ms: Some(config.replica_timeout_ms),
+            max_parallel_replicas: Some(config.max_parallel_replicas),
+            connect_timeout_ms: u64::try_from(config.rpc_timeouts.connect_timeout.as_millis()).ok(),
+            read_timeout_ms: u64::try_from(config.rpc_timeouts.read_timeout.as_millis()).ok(),
+            rpc_max_retries: Some(config.rpc_timeouts.max_retries),
+            rpc_retry_backoff_ms: u64::try_from(config.rpc_timeouts.retry_backoff.as_millis()).ok(),
+            proxy: config.proxy.map(|proxy| proxy.to_string()),
+            tx_confirm_timeout_secs: Some(config.tx_confirm_timeout_secs),
+            color_depth: config.color_depth.map(|depth| depth.to_string()),
         }
     }
 }
 
 impl Config {
     pub fn default(mainnet: bool, node_host: &str, node_port: u16) -> Config {
+        let identity = Identity::generate();
         Config {
             mainnet,
             node_host: node_host.into(),
             node_port,
-            private_key: Secp256k1PrivateKey::random(),
+            fallback_nodes: vec![],
+            identities: vec![ConfigIdentity {
+                name: DEFAULT_IDENTITY_NAME.into(),
+                private_key: Some(identity.root_key()),
+                mnemonic: Some(identity),
+                keystore: None,
+                wrbpod: WrbpodAddress::new(
+                    QualifiedContractIdentifier::parse(
+                        "SP000000000000000000002Q6VF78.you-need-to-set-up-your-wrbpod",
+                    )
+                    .unwrap(),
+                    0,
+                ),
+            }],
+            active_identity: 0,
             storage: "./db".into(),
             debug_path: "./debug.log".into(),
-            wrbpod: WrbpodAddress::new(
-                QualifiedContractIdentifier::parse(
-                    "SP000000000000000000002Q6VF78.you-need-to-set-up-your-wrbpod",
-                )
-                .unwrap(),
-                0,
-            ),
             mock_stackerdb_paths: HashMap::new(),
+            pinned_sites: HashMap::new(),
+            replica_timeout_ms: DEFAULT_REPLICA_TIMEOUT_MS,
+            max_parallel_replicas: DEFAULT_MAX_PARALLEL_REPLICAS as u64,
+            rpc_timeouts: RPCTimeouts::default(),
+            proxy: None,
+            tx_confirm_timeout_secs: DEFAULT_TX_CONFIRM_TIMEOUT_SECS,
+            color_depth: None,
             __path: "".into(),
         }
     }
@@ -197,6 +510,19 @@ impl Config {
             abs_stackerdb_paths.insert(addr.clone(), c.abspath(path));
         }
         c.mock_stackerdb_paths = abs_stackerdb_paths;
+        let abs_keystores: Vec<_> = c
+            .identities
+            .iter()
+            .map(|identity| {
+                identity
+                    .keystore
+                    .as_ref()
+                    .map(|keystore| c.abspath(keystore))
+            })
+            .collect();
+        for (identity, keystore) in c.identities.iter_mut().zip(abs_keystores.into_iter()) {
+            identity.keystore = keystore;
+        }
         Ok(c)
     }
 
@@ -208,18 +534,144 @@ impl Config {
         (self.node_host.clone(), self.node_port)
     }
 
+    pub fn get_fallback_node_addrs(&self) -> &Vec<(String, u16)> {
+        &self.fallback_nodes
+    }
+
+    fn active(&self) -> &ConfigIdentity {
+        &self.identities[self.active_identity]
+    }
+
+    /// Names of all of the user's identities.  The default identity is first.
+    pub fn identity_names(&self) -> Vec<String> {
+        self.identities
+            .iter()
+            .map(|identity| identity.name.clone())
+            .collect()
+    }
+
+    /// Name of the identity we're running as
+    pub fn active_identity_name(&self) -> &str {
+        &self.active().name
+    }
+
+    /// Run as the identity called `name`
+    pub fn select_identity(&mut self, name: &str) -> Result<(), String> {
+        let Some(idx) = self
+            .identities
+            .iter()
+            .position(|identity| identity.name == name)
+        else {
+            return Err(format!("No such identity '{}'", name));
+        };
+        self.active_identity = idx;
+        Ok(())
+    }
+
+    /// Get the root identity key, which originates transactions.
+    /// Panics if it's in a keystore that hasn't been unlocked; see `unlock_keystore()`.
     pub fn private_key(&self) -> &Secp256k1PrivateKey {
-        &self.private_key
+        self.active()
+            .private_key
+            .as_ref()
+            .expect("FATAL: identity keystore is locked")
     }
 
+    /// Get the mnemonic-derived identity, if the identity isn't a lone private key
+    pub fn identity(&self) -> Option<&Identity> {
+        self.active().mnemonic.as_ref()
+    }
+
+    /// Get the key that owns and signs the user's wrbpod.
+    /// This is the identity key if there's no mnemonic.
+    pub fn wrbpod_private_key(&self) -> Secp256k1PrivateKey {
+        match self.identity() {
+            Some(identity) => identity.wrbpod_key(),
+            None => self.private_key().clone(),
+        }
+    }
+
+    /// Get the user's key for the wrbsite `name.namespace`.
+    /// This is the identity key if there's no mnemonic.
+    pub fn site_private_key(&self, name: &str, namespace: &str) -> Secp256k1PrivateKey {
+        match self.identity() {
+            Some(identity) => identity.site_key(name, namespace),
+            None => self.private_key().clone(),
+        }
+    }
+
+    /// Path to the encrypted keystore holding the identity, if there is one
+    pub fn keystore_path(&self) -> Option<&String> {
+        self.active().keystore.as_ref()
+    }
+
+    /// Is the identity in a keystore that has yet to be unlocked?
+    pub fn is_locked(&self) -> bool {
+        self.active().private_key.is_none()
+    }
+
+    /// Decrypt the identity from the keystore
+    pub fn unlock_keystore(&mut self, passphrase: &str) -> Result<(), String> {
+        let identity = &mut self.identities[self.active_identity];
+        let Some(keystore_path) = identity.keystore.as_ref() else {
+            return Ok(());
+        };
+        let secret = Keystore::load(keystore_path)?.decrypt_secret(passphrase)?;
+        identity.private_key = Some(secret.private_key());
+        if let KeystoreSecret::Mnemonic(mnemonic) = secret {
+            identity.mnemonic = Some(mnemonic);
+        }
+        Ok(())
+    }
+
+    /// Encrypt the identity into a new keystore at `keystore_path`, and refer to it instead, so
+    /// that the identity is no longer written to the config file in plaintext.  The identity stays
+    /// unlocked.
+    pub fn store_identity_in_keystore(
+        &mut self,
+        keystore_path: &str,
+        passphrase: &str,
+    ) -> Result<(), String> {
+        self.store_identity_in_keystore_with_work_factor(
+            keystore_path,
+            passphrase,
+            DEFAULT_SCRYPT_LOG_N,
+        )
+    }
+
+    /// Encrypt the identity into a new keystore at `keystore_path`, using scrypt work factor
+    /// 2^log_n
+    pub fn store_identity_in_keystore_with_work_factor(
+        &mut self,
+        keystore_path: &str,
+        passphrase: &str,
+        log_n: u8,
+    ) -> Result<(), String> {
+        let identity = &mut self.identities[self.active_identity];
+        let secret = match (identity.mnemonic.as_ref(), identity.private_key.as_ref()) {
+            (Some(mnemonic), _) => KeystoreSecret::Mnemonic(mnemonic.clone()),
+            (None, Some(private_key)) => KeystoreSecret::PrivateKey(private_key.clone()),
+            (None, None) => return Err("identity keystore is locked".into()),
+        };
+        Keystore::encrypt_secret_with_work_factor(&secret, passphrase, log_n)?
+            .store(keystore_path)?;
+        identity.keystore = Some(keystore_path.to_string());
+        Ok(())
+    }
+
+    /// The wrbpod of the identity we're running as
     pub fn default_wrbpod(&self) -> &WrbpodAddress {
-        &self.wrbpod
+        &self.active().wrbpod
     }
 
     pub fn mock_stackerdb_paths(&self) -> &HashMap<QualifiedContractIdentifier, String> {
         &self.mock_stackerdb_paths
     }
 
+    pub fn pinned_sites(&self) -> &HashMap<String, Sha512Trunc256Sum> {
+        &self.pinned_sites
+    }
+
     /// This is the contract ID of the BNS contract that can resolve a name to its owner and price.
     pub fn get_bns_contract_id(&self) -> QualifiedContractIdentifier {
         if self.mainnet {
@@ -252,6 +704,51 @@ impl Config {
         self.abspath(&self.storage)
     }
 
+    /// Path to the DB of wrbsite publishers we trust
+    pub fn site_pins_path(&self) -> String {
+        format!("{}/site_pins.sqlite", &self.db_path())
+    }
+
+    /// Path to the DB of which identity each wrbsite runs as
+    pub fn site_identities_path(&self) -> String {
+        format!("{}/site_identities.sqlite", &self.db_path())
+    }
+
+    /// Path to the DB of nonces handed out to transactions that haven't been mined yet
+    pub fn nonces_path(&self) -> String {
+        format!("{}/nonces.sqlite", &self.db_path())
+    }
+
+    /// Path to the DB of replica latency and failure stats
+    pub fn replica_stats_path(&self) -> String {
+        format!("{}/replica_stats.sqlite", &self.db_path())
+    }
+
+    pub fn replica_timeout(&self) -> Duration {
+        Duration::from_millis(self.replica_timeout_ms)
+    }
+
+    pub fn max_parallel_replicas(&self) -> usize {
+        usize::try_from(self.max_parallel_replicas).unwrap_or(usize::MAX)
+    }
+
+    pub fn rpc_timeouts(&self) -> RPCTimeouts {
+        self.rpc_timeouts.clone()
+    }
+
+    pub fn proxy(&self) -> Option<&ProxyConfig> {
+        self.proxy.as_ref()
+    }
+
+    pub fn tx_confirm_timeout(&self) -> Duration {
+        Duration::from_secs(self.tx_confirm_timeout_secs)
+    }
+
+    /// The configured color depth, if it's not to be detected
+    pub fn color_depth(&self) -> Option<ColorDepth> {
+        self.color_depth
+    }
+
     pub fn debug_path(&self) -> String {
         self.abspath(&self.debug_path)
     }
@@ -0,0 +1,134 @@
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use bip32::{DerivationPath, XPrv};
+use bip39::Mnemonic;
+
+use rand::thread_rng;
+use rand::RngCore;
+
+use sha2::{Digest, Sha256};
+
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+/// Path to the root identity key.  This is the first account of a Stacks wallet, so the same
+/// mnemonic yields the same address here as it does in other Stacks wallets.
+pub const ROOT_KEY_PATH: &str = "m/44'/5757'/0'/0/0";
+
+/// Hardened purpose index under which wrb-specific keys are derived ("wrb" in ASCII)
+pub const WRB_KEY_PURPOSE: u32 = 0x777262;
+
+/// Branch under `WRB_KEY_PURPOSE` for the wrbpod signing key
+const WRBPOD_KEY_BRANCH: u32 = 0;
+
+/// Branch under `WRB_KEY_PURPOSE` for per-wrbsite keys
+const SITE_KEY_BRANCH: u32 = 1;
+
+/// Bytes of entropy in generated mnemonics (24 words)
+const MNEMONIC_ENTROPY_LEN: usize = 32;
+
+/// A BIP39 mnemonic, from which all of the user's keys are deterministically derived:
+/// * the root identity key, which originates transactions (`ROOT_KEY_PATH`)
+/// * the wrbpod signing key (`m/<WRB_KEY_PURPOSE>'/0'`)
+/// * one key per wrbsite, so sites cannot link the user to other sites
+///   (`m/<WRB_KEY_PURPOSE>'/1'/<h0>'/.../<h7>'`, where `h0..h7` come from the hash of the
+///   site's `name.namespace`)
+#[derive(Clone, PartialEq)]
+pub struct Identity {
+    mnemonic: Mnemonic,
+    seed: [u8; 64],
+}
+
+/// Never print the seed
+impl fmt::Debug for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Identity(..)")
+    }
+}
+
+impl Identity {
+    /// Generate a new 24-word mnemonic
+    pub fn generate() -> Self {
+        let mut entropy = [0u8; MNEMONIC_ENTROPY_LEN];
+        thread_rng().fill_bytes(&mut entropy);
+        let mnemonic =
+            Mnemonic::from_entropy(&entropy).expect("FATAL: could not make mnemonic from entropy");
+        Self::from(mnemonic)
+    }
+
+    /// Decode a BIP39 mnemonic phrase
+    pub fn from_mnemonic(phrase: &str) -> Result<Self, String> {
+        let mnemonic = Mnemonic::parse_normalized(phrase.trim())
+            .map_err(|e| format!("Invalid mnemonic: {}", &e))?;
+        Ok(Self::from(mnemonic))
+    }
+
+    /// The mnemonic phrase, for backing up
+    pub fn mnemonic(&self) -> String {
+        self.mnemonic.to_string()
+    }
+
+    fn derive_key(&self, path: &DerivationPath) -> Secp256k1PrivateKey {
+        let xprv =
+            XPrv::derive_from_path(self.seed, path).expect("FATAL: failed to derive key from seed");
+        let mut privkey = Secp256k1PrivateKey::from_slice(&xprv.private_key().to_bytes())
+            .expect("FATAL: derived an invalid private key");
+        privkey.set_compress_public(true);
+        privkey
+    }
+
+    fn wrb_path(branch: u32) -> String {
+        format!("m/{}'/{}'", WRB_KEY_PURPOSE, branch)
+    }
+
+    /// Derivation path of the key for the wrbsite `name.namespace`
+    pub fn site_key_path(name: &str, namespace: &str) -> DerivationPath {
+        let mut path = Self::wrb_path(SITE_KEY_BRANCH);
+        let site_hash = Sha256::digest(format!("{}.{}", name, namespace).as_bytes());
+        for chunk in site_hash.chunks_exact(4) {
+            let index = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) & 0x7fffffff;
+            path.push_str(&format!("/{}'", index));
+        }
+        path.parse().expect("FATAL: invalid site key path")
+    }
+
+    /// The root identity key, which originates transactions
+    pub fn root_key(&self) -> Secp256k1PrivateKey {
+        self.derive_key(&ROOT_KEY_PATH.parse().expect("FATAL: invalid root key path"))
+    }
+
+    /// The key that owns and signs the user's wrbpod
+    pub fn wrbpod_key(&self) -> Secp256k1PrivateKey {
+        self.derive_key(
+            &Self::wrb_path(WRBPOD_KEY_BRANCH)
+                .parse()
+                .expect("FATAL: invalid wrbpod key path"),
+        )
+    }
+
+    /// The key for the wrbsite `name.namespace`
+    pub fn site_key(&self, name: &str, namespace: &str) -> Secp256k1PrivateKey {
+        self.derive_key(&Self::site_key_path(name, namespace))
+    }
+}
+
+impl From<Mnemonic> for Identity {
+    fn from(mnemonic: Mnemonic) -> Self {
+        let seed = mnemonic.to_seed_normalized("");
+        Self { mnemonic, seed }
+    }
+}
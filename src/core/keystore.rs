@@ -0,0 +1,341 @@
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+use rand::thread_rng;
+use rand::RngCore;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use termion::input::TermRead;
+
+use stacks_common::util::hash::{hex_bytes, to_hex};
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::core::identity::Identity;
+
+/// Environment variable from which to read a keystore passphrase instead of prompting for it
+pub const KEYSTORE_PASSPHRASE_ENV: &str = "WRB_KEYSTORE_PASSPHRASE";
+
+/// Environment variable from which to read the passphrase for a new keystore (or a keystore whose
+/// passphrase is being changed) instead of prompting for it
+pub const NEW_KEYSTORE_PASSPHRASE_ENV: &str = "WRB_NEW_KEYSTORE_PASSPHRASE";
+
+/// Keystore format version
+pub const KEYSTORE_VERSION: u32 = 1;
+
+/// scrypt work factor (N = 2^log_n) for new keystores
+pub const DEFAULT_SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// Largest scrypt parameters we'll accept from a keystore file.  scrypt needs 128 * r * 2^log_n
+/// bytes of memory, so a keystore with unbounded parameters could exhaust memory on unlock.
+pub const MAX_SCRYPT_LOG_N: u8 = 20;
+pub const MAX_SCRYPT_R: u32 = 16;
+pub const MAX_SCRYPT_P: u32 = 16;
+
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// What a keystore holds
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeystoreSecret {
+    /// a lone private key
+    PrivateKey(Secp256k1PrivateKey),
+    /// a mnemonic, from which all identity keys are derived
+    Mnemonic(Identity),
+}
+
+impl KeystoreSecret {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::PrivateKey(..) => "private-key",
+            Self::Mnemonic(..) => "mnemonic",
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::PrivateKey(privk) => privk.to_bytes(),
+            Self::Mnemonic(identity) => identity.mnemonic().into_bytes(),
+        }
+    }
+
+    /// The root identity key
+    pub fn private_key(&self) -> Secp256k1PrivateKey {
+        match self {
+            Self::PrivateKey(privk) => privk.clone(),
+            Self::Mnemonic(identity) => identity.root_key(),
+        }
+    }
+}
+
+fn default_keystore_contents() -> String {
+    "private-key".into()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeystoreKDFParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    /// hex-encoded salt
+    pub salt: String,
+}
+
+/// A private key or mnemonic, encrypted with a key derived from a passphrase.
+/// The key is derived with scrypt, and encrypted with XChaCha20-Poly1305.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    /// always "scrypt"
+    pub kdf: String,
+    pub kdf_params: KeystoreKDFParams,
+    /// always "xchacha20poly1305"
+    pub cipher: String,
+    /// "private-key" or "mnemonic"
+    #[serde(default = "default_keystore_contents")]
+    pub contents: String,
+    /// hex-encoded nonce
+    pub nonce: String,
+    /// hex-encoded encrypted private key, with its authentication tag
+    pub ciphertext: String,
+}
+
+impl Keystore {
+    fn derive_key(passphrase: &str, params: &KeystoreKDFParams) -> Result<[u8; KEY_LEN], String> {
+        if params.log_n > MAX_SCRYPT_LOG_N || params.r > MAX_SCRYPT_R || params.p > MAX_SCRYPT_P {
+            return Err(format!(
+                "scrypt work factor too high (log_n={}, r={}, p={}; at most log_n={}, r={}, p={})",
+                params.log_n, params.r, params.p, MAX_SCRYPT_LOG_N, MAX_SCRYPT_R, MAX_SCRYPT_P
+            ));
+        }
+        let salt = hex_bytes(&params.salt).map_err(|e| format!("Invalid salt: {:?}", &e))?;
+        let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, KEY_LEN)
+            .map_err(|e| format!("Invalid scrypt parameters: {}", &e))?;
+        let mut key = [0u8; KEY_LEN];
+        scrypt::scrypt(passphrase.as_bytes(), &salt, &scrypt_params, &mut key)
+            .map_err(|e| format!("Failed to derive key: {}", &e))?;
+        Ok(key)
+    }
+
+    /// Encrypt a secret with a passphrase, using scrypt work factor 2^log_n
+    pub fn encrypt_secret_with_work_factor(
+        secret: &KeystoreSecret,
+        passphrase: &str,
+        log_n: u8,
+    ) -> Result<Self, String> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        thread_rng().fill_bytes(&mut salt);
+        thread_rng().fill_bytes(&mut nonce);
+
+        let kdf_params = KeystoreKDFParams {
+            log_n,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: to_hex(&salt),
+        };
+        let key = Self::derive_key(passphrase, &kdf_params)?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+        let ciphertext = cipher
+            .encrypt(XNonce::from_slice(&nonce), secret.to_bytes().as_slice())
+            .map_err(|e| format!("Failed to encrypt key: {}", &e))?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            kdf: "scrypt".into(),
+            kdf_params,
+            cipher: "xchacha20poly1305".into(),
+            contents: secret.kind().into(),
+            nonce: to_hex(&nonce),
+            ciphertext: to_hex(&ciphertext),
+        })
+    }
+
+    /// Encrypt a private key with a passphrase, using scrypt work factor 2^log_n
+    pub fn encrypt_with_work_factor(
+        privk: &Secp256k1PrivateKey,
+        passphrase: &str,
+        log_n: u8,
+    ) -> Result<Self, String> {
+        Self::encrypt_secret_with_work_factor(
+            &KeystoreSecret::PrivateKey(privk.clone()),
+            passphrase,
+            log_n,
+        )
+    }
+
+    /// Encrypt a secret with a passphrase
+    pub fn encrypt_secret(secret: &KeystoreSecret, passphrase: &str) -> Result<Self, String> {
+        Self::encrypt_secret_with_work_factor(secret, passphrase, DEFAULT_SCRYPT_LOG_N)
+    }
+
+    /// Encrypt a private key with a passphrase
+    pub fn encrypt(privk: &Secp256k1PrivateKey, passphrase: &str) -> Result<Self, String> {
+        Self::encrypt_with_work_factor(privk, passphrase, DEFAULT_SCRYPT_LOG_N)
+    }
+
+    /// Decrypt the root identity key.  Fails if the passphrase is wrong or the keystore was
+    /// altered.
+    pub fn decrypt(&self, passphrase: &str) -> Result<Secp256k1PrivateKey, String> {
+        Ok(self.decrypt_secret(passphrase)?.private_key())
+    }
+
+    /// Decrypt the secret.  Fails if the passphrase is wrong or the keystore was altered.
+    pub fn decrypt_secret(&self, passphrase: &str) -> Result<KeystoreSecret, String> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(format!("Unsupported keystore version {}", self.version));
+        }
+        if self.kdf != "scrypt" || self.cipher != "xchacha20poly1305" {
+            return Err(format!(
+                "Unsupported keystore scheme {}/{}",
+                &self.kdf, &self.cipher
+            ));
+        }
+        let nonce = hex_bytes(&self.nonce).map_err(|e| format!("Invalid nonce: {:?}", &e))?;
+        if nonce.len() != NONCE_LEN {
+            return Err("Invalid nonce length".into());
+        }
+        let ciphertext =
+            hex_bytes(&self.ciphertext).map_err(|e| format!("Invalid ciphertext: {:?}", &e))?;
+
+        let key = Self::derive_key(passphrase, &self.kdf_params)?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+        let plaintext = cipher
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| "Wrong passphrase, or keystore is corrupt".to_string())?;
+        match self.contents.as_str() {
+            "private-key" => Secp256k1PrivateKey::from_slice(&plaintext)
+                .map(KeystoreSecret::PrivateKey)
+                .map_err(|e| format!("Keystore does not hold a private key: {:?}", &e)),
+            "mnemonic" => {
+                let phrase = String::from_utf8(plaintext)
+                    .map_err(|_| "Keystore does not hold a mnemonic".to_string())?;
+                Identity::from_mnemonic(&phrase).map(KeystoreSecret::Mnemonic)
+            }
+            _ => Err(format!("Unsupported keystore contents {}", &self.contents)),
+        }
+    }
+
+    pub fn from_json(json_str: &str) -> Result<Self, String> {
+        serde_json::from_str(json_str).map_err(|e| format!("Invalid keystore: {}", &e))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("FATAL: failed to encode keystore")
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json_str = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read keystore {}: {}", path, &e))?;
+        Self::from_json(&json_str)
+    }
+
+    /// Store to `path`, readable only by the owner.  Overwrites any existing file.
+    /// The keystore is written to a temporary file in the same directory first, and then
+    /// renamed over `path`, so a failed write never destroys the existing keystore.
+    pub fn store(&self, path: &str) -> Result<(), String> {
+        let tmp_path = format!("{}.tmp-{}", path, std::process::id());
+        let res = Self::write_synced(&tmp_path, self.to_json().as_bytes())
+            .and_then(|_| {
+                fs::rename(&tmp_path, path)
+                    .map_err(|e| format!("Failed to replace {}: {}", path, &e))
+            })
+            .and_then(|_| {
+                // make the rename itself durable
+                let dir = match Path::new(path).parent() {
+                    Some(dir) if !dir.as_os_str().is_empty() => dir,
+                    _ => Path::new("."),
+                };
+                fs::File::open(dir)
+                    .and_then(|dir| dir.sync_all())
+                    .map_err(|e| format!("Failed to sync {}: {}", dir.display(), &e))
+            });
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        res
+    }
+
+    /// Write `bytes` to a new file at `path`, readable only by the owner, and flush it to disk
+    fn write_synced(path: &str, bytes: &[u8]) -> Result<(), String> {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path, &e))?;
+        file.write_all(bytes)
+            .map_err(|e| format!("Failed to write {}: {}", path, &e))?;
+        file.sync_all()
+            .map_err(|e| format!("Failed to sync {}: {}", path, &e))
+    }
+}
+
+/// Get a keystore passphrase from $WRB_KEYSTORE_PASSPHRASE, or by prompting on the terminal
+pub fn read_passphrase(prompt: &str) -> Result<String, String> {
+    if let Ok(passphrase) = env::var(KEYSTORE_PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    read_passphrase_from_tty(prompt)
+}
+
+/// Prompt for a passphrase on the terminal, without echoing it
+pub fn read_passphrase_from_tty(prompt: &str) -> Result<String, String> {
+    let mut tty_in = termion::get_tty().map_err(|e| format!("Failed to open terminal: {}", &e))?;
+    let mut tty_out = tty_in
+        .try_clone()
+        .map_err(|e| format!("Failed to open terminal: {}", &e))?;
+    write!(tty_out, "{}", prompt).map_err(|e| format!("Failed to write prompt: {}", &e))?;
+    let passphrase = tty_in
+        .read_passwd(&mut tty_out)
+        .map_err(|e| format!("Failed to read passphrase: {}", &e))?
+        .ok_or("No passphrase given".to_string())?;
+    let _ = writeln!(tty_out);
+    Ok(passphrase)
+}
+
+/// Get a new passphrase from $WRB_NEW_KEYSTORE_PASSPHRASE, or by prompting twice on the terminal
+/// and making sure both match.  $WRB_KEYSTORE_PASSPHRASE is never used, since it holds the
+/// passphrase of an existing keystore.
+pub fn read_new_passphrase() -> Result<String, String> {
+    let passphrase = match env::var(NEW_KEYSTORE_PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let passphrase = read_passphrase_from_tty("New passphrase: ")?;
+            let confirm = read_passphrase_from_tty("Repeat new passphrase: ")?;
+            if passphrase != confirm {
+                return Err("Passphrases do not match".into());
+            }
+            passphrase
+        }
+    };
+    if passphrase.is_empty() {
+        return Err("Passphrase cannot be empty".into());
+    }
+    Ok(passphrase)
+}
@@ -16,6 +16,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -32,8 +33,10 @@ use libstackerdb::StackerDBChunkData;
 use clarity::vm::types::QualifiedContractIdentifier;
 
 use crate::runner;
+use crate::runner::pins::{SitePin, SitePinMismatch};
 use crate::runner::stackerdb::StackerDBSession;
 
+use crate::core::keystore::read_passphrase;
 use crate::storage::StackerDBClient;
 use crate::storage::Wrbpod;
 use crate::storage::WrbpodAddress;
@@ -49,6 +52,11 @@ use lazy_static::lazy_static;
 
 pub mod config;
 pub mod globals;
+pub mod identity;
+pub mod keystore;
+
+#[cfg(test)]
+pub mod tests;
 
 pub use crate::core::config::Config;
 pub use crate::core::config::ConfigFile;
@@ -109,11 +117,38 @@ where
     with_globals(|globals| globals.config.as_ref().map(|cfg| func(cfg)))
 }
 
+/// Unlock the active identity's keystore in the global config, if it's locked.  Its passphrase is
+/// read from $WRB_KEYSTORE_PASSPHRASE, or prompted for.
+/// Call this only once the identity's keys are actually needed, so that commands which never use
+/// them don't ask for the passphrase.
+pub fn unlock_identity() -> Result<(), String> {
+    let Some(Some(keystore_path)) = with_global_config(|cfg| {
+        if cfg.is_locked() {
+            cfg.keystore_path().cloned()
+        } else {
+            None
+        }
+    }) else {
+        return Ok(());
+    };
+    let passphrase = read_passphrase(&format!("Passphrase for {}: ", &keystore_path))?;
+    with_globals(|globals| match globals.config.as_mut() {
+        Some(cfg) => cfg
+            .unlock_keystore(&passphrase)
+            .map_err(|e| format!("failed to unlock '{}': {}", &keystore_path, &e)),
+        None => Err("System is not initialized".into()),
+    })
+}
+
 /// Make a runner
 pub fn make_runner() -> Runner {
     let (node_host, node_port) =
         with_global_config(|cfg| cfg.get_node_addr()).expect("FATAL: system not initialized");
 
+    let (fallback_nodes, mainnet) =
+        with_global_config(|cfg| (cfg.get_fallback_node_addrs().clone(), cfg.mainnet()))
+            .expect("FATAL: system not initialized");
+
     let (bns_contract_id, zonefile_contract_id, mock_stackerdb_paths) = with_global_config(|cfg| {
         (
             cfg.get_bns_contract_id(),
@@ -123,8 +158,24 @@ pub fn make_runner() -> Runner {
     })
     .expect("FATAL: system not initialized");
 
+    let (site_pins_path, pinned_sites) =
+        with_global_config(|cfg| (cfg.site_pins_path(), cfg.pinned_sites().clone()))
+            .expect("FATAL: system not initialized");
+
+    let (replica_stats_path, replica_timeout, max_parallel_replicas) = with_global_config(|cfg| {
+        (
+            cfg.replica_stats_path(),
+            cfg.replica_timeout(),
+            cfg.max_parallel_replicas(),
+        )
+    })
+    .expect("FATAL: system not initialized");
+
     let runner = Runner::new(bns_contract_id, zonefile_contract_id, node_host, node_port)
-        .with_mock_stackerdb_paths(mock_stackerdb_paths);
+        .with_node_failover(fallback_nodes, mainnet)
+        .with_mock_stackerdb_paths(mock_stackerdb_paths)
+        .with_site_pins(site_pins_path, pinned_sites)
+        .with_replica_stats(replica_stats_path, replica_timeout, max_parallel_replicas);
 
     runner
 }
@@ -141,14 +192,43 @@ pub fn split_fqn(wrbsite_name: &str) -> Result<(String, String), String> {
     Ok((name.to_string(), namespace.to_string()))
 }
 
+/// Reasons why a wrbsite could not be loaded
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    /// The wrbsite's publisher changed since it was pinned.
+    /// Contains (old pin, new pin).  Pass the new pin to `accept_site_pin()` to trust it.
+    PublisherChanged(SitePin, SitePin),
+    /// Any other failure
+    Failed(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::PublisherChanged(old_pin, new_pin) => write!(
+                f,
+                "Refusing to load '{}': publisher changed from {} (signer {}) to {} (signer {})",
+                &old_pin.name,
+                &old_pin.contract_id,
+                &old_pin.signer,
+                &new_pin.contract_id,
+                &new_pin.signer
+            ),
+            LoadError::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
 /// Resolve a name to its wrbsite and version.
+/// If the wrbsite's publisher changed, then LoadError::PublisherChanged(..) is returned so the
+/// caller can ask the user whether or not to trust the new one.
 /// Used in prod - uses NodeBNSResolver and StackerDBSession
-pub fn wrbsite_load(wrbsite_name: &str) -> Result<(Vec<u8>, u32), String> {
+pub fn wrbsite_load(wrbsite_name: &str) -> Result<(Vec<u8>, u32), LoadError> {
     let (name, namespace) = split_fqn(wrbsite_name).map_err(|e_str| {
-        format!(
+        LoadError::Failed(format!(
             "Invalid fully qualified name; could not decode name and namespace: {}",
             &e_str
-        )
+        ))
     })?;
 
     let mut resolver = NodeBNSResolver::new();
@@ -156,27 +236,44 @@ pub fn wrbsite_load(wrbsite_name: &str) -> Result<(Vec<u8>, u32), String> {
 
     let Some(home_node_addr) = runner
         .resolve_node()
-        .map_err(|e| format!("Failed to resolve node: {:?}", &e))?
+        .map_err(|e| LoadError::Failed(format!("Failed to resolve node: {:?}", &e)))?
     else {
-        return Err("Not connected to home node".into());
+        return Err(LoadError::Failed("Not connected to home node".into()));
     };
 
-    let (wrbsite_bytes, version) = runner
-        .wrbsite_load_ext(
-            &mut resolver,
-            &name,
-            &namespace,
-            |contract_id: &QualifiedContractIdentifier, node_addr: &SocketAddr| {
-                Runner::home_node_connect(contract_id, node_addr)
-            },
-            |contract_id: &QualifiedContractIdentifier, node_p2p_addr: &SocketAddr| {
-                Runner::replica_node_connect(contract_id, &home_node_addr, node_p2p_addr)
-            },
-        )
-        .map_err(|e| format!("Failed to load '{}': {:?}", wrbsite_name, &e))?
-        .ok_or_else(|| format!("No wrbsite found for '{}'", wrbsite_name))?;
+    let res = runner.wrbsite_load_ext(
+        &mut resolver,
+        &name,
+        &namespace,
+        |contract_id: &QualifiedContractIdentifier, node_addr: &SocketAddr| {
+            Runner::home_node_connect(contract_id, node_addr)
+        },
+        |contract_id: &QualifiedContractIdentifier, node_p2p_addr: &SocketAddr| {
+            Runner::replica_node_connect(contract_id, &home_node_addr, node_p2p_addr)
+        },
+    );
+
+    match res {
+        Ok(Some((wrbsite_bytes, version))) => Ok((wrbsite_bytes, version)),
+        Ok(None) => Err(LoadError::Failed(format!(
+            "No wrbsite found for '{}'",
+            wrbsite_name
+        ))),
+        Err(runner::Error::SitePinMismatch(_, SitePinMismatch::Publisher(old_pin, new_pin))) => {
+            Err(LoadError::PublisherChanged(old_pin, new_pin))
+        }
+        Err(e) => Err(LoadError::Failed(format!(
+            "Failed to load '{}': {:?}",
+            wrbsite_name, &e
+        ))),
+    }
+}
 
-    Ok((wrbsite_bytes, version))
+/// Trust a wrbsite's new publisher, so it loads the next time.
+pub fn accept_site_pin(new_pin: &SitePin) -> Result<(), String> {
+    make_runner()
+        .accept_site_pin(new_pin)
+        .map_err(|e| format!("Failed to store pin for '{}': {:?}", &new_pin.name, &e))
 }
 
 /// Load the wrbsite for the given name from the given source.
@@ -185,16 +282,20 @@ pub fn wrbsite_load(wrbsite_name: &str) -> Result<(Vec<u8>, u32), String> {
 pub fn load_wrbsite_source(
     wrbsite_name: &str,
     source: Option<String>,
-) -> Result<(Vec<u8>, u32), String> {
+) -> Result<(Vec<u8>, u32), LoadError> {
     let Some(path) = source else {
-        return wrbsite_load(wrbsite_name)
-            .map_err(|e| format!("Failed to load '{}': {:?}", wrbsite_name, &e));
+        return wrbsite_load(wrbsite_name);
     };
 
     // treat source as a path to uncompressed clarity code
-    let code = fs::read_to_string(&path).map_err(|e| format!("Invalid path: {}", &e))?;
-    let bytes = Renderer::encode_bytes(code.as_bytes())
-        .map_err(|e| format!("Failed to encode source code from '{}': {:?}", &path, &e))?;
+    let code = fs::read_to_string(&path)
+        .map_err(|e| LoadError::Failed(format!("Invalid path: {}", &e)))?;
+    let bytes = Renderer::encode_bytes(code.as_bytes()).map_err(|e| {
+        LoadError::Failed(format!(
+            "Failed to encode source code from '{}': {:?}",
+            &path, &e
+        ))
+    })?;
 
     Ok((bytes, 0))
 }
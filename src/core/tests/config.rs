@@ -0,0 +1,178 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::core::config::DEFAULT_IDENTITY_NAME;
+use crate::core::identity::Identity;
+use crate::core::Config;
+use crate::core::ConfigFile;
+use crate::ui::scanline::ColorDepth;
+
+const TEST_MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn make_identities_config(work_privkey: &Secp256k1PrivateKey) -> String {
+    format!(
+        r#"
+mainnet = true
+node_host = "localhost"
+node_port = 20443
+mnemonic = "{}"
+wrbpod = "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.wrbpod/0"
+
+[[identity]]
+name = "work"
+private_key = "{}"
+wrbpod = "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.work-wrbpod/1"
+
+[[identity]]
+name = "play"
+keystore = "play.json"
+wrbpod = "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.play-wrbpod/2"
+"#,
+        TEST_MNEMONIC,
+        work_privkey.to_hex()
+    )
+}
+
+#[test]
+fn test_config_identities() {
+    let work_privkey = Secp256k1PrivateKey::random();
+    let conf_toml = make_identities_config(&work_privkey);
+    let mut conf = Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).unwrap();
+
+    assert_eq!(
+        conf.identity_names(),
+        vec![
+            DEFAULT_IDENTITY_NAME.to_string(),
+            "work".to_string(),
+            "play".to_string()
+        ]
+    );
+
+    // default identity is active
+    let identity = Identity::from_mnemonic(TEST_MNEMONIC).unwrap();
+    assert_eq!(conf.active_identity_name(), DEFAULT_IDENTITY_NAME);
+    assert_eq!(conf.private_key(), &identity.root_key());
+    assert_eq!(conf.wrbpod_private_key(), identity.wrbpod_key());
+    assert_eq!(
+        conf.default_wrbpod().to_string(),
+        "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.wrbpod/0"
+    );
+
+    // each identity has its own key and wrbpod
+    conf.select_identity("work").unwrap();
+    assert_eq!(conf.active_identity_name(), "work");
+    assert!(!conf.is_locked());
+    assert!(conf.identity().is_none());
+    assert_eq!(conf.private_key(), &work_privkey);
+    assert_eq!(conf.wrbpod_private_key(), work_privkey);
+    assert_eq!(
+        conf.default_wrbpod().to_string(),
+        "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.work-wrbpod/1"
+    );
+
+    conf.select_identity("play").unwrap();
+    assert!(conf.is_locked());
+    assert_eq!(conf.keystore_path().map(|s| s.as_str()), Some("play.json"));
+    assert_eq!(
+        conf.default_wrbpod().to_string(),
+        "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.play-wrbpod/2"
+    );
+
+    // no such identity
+    assert!(conf.select_identity("nope").is_err());
+    assert_eq!(conf.active_identity_name(), "play");
+
+    // identities survive a round trip
+    conf.select_identity(DEFAULT_IDENTITY_NAME).unwrap();
+    let conf_toml = toml::to_string(&ConfigFile::from(conf.clone())).unwrap();
+    let conf_2 = Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).unwrap();
+    assert_eq!(conf_2, conf);
+
+    // keystore paths are relative to the config file
+    let path = "/tmp/wrb-test-config-identities.toml";
+    fs::write(path, &make_identities_config(&work_privkey)).unwrap();
+    let mut conf = Config::from_path(path).unwrap();
+    conf.select_identity("play").unwrap();
+    assert_eq!(
+        conf.keystore_path().map(|s| s.as_str()),
+        Some("/tmp/play.json")
+    );
+}
+
+#[test]
+fn test_config_color_depth() {
+    let work_privkey = Secp256k1PrivateKey::random();
+
+    // detected from the environment unless given
+    let conf_toml = make_identities_config(&work_privkey);
+    let conf = Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).unwrap();
+    assert_eq!(conf.color_depth(), None);
+
+    let conf_toml = format!(
+        "color_depth = \"16\"\n{}",
+        make_identities_config(&work_privkey)
+    );
+    let conf = Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).unwrap();
+    assert_eq!(conf.color_depth(), Some(ColorDepth::Ansi16));
+
+    // survives a round trip
+    let conf_toml = toml::to_string(&ConfigFile::from(conf.clone())).unwrap();
+    let conf_2 = Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).unwrap();
+    assert_eq!(conf_2, conf);
+
+    let conf_toml = format!(
+        "color_depth = \"8\"\n{}",
+        make_identities_config(&work_privkey)
+    );
+    assert!(Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).is_err());
+}
+
+#[test]
+fn test_config_identities_invalid() {
+    let work_privkey = Secp256k1PrivateKey::random();
+
+    // duplicate names
+    let conf_toml = make_identities_config(&work_privkey).replace("\"play\"", "\"work\"");
+    let err = Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).unwrap_err();
+    assert!(err.contains("Duplicate identity 'work'"), "{}", &err);
+
+    // an identity needs exactly one key
+    let conf_toml = make_identities_config(&work_privkey)
+        .replace("keystore = \"play.json\"", "mnemonic = \"not a mnemonic\"");
+    let err = Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).unwrap_err();
+    assert!(err.contains("Invalid identity 'play'"), "{}", &err);
+
+    let conf_toml = make_identities_config(&work_privkey).replace(
+        "keystore = \"play.json\"",
+        &format!(
+            "keystore = \"play.json\"\nprivate_key = \"{}\"",
+            work_privkey.to_hex()
+        ),
+    );
+    let err = Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).unwrap_err();
+    assert!(err.contains("Invalid identity 'play'"), "{}", &err);
+
+    // bad wrbpod
+    let conf_toml = make_identities_config(&work_privkey).replace("work-wrbpod/1", "work-wrbpod");
+    let err = Config::try_from(ConfigFile::from_str(&conf_toml).unwrap()).unwrap_err();
+    assert!(err.contains("Invalid identity 'work'"), "{}", &err);
+}
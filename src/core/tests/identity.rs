@@ -0,0 +1,111 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::chainstate::StacksPublicKey;
+
+use crate::core::identity::Identity;
+
+/// BIP39 test vector
+const TEST_MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+#[test]
+fn test_identity_mnemonic() {
+    let identity = Identity::from_mnemonic(TEST_MNEMONIC).unwrap();
+    assert_eq!(identity.mnemonic(), TEST_MNEMONIC);
+
+    // whitespace is tolerated
+    let identity_2 = Identity::from_mnemonic(&format!("  {}\n", TEST_MNEMONIC)).unwrap();
+    assert_eq!(identity_2, identity);
+
+    // bad checksum
+    assert!(Identity::from_mnemonic(&TEST_MNEMONIC.replace("about", "abandon")).is_err());
+
+    // not a word
+    assert!(Identity::from_mnemonic(&TEST_MNEMONIC.replace("about", "wrb")).is_err());
+
+    // generated mnemonics are 24 words, and decode to the same identity
+    let identity = Identity::generate();
+    assert_eq!(identity.mnemonic().split_whitespace().count(), 24);
+    assert_eq!(
+        Identity::from_mnemonic(&identity.mnemonic()).unwrap(),
+        identity
+    );
+    assert_ne!(Identity::generate(), identity);
+}
+
+#[test]
+fn test_identity_keys() {
+    let identity = Identity::from_mnemonic(TEST_MNEMONIC).unwrap();
+
+    // keys are deterministic
+    let identity_2 = Identity::from_mnemonic(TEST_MNEMONIC).unwrap();
+    assert_eq!(identity.root_key(), identity_2.root_key());
+    assert_eq!(identity.wrbpod_key(), identity_2.wrbpod_key());
+    assert_eq!(
+        identity.site_key("hello", "world"),
+        identity_2.site_key("hello", "world")
+    );
+
+    // keys are all different
+    let keys = vec![
+        identity.root_key(),
+        identity.wrbpod_key(),
+        identity.site_key("hello", "world"),
+        identity.site_key("hello", "stx"),
+        identity.site_key("hello2", "world"),
+        Identity::generate().root_key(),
+    ];
+    for (i, key_i) in keys.iter().enumerate() {
+        for key_j in keys[i + 1..].iter() {
+            assert_ne!(key_i, key_j);
+        }
+    }
+
+    // public keys are compressed
+    for key in keys.iter() {
+        assert!(StacksPublicKey::from_private(key).compressed());
+    }
+
+    // site key paths are all hardened
+    let path = Identity::site_key_path("hello", "world").to_string();
+    assert_eq!(path.split('/').count(), 11);
+    assert!(path.split('/').skip(1).all(|index| index.ends_with('\'')));
+}
+
+#[test]
+fn test_identity_root_key_vector() {
+    // TEST_MNEMONIC's first Stacks wallet account (m/44'/5757'/0'/0/0).  As a check on the
+    // derivation, the same mnemonic's first Bitcoin account (m/44'/0'/0'/0/0) is the well-known
+    // 1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA.
+    let identity = Identity::from_mnemonic(TEST_MNEMONIC).unwrap();
+    let root_key = identity.root_key();
+    let public_key = StacksPublicKey::from_private(&root_key);
+    assert_eq!(
+        public_key.to_hex(),
+        "03d5d038bce81b3965314dba54f636f093c7dbdd6617cded013a53474fbccb100c"
+    );
+    assert_eq!(
+        StacksAddress::p2pkh(true, &public_key).to_string(),
+        "SPC5KHM41H6WHAST7MWWDD807YSPRQKJ69FSH54J"
+    );
+    assert_eq!(
+        StacksAddress::p2pkh(false, &public_key).to_string(),
+        "STC5KHM41H6WHAST7MWWDD807YSPRQKJ68T330BQ"
+    );
+}
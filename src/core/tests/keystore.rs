@@ -0,0 +1,266 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use stacks_common::util::hash::{hex_bytes, to_hex};
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::core::identity::Identity;
+use crate::core::keystore::{Keystore, KeystoreSecret};
+use crate::core::Config;
+use crate::core::ConfigFile;
+
+/// low work factor so the tests run quickly
+const TEST_LOG_N: u8 = 4;
+
+#[test]
+fn test_keystore_roundtrip() {
+    let privk = Secp256k1PrivateKey::random();
+    let keystore = Keystore::encrypt_with_work_factor(&privk, "hunter2", TEST_LOG_N).unwrap();
+    assert_eq!(keystore.decrypt("hunter2").unwrap(), privk);
+
+    // survives encoding
+    let keystore_json = keystore.to_json();
+    assert!(!keystore_json.contains(&privk.to_hex()));
+    let decoded = Keystore::from_json(&keystore_json).unwrap();
+    assert_eq!(decoded, keystore);
+    assert_eq!(decoded.decrypt("hunter2").unwrap(), privk);
+
+    // same key and passphrase still yields a different keystore
+    let keystore_2 = Keystore::encrypt_with_work_factor(&privk, "hunter2", TEST_LOG_N).unwrap();
+    assert_ne!(keystore_2.kdf_params.salt, keystore.kdf_params.salt);
+    assert_ne!(keystore_2.ciphertext, keystore.ciphertext);
+}
+
+#[test]
+fn test_keystore_store() {
+    let path = "/tmp/wrb-test-keystore-store.json";
+    if fs::metadata(path).is_ok() {
+        fs::remove_file(path).unwrap();
+    }
+
+    let privk = Secp256k1PrivateKey::random();
+    let keystore = Keystore::encrypt_with_work_factor(&privk, "hunter2", TEST_LOG_N).unwrap();
+    keystore.store(path).unwrap();
+    assert_eq!(Keystore::load(path).unwrap(), keystore);
+    assert_eq!(
+        fs::metadata(path).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+
+    // overwriting replaces the file, and leaves no temporary file behind
+    let keystore_2 = Keystore::encrypt_with_work_factor(&privk, "hunter3", TEST_LOG_N).unwrap();
+    keystore_2.store(path).unwrap();
+    assert_eq!(Keystore::load(path).unwrap(), keystore_2);
+    let leftovers: Vec<_> = fs::read_dir("/tmp")
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("wrb-test-keystore-store.json.tmp")
+        })
+        .collect();
+    assert!(leftovers.is_empty());
+
+    // a failed write reports an error, and creates nothing
+    let bad_path = "/tmp/wrb-test-keystore-store-no-such-dir/keystore.json";
+    assert!(keystore.store(bad_path).is_err());
+    assert!(fs::metadata(bad_path).is_err());
+}
+
+#[test]
+fn test_keystore_rejects_bad_input() {
+    let privk = Secp256k1PrivateKey::random();
+    let keystore = Keystore::encrypt_with_work_factor(&privk, "hunter2", TEST_LOG_N).unwrap();
+
+    // wrong passphrase
+    assert!(keystore.decrypt("hunter3").is_err());
+
+    // tampered ciphertext
+    let mut tampered = keystore.clone();
+    let mut ciphertext = hex_bytes(&tampered.ciphertext).unwrap();
+    ciphertext[0] ^= 0x01;
+    tampered.ciphertext = to_hex(&ciphertext);
+    assert!(tampered.decrypt("hunter2").is_err());
+
+    // tampered KDF parameters
+    let mut tampered = keystore.clone();
+    tampered.kdf_params.log_n += 1;
+    assert!(tampered.decrypt("hunter2").is_err());
+
+    // scrypt parameters too expensive to try
+    let mut tampered = keystore.clone();
+    tampered.kdf_params.log_n = 40;
+    assert!(tampered
+        .decrypt("hunter2")
+        .unwrap_err()
+        .contains("work factor too high"));
+
+    let mut tampered = keystore.clone();
+    tampered.kdf_params.r = u32::MAX;
+    assert!(tampered
+        .decrypt("hunter2")
+        .unwrap_err()
+        .contains("work factor too high"));
+
+    // unknown version
+    let mut tampered = keystore.clone();
+    tampered.version += 1;
+    assert!(tampered.decrypt("hunter2").is_err());
+
+    // not a keystore
+    assert!(Keystore::from_json("{}").is_err());
+}
+
+#[test]
+fn test_keystore_mnemonic() {
+    let identity = Identity::generate();
+    let secret = KeystoreSecret::Mnemonic(identity.clone());
+    let keystore =
+        Keystore::encrypt_secret_with_work_factor(&secret, "hunter2", TEST_LOG_N).unwrap();
+    assert_eq!(keystore.contents, "mnemonic");
+    assert!(!keystore.to_json().contains(&identity.mnemonic()));
+
+    assert_eq!(keystore.decrypt_secret("hunter2").unwrap(), secret);
+    assert_eq!(keystore.decrypt("hunter2").unwrap(), identity.root_key());
+    assert!(keystore.decrypt_secret("hunter3").is_err());
+
+    // keystores from before mnemonics hold private keys
+    let privk = Secp256k1PrivateKey::random();
+    let keystore = Keystore::encrypt_with_work_factor(&privk, "hunter2", TEST_LOG_N).unwrap();
+    let mut keystore_json: serde_json::Value = serde_json::from_str(&keystore.to_json()).unwrap();
+    keystore_json.as_object_mut().unwrap().remove("contents");
+    let keystore = Keystore::from_json(&keystore_json.to_string()).unwrap();
+    assert_eq!(
+        keystore.decrypt_secret("hunter2").unwrap(),
+        KeystoreSecret::PrivateKey(privk)
+    );
+
+    // can't pass off a mnemonic as a private key
+    let keystore =
+        Keystore::encrypt_secret_with_work_factor(&secret, "hunter2", TEST_LOG_N).unwrap();
+    let mut tampered = keystore.clone();
+    tampered.contents = "private-key".into();
+    assert!(tampered.decrypt_secret("hunter2").is_err());
+}
+
+#[test]
+fn test_config_keystore() {
+    let conf = Config::default(true, "localhost", 20443);
+    let identity = conf.identity().cloned().unwrap();
+    assert_eq!(conf.private_key(), &identity.root_key());
+    let conf_toml = toml::to_string(&ConfigFile::from(conf)).unwrap();
+    let mut conf_value: toml::Table = toml::from_str(&conf_toml).unwrap();
+    assert!(conf_value.contains_key("mnemonic"));
+    assert!(!conf_value.contains_key("private_key"));
+
+    // plaintext mnemonic is unlocked
+    let conf_file: ConfigFile = toml::from_str(&conf_toml).unwrap();
+    let conf = Config::try_from(conf_file).unwrap();
+    assert!(!conf.is_locked());
+    assert_eq!(conf.identity(), Some(&identity));
+    assert_eq!(conf.private_key(), &identity.root_key());
+    assert_eq!(conf.wrbpod_private_key(), identity.wrbpod_key());
+    assert_eq!(
+        conf.site_private_key("hello", "world"),
+        identity.site_key("hello", "world")
+    );
+
+    // both a mnemonic and a keystore is an error
+    conf_value.insert("keystore".into(), "/tmp/keystore.json".into());
+    let conf_file: ConfigFile = toml::from_str(&toml::to_string(&conf_value).unwrap()).unwrap();
+    assert!(Config::try_from(conf_file).is_err());
+
+    // keystore alone is locked until unlocked
+    conf_value.remove("mnemonic");
+    let conf_file: ConfigFile = toml::from_str(&toml::to_string(&conf_value).unwrap()).unwrap();
+    let conf = Config::try_from(conf_file).unwrap();
+    assert!(conf.is_locked());
+    assert_eq!(
+        conf.keystore_path().map(|s| s.as_str()),
+        Some("/tmp/keystore.json")
+    );
+
+    // writing the config back keeps the keystore, not the key
+    let conf_toml = toml::to_string(&ConfigFile::from(conf)).unwrap();
+    assert!(conf_toml.contains("keystore"));
+    assert!(!conf_toml.contains("private_key"));
+    assert!(!conf_toml.contains("mnemonic"));
+
+    // both a key and a keystore is an error
+    let privk = Secp256k1PrivateKey::random();
+    conf_value.insert("private_key".into(), privk.to_hex().into());
+    let conf_file: ConfigFile = toml::from_str(&toml::to_string(&conf_value).unwrap()).unwrap();
+    assert!(Config::try_from(conf_file).is_err());
+
+    // a lone private key is used for everything
+    conf_value.remove("keystore");
+    let conf_file: ConfigFile = toml::from_str(&toml::to_string(&conf_value).unwrap()).unwrap();
+    let conf = Config::try_from(conf_file).unwrap();
+    assert!(conf.identity().is_none());
+    assert_eq!(conf.private_key(), &privk);
+    assert_eq!(conf.wrbpod_private_key(), privk);
+    assert_eq!(conf.site_private_key("hello", "world"), privk);
+
+    // neither is an error
+    conf_value.remove("private_key");
+    let conf_file: ConfigFile = toml::from_str(&toml::to_string(&conf_value).unwrap()).unwrap();
+    assert!(Config::try_from(conf_file).is_err());
+}
+
+#[test]
+fn test_config_store_identity_in_keystore() {
+    let keystore_path = "/tmp/wrb-test-config-identity-keystore.json";
+    if fs::metadata(keystore_path).is_ok() {
+        fs::remove_file(keystore_path).unwrap();
+    }
+
+    let mut conf = Config::default(true, "localhost", 20443);
+    let identity = conf.identity().cloned().unwrap();
+    conf.store_identity_in_keystore_with_work_factor(keystore_path, "hunter2", TEST_LOG_N)
+        .unwrap();
+
+    // still unlocked
+    assert!(!conf.is_locked());
+    assert_eq!(conf.identity(), Some(&identity));
+    assert_eq!(
+        conf.keystore_path().map(|s| s.as_str()),
+        Some(keystore_path)
+    );
+
+    // the config file only refers to the keystore
+    let conf_toml = toml::to_string(&ConfigFile::from(conf)).unwrap();
+    assert!(conf_toml.contains(keystore_path));
+    assert!(!conf_toml.contains("private_key"));
+    assert!(!conf_toml.contains("mnemonic"));
+    assert!(!conf_toml.contains(&identity.mnemonic()));
+
+    // and the keystore holds the mnemonic
+    let conf_file: ConfigFile = toml::from_str(&conf_toml).unwrap();
+    let mut conf = Config::try_from(conf_file).unwrap();
+    assert!(conf.is_locked());
+    assert!(conf.unlock_keystore("hunter3").is_err());
+    conf.unlock_keystore("hunter2").unwrap();
+    assert_eq!(conf.identity(), Some(&identity));
+    assert_eq!(conf.private_key(), &identity.root_key());
+
+    fs::remove_file(keystore_path).unwrap();
+}
@@ -43,13 +43,16 @@ use std::fs;
 use std::io::{stdin, stdout, Read};
 use std::path::Path;
 use std::process;
+use std::sync::mpsc::channel;
 use std::thread;
 use std::time::Duration;
 
 use crate::core::Config;
 use crate::core::ConfigFile;
+use crate::core::LoadError;
 use crate::runner::bns::BNSResolver;
 use crate::runner::bns::NodeBNSResolver;
+use crate::runner::identities::SiteIdentityDB;
 use crate::runner::site::WrbTxtRecord;
 use crate::runner::site::WrbTxtRecordV1;
 use crate::runner::site::ZonefileResourceRecord;
@@ -62,6 +65,7 @@ use crate::tx::Txid;
 
 use crate::ui::events::WrbChannels;
 use crate::ui::events::WrbEvent;
+use crate::ui::scanline::ColorDepth;
 use crate::ui::Renderer;
 use crate::viewer::Viewer;
 use crate::vm::ClarityVM;
@@ -75,6 +79,7 @@ use crate::storage::WrbpodSlices;
 use crate::storage::WrbpodSuperblock;
 
 use crate::core::globals::redirect_logfile;
+use crate::core::keystore::{read_new_passphrase, read_passphrase};
 use crate::core::with_global_config;
 use crate::core::with_globals;
 
@@ -109,10 +114,12 @@ use libstackerdb::StackerDBChunkData;
 
 use cli::{
     consume_arg, load_wrbsite_source, make_runner, split_fqn, subcommand_bns, subcommand_clarity,
-    subcommand_site, subcommand_wrbpod, usage,
+    subcommand_key, subcommand_site, subcommand_tx, subcommand_wrbpod, usage,
 };
 
 const DEFAULT_CONFIG: &str = ".wrb/config.toml";
+/// Name of the keystore created beside a new config file, to hold its default identity
+const DEFAULT_KEYSTORE_FILENAME: &str = "keystore.json";
 
 fn main() {
     let mut argv: Vec<String> = env::args().collect();
@@ -140,6 +147,14 @@ fn main() {
         })
         .unwrap();
 
+    // get the identity to run as, if given
+    let identity_opt = consume_arg(&mut argv, &["-i", "--identity"], true)
+        .map_err(|e| {
+            usage(&e);
+            unreachable!()
+        })
+        .unwrap();
+
     // get the wrbsite data source, if given
     let wrbsite_data_source_opt = consume_arg(&mut argv, &["-s", "--source"], true)
         .map_err(|e| {
@@ -156,16 +171,47 @@ fn main() {
 
     let wrbsite_name = argv[1].clone();
 
-    // create the config file if it doesn't exist
+    // create the config file if it doesn't exist, with a new identity in a keystore beside it
+    let mut new_passphrase_opt = None;
     if fs::metadata(&conf_path).is_err() {
-        let default_conf = Config::default(true, "localhost", 20443);
-        let default_conf_file = ConfigFile::from(default_conf);
-        let default_conf_str = toml::to_string(&default_conf_file).unwrap();
-
         let conf_pathbuf = Path::new(&conf_path);
         if let Some(conf_dir) = conf_pathbuf.parent() {
             fs::create_dir_all(&conf_dir).unwrap();
         }
+
+        let mut default_conf = Config::default(true, "localhost", 20443);
+        // relative keystore paths in the config file are relative to the config file, so write
+        // an absolute one
+        let keystore_path = env::current_dir()
+            .map(|cwd| cwd.join(conf_pathbuf))
+            .unwrap_or_else(|_| conf_pathbuf.to_path_buf())
+            .with_file_name(DEFAULT_KEYSTORE_FILENAME)
+            .display()
+            .to_string();
+        eprintln!(
+            "Creating a new identity in {}. Choose a passphrase to encrypt it with.",
+            &keystore_path
+        );
+        let passphrase = read_new_passphrase().unwrap_or_else(|e| {
+            eprintln!("FATAL: {}", &e);
+            process::exit(1);
+        });
+        default_conf
+            .store_identity_in_keystore(&keystore_path, &passphrase)
+            .unwrap_or_else(|e| {
+                eprintln!("FATAL: {}", &e);
+                process::exit(1);
+            });
+        if let Some(identity) = default_conf.identity() {
+            eprintln!(
+                "Write down this mnemonic and keep it safe.  It is the only backup of your identity:"
+            );
+            eprintln!("{}", &identity.mnemonic());
+        }
+        new_passphrase_opt = Some(passphrase);
+
+        let default_conf_file = ConfigFile::from(default_conf);
+        let default_conf_str = toml::to_string(&default_conf_file).unwrap();
         fs::write(&conf_path, default_conf_str)
             .map_err(|e| {
                 eprintln!(
@@ -178,7 +224,7 @@ fn main() {
     }
 
     // load up config
-    let conf = Config::from_path(&conf_path)
+    let mut conf = Config::from_path(&conf_path)
         .map_err(|e| {
             usage(&format!(
                 "Could not load config from '{}': {}",
@@ -188,6 +234,14 @@ fn main() {
         })
         .unwrap();
 
+    // no need to ask again for the passphrase of the keystore we just made
+    if let Some(passphrase) = new_passphrase_opt {
+        conf.unlock_keystore(&passphrase).unwrap_or_else(|e| {
+            eprintln!("FATAL: {}", &e);
+            process::exit(1);
+        });
+    }
+
     // set up the wrb client
     let db_path = conf.db_path();
     if fs::metadata(&db_path).is_err() {
@@ -198,10 +252,56 @@ fn main() {
             })
             .unwrap();
     }
-    core::init_config(conf.clone());
 
     // this might be a command instead of a wrbsite
     let cmd = argv[1].clone();
+    let is_wrbsite = !["clarity", "wrbpod", "bns", "site", "tx", "key"].contains(&cmd.as_str());
+
+    // pick the identity to run as.  Wrbsites run as whichever identity the user last chose for
+    // them, unless overridden.
+    let site_identity_opt = if identity_opt.is_none() && is_wrbsite {
+        SiteIdentityDB::open(&conf.site_identities_path())
+            .and_then(|db| db.get_site_identity(&wrbsite_name))
+            .unwrap_or_else(|e| {
+                eprintln!("FATAL: failed to load site identities: {:?}", &e);
+                process::exit(1);
+            })
+            .filter(|identity| {
+                let known = conf.identity_names().contains(identity);
+                if !known {
+                    eprintln!(
+                        "WARN: {} runs as identity '{}', which is no longer configured",
+                        &wrbsite_name, identity
+                    );
+                }
+                known
+            })
+    } else {
+        None
+    };
+    if let Some(identity) = identity_opt.or(site_identity_opt) {
+        conf.select_identity(&identity).unwrap_or_else(|e| {
+            eprintln!("FATAL: {}", &e);
+            process::exit(1);
+        });
+    }
+
+    // unlock a wrbsite's identity key up front, since the viewer can't prompt for the
+    // passphrase once it's running.  Commands unlock it themselves if they need it.
+    if is_wrbsite && conf.is_locked() {
+        let keystore_path = conf.keystore_path().cloned().unwrap_or_default();
+        let passphrase = read_passphrase(&format!("Passphrase for {}: ", &keystore_path))
+            .unwrap_or_else(|e| {
+                eprintln!("FATAL: {}", &e);
+                process::exit(1);
+            });
+        conf.unlock_keystore(&passphrase).unwrap_or_else(|e| {
+            eprintln!("FATAL: failed to unlock '{}': {}", &keystore_path, &e);
+            process::exit(1);
+        });
+    }
+    core::init_config(conf.clone());
+
     if cmd == "clarity" {
         // clarity tooling mode
         subcommand_clarity(argv);
@@ -218,31 +318,72 @@ fn main() {
         // site tooling mode
         subcommand_site(argv);
         process::exit(0);
+    } else if cmd == "tx" {
+        // transaction tooling mode
+        subcommand_tx(argv);
+        process::exit(0);
+    } else if cmd == "key" {
+        // keystore tooling mode
+        subcommand_key(argv);
+        process::exit(0);
     }
 
     redirect_logfile(&debug_path_opt.unwrap_or(conf.debug_path())).unwrap();
 
     wrb_debug!("Booted up");
 
-    let (bytes, version) = load_wrbsite_source(&wrbsite_name, wrbsite_data_source_opt)
-        .map_err(|e| {
-            usage(&e);
-            unreachable!()
-        })
-        .unwrap();
+    // if the wrbsite's publisher changed, then don't load it until the user trusts the new one
+    let (loaded_opt, publisher_changed_opt) =
+        match load_wrbsite_source(&wrbsite_name, wrbsite_data_source_opt.clone()) {
+            Ok(loaded) => (Some(loaded), None),
+            Err(LoadError::PublisherChanged(old_pin, new_pin)) => (None, Some((old_pin, new_pin))),
+            Err(e) => {
+                usage(&e.to_string());
+                unreachable!()
+            }
+        };
 
-    // load the page
-    let mut vm =
-        ClarityVM::new(&db_path, &wrbsite_name, version).expect("Failed to instantiate ClarityVM");
     let mut renderer = Renderer::new(1_000_000_000);
 
     let (render_channels, ui_channels) = WrbChannels::new();
+    let (accept_pin_send, accept_pin_recv) = channel();
 
     let event_pipe = ui_channels.get_event_sender();
-    let viewer = Viewer::new(ui_channels, &wrbsite_name);
+    let mut viewer = Viewer::new(ui_channels, &wrbsite_name)
+        .with_identities(
+            conf.identity_names(),
+            conf.active_identity_name(),
+            conf.site_identities_path(),
+        )
+        .with_color_depth(conf.color_depth().unwrap_or_else(ColorDepth::from_env));
+
+    if let Some((old_pin, new_pin)) = publisher_changed_opt {
+        viewer = viewer.with_publisher_changed(&old_pin, new_pin, accept_pin_send);
+    }
 
     let render_event_pipe = event_pipe.clone();
     let render_handle = thread::spawn(move || {
+        let (bytes, version) = match loaded_opt {
+            Some(loaded) => loaded,
+            None => {
+                // wait for the user to trust the new publisher
+                if accept_pin_recv.recv().is_err() {
+                    return;
+                }
+                match load_wrbsite_source(&wrbsite_name, wrbsite_data_source_opt) {
+                    Ok(loaded) => loaded,
+                    Err(e) => {
+                        wrb_error!("Failed to load '{}': {}", &wrbsite_name, &e);
+                        let _ = render_event_pipe.send(WrbEvent::Close);
+                        return;
+                    }
+                }
+            }
+        };
+
+        // load the page
+        let mut vm = ClarityVM::new(&db_path, &wrbsite_name, version)
+            .expect("Failed to instantiate ClarityVM");
         if let Err(e) = renderer.run_page(&mut vm, &bytes, render_channels) {
             wrb_error!("Failed to run page: {:?}", &e);
             let _ = render_event_pipe.send(WrbEvent::Close);
@@ -22,11 +22,13 @@ use std::{io, mem};
 
 use clarity::vm::types::{QualifiedContractIdentifier, StandardPrincipalData};
 use clarity::vm::ContractName;
+use libstackerdb::StackerDBChunkData;
 use sha2::{Digest, Sha512_256};
 use stacks_common::codec::{
     read_next, read_next_at_most, read_next_exact, write_next, Error as codec_error,
     StacksMessageCodec, MAX_MESSAGE_LEN, MAX_RELAYERS_LEN, PREAMBLE_ENCODED_SIZE,
 };
+use stacks_common::types::chainstate::ConsensusHash;
 use stacks_common::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash};
 use stacks_common::types::net::PeerAddress;
 use stacks_common::types::StacksPublicKeyBuffer;
@@ -357,6 +359,86 @@ impl StacksMessageCodec for StackerDBHandshakeData {
     }
 }
 
+impl StacksMessageCodec for StackerDBGetChunkInvData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        contract_id_consensus_serialize(fd, &self.contract_id)?;
+        write_next(fd, &self.rc_consensus_hash)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<StackerDBGetChunkInvData, codec_error> {
+        let contract_id: QualifiedContractIdentifier = contract_id_consensus_deserialize(fd)?;
+        let rc_consensus_hash: ConsensusHash = read_next(fd)?;
+        Ok(StackerDBGetChunkInvData {
+            contract_id,
+            rc_consensus_hash,
+        })
+    }
+}
+
+impl StacksMessageCodec for StackerDBChunkInvData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        if self.slot_versions.len() > (STACKERDB_INV_MAX as usize) {
+            return Err(codec_error::ArrayTooLong);
+        }
+        write_next(fd, &self.slot_versions)?;
+        write_next(fd, &self.num_outbound_replicas)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<StackerDBChunkInvData, codec_error> {
+        let slot_versions: Vec<u32> = read_next_at_most(fd, STACKERDB_INV_MAX)?;
+        let num_outbound_replicas: u32 = read_next(fd)?;
+        Ok(StackerDBChunkInvData {
+            slot_versions,
+            num_outbound_replicas,
+        })
+    }
+}
+
+impl StacksMessageCodec for StackerDBGetChunkData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        contract_id_consensus_serialize(fd, &self.contract_id)?;
+        write_next(fd, &self.rc_consensus_hash)?;
+        write_next(fd, &self.slot_id)?;
+        write_next(fd, &self.slot_version)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<StackerDBGetChunkData, codec_error> {
+        let contract_id: QualifiedContractIdentifier = contract_id_consensus_deserialize(fd)?;
+        let rc_consensus_hash: ConsensusHash = read_next(fd)?;
+        let slot_id: u32 = read_next(fd)?;
+        let slot_version: u32 = read_next(fd)?;
+        Ok(StackerDBGetChunkData {
+            contract_id,
+            rc_consensus_hash,
+            slot_id,
+            slot_version,
+        })
+    }
+}
+
+impl StacksMessageCodec for StackerDBPushChunkData {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
+        contract_id_consensus_serialize(fd, &self.contract_id)?;
+        write_next(fd, &self.rc_consensus_hash)?;
+        write_next(fd, &self.chunk_data)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<StackerDBPushChunkData, codec_error> {
+        let contract_id: QualifiedContractIdentifier = contract_id_consensus_deserialize(fd)?;
+        let rc_consensus_hash: ConsensusHash = read_next(fd)?;
+        let chunk_data: StackerDBChunkData = read_next(fd)?;
+        Ok(StackerDBPushChunkData {
+            contract_id,
+            rc_consensus_hash,
+            chunk_data,
+        })
+    }
+}
+
 impl StacksMessageCodec for RelayData {
     fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), codec_error> {
         write_next(fd, &self.peer)?;
@@ -382,9 +464,23 @@ impl StacksMessageCodec for StacksMessageID {
             x if x == StacksMessageID::Handshake as u8 => StacksMessageID::Handshake,
             x if x == StacksMessageID::HandshakeAccept as u8 => StacksMessageID::HandshakeAccept,
             x if x == StacksMessageID::HandshakeReject as u8 => StacksMessageID::HandshakeReject,
+            x if x == StacksMessageID::Nack as u8 => StacksMessageID::Nack,
             x if x == StacksMessageID::StackerDBHandshakeAccept as u8 => {
                 StacksMessageID::StackerDBHandshakeAccept
             }
+            x if x == StacksMessageID::StackerDBGetChunkInv as u8 => {
+                StacksMessageID::StackerDBGetChunkInv
+            }
+            x if x == StacksMessageID::StackerDBChunkInv as u8 => {
+                StacksMessageID::StackerDBChunkInv
+            }
+            x if x == StacksMessageID::StackerDBGetChunk as u8 => {
+                StacksMessageID::StackerDBGetChunk
+            }
+            x if x == StacksMessageID::StackerDBChunk as u8 => StacksMessageID::StackerDBChunk,
+            x if x == StacksMessageID::StackerDBPushChunk as u8 => {
+                StacksMessageID::StackerDBPushChunk
+            }
             _ => {
                 return Err(codec_error::DeserializeError(
                     "Unknown message ID".to_string(),
@@ -405,6 +501,13 @@ impl StacksMessageType {
             StacksMessageType::StackerDBHandshakeAccept(ref _h, ref _m) => {
                 StacksMessageID::StackerDBHandshakeAccept
             }
+            StacksMessageType::StackerDBGetChunkInv(ref _m) => {
+                StacksMessageID::StackerDBGetChunkInv
+            }
+            StacksMessageType::StackerDBChunkInv(ref _m) => StacksMessageID::StackerDBChunkInv,
+            StacksMessageType::StackerDBGetChunk(ref _m) => StacksMessageID::StackerDBGetChunk,
+            StacksMessageType::StackerDBChunk(ref _m) => StacksMessageID::StackerDBChunk,
+            StacksMessageType::StackerDBPushChunk(ref _m) => StacksMessageID::StackerDBPushChunk,
         }
     }
 }
@@ -421,6 +524,11 @@ impl StacksMessageCodec for StacksMessageType {
                 write_next(fd, h)?;
                 write_next(fd, m)?
             }
+            StacksMessageType::StackerDBGetChunkInv(ref m) => write_next(fd, m)?,
+            StacksMessageType::StackerDBChunkInv(ref m) => write_next(fd, m)?,
+            StacksMessageType::StackerDBGetChunk(ref m) => write_next(fd, m)?,
+            StacksMessageType::StackerDBChunk(ref m) => write_next(fd, m)?,
+            StacksMessageType::StackerDBPushChunk(ref m) => write_next(fd, m)?,
         }
         Ok(())
     }
@@ -446,6 +554,26 @@ impl StacksMessageCodec for StacksMessageType {
                 let m: StackerDBHandshakeData = read_next(fd)?;
                 StacksMessageType::StackerDBHandshakeAccept(h, m)
             }
+            StacksMessageID::StackerDBGetChunkInv => {
+                let m: StackerDBGetChunkInvData = read_next(fd)?;
+                StacksMessageType::StackerDBGetChunkInv(m)
+            }
+            StacksMessageID::StackerDBChunkInv => {
+                let m: StackerDBChunkInvData = read_next(fd)?;
+                StacksMessageType::StackerDBChunkInv(m)
+            }
+            StacksMessageID::StackerDBGetChunk => {
+                let m: StackerDBGetChunkData = read_next(fd)?;
+                StacksMessageType::StackerDBGetChunk(m)
+            }
+            StacksMessageID::StackerDBChunk => {
+                let m: StackerDBChunkData = read_next(fd)?;
+                StacksMessageType::StackerDBChunk(m)
+            }
+            StacksMessageID::StackerDBPushChunk => {
+                let m: StackerDBPushChunkData = read_next(fd)?;
+                StacksMessageType::StackerDBPushChunk(m)
+            }
         };
         Ok(message)
     }
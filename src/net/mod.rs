@@ -56,6 +56,9 @@ use serde::{Deserialize, Serialize};
 pub mod codec;
 pub mod session;
 
+#[cfg(test)]
+pub mod tests;
+
 #[derive(Debug)]
 pub enum Error {
     /// Failed to encode
@@ -473,6 +476,52 @@ pub struct StackerDBHandshakeData {
     pub smart_contracts: Vec<QualifiedContractIdentifier>,
 }
 
+/// Request for a StackerDB's chunk inventory
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackerDBGetChunkInvData {
+    /// smart contract being used to determine chunk quantity and order
+    pub contract_id: QualifiedContractIdentifier,
+    /// consensus hash of the Stacks chain tip in this reward cycle
+    pub rc_consensus_hash: ConsensusHash,
+}
+
+/// Inventory bitvector for chunks in a StackerDB replica
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackerDBChunkInvData {
+    /// version vector of chunks available.
+    /// The max-length is a protocol constant.
+    pub slot_versions: Vec<u32>,
+    /// number of outbound replicas the sender is connected to
+    pub num_outbound_replicas: u32,
+}
+
+/// Request for a StackerDB chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackerDBGetChunkData {
+    /// smart contract being used to determine slot quantity and order
+    pub contract_id: QualifiedContractIdentifier,
+    /// consensus hash of the Stacks chain tip in this reward cycle
+    pub rc_consensus_hash: ConsensusHash,
+    /// slot ID
+    pub slot_id: u32,
+    /// last-seen slot version
+    pub slot_version: u32,
+}
+
+/// Push a StackerDB chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackerDBPushChunkData {
+    /// smart contract being used to determine chunk quantity and order
+    pub contract_id: QualifiedContractIdentifier,
+    /// consensus hash of the Stacks chain tip in this reward cycle
+    pub rc_consensus_hash: ConsensusHash,
+    /// the pushed chunk
+    pub chunk_data: StackerDBChunkData,
+}
+
+/// Maximum number of slots in a StackerDB chunk inventory
+pub const STACKERDB_INV_MAX: u32 = 4096;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct NackData {
     pub error_code: u32,
@@ -515,6 +564,11 @@ pub enum StacksMessageType {
     HandshakeReject,
     Nack(NackData),
     StackerDBHandshakeAccept(HandshakeAcceptData, StackerDBHandshakeData),
+    StackerDBGetChunkInv(StackerDBGetChunkInvData),
+    StackerDBChunkInv(StackerDBChunkInvData),
+    StackerDBGetChunk(StackerDBGetChunkData),
+    StackerDBChunk(StackerDBChunkData),
+    StackerDBPushChunk(StackerDBPushChunkData),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -525,6 +579,11 @@ pub enum StacksMessageID {
     HandshakeReject = 2,
     Nack = 14,
     StackerDBHandshakeAccept = 19,
+    StackerDBGetChunkInv = 21,
+    StackerDBChunkInv = 22,
+    StackerDBGetChunk = 23,
+    StackerDBChunk = 24,
+    StackerDBPushChunk = 25,
 }
 
 /// Message type for all P2P Stacks network messages
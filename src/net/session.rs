@@ -21,16 +21,20 @@ use std::collections::HashSet;
 use std::io::prelude::*;
 use std::io::Read;
 use std::net::TcpStream;
+use std::sync::Mutex;
+use std::time::Duration;
 use std::{io, mem};
 
 use clarity::vm::types::{QualifiedContractIdentifier, StandardPrincipalData};
 use clarity::vm::ContractName;
+use libstackerdb::StackerDBChunkData;
 use sha2::{Digest, Sha512_256};
 use stacks_common::codec::{
     read_next, read_next_at_most, read_next_exact, write_next, Error as codec_error,
     StacksMessageCodec, MAX_MESSAGE_LEN, MAX_RELAYERS_LEN, PREAMBLE_ENCODED_SIZE,
 };
 
+use stacks_common::types::chainstate::ConsensusHash;
 use stacks_common::types::chainstate::SortitionId;
 use stacks_common::types::chainstate::StacksPrivateKey;
 use stacks_common::types::chainstate::{BlockHeaderHash, BurnchainHeaderHash};
@@ -44,6 +48,7 @@ use stacks_common::util::secp256k1::{
 };
 
 use crate::net::*;
+use crate::runner::http::{rpc_connect, HttpConnectionPool};
 use crate::runner::RPCPeerInfoData;
 use crate::runner::Runner;
 use crate::stacks_common::types::{PrivateKey, PublicKey};
@@ -173,6 +178,12 @@ pub struct NodeSession {
 }
 
 impl NodeSession {
+    /// Make each later read and write on the session's socket give up within `timeout`
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<(), io::Error> {
+        self.tcp_socket.set_read_timeout(Some(timeout))?;
+        self.tcp_socket.set_write_timeout(Some(timeout))
+    }
+
     /// Make a StacksMessage.  Sign it and set a sequence number.
     fn make_peer_message(&mut self, payload: StacksMessageType) -> Result<StacksMessage, String> {
         let mut msg = StacksMessage::new(
@@ -215,12 +226,14 @@ impl NodeSession {
     /// Returns error text on failure.
     pub fn begin(data_addr: SocketAddr, replica_peer_addr: SocketAddr) -> Result<Self, String> {
         // get /v2/info
-        let peer_info = Runner::run_get_info(&data_addr)
+        let pool = Mutex::new(HttpConnectionPool::default());
+        let peer_info = Runner::run_get_info(&pool, &data_addr)
             .map_err(|e| format!("Failed to query /v2/info: {:?}", &e))?;
 
         // convert `pox_consensus` and `stable_pox_consensus` into their respective burn block
         // hashes
         let sort_info = Runner::run_get_sortition_info(
+            &pool,
             &data_addr,
             "consensus",
             &format!("{}", &peer_info.pox_consensus),
@@ -230,6 +243,7 @@ impl NodeSession {
         .ok_or_else(|| format!("No sortition returned for {}", &peer_info.pox_consensus))?;
 
         let stable_sort_info = Runner::run_get_sortition_info(
+            &pool,
             &data_addr,
             "consensus",
             &format!("{}", &peer_info.stable_pox_consensus),
@@ -256,7 +270,7 @@ impl NodeSession {
             UrlString::try_from(format!("http://127.0.0.1:{}", data_addr.port()).as_str()).unwrap(),
         );
 
-        let tcp_socket = TcpStream::connect(&replica_peer_addr)
+        let tcp_socket = rpc_connect(&replica_peer_addr)
             .map_err(|e| format!("Failed to open {:?}: {:?}", &replica_peer_addr, &e))?;
 
         let mut session = Self {
@@ -299,3 +313,126 @@ impl NodeSession {
         Ok(session)
     }
 }
+
+/// Most unrelated messages we'll skip while waiting for a reply
+const MAX_UNSOLICITED_MESSAGES: usize = 32;
+
+impl NodeSession {
+    /// Send a request and wait for the peer's reply to it.
+    /// Messages the peer sends in the meantime which aren't replies to this request are skipped.
+    ///
+    /// Returns the reply's payload on success.
+    /// Returns error text on failure.
+    fn request(&mut self, payload: StacksMessageType) -> Result<StacksMessageType, String> {
+        let msg = self.make_peer_message(payload)?;
+        let seq = msg.preamble.seq;
+        self.send_peer_message(msg)?;
+
+        for _ in 0..MAX_UNSOLICITED_MESSAGES {
+            let resp = self.recv_peer_message()?;
+            if resp.preamble.seq != seq {
+                wrb_debug!("Skipping unsolicited message {:?}", &resp);
+                continue;
+            }
+            return Ok(resp.payload);
+        }
+        Err(format!(
+            "Peer did not reply to request {} after {} messages",
+            seq, MAX_UNSOLICITED_MESSAGES
+        ))
+    }
+
+    /// Does the peer replicate this StackerDB?
+    pub fn replicates_stackerdb(&self, contract_id: &QualifiedContractIdentifier) -> bool {
+        self.stackerdb_accept_data
+            .as_ref()
+            .map(|data| data.smart_contracts.contains(contract_id))
+            .unwrap_or(false)
+    }
+
+    /// Get the reward cycle consensus hash the peer's StackerDBs are bound to
+    fn stackerdb_rc_consensus_hash(
+        &self,
+        contract_id: &QualifiedContractIdentifier,
+    ) -> Result<ConsensusHash, String> {
+        if !self.replicates_stackerdb(contract_id) {
+            return Err(format!("Peer does not replicate StackerDB {}", contract_id));
+        }
+        self.stackerdb_accept_data
+            .as_ref()
+            .map(|data| data.rc_consensus_hash.clone())
+            .ok_or_else(|| "Peer does not support StackerDBs".to_string())
+    }
+
+    /// Get the slot versions of a StackerDB from the peer.
+    /// Returns error text on failure, including if the peer NACKs the request.
+    pub fn get_stackerdb_chunk_inv(
+        &mut self,
+        contract_id: &QualifiedContractIdentifier,
+    ) -> Result<StackerDBChunkInvData, String> {
+        let rc_consensus_hash = self.stackerdb_rc_consensus_hash(contract_id)?;
+        let resp = self.request(StacksMessageType::StackerDBGetChunkInv(
+            StackerDBGetChunkInvData {
+                contract_id: contract_id.clone(),
+                rc_consensus_hash,
+            },
+        ))?;
+        match resp {
+            StacksMessageType::StackerDBChunkInv(inv) => Ok(inv),
+            StacksMessageType::Nack(nack) => Err(format!(
+                "Peer NACKed chunk inventory request for {}: error code {}",
+                contract_id, nack.error_code
+            )),
+            x => Err(format!(
+                "Peer returned unexpected message (expected StackerDBChunkInv): {:?}",
+                &x
+            )),
+        }
+    }
+
+    /// Get a specific version of a StackerDB chunk from the peer.
+    /// Returns Ok(Some(chunk)) if the peer has this version of the chunk.
+    /// Returns Ok(None) if the peer has a different version of the chunk.
+    /// Returns error text on failure.
+    pub fn get_stackerdb_chunk(
+        &mut self,
+        contract_id: &QualifiedContractIdentifier,
+        slot_id: u32,
+        slot_version: u32,
+    ) -> Result<Option<StackerDBChunkData>, String> {
+        let rc_consensus_hash = self.stackerdb_rc_consensus_hash(contract_id)?;
+        let resp = self.request(StacksMessageType::StackerDBGetChunk(
+            StackerDBGetChunkData {
+                contract_id: contract_id.clone(),
+                rc_consensus_hash,
+                slot_id,
+                slot_version,
+            },
+        ))?;
+        match resp {
+            StacksMessageType::StackerDBChunk(chunk) => {
+                if chunk.slot_id != slot_id || chunk.slot_version != slot_version {
+                    return Err(format!(
+                        "Peer returned chunk {}.{} instead of {}.{}",
+                        chunk.slot_id, chunk.slot_version, slot_id, slot_version
+                    ));
+                }
+                Ok(Some(chunk))
+            }
+            StacksMessageType::Nack(nack)
+                if nack.error_code == NackErrorCodes::StaleVersion
+                    || nack.error_code == NackErrorCodes::FutureVersion =>
+            {
+                Ok(None)
+            }
+            StacksMessageType::Nack(nack) => Err(format!(
+                "Peer NACKed request for chunk {}.{} in {}: error code {}",
+                slot_id, slot_version, contract_id, nack.error_code
+            )),
+            x => Err(format!(
+                "Peer returned unexpected message (expected StackerDBChunk): {:?}",
+                &x
+            )),
+        }
+    }
+}
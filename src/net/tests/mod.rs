@@ -0,0 +1,84 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use clarity::vm::types::QualifiedContractIdentifier;
+use libstackerdb::StackerDBChunkData;
+use stacks_common::codec::{read_next, StacksMessageCodec};
+use stacks_common::types::chainstate::ConsensusHash;
+use stacks_common::types::chainstate::StacksPrivateKey;
+
+use crate::net::*;
+
+fn check_codec(msg: StacksMessageType) {
+    let mut bytes = vec![];
+    msg.consensus_serialize(&mut bytes).unwrap();
+    assert_eq!(bytes[0], msg.get_message_id() as u8);
+
+    let decoded: StacksMessageType = read_next(&mut &bytes[..]).unwrap();
+    assert_eq!(decoded, msg);
+}
+
+#[test]
+fn test_stackerdb_message_codec() {
+    let contract_id =
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2")
+            .unwrap();
+    let rc_consensus_hash = ConsensusHash([0x11; 20]);
+
+    let mut chunk = StackerDBChunkData::new(3, 4, vec![1, 2, 3, 4, 5]);
+    chunk.sign(&StacksPrivateKey::random()).unwrap();
+
+    check_codec(StacksMessageType::Nack(NackData::new(
+        NackErrorCodes::StaleVersion,
+    )));
+    check_codec(StacksMessageType::StackerDBGetChunkInv(
+        StackerDBGetChunkInvData {
+            contract_id: contract_id.clone(),
+            rc_consensus_hash: rc_consensus_hash.clone(),
+        },
+    ));
+    check_codec(StacksMessageType::StackerDBChunkInv(
+        StackerDBChunkInvData {
+            slot_versions: vec![0, 1, 2, 3],
+            num_outbound_replicas: 5,
+        },
+    ));
+    check_codec(StacksMessageType::StackerDBGetChunk(
+        StackerDBGetChunkData {
+            contract_id: contract_id.clone(),
+            rc_consensus_hash: rc_consensus_hash.clone(),
+            slot_id: 3,
+            slot_version: 4,
+        },
+    ));
+    check_codec(StacksMessageType::StackerDBChunk(chunk.clone()));
+    check_codec(StacksMessageType::StackerDBPushChunk(
+        StackerDBPushChunkData {
+            contract_id: contract_id.clone(),
+            rc_consensus_hash: rc_consensus_hash.clone(),
+            chunk_data: chunk,
+        },
+    ));
+
+    // inventories are bounded
+    let too_big = StacksMessageType::StackerDBChunkInv(StackerDBChunkInvData {
+        slot_versions: vec![0; (STACKERDB_INV_MAX + 1) as usize],
+        num_outbound_replicas: 0,
+    });
+    let mut bytes = vec![];
+    assert!(too_big.consensus_serialize(&mut bytes).is_err());
+}
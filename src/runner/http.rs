@@ -19,16 +19,330 @@ use std::collections::HashMap;
 use std::io;
 use std::io::{Read, Write};
 use std::net::SocketAddr;
+use std::net::TcpStream;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
 use stacks_common::codec::MAX_MESSAGE_LEN;
 use stacks_common::deps_common::httparse;
 use stacks_common::util::chunked_encoding::*;
 
+use crate::core::with_global_config;
+use crate::runner::proxy::{proxy_config, proxy_connect};
 use crate::runner::Error;
 
 pub const MAX_HTTP_HEADERS: usize = 32;
 pub const MAX_HTTP_HEADER_LEN: usize = 4096;
 
+pub const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 5_000;
+pub const DEFAULT_READ_TIMEOUT_MS: u64 = 30_000;
+pub const DEFAULT_RPC_MAX_RETRIES: u32 = 3;
+pub const DEFAULT_RPC_RETRY_BACKOFF_MS: u64 = 250;
+/// Longest we'll ever wait between retries
+pub const MAX_RPC_RETRY_BACKOFF_MS: u64 = 30_000;
+
+/// Most idle keep-alive connections we hold open to any one node
+pub const DEFAULT_MAX_IDLE_CONNECTIONS_PER_HOST: usize = 4;
+
+/// Socket timeouts and retry policy for talking to nodes
+#[derive(Debug, Clone, PartialEq)]
+pub struct RPCTimeouts {
+    /// how long to wait for a TCP connection to be established
+    pub connect_timeout: Duration,
+    /// how long to wait on a single read or write
+    pub read_timeout: Duration,
+    /// how many times to retry an idempotent request after a network failure
+    pub max_retries: u32,
+    /// how long to wait before the first retry.  Doubles on each subsequent retry.
+    pub retry_backoff: Duration,
+}
+
+impl Default for RPCTimeouts {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_millis(DEFAULT_CONNECT_TIMEOUT_MS),
+            read_timeout: Duration::from_millis(DEFAULT_READ_TIMEOUT_MS),
+            max_retries: DEFAULT_RPC_MAX_RETRIES,
+            retry_backoff: Duration::from_millis(DEFAULT_RPC_RETRY_BACKOFF_MS),
+        }
+    }
+}
+
+impl RPCTimeouts {
+    /// These timeouts, but with every connect, read, and write giving up within `timeout`, and
+    /// with no retries.
+    pub fn bounded_by(&self, timeout: Duration) -> Self {
+        Self {
+            connect_timeout: self.connect_timeout.min(timeout),
+            read_timeout: self.read_timeout.min(timeout),
+            max_retries: 0,
+            retry_backoff: self.retry_backoff.clone(),
+        }
+    }
+
+    /// How long to wait before the given retry (0-indexed)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self.retry_backoff.saturating_mul(1u32 << attempt.min(16));
+        backoff.min(Duration::from_millis(MAX_RPC_RETRY_BACKOFF_MS))
+    }
+}
+
+/// Get the configured RPC timeouts, or the defaults if the system is not initialized
+pub fn rpc_timeouts() -> RPCTimeouts {
+    with_global_config(|cfg| cfg.rpc_timeouts()).unwrap_or_default()
+}
+
+/// Connect to a node, applying the configured connect and read/write timeouts.
+/// Goes through the configured proxy, if there is one.
+pub fn rpc_connect(node_addr: &SocketAddr) -> Result<TcpStream, Error> {
+    rpc_connect_ext(node_addr, &rpc_timeouts())
+}
+
+/// Connect to a node, applying the given connect and read/write timeouts.
+/// Goes through the configured proxy, if there is one.
+pub fn rpc_connect_ext(node_addr: &SocketAddr, timeouts: &RPCTimeouts) -> Result<TcpStream, Error> {
+    let sock = if let Some(proxy) = proxy_config() {
+        proxy_connect(&proxy, node_addr, timeouts.connect_timeout)?
+    } else {
+        TcpStream::connect_timeout(node_addr, timeouts.connect_timeout)?
+    };
+    sock.set_read_timeout(Some(timeouts.read_timeout))?;
+    sock.set_write_timeout(Some(timeouts.read_timeout))?;
+    Ok(sock)
+}
+
+/// Is this error the sort of thing that might go away if we try again?
+pub fn is_retryable_error(e: &Error) -> bool {
+    match e {
+        Error::Timeout(_) | Error::ConnectionRefused(_) | Error::IO(_) => true,
+        _ => false,
+    }
+}
+
+/// Run `todo` until it succeeds, fails with a non-retryable error, or we run out of retries.
+/// Waits with exponential backoff between attempts.
+/// If `idempotent` is false, then `todo` is only run once.
+pub fn with_rpc_retries<F, R>(
+    timeouts: &RPCTimeouts,
+    idempotent: bool,
+    what: &str,
+    mut todo: F,
+) -> Result<R, Error>
+where
+    F: FnMut() -> Result<R, Error>,
+{
+    let mut attempt = 0;
+    loop {
+        match todo() {
+            Ok(res) => {
+                return Ok(res);
+            }
+            Err(e) => {
+                if !idempotent || attempt >= timeouts.max_retries || !is_retryable_error(&e) {
+                    return Err(e);
+                }
+                let backoff = timeouts.backoff(attempt);
+                wrb_debug!(
+                    "{} failed (attempt {}): {:?}. Retrying in {:?}",
+                    what,
+                    attempt + 1,
+                    &e,
+                    &backoff
+                );
+                thread::sleep(backoff);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Idle keep-alive connections to nodes, keyed by address
+pub struct HttpConnectionPool {
+    idle: HashMap<SocketAddr, Vec<TcpStream>>,
+    max_idle_per_host: usize,
+}
+
+impl HttpConnectionPool {
+    pub fn new(max_idle_per_host: usize) -> Self {
+        Self {
+            idle: HashMap::new(),
+            max_idle_per_host,
+        }
+    }
+
+    /// Take an idle connection to `addr`, if there is one
+    pub fn take(&mut self, addr: &SocketAddr) -> Option<TcpStream> {
+        let conns = self.idle.get_mut(addr)?;
+        let sock = conns.pop();
+        if conns.is_empty() {
+            self.idle.remove(addr);
+        }
+        sock
+    }
+
+    /// Return a connection to the pool once its last response has been fully read.
+    /// It is dropped if there are already enough idle connections to `addr`.
+    pub fn put(&mut self, addr: &SocketAddr, sock: TcpStream) {
+        let conns = self.idle.entry(addr.clone()).or_insert_with(Vec::new);
+        if conns.len() < self.max_idle_per_host {
+            conns.push(sock);
+        }
+    }
+
+    /// How many idle connections to `addr` are there?
+    pub fn num_idle(&self, addr: &SocketAddr) -> usize {
+        self.idle.get(addr).map(|conns| conns.len()).unwrap_or(0)
+    }
+
+    /// Drop all idle connections
+    pub fn clear(&mut self) {
+        self.idle.clear();
+    }
+}
+
+impl Default for HttpConnectionPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_IDLE_CONNECTIONS_PER_HOST)
+    }
+}
+
+/// Run an HTTP request on a node, reusing an idle connection from `pool` if there is one.
+/// If a reused connection turns out to have been closed by the node, the request is tried once
+/// more on a fresh connection.  The connection goes back into the pool afterwards if the node
+/// is willing to keep it open.
+/// Requests that are not `idempotent` always go out on a fresh connection, since the node may
+/// have acted on one sent over a connection that then failed, and it must not be sent twice.
+/// A non-200 status is not an error here; see `run_pooled_http_request()`.
+pub fn run_pooled_http_request_full(
+    pool: &Mutex<HttpConnectionPool>,
+    node_addr: &SocketAddr,
+    verb: &str,
+    path: &str,
+    content_type: Option<&str>,
+    payload: &[u8],
+    idempotent: bool,
+) -> Result<HttpResponse, Error> {
+    let reused = if idempotent {
+        pool.lock()
+            .expect("FATAL: connection pool lock poisoned")
+            .take(node_addr)
+    } else {
+        None
+    };
+    if let Some(mut sock) = reused {
+        match run_http_request_full(&mut sock, node_addr, verb, path, content_type, payload) {
+            Ok(resp) => {
+                if resp.keep_alive {
+                    pool.lock()
+                        .expect("FATAL: connection pool lock poisoned")
+                        .put(node_addr, sock);
+                }
+                return Ok(resp);
+            }
+            Err(Error::IO(e)) => {
+                wrb_debug!(
+                    "Idle connection to {} is no longer usable ({:?}); reconnecting",
+                    node_addr,
+                    &e
+                );
+            }
+            Err(e) => {
+                return Err(e);
+            }
+        }
+    }
+
+    let mut sock = rpc_connect(node_addr)?;
+    let resp = run_http_request_full(&mut sock, node_addr, verb, path, content_type, payload)?;
+    if resp.keep_alive {
+        pool.lock()
+            .expect("FATAL: connection pool lock poisoned")
+            .put(node_addr, sock);
+    }
+    Ok(resp)
+}
+
+/// Run an HTTP request on a node, reusing an idle connection from `pool` if there is one.
+/// Return the HTTP reply, decoded if it was chunked.  A non-200 status is an Error::HttpError.
+pub fn run_pooled_http_request(
+    pool: &Mutex<HttpConnectionPool>,
+    node_addr: &SocketAddr,
+    verb: &str,
+    path: &str,
+    content_type: Option<&str>,
+    payload: &[u8],
+    idempotent: bool,
+) -> Result<Vec<u8>, Error> {
+    run_pooled_http_request_full(
+        pool,
+        node_addr,
+        verb,
+        path,
+        content_type,
+        payload,
+        idempotent,
+    )?
+    .into_body()
+}
+
+/// Run an HTTP request on a node, with the configured timeouts.  Connections are kept alive in
+/// `pool` and reused across requests.
+/// Idempotent requests are retried on network failures.
+/// Return the full HTTP response, whatever its status.
+pub fn run_rpc_request_full(
+    pool: &Mutex<HttpConnectionPool>,
+    node_addr: &SocketAddr,
+    verb: &str,
+    path: &str,
+    content_type: Option<&str>,
+    payload: &[u8],
+    idempotent: bool,
+) -> Result<HttpResponse, Error> {
+    let timeouts = rpc_timeouts();
+    with_rpc_retries(
+        &timeouts,
+        idempotent,
+        &format!("{} {} on {}", verb, path, node_addr),
+        || {
+            run_pooled_http_request_full(
+                pool,
+                node_addr,
+                verb,
+                path,
+                content_type,
+                payload,
+                idempotent,
+            )
+        },
+    )
+}
+
+/// Run an HTTP request on a node, with the configured timeouts.  Connections are kept alive in
+/// `pool` and reused across requests.
+/// Idempotent requests are retried on network failures.
+/// Return the HTTP reply, decoded if it was chunked
+pub fn run_rpc_request(
+    pool: &Mutex<HttpConnectionPool>,
+    node_addr: &SocketAddr,
+    verb: &str,
+    path: &str,
+    content_type: Option<&str>,
+    payload: &[u8],
+    idempotent: bool,
+) -> Result<Vec<u8>, Error> {
+    run_rpc_request_full(
+        pool,
+        node_addr,
+        verb,
+        path,
+        content_type,
+        payload,
+        idempotent,
+    )?
+    .into_body()
+}
+
 /// Decoding of the relevant parts of a signer-directed HTTP request from the Stacks node
 #[derive(Debug)]
 pub struct WrbHttpRequest {
@@ -220,16 +534,146 @@ pub fn decode_http_body(headers: &HashMap<String, String>, mut buf: &[u8]) -> io
     Ok(body)
 }
 
-/// Run an HTTP request, synchronously, through the given read/write handle
-/// Return the HTTP reply, decoded if it was chunked
-pub fn run_http_request<S: Read + Write>(
+/// Read an HTTP response header block from `sock`, up to and including the terminating blank
+/// line.  Reads one byte at a time so that nothing past the headers is consumed.
+pub(crate) fn read_http_response_headers<S: Read>(sock: &mut S) -> Result<Vec<u8>, Error> {
+    let max_len = MAX_HTTP_HEADERS * MAX_HTTP_HEADER_LEN;
+    let mut buf = Vec::with_capacity(1024);
+    let mut byte = [0u8; 1];
+    while !buf.ends_with(b"\r\n\r\n") {
+        if buf.len() >= max_len {
+            return Err(Error::Deserialize(
+                "HTTP response headers are too big".to_string(),
+            ));
+        }
+        let nr = sock.read(&mut byte)?;
+        if nr == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        buf.push(byte[0]);
+    }
+    Ok(buf)
+}
+
+/// Read one CRLF-terminated line (without the CRLF) from `sock`.
+/// Returns None if the stream ended before any bytes were read.
+fn read_http_line<S: Read>(sock: &mut S) -> Result<Option<Vec<u8>>, Error> {
+    let mut line = vec![];
+    let mut byte = [0u8; 1];
+    loop {
+        let nr = sock.read(&mut byte)?;
+        if nr == 0 {
+            if line.is_empty() {
+                return Ok(None);
+            }
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        if byte[0] == b'\n' {
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            return Ok(Some(line));
+        }
+        if line.len() >= MAX_HTTP_HEADER_LEN {
+            return Err(Error::Deserialize("HTTP line is too long".to_string()));
+        }
+        line.push(byte[0]);
+    }
+}
+
+/// Read a chunked HTTP body from `sock`, stopping right after the terminating chunk so the
+/// connection can be reused.
+/// Returns the decoded body, and whether or not the body was properly terminated.
+fn read_http_chunked_body<S: Read>(sock: &mut S) -> Result<(Vec<u8>, bool), Error> {
+    let mut body = vec![];
+    loop {
+        let line = match read_http_line(sock)? {
+            Some(line) => line,
+            None => {
+                // stream ended without a terminating chunk
+                return Ok((body, false));
+            }
+        };
+        let line_str = String::from_utf8_lossy(&line);
+        let size_str = line_str.split(';').next().unwrap_or("").trim();
+        let chunk_len = usize::from_str_radix(size_str, 16).map_err(|_| {
+            Error::MalformedResponse(format!("Invalid HTTP chunk size \"{}\"", size_str))
+        })?;
+        if chunk_len == 0 {
+            // skip trailers
+            while let Some(trailer) = read_http_line(sock)? {
+                if trailer.is_empty() {
+                    return Ok((body, true));
+                }
+            }
+            return Ok((body, false));
+        }
+        if body.len().saturating_add(chunk_len) > MAX_MESSAGE_LEN as usize {
+            return Err(Error::MalformedResponse("HTTP body is too big".to_string()));
+        }
+        let offset = body.len();
+        body.resize(offset + chunk_len, 0);
+        sock.read_exact(&mut body[offset..])?;
+        match read_http_line(sock)? {
+            Some(trailer) if trailer.is_empty() => {}
+            _ => {
+                return Err(Error::MalformedResponse(
+                    "HTTP chunk is not terminated by CRLF".to_string(),
+                ));
+            }
+        }
+    }
+}
+
+/// A decoded HTTP response
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    /// HTTP status code
+    pub code: u32,
+    /// response headers, with lower-cased names
+    pub headers: HashMap<String, String>,
+    /// offset of the body in the raw response
+    pub body_offset: usize,
+    /// response body, decoded if it was chunked
+    pub body: Vec<u8>,
+    /// whether or not the connection can be reused for another request
+    pub keep_alive: bool,
+}
+
+impl HttpResponse {
+    /// Get the body of a 200 response, or an Error::HttpError for anything else
+    pub fn into_body(self) -> Result<Vec<u8>, Error> {
+        if self.code == 200 {
+            return Ok(self.body);
+        }
+
+        if self.body.is_empty() {
+            wrb_debug!("No HTTP body");
+        } else {
+            wrb_debug!(
+                "HTTP Error\nHTTP code: {}\nHTTP body: {}\n",
+                self.code,
+                &String::from_utf8_lossy(&self.body)
+            );
+        }
+        Err(Error::HttpError(self.code, self.headers, self.body_offset))
+    }
+}
+
+/// Run an HTTP/1.1 request, synchronously, through the given read/write handle.
+/// The response body is framed by its Content-Length or chunked encoding if possible, so the
+/// handle is left positioned at the start of the next response.  Otherwise, the body is read
+/// until EOF.
+/// Unlike `run_http_request_ext()`, a non-200 status is not an error; the caller gets the full
+/// response, including the body, so it can interpret the error itself.
+pub fn run_http_request_full<S: Read + Write>(
     sock: &mut S,
     host: &SocketAddr,
     verb: &str,
     path: &str,
     content_type: Option<&str>,
     payload: &[u8],
-) -> Result<Vec<u8>, Error> {
+) -> Result<HttpResponse, Error> {
     let content_length_hdr = if !payload.is_empty() {
         format!("Content-Length: {}\r\n", payload.len())
     } else {
@@ -238,12 +682,12 @@ pub fn run_http_request<S: Read + Write>(
 
     let req_txt = if let Some(content_type) = content_type {
         format!(
-            "{} {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\nContent-Type: {}\r\n{}User-Agent: wrb/0.1\r\nAccept: */*\r\n\r\n",
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: keep-alive\r\nContent-Type: {}\r\n{}User-Agent: wrb/0.1\r\nAccept: */*\r\n\r\n",
             verb, path, host, content_type, content_length_hdr
         )
     } else {
         format!(
-            "{} {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n{}User-Agent: wrb/0.1\r\nAccept: */*\r\n\r\n",
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: keep-alive\r\n{}User-Agent: wrb/0.1\r\nAccept: */*\r\n\r\n",
             verb, path, host, content_length_hdr
         )
     };
@@ -251,12 +695,10 @@ pub fn run_http_request<S: Read + Write>(
 
     sock.write_all(req_txt.as_bytes())?;
     sock.write_all(payload)?;
+    sock.flush()?;
 
-    let mut buf = vec![];
-
-    sock.read_to_end(&mut buf)?;
-
-    let (code, headers, body_offset) = match decode_http_response(&buf) {
+    let header_buf = read_http_response_headers(sock)?;
+    let (code, headers, body_offset) = match decode_http_response(&header_buf) {
         Ok((headers, body_offset)) => (200, headers, body_offset),
         Err(Error::HttpError(code, headers, body_offset)) => (code, headers, body_offset),
         Err(e) => {
@@ -264,25 +706,82 @@ pub fn run_http_request<S: Read + Write>(
         }
     };
 
-    if body_offset >= buf.len() {
-        // no body
-        wrb_debug!("No HTTP body");
-        if code == 200 {
-            return Ok(vec![]);
-        } else {
-            return Err(Error::HttpError(code, headers, body_offset));
-        }
-    }
+    // HTTP/1.1 connections persist unless the server says otherwise; HTTP/1.0 connections
+    // persist only if the server says so.
+    let connection_hdr = headers
+        .get("connection")
+        .map(|v| v.to_lowercase())
+        .unwrap_or("".to_string());
+    let mut keep_alive = if header_buf.starts_with(b"HTTP/1.0") {
+        connection_hdr == "keep-alive"
+    } else {
+        connection_hdr != "close"
+    };
 
-    let body = decode_http_body(&headers, &buf[body_offset..])?;
-    if code == 200 {
-        return Ok(body);
+    let chunked = headers
+        .get("transfer-encoding")
+        .map(|v| v.to_lowercase() == "chunked")
+        .unwrap_or(false);
+
+    let body = if verb == "HEAD" || code == 204 || code == 304 {
+        vec![]
+    } else if chunked {
+        let (body, terminated) = read_http_chunked_body(sock)?;
+        keep_alive = keep_alive && terminated;
+        body
+    } else if let Some(len_str) = headers.get("content-length") {
+        let len: usize = len_str.trim().parse().map_err(|_| {
+            Error::MalformedResponse(format!("Invalid Content-Length \"{}\"", len_str))
+        })?;
+        if len > MAX_MESSAGE_LEN as usize {
+            return Err(Error::MalformedResponse("HTTP body is too big".to_string()));
+        }
+        let mut body = vec![0u8; len];
+        sock.read_exact(&mut body)?;
+        body
     } else {
-        wrb_debug!(
-            "HTTP Error\nHTTP code: {}\nHTTP body: {}\n",
-            code,
-            &String::from_utf8_lossy(&body)
-        );
-        return Err(Error::HttpError(code, headers, body_offset));
-    }
+        // no framing, so the body ends when the connection does
+        let mut body = vec![];
+        sock.read_to_end(&mut body)?;
+        keep_alive = false;
+        body
+    };
+
+    Ok(HttpResponse {
+        code,
+        headers,
+        body_offset,
+        body,
+        keep_alive,
+    })
+}
+
+/// Run an HTTP/1.1 request, synchronously, through the given read/write handle.
+/// See `run_http_request_full()` for how the response is framed.
+/// Return the HTTP reply, decoded if it was chunked, and whether or not the connection can be
+/// reused for another request.
+pub fn run_http_request_ext<S: Read + Write>(
+    sock: &mut S,
+    host: &SocketAddr,
+    verb: &str,
+    path: &str,
+    content_type: Option<&str>,
+    payload: &[u8],
+) -> Result<(Vec<u8>, bool), Error> {
+    let resp = run_http_request_full(sock, host, verb, path, content_type, payload)?;
+    let keep_alive = resp.keep_alive;
+    resp.into_body().map(|body| (body, keep_alive))
+}
+
+/// Run an HTTP request, synchronously, through the given read/write handle
+/// Return the HTTP reply, decoded if it was chunked
+pub fn run_http_request<S: Read + Write>(
+    sock: &mut S,
+    host: &SocketAddr,
+    verb: &str,
+    path: &str,
+    content_type: Option<&str>,
+    payload: &[u8],
+) -> Result<Vec<u8>, Error> {
+    run_http_request_ext(sock, host, verb, path, content_type, payload).map(|(body, _)| body)
 }
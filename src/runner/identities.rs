@@ -0,0 +1,102 @@
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+
+use rusqlite::Connection;
+use rusqlite::OpenFlags;
+
+use crate::runner::Error;
+
+use crate::util::sqlite::{query_row, sqlite_open, tx_begin_immediate};
+
+const SITE_IDENTITIES_SCHEMA: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS site_identities(
+        name TEXT PRIMARY KEY NOT NULL,
+        -- name of the configured identity the site runs as
+        identity TEXT NOT NULL
+    );"#,
+    r#"
+    CREATE TABLE IF NOT EXISTS schema_version(
+        version INTEGER NOT NULL
+    );
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (1);
+    "#,
+];
+
+/// Database of which identity each wrbsite runs as, stored in the wrb storage directory.
+/// Wrbsites without an entry run as the default identity.
+pub struct SiteIdentityDB {
+    pub path: String,
+    conn: Connection,
+}
+
+impl SiteIdentityDB {
+    /// Open the site identity DB, creating it if it doesn't exist
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let (create, open_flags) = if path != ":memory:" && fs::metadata(path).is_ok() {
+            (false, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        } else {
+            (
+                true,
+                OpenFlags::SQLITE_OPEN_CREATE | OpenFlags::SQLITE_OPEN_READ_WRITE,
+            )
+        };
+
+        let mut conn = sqlite_open(path, open_flags, true)?;
+        if create {
+            wrb_debug!("Instantiate SiteIdentityDB at {}", path);
+            let tx = tx_begin_immediate(&mut conn)?;
+            for cmd in SITE_IDENTITIES_SCHEMA.iter() {
+                tx.execute(cmd, rusqlite::params![])?;
+            }
+            tx.commit()?;
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            conn,
+        })
+    }
+
+    /// Get the name of the identity that a wrbsite runs as, if one was chosen
+    pub fn get_site_identity(&self, name: &str) -> Result<Option<String>, Error> {
+        let sql = "SELECT identity FROM site_identities WHERE name = ?1";
+        Ok(query_row(&self.conn, sql, rusqlite::params![name])?)
+    }
+
+    /// Run a wrbsite as the identity `identity` from now on
+    pub fn put_site_identity(&mut self, name: &str, identity: &str) -> Result<(), Error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        let sql = "INSERT OR REPLACE INTO site_identities (name,identity) VALUES (?1,?2)";
+        tx.execute(sql, rusqlite::params![name, identity])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Run a wrbsite as the default identity from now on
+    pub fn remove_site_identity(&mut self, name: &str) -> Result<(), Error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        tx.execute(
+            "DELETE FROM site_identities WHERE name = ?1",
+            rusqlite::params![name],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+}
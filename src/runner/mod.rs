@@ -23,8 +23,10 @@ use std::io;
 use std::net::SocketAddr;
 use std::net::TcpStream;
 use std::net::ToSocketAddrs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use crate::runner::http::run_http_request;
+use crate::runner::http::{run_rpc_request, HttpConnectionPool};
 use rusqlite::Error as sqlite_error;
 
 use clarity::vm::types::QualifiedContractIdentifier;
@@ -36,7 +38,7 @@ use stacks_common::types::chainstate::StacksAddress;
 use stacks_common::types::chainstate::{BlockHeaderHash, ConsensusHash, StacksBlockId};
 use stacks_common::types::net::PeerAddress;
 use stacks_common::types::StacksPublicKeyBuffer;
-use stacks_common::util::hash::{hex_bytes, Hash160, Sha256Sum};
+use stacks_common::util::hash::{hex_bytes, Hash160, Sha256Sum, Sha512Trunc256Sum};
 use stacks_common::util::HexError;
 
 use serde::Deserialize;
@@ -51,9 +53,20 @@ use crate::util::sqlite::Error as DBError;
 
 use crate::storage::Error as StorageError;
 
+use crate::runner::nodes::{check_network_id, get_node_health, NODE_FAILURE_COOLDOWN_SECS};
+use crate::runner::pins::SitePinMismatch;
+use crate::runner::proxy::resolve_host;
+use crate::runner::tx::RPCTxRejection;
+
 pub mod bns;
 pub mod http;
+pub mod identities;
+pub mod nodes;
+pub mod nonces;
+pub mod pins;
 pub mod process;
+pub mod proxy;
+pub mod replicas;
 pub mod site;
 pub mod stackerdb;
 pub mod tx;
@@ -143,6 +156,11 @@ pub struct RPCSortitionInfo {
     pub committed_block_hash: Option<BlockHeaderHash>,
 }
 
+/// Default time to wait for replicas to serve a wrbsite chunk
+pub const DEFAULT_REPLICA_TIMEOUT_MS: u64 = 10_000;
+/// Default number of replicas to query at once for a wrbsite chunk
+pub const DEFAULT_MAX_PARALLEL_REPLICAS: usize = 4;
+
 #[derive(Debug, Clone)]
 pub enum Error {
     FailedToRun(String, Vec<String>),
@@ -163,6 +181,13 @@ pub enum Error {
     Storage(String),
     Clarity(String),
     Database(String),
+    SitePinMismatch(String, SitePinMismatch),
+    /// Timed out connecting to or talking to a node
+    Timeout(String),
+    /// The node refused the connection
+    ConnectionRefused(String),
+    /// The node would not accept a transaction into its mempool
+    TxRejected(RPCTxRejection),
 }
 
 impl fmt::Display for Error {
@@ -194,6 +219,12 @@ impl fmt::Display for Error {
             Error::Storage(ref msg) => write!(f, "Storage error: {}", msg),
             Error::Clarity(ref err) => write!(f, "Clarity error: {}", err),
             Error::Database(ref err) => write!(f, "Database error: {}", err),
+            Error::SitePinMismatch(ref name, ref mismatch) => {
+                write!(f, "Pin mismatch for '{}': {}", name, mismatch)
+            }
+            Error::Timeout(ref msg) => write!(f, "Timed out: {}", msg),
+            Error::ConnectionRefused(ref msg) => write!(f, "Connection refused: {}", msg),
+            Error::TxRejected(ref rejection) => write!(f, "Transaction rejected: {}", rejection),
         }
     }
 }
@@ -219,13 +250,24 @@ impl error::Error for Error {
             Error::Storage(_) => None,
             Error::Clarity(_) => None,
             Error::Database(_) => None,
+            Error::SitePinMismatch(..) => None,
+            Error::Timeout(_) => None,
+            Error::ConnectionRefused(_) => None,
+            Error::TxRejected(_) => None,
         }
     }
 }
 
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Error {
-        Error::IO(format!("{:?}", &e))
+        match e.kind() {
+            // read timeouts show up as WouldBlock on some platforms
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+                Error::Timeout(format!("{:?}", &e))
+            }
+            io::ErrorKind::ConnectionRefused => Error::ConnectionRefused(format!("{:?}", &e)),
+            _ => Error::IO(format!("{:?}", &e)),
+        }
     }
 }
 
@@ -264,8 +306,24 @@ pub struct Runner {
     zonefile_contract_id: QualifiedContractIdentifier,
     node_host: String,
     node_port: u16,
+    /// nodes to fail over to if the main node is unreachable, in order of preference
+    fallback_nodes: Vec<(String, u16)>,
+    /// if set, only use nodes on mainnet (true) or testnet (false)
+    mainnet: Option<bool>,
     node: Option<SocketAddr>,
     mock_stackerdb_paths: HashMap<QualifiedContractIdentifier, String>,
+    /// path to the DB of wrbsite publisher pins, if we're pinning
+    site_pins_path: Option<String>,
+    /// wrbsite code hashes pinned by the config file
+    pinned_code_hashes: HashMap<String, Sha512Trunc256Sum>,
+    /// path to the DB of replica stats, if we're tracking them
+    replica_stats_path: Option<String>,
+    /// how long to wait for replicas to serve a wrbsite chunk
+    replica_timeout: Duration,
+    /// how many replicas to query at once
+    max_parallel_replicas: usize,
+    /// idle keep-alive connections to nodes, reused by this runner's RPC requests
+    rpc_pool: Arc<Mutex<HttpConnectionPool>>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -299,8 +357,16 @@ impl Runner {
             zonefile_contract_id,
             node_host,
             node_port,
+            fallback_nodes: vec![],
+            mainnet: None,
             node: None,
             mock_stackerdb_paths: HashMap::new(),
+            site_pins_path: None,
+            pinned_code_hashes: HashMap::new(),
+            replica_stats_path: None,
+            replica_timeout: Duration::from_millis(DEFAULT_REPLICA_TIMEOUT_MS),
+            max_parallel_replicas: DEFAULT_MAX_PARALLEL_REPLICAS,
+            rpc_pool: Arc::new(Mutex::new(HttpConnectionPool::default())),
         }
     }
 
@@ -312,14 +378,74 @@ impl Runner {
         self
     }
 
+    pub fn with_node_failover(mut self, fallback_nodes: Vec<(String, u16)>, mainnet: bool) -> Self {
+        self.fallback_nodes = fallback_nodes;
+        self.mainnet = Some(mainnet);
+        self
+    }
+
+    pub fn with_site_pins(
+        mut self,
+        site_pins_path: String,
+        pinned_code_hashes: HashMap<String, Sha512Trunc256Sum>,
+    ) -> Self {
+        self.site_pins_path = Some(site_pins_path);
+        self.pinned_code_hashes = pinned_code_hashes;
+        self
+    }
+
+    pub fn with_replica_stats(
+        mut self,
+        replica_stats_path: String,
+        replica_timeout: Duration,
+        max_parallel_replicas: usize,
+    ) -> Self {
+        self.replica_stats_path = Some(replica_stats_path);
+        self.replica_timeout = replica_timeout;
+        self.max_parallel_replicas = max_parallel_replicas.max(1);
+        self
+    }
+
+    /// Resolve the node to talk to.
+    /// If failover is configured, then the configured nodes are considered in order.  Nodes on
+    /// the wrong network are skipped, and nodes which failed recently are only used if nothing
+    /// else is available.
     pub fn resolve_node(&mut self) -> Result<Option<SocketAddr>, Error> {
-        if self.node.is_none() {
-            let mut addrs: Vec<_> = (self.node_host.as_str(), self.node_port)
-                .to_socket_addrs()?
-                .collect();
+        if self.node.is_some() {
+            return Ok(self.node.clone());
+        }
+        if self.fallback_nodes.is_empty() && self.mainnet.is_none() {
+            let mut addrs = resolve_host(&self.node_host, self.node_port)?;
             return Ok(addrs.pop());
         }
-        Ok(self.node.clone())
+
+        let cooldown = Duration::from_secs(NODE_FAILURE_COOLDOWN_SECS);
+        let mut unhealthy = None;
+        for addr in self.candidate_nodes() {
+            let health = get_node_health(&addr);
+            let wrong_network = match (self.mainnet, health.network_id) {
+                (Some(mainnet), Some(network_id)) => !check_network_id(mainnet, network_id),
+                _ => false,
+            };
+            if wrong_network {
+                continue;
+            }
+            if health.is_cooling_down(cooldown) {
+                unhealthy = unhealthy.or(Some(addr));
+                continue;
+            }
+            match self.check_node_network(&addr) {
+                Some(true) => {
+                    self.node = Some(addr.clone());
+                    return Ok(Some(addr));
+                }
+                Some(false) => {}
+                None => {
+                    unhealthy = unhealthy.or(Some(addr));
+                }
+            }
+        }
+        Ok(unhealthy)
     }
 
     pub fn get_bns_contract_id(&self) -> QualifiedContractIdentifier {
@@ -332,13 +458,12 @@ impl Runner {
 
     /// Run a read-only function call on the node, given a resolved socket address to the node
     pub fn run_call_readonly(
+        pool: &Mutex<HttpConnectionPool>,
         node_addr: &SocketAddr,
         contract_id: &QualifiedContractIdentifier,
         function_name: &str,
         function_args: &[Value],
     ) -> Result<Value, Error> {
-        let mut sock = TcpStream::connect(node_addr)?;
-
         let mut arguments = vec![];
         for arg in function_args.iter() {
             let v = arg.serialize_to_hex()?;
@@ -359,8 +484,8 @@ impl Runner {
             function_name,
             &payload_json
         );
-        let bytes = run_http_request(
-            &mut sock,
+        let bytes = run_rpc_request(
+            pool,
             node_addr,
             "POST",
             &format!(
@@ -369,6 +494,7 @@ impl Runner {
             ),
             Some("application/json"),
             payload_json.as_bytes(),
+            true,
         )?;
 
         // try to convert into the response
@@ -406,16 +532,18 @@ impl Runner {
         function_name: &str,
         function_args: &[Value],
     ) -> Result<Value, Error> {
-        let Some(node_addr) = self.resolve_node()? else {
-            return Err(Error::NotConnected);
-        };
-        Self::run_call_readonly(&node_addr, contract_id, function_name, function_args)
+        let pool = self.rpc_pool.clone();
+        self.with_node_failover("call-read-only", |node_addr| {
+            Self::run_call_readonly(&pool, node_addr, contract_id, function_name, function_args)
+        })
     }
 
     /// Get /v2/info
-    pub fn run_get_info(node_addr: &SocketAddr) -> Result<RPCPeerInfoData, Error> {
-        let mut sock = TcpStream::connect(node_addr)?;
-        let bytes = run_http_request(&mut sock, node_addr, "GET", "/v2/info", None, &[])?;
+    pub fn run_get_info(
+        pool: &Mutex<HttpConnectionPool>,
+        node_addr: &SocketAddr,
+    ) -> Result<RPCPeerInfoData, Error> {
+        let bytes = run_rpc_request(pool, node_addr, "GET", "/v2/info", None, &[], true)?;
 
         let response: RPCPeerInfoData = serde_json::from_slice(&bytes)
             .map_err(|_| Error::Deserialize("Failed to decode /v2/info response".into()))?;
@@ -425,18 +553,19 @@ impl Runner {
 
     /// Get /v3/sortitions/{:key}/{:value}
     pub fn run_get_sortition_info(
+        pool: &Mutex<HttpConnectionPool>,
         node_addr: &SocketAddr,
         key: &str,
         value: &str,
     ) -> Result<Vec<RPCSortitionInfo>, Error> {
-        let mut sock = TcpStream::connect(node_addr)?;
-        let bytes = run_http_request(
-            &mut sock,
+        let bytes = run_rpc_request(
+            pool,
             node_addr,
             "GET",
             &format!("/v3/sortitions/{}/{}", key, value),
             None,
             &[],
+            true,
         )?;
 
         let response: Vec<RPCSortitionInfo> = serde_json::from_slice(&bytes)
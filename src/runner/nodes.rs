@@ -0,0 +1,247 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use lazy_static::lazy_static;
+
+use crate::runner::http::{is_retryable_error, rpc_connect, run_http_request};
+use crate::runner::proxy::resolve_host;
+use crate::runner::Error;
+use crate::runner::RPCPeerInfoData;
+use crate::runner::Runner;
+
+/// Network ID reported in /v2/info by mainnet nodes
+pub const MAINNET_NETWORK_ID: u32 = 0x00000001;
+
+/// How long to avoid a node after it fails a request
+pub const NODE_FAILURE_COOLDOWN_SECS: u64 = 30;
+
+/// What we know about a node's health in this process
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeHealth {
+    /// number of consecutive failed requests
+    pub failures: u64,
+    /// when the last failure happened
+    pub last_failure: Option<Instant>,
+    /// network ID the node reported in /v2/info.  None means we haven't asked.
+    pub network_id: Option<u32>,
+}
+
+impl NodeHealth {
+    pub fn new() -> Self {
+        Self {
+            failures: 0,
+            last_failure: None,
+            network_id: None,
+        }
+    }
+
+    /// Is this node still being avoided after a recent failure?
+    pub fn is_cooling_down(&self, cooldown: Duration) -> bool {
+        if self.failures == 0 {
+            return false;
+        }
+        self.last_failure
+            .map(|last_failure| last_failure.elapsed() < cooldown)
+            .unwrap_or(false)
+    }
+}
+
+lazy_static! {
+    /// Health of every node we've talked to in this process.  Runners are short-lived, so this
+    /// is shared between all of them.
+    static ref NODE_HEALTH: Mutex<HashMap<SocketAddr, NodeHealth>> = Mutex::new(HashMap::new());
+}
+
+fn with_node_health<F, R>(addr: &SocketAddr, todo: F) -> R
+where
+    F: FnOnce(&mut NodeHealth) -> R,
+{
+    let mut table = NODE_HEALTH
+        .lock()
+        .expect("FATAL: node health mutex poisoned");
+    let health = table.entry(addr.clone()).or_insert_with(NodeHealth::new);
+    todo(health)
+}
+
+/// Get what we know about a node
+pub fn get_node_health(addr: &SocketAddr) -> NodeHealth {
+    with_node_health(addr, |health| health.clone())
+}
+
+/// Record that a node served a request
+pub fn record_node_success(addr: &SocketAddr) {
+    with_node_health(addr, |health| {
+        health.failures = 0;
+        health.last_failure = None;
+    })
+}
+
+/// Record that a node failed to serve a request
+pub fn record_node_failure(addr: &SocketAddr) {
+    with_node_health(addr, |health| {
+        health.failures = health.failures.saturating_add(1);
+        health.last_failure = Some(Instant::now());
+    })
+}
+
+/// Record the network ID a node reported
+pub fn record_node_network_id(addr: &SocketAddr, network_id: u32) {
+    with_node_health(addr, |health| {
+        health.network_id = Some(network_id);
+    })
+}
+
+/// Forget everything we know about node health
+pub fn clear_node_health() {
+    NODE_HEALTH
+        .lock()
+        .expect("FATAL: node health mutex poisoned")
+        .clear();
+}
+
+/// Is a node reporting this network ID on the network we expect?
+pub fn check_network_id(mainnet: bool, network_id: u32) -> bool {
+    (network_id == MAINNET_NETWORK_ID) == mainnet
+}
+
+impl Runner {
+    /// Resolve the configured nodes to addresses, in order of preference.
+    /// Nodes whose names don't resolve are skipped.
+    pub(crate) fn candidate_nodes(&self) -> Vec<SocketAddr> {
+        let mut candidates = vec![];
+        let node_names = std::iter::once((self.node_host.clone(), self.node_port))
+            .chain(self.fallback_nodes.iter().cloned());
+        for (host, port) in node_names {
+            let addr_opt = match resolve_host(&host, port) {
+                Ok(mut addrs) => addrs.pop(),
+                Err(e) => {
+                    wrb_warn!("Failed to resolve node {}:{}: {:?}", &host, port, &e);
+                    None
+                }
+            };
+            if let Some(addr) = addr_opt {
+                if !candidates.contains(&addr) {
+                    candidates.push(addr);
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Configured nodes which are cooling down after a recent failure, in order of preference.
+    /// Nodes known to be on the wrong network are skipped.
+    pub(crate) fn cooling_down_nodes(&self) -> Vec<SocketAddr> {
+        let cooldown = Duration::from_secs(NODE_FAILURE_COOLDOWN_SECS);
+        self.candidate_nodes()
+            .into_iter()
+            .filter(|addr| {
+                let health = get_node_health(addr);
+                let wrong_network = match (self.mainnet, health.network_id) {
+                    (Some(mainnet), Some(network_id)) => !check_network_id(mainnet, network_id),
+                    _ => false,
+                };
+                !wrong_network && health.is_cooling_down(cooldown)
+            })
+            .collect()
+    }
+
+    /// Check that a node is on the network we expect, using its /v2/info.
+    /// The result is remembered, so each node is only asked once per process.
+    /// Returns Some(true) if the node is on our network, Some(false) if it isn't, and None if
+    /// the node could not be reached.
+    pub(crate) fn check_node_network(&self, addr: &SocketAddr) -> Option<bool> {
+        let Some(mainnet) = self.mainnet else {
+            return Some(true);
+        };
+        if let Some(network_id) = get_node_health(addr).network_id {
+            return Some(check_network_id(mainnet, network_id));
+        }
+
+        // only try once -- if this node is down, we'd rather move on to the next one
+        let peer_info_res = rpc_connect(addr).and_then(|mut sock| {
+            let bytes = run_http_request(&mut sock, addr, "GET", "/v2/info", None, &[])?;
+            serde_json::from_slice::<RPCPeerInfoData>(&bytes)
+                .map_err(|_| Error::Deserialize("Failed to decode /v2/info response".into()))
+        });
+        let peer_info = match peer_info_res {
+            Ok(peer_info) => peer_info,
+            Err(e) => {
+                wrb_debug!("Failed to query /v2/info on {}: {:?}", addr, &e);
+                record_node_failure(addr);
+                return None;
+            }
+        };
+
+        let network_ok = check_network_id(mainnet, peer_info.network_id);
+        if !network_ok {
+            wrb_warn!(
+                "Node {} reports network ID {:08x}, but we expect a {} node; ignoring it",
+                addr,
+                peer_info.network_id,
+                if mainnet { "mainnet" } else { "testnet" }
+            );
+        }
+        record_node_network_id(addr, peer_info.network_id);
+        Some(network_ok)
+    }
+
+    /// Run `todo` against the resolved node.  If the node can't be reached, then mark it as
+    /// unhealthy and try the next configured node, until every node has been tried once.
+    /// Once the healthy nodes run out, the nodes that are cooling down are tried in order.
+    /// Errors which the node reported itself are returned as-is.
+    pub fn with_node_failover<F, R>(&mut self, what: &str, mut todo: F) -> Result<R, Error>
+    where
+        F: FnMut(&SocketAddr) -> Result<R, Error>,
+    {
+        let mut tried = vec![];
+        let mut last_error = None;
+        loop {
+            let node_addr_opt = match self.resolve_node()?.filter(|addr| !tried.contains(addr)) {
+                Some(node_addr) => Some(node_addr),
+                None => self
+                    .cooling_down_nodes()
+                    .into_iter()
+                    .find(|addr| !tried.contains(addr)),
+            };
+            let Some(node_addr) = node_addr_opt else {
+                return Err(last_error.unwrap_or(Error::NotConnected));
+            };
+            match todo(&node_addr) {
+                Ok(res) => {
+                    record_node_success(&node_addr);
+                    return Ok(res);
+                }
+                Err(e) => {
+                    if !is_retryable_error(&e) {
+                        return Err(e);
+                    }
+                    wrb_warn!("{} failed on node {}: {:?}", what, &node_addr, &e);
+                    record_node_failure(&node_addr);
+                    self.node = None;
+                    tried.push(node_addr);
+                    last_error = Some(e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+
+use rusqlite::Connection;
+use rusqlite::OpenFlags;
+use rusqlite::Row;
+
+use clarity::vm::types::PrincipalData;
+use clarity::vm::types::StacksAddressExtensions;
+
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::util::get_epoch_time_secs;
+use stacks_common::util::hash::{hex_bytes, to_hex};
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::runner::tx::TxRejectionReason;
+use crate::runner::Error;
+use crate::runner::Runner;
+
+use crate::tx::replace_tx_fee;
+use crate::tx::StacksTransaction;
+use crate::tx::Txid;
+
+use crate::util::sqlite::Error as DBError;
+use crate::util::sqlite::FromColumn;
+use crate::util::sqlite::FromRow;
+use crate::util::sqlite::{query_row, query_rows, sqlite_open, tx_begin_immediate, u64_to_sql};
+
+/// How long a nonce can be handed out without a transaction being recorded for it before we
+/// assume the process that asked for it has gone away
+pub const NONCE_RESERVATION_TIMEOUT_SECS: u64 = 600;
+
+const NONCE_SCHEMA: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS nonces(
+        -- account that will use this nonce
+        principal TEXT NOT NULL,
+        nonce INTEGER NOT NULL,
+        -- when the nonce was handed out, or when its transaction was last (re)broadcast
+        updated_at INTEGER NOT NULL,
+        -- broadcast transaction using this nonce.  NULL if one hasn't been broadcast yet.
+        txid TEXT,
+        tx_fee INTEGER,
+        tx TEXT,
+        PRIMARY KEY(principal, nonce)
+    );"#,
+    r#"
+    CREATE TABLE IF NOT EXISTS schema_version(
+        version INTEGER NOT NULL
+    );
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (1);
+    "#,
+];
+
+/// A nonce we've handed out, which the node has not yet seen used in a block
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingNonce {
+    pub principal: PrincipalData,
+    pub nonce: u64,
+    pub updated_at: u64,
+    /// the transaction broadcast with this nonce, if there is one yet
+    pub tx: Option<StacksTransaction>,
+}
+
+impl PendingNonce {
+    pub fn txid(&self) -> Option<Txid> {
+        self.tx.as_ref().map(|tx| tx.txid())
+    }
+}
+
+impl FromRow<PendingNonce> for PendingNonce {
+    fn from_row<'a>(row: &'a Row) -> Result<Self, DBError> {
+        let principal_str: String = row.get("principal")?;
+        let principal = PrincipalData::parse(&principal_str).map_err(|_| DBError::ParseError)?;
+        let nonce = u64::from_column(row, "nonce")?;
+        let updated_at = u64::from_column(row, "updated_at")?;
+        let tx_hex: Option<String> = row.get("tx")?;
+        let tx = match tx_hex {
+            Some(tx_hex) => {
+                let tx_bytes = hex_bytes(&tx_hex).map_err(|_| DBError::ParseError)?;
+                let tx = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+                    .map_err(|_| DBError::ParseError)?;
+                Some(tx)
+            }
+            None => None,
+        };
+        Ok(Self {
+            principal,
+            nonce,
+            updated_at,
+            tx,
+        })
+    }
+}
+
+/// Database of nonces handed out to transactions that have not been mined yet, stored in the
+/// wrb storage directory.  This lets several transactions from the same account be in flight at
+/// once, including from different wrb processes.
+pub struct NonceDB {
+    pub path: String,
+    conn: Connection,
+}
+
+impl NonceDB {
+    /// Open the nonce DB, creating it if it doesn't exist
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let (create, open_flags) = if path != ":memory:" && fs::metadata(path).is_ok() {
+            (false, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        } else {
+            (
+                true,
+                OpenFlags::SQLITE_OPEN_CREATE | OpenFlags::SQLITE_OPEN_READ_WRITE,
+            )
+        };
+
+        let mut conn = sqlite_open(path, open_flags, true)?;
+        if create {
+            wrb_debug!("Instantiate NonceDB at {}", path);
+            let tx = tx_begin_immediate(&mut conn)?;
+            for cmd in NONCE_SCHEMA.iter() {
+                tx.execute(cmd, rusqlite::params![])?;
+            }
+            tx.commit()?;
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            conn,
+        })
+    }
+
+    /// Hand out the lowest nonce for `principal` which is at least `chain_nonce` and which
+    /// hasn't been handed out already.  Nonces below `chain_nonce` have been used in blocks, so
+    /// they're forgotten.
+    pub fn reserve_nonce(
+        &mut self,
+        principal: &PrincipalData,
+        chain_nonce: u64,
+        now: u64,
+    ) -> Result<u64, Error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        tx.execute(
+            "DELETE FROM nonces WHERE principal = ?1 AND nonce < ?2",
+            rusqlite::params![&principal.to_string(), u64_to_sql(chain_nonce)?],
+        )?;
+
+        let taken: Vec<u64> = query_rows(
+            &tx,
+            "SELECT nonce FROM nonces WHERE principal = ?1 ORDER BY nonce ASC",
+            rusqlite::params![&principal.to_string()],
+        )?;
+
+        let mut nonce = chain_nonce;
+        for taken_nonce in taken.into_iter() {
+            if taken_nonce != nonce {
+                break;
+            }
+            nonce = nonce
+                .checked_add(1)
+                .ok_or_else(|| Error::Database("Nonce overflow".into()))?;
+        }
+
+        tx.execute(
+            "INSERT INTO nonces (principal,nonce,updated_at) VALUES (?1,?2,?3)",
+            rusqlite::params![&principal.to_string(), u64_to_sql(nonce)?, u64_to_sql(now)?],
+        )?;
+        tx.commit()?;
+        Ok(nonce)
+    }
+
+    /// Record the transaction that was broadcast with a nonce, replacing any earlier one.
+    /// If `principal` sponsors `tx`, then the sponsor nonce is the one recorded.
+    pub fn record_tx(
+        &mut self,
+        principal: &PrincipalData,
+        tx: &StacksTransaction,
+        now: u64,
+    ) -> Result<(), Error> {
+        let nonce = match (tx.sponsor_address(), tx.get_sponsor_nonce()) {
+            (Some(sponsor_addr), Some(sponsor_nonce))
+                if &sponsor_addr.to_account_principal() == principal =>
+            {
+                sponsor_nonce
+            }
+            _ => tx.get_origin_nonce(),
+        };
+        let db_tx = tx_begin_immediate(&mut self.conn)?;
+        let sql = "INSERT OR REPLACE INTO nonces (principal,nonce,updated_at,txid,tx_fee,tx) VALUES (?1,?2,?3,?4,?5,?6)";
+        let args = rusqlite::params![
+            &principal.to_string(),
+            u64_to_sql(nonce)?,
+            u64_to_sql(now)?,
+            &tx.txid().to_hex(),
+            u64_to_sql(tx.get_tx_fee())?,
+            &to_hex(&tx.serialize_to_vec())
+        ];
+        db_tx.execute(sql, args)?;
+        db_tx.commit()?;
+        Ok(())
+    }
+
+    /// Give back a nonce, e.g. because its transaction was never broadcast
+    pub fn release_nonce(&mut self, principal: &PrincipalData, nonce: u64) -> Result<(), Error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        tx.execute(
+            "DELETE FROM nonces WHERE principal = ?1 AND nonce = ?2",
+            rusqlite::params![&principal.to_string(), u64_to_sql(nonce)?],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Forget nonces below `chain_nonce`, which have been used in blocks, and nonces that were
+    /// handed out before `stale_before` but never had a transaction recorded.
+    pub fn prune(
+        &mut self,
+        principal: &PrincipalData,
+        chain_nonce: u64,
+        stale_before: u64,
+    ) -> Result<(), Error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        tx.execute(
+            "DELETE FROM nonces WHERE principal = ?1 AND nonce < ?2",
+            rusqlite::params![&principal.to_string(), u64_to_sql(chain_nonce)?],
+        )?;
+        tx.execute(
+            "DELETE FROM nonces WHERE principal = ?1 AND txid IS NULL AND updated_at < ?2",
+            rusqlite::params![&principal.to_string(), u64_to_sql(stale_before)?],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get a nonce we've handed out, if it's still pending
+    pub fn get_pending_nonce(
+        &self,
+        principal: &PrincipalData,
+        nonce: u64,
+    ) -> Result<Option<PendingNonce>, Error> {
+        let sql = "SELECT * FROM nonces WHERE principal = ?1 AND nonce = ?2";
+        Ok(query_row(
+            &self.conn,
+            sql,
+            rusqlite::params![&principal.to_string(), u64_to_sql(nonce)?],
+        )?)
+    }
+
+    /// Is there a hole in the account's pending nonces?  They should run without a break from
+    /// `chain_nonce`; if one is missing, the transactions after it can't be mined.  This happens
+    /// when a transaction was dropped, or was handed to someone else and not recorded here.
+    pub fn has_nonce_gap(
+        &self,
+        principal: &PrincipalData,
+        chain_nonce: u64,
+    ) -> Result<bool, Error> {
+        let mut expected = chain_nonce;
+        for pending in self.get_pending_nonces(principal)?.into_iter() {
+            if pending.nonce != expected {
+                return Ok(true);
+            }
+            expected = expected.saturating_add(1);
+        }
+        Ok(false)
+    }
+
+    /// Get all pending nonces for an account, in order
+    pub fn get_pending_nonces(
+        &self,
+        principal: &PrincipalData,
+    ) -> Result<Vec<PendingNonce>, Error> {
+        let sql = "SELECT * FROM nonces WHERE principal = ?1 ORDER BY nonce ASC";
+        Ok(query_rows(
+            &self.conn,
+            sql,
+            rusqlite::params![&principal.to_string()],
+        )?)
+    }
+}
+
+impl Runner {
+    /// Forget nonces the node has seen used, as well as abandoned reservations.
+    /// Returns the account's nonce as of the chain tip.
+    pub fn prune_nonces(
+        &mut self,
+        nonce_db: &mut NonceDB,
+        principal: &PrincipalData,
+    ) -> Result<u64, Error> {
+        let chain_nonce = self.get_account(principal)?.nonce;
+        let now = get_epoch_time_secs();
+        nonce_db.prune(
+            principal,
+            chain_nonce,
+            now.saturating_sub(NONCE_RESERVATION_TIMEOUT_SECS),
+        )?;
+        Ok(chain_nonce)
+    }
+
+    /// Bring the nonce DB up to date with the node.
+    /// Besides pruning it, transactions still pending are broadcast again, so that a node which
+    /// dropped one from its mempool picks it back up.  If the node now refuses one outright,
+    /// its nonce is released so it can be handed out again -- unless the refusal is because
+    /// some other transaction already has that nonce in the mempool.
+    /// Returns the account's nonce as of the chain tip.
+    pub fn reconcile_nonces(
+        &mut self,
+        nonce_db: &mut NonceDB,
+        principal: &PrincipalData,
+    ) -> Result<u64, Error> {
+        let chain_nonce = self.prune_nonces(nonce_db, principal)?;
+        for pending in nonce_db.get_pending_nonces(principal)?.into_iter() {
+            let Some(tx) = pending.tx else {
+                continue;
+            };
+            if tx.verify().is_err() {
+                // not fully signed yet -- it was handed off to its sponsor or to a multisig
+                // account's cosigners to finish and broadcast
+                continue;
+            }
+            match self.post_tx(&tx) {
+                Ok(_) => {}
+                Err(Error::TxRejected(rejection)) => {
+                    if rejection.reason_code() == TxRejectionReason::ConflictingNonceInMempool {
+                        wrb_debug!(
+                            "Nonce {} of {} is taken in the mempool by another transaction",
+                            pending.nonce,
+                            principal
+                        );
+                        continue;
+                    }
+                    wrb_warn!(
+                        "Node no longer accepts transaction {} (nonce {} of {}): {}",
+                        &tx.txid(),
+                        pending.nonce,
+                        principal,
+                        &rejection
+                    );
+                    nonce_db.release_nonce(principal, pending.nonce)?;
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(chain_nonce)
+    }
+
+    /// Get the next nonce to use for a transaction from `principal`, taking into account
+    /// transactions we've broadcast that have not been mined yet.
+    /// Pending transactions are only rebroadcast if there's a gap in the pending nonces.
+    pub fn next_nonce(
+        &mut self,
+        nonce_db: &mut NonceDB,
+        principal: &PrincipalData,
+    ) -> Result<u64, Error> {
+        let mut chain_nonce = self.prune_nonces(nonce_db, principal)?;
+        if nonce_db.has_nonce_gap(principal, chain_nonce)? {
+            wrb_debug!("Gap in pending nonces of {}; reconciling", principal);
+            chain_nonce = self.reconcile_nonces(nonce_db, principal)?;
+        }
+        nonce_db.reserve_nonce(principal, chain_nonce, get_epoch_time_secs())
+    }
+
+    /// Make a transaction which replaces the pending one with the given nonce, but pays
+    /// `tx_fee` instead.  The node only accepts the replacement if it pays a higher fee.
+    pub fn make_replace_by_fee_tx(
+        &mut self,
+        nonce_db: &NonceDB,
+        principal: &PrincipalData,
+        nonce: u64,
+        tx_fee: u64,
+        privkey: &Secp256k1PrivateKey,
+    ) -> Result<StacksTransaction, Error> {
+        let Some(pending) = nonce_db.get_pending_nonce(principal, nonce)? else {
+            return Err(Error::Database(format!(
+                "No pending transaction with nonce {} for {}",
+                nonce, principal
+            )));
+        };
+        let Some(tx) = pending.tx else {
+            return Err(Error::Database(format!(
+                "Nonce {} for {} has not been used by a broadcast transaction",
+                nonce, principal
+            )));
+        };
+        if tx_fee <= tx.get_tx_fee() {
+            return Err(Error::MalformedRequest(format!(
+                "Replacement fee {} must be higher than the current fee {}",
+                tx_fee,
+                tx.get_tx_fee()
+            )));
+        }
+        replace_tx_fee(&tx, tx_fee, privkey)
+            .map_err(|e| Error::MalformedRequest(format!("Failed to re-sign transaction: {}", &e)))
+    }
+}
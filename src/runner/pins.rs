@@ -0,0 +1,232 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::fs;
+
+use rusqlite::Connection;
+use rusqlite::OpenFlags;
+use rusqlite::Row;
+
+use clarity::vm::types::QualifiedContractIdentifier;
+
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::Address;
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+use crate::runner::Error;
+
+use crate::util::sqlite::Error as DBError;
+use crate::util::sqlite::FromColumn;
+use crate::util::sqlite::FromRow;
+use crate::util::sqlite::{query_row, query_rows, sqlite_open, tx_begin_immediate};
+
+const SITE_PINS_SCHEMA: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS site_pins(
+        name TEXT PRIMARY KEY NOT NULL,
+        contract_id TEXT NOT NULL,
+        signer TEXT NOT NULL,
+        -- if set, then the site's code must hash to this value
+        code_hash TEXT
+    );"#,
+    r#"
+    CREATE TABLE IF NOT EXISTS schema_version(
+        version INTEGER NOT NULL
+    );
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (1);
+    "#,
+];
+
+/// Publisher information for a wrbsite that we trust.
+/// The first time we load a wrbsite, we record the StackerDB and signer that served it.
+/// Subsequent loads must be served by the same StackerDB and signer, unless the user says
+/// otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitePin {
+    /// fully-qualified BNS name (i.e. `name.namespace`)
+    pub name: String,
+    /// StackerDB which hosts the site
+    pub contract_id: QualifiedContractIdentifier,
+    /// address of the signer of the site's slot
+    pub signer: StacksAddress,
+    /// if given, the site's code must hash to this
+    pub code_hash: Option<Sha512Trunc256Sum>,
+}
+
+/// Reasons why a loaded wrbsite does not match its pin
+#[derive(Debug, Clone, PartialEq)]
+pub enum SitePinMismatch {
+    /// The site's zonefile now points to a different StackerDB or signer.
+    /// Contains (old pin, new pin)
+    Publisher(SitePin, SitePin),
+    /// The site's code does not match the pinned code hash.
+    /// Contains (pinned hash, observed hash)
+    CodeHash(Sha512Trunc256Sum, Sha512Trunc256Sum),
+}
+
+impl fmt::Display for SitePinMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SitePinMismatch::Publisher(old, new) => write!(
+                f,
+                "publisher of '{}' changed from {} (signer {}) to {} (signer {})",
+                &old.name, &old.contract_id, &old.signer, &new.contract_id, &new.signer
+            ),
+            SitePinMismatch::CodeHash(pinned, observed) => write!(
+                f,
+                "code hash is {}, but it is pinned to {}",
+                observed, pinned
+            ),
+        }
+    }
+}
+
+impl SitePin {
+    pub fn new(
+        name: &str,
+        contract_id: QualifiedContractIdentifier,
+        signer: StacksAddress,
+        code_hash: Option<Sha512Trunc256Sum>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            contract_id,
+            signer,
+            code_hash,
+        }
+    }
+
+    /// Check a freshly-loaded site against this pin.
+    /// `observed` is the pin we would create for the site we just loaded, and `code_hash` is the
+    /// hash of the code we loaded.
+    pub fn check(
+        &self,
+        observed: &SitePin,
+        code_hash: &Sha512Trunc256Sum,
+    ) -> Result<(), SitePinMismatch> {
+        if self.contract_id != observed.contract_id || self.signer != observed.signer {
+            return Err(SitePinMismatch::Publisher(self.clone(), observed.clone()));
+        }
+        if let Some(pinned_hash) = self.code_hash.as_ref() {
+            if pinned_hash != code_hash {
+                return Err(SitePinMismatch::CodeHash(
+                    pinned_hash.clone(),
+                    code_hash.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromRow<SitePin> for SitePin {
+    fn from_row<'a>(row: &'a Row) -> Result<Self, DBError> {
+        let name: String = row.get("name")?;
+        let contract_id = QualifiedContractIdentifier::from_column(row, "contract_id")?;
+        let signer_str: String = row.get("signer")?;
+        let signer = StacksAddress::from_string(&signer_str).ok_or(DBError::ParseError)?;
+        let code_hash_str: Option<String> = row.get("code_hash")?;
+        let code_hash = match code_hash_str {
+            Some(hash_str) => {
+                Some(Sha512Trunc256Sum::from_hex(&hash_str).map_err(|_| DBError::ParseError)?)
+            }
+            None => None,
+        };
+        Ok(Self {
+            name,
+            contract_id,
+            signer,
+            code_hash,
+        })
+    }
+}
+
+/// Database of trusted wrbsite publishers, stored in the wrb storage directory
+pub struct SitePinDB {
+    pub path: String,
+    conn: Connection,
+}
+
+impl SitePinDB {
+    /// Open the pin DB, creating it if it doesn't exist
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let (create, open_flags) = if path != ":memory:" && fs::metadata(path).is_ok() {
+            (false, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        } else {
+            (
+                true,
+                OpenFlags::SQLITE_OPEN_CREATE | OpenFlags::SQLITE_OPEN_READ_WRITE,
+            )
+        };
+
+        let mut conn = sqlite_open(path, open_flags, true)?;
+        if create {
+            wrb_debug!("Instantiate SitePinDB at {}", path);
+            let tx = tx_begin_immediate(&mut conn)?;
+            for cmd in SITE_PINS_SCHEMA.iter() {
+                tx.execute(cmd, rusqlite::params![])?;
+            }
+            tx.commit()?;
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            conn,
+        })
+    }
+
+    /// Get the pin for a name, if we have one
+    pub fn get_pin(&self, name: &str) -> Result<Option<SitePin>, Error> {
+        let sql = "SELECT * FROM site_pins WHERE name = ?1";
+        Ok(query_row(&self.conn, sql, rusqlite::params![name])?)
+    }
+
+    /// Get all pins
+    pub fn get_all_pins(&self) -> Result<Vec<SitePin>, Error> {
+        let sql = "SELECT * FROM site_pins ORDER BY name ASC";
+        Ok(query_rows(&self.conn, sql, rusqlite::params![])?)
+    }
+
+    /// Store a pin, replacing whatever was there before
+    pub fn put_pin(&mut self, pin: &SitePin) -> Result<(), Error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        let sql = "INSERT OR REPLACE INTO site_pins (name,contract_id,signer,code_hash) VALUES (?1,?2,?3,?4)";
+        let args = rusqlite::params![
+            &pin.name,
+            &pin.contract_id.to_string(),
+            &pin.signer.to_string(),
+            &pin.code_hash.as_ref().map(|h| h.to_hex())
+        ];
+        tx.execute(sql, args)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Forget a pin
+    pub fn remove_pin(&mut self, name: &str) -> Result<(), Error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        tx.execute(
+            "DELETE FROM site_pins WHERE name = ?1",
+            rusqlite::params![name],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+}
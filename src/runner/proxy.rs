@@ -0,0 +1,320 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+
+use crate::core::with_global_config;
+use crate::runner::http::{decode_http_response, read_http_response_headers};
+use crate::runner::Error;
+
+/// Kind of proxy server
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProxyKind {
+    /// SOCKS5, without authentication
+    Socks5,
+    /// HTTP proxy which supports the CONNECT verb
+    HttpConnect,
+}
+
+/// Proxy through which all outbound connections are made
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProxyConfig {
+    pub kind: ProxyKind,
+    /// address of the proxy itself
+    pub addr: SocketAddr,
+    /// whether or not host names are resolved by the proxy instead of by us
+    pub remote_dns: bool,
+}
+
+impl ProxyConfig {
+    /// Parse a proxy URL.  Supported schemes are
+    /// * `socks5://` -- SOCKS5, with host names resolved locally
+    /// * `socks5h://` -- SOCKS5, with host names resolved by the proxy
+    /// * `http://` -- HTTP CONNECT, with host names resolved by the proxy
+    pub fn parse(proxy_url: &str) -> Result<Self, String> {
+        let url = url::Url::parse(proxy_url)
+            .map_err(|e| format!("Invalid proxy URL '{}': {}", proxy_url, &e))?;
+        let (kind, remote_dns, default_port) = match url.scheme() {
+            "socks5" => (ProxyKind::Socks5, false, 1080),
+            "socks5h" => (ProxyKind::Socks5, true, 1080),
+            "http" => (ProxyKind::HttpConnect, true, 8080),
+            scheme => {
+                return Err(format!(
+                    "Unsupported proxy scheme '{}': expected socks5, socks5h, or http",
+                    scheme
+                ));
+            }
+        };
+        let host = url
+            .host_str()
+            .ok_or_else(|| format!("Invalid proxy URL '{}': no host", proxy_url))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let port = url.port().unwrap_or(default_port);
+        let addr = (host, port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Failed to resolve proxy '{}': {}", host, &e))?
+            .next()
+            .ok_or_else(|| format!("Failed to resolve proxy '{}'", host))?;
+        Ok(Self {
+            kind,
+            addr,
+            remote_dns,
+        })
+    }
+}
+
+impl fmt::Display for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let scheme = match (self.kind, self.remote_dns) {
+            (ProxyKind::Socks5, false) => "socks5",
+            (ProxyKind::Socks5, true) => "socks5h",
+            (ProxyKind::HttpConnect, _) => "http",
+        };
+        write!(f, "{}://{}", scheme, &self.addr)
+    }
+}
+
+/// Get the configured proxy, if there is one
+pub fn proxy_config() -> Option<ProxyConfig> {
+    with_global_config(|cfg| cfg.proxy().cloned()).flatten()
+}
+
+/// Host names are mapped to addresses in 240.0.0.0/4 (reserved, and never routable) when the
+/// proxy resolves them for us.  This lets the rest of wrb pass them around as `SocketAddr`s.
+const VIRTUAL_ADDR_PREFIX: u32 = 0xf000_0000;
+const VIRTUAL_ADDR_MASK: u32 = 0xf000_0000;
+
+struct VirtualAddrMap {
+    by_host: HashMap<String, Ipv4Addr>,
+    by_addr: HashMap<Ipv4Addr, String>,
+}
+
+lazy_static! {
+    static ref VIRTUAL_ADDRS: Mutex<VirtualAddrMap> = Mutex::new(VirtualAddrMap {
+        by_host: HashMap::new(),
+        by_addr: HashMap::new(),
+    });
+}
+
+/// Get the placeholder address for a host name which the proxy will resolve
+pub fn virtual_addr(host: &str, port: u16) -> SocketAddr {
+    let host = host.to_lowercase();
+    let mut map = VIRTUAL_ADDRS
+        .lock()
+        .expect("FATAL: virtual address mutex poisoned");
+    if let Some(ip) = map.by_host.get(&host) {
+        return SocketAddr::new(IpAddr::V4(ip.clone()), port);
+    }
+    let index = u32::try_from(map.by_host.len()).expect("too many virtual addresses") + 1;
+    if index & VIRTUAL_ADDR_MASK != 0 {
+        panic!("FATAL: ran out of virtual addresses");
+    }
+    let ip = Ipv4Addr::from(VIRTUAL_ADDR_PREFIX | index);
+    map.by_host.insert(host.clone(), ip.clone());
+    map.by_addr.insert(ip.clone(), host);
+    SocketAddr::new(IpAddr::V4(ip), port)
+}
+
+/// Get the host name behind a placeholder address, if it is one
+pub fn virtual_addr_host(addr: &SocketAddr) -> Option<String> {
+    let IpAddr::V4(ip) = addr.ip() else {
+        return None;
+    };
+    if u32::from(ip) & VIRTUAL_ADDR_MASK != VIRTUAL_ADDR_PREFIX {
+        return None;
+    }
+    VIRTUAL_ADDRS
+        .lock()
+        .expect("FATAL: virtual address mutex poisoned")
+        .by_addr
+        .get(&ip)
+        .cloned()
+}
+
+/// Resolve a host and port to addresses.
+/// If the configured proxy resolves host names, then this does not do a DNS lookup, but
+/// instead returns a placeholder address which `rpc_connect()` will hand to the proxy.
+pub fn resolve_host(host: &str, port: u16) -> Result<Vec<SocketAddr>, Error> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, port)]);
+    }
+    if proxy_config()
+        .map(|proxy| proxy.remote_dns)
+        .unwrap_or(false)
+    {
+        return Ok(vec![virtual_addr(host, port)]);
+    }
+    Ok((host, port).to_socket_addrs()?.collect())
+}
+
+/// Resolve the host and port in a URL, using `resolve_host()`
+pub fn resolve_url(url_str: &str) -> Result<Option<SocketAddr>, Error> {
+    let Ok(url) = url::Url::parse(url_str) else {
+        return Ok(None);
+    };
+    let Some(host_str) = url.host_str() else {
+        return Ok(None);
+    };
+    let Some(port) = url.port_or_known_default() else {
+        return Ok(None);
+    };
+    Ok(resolve_host(host_str, port)?.into_iter().next())
+}
+
+/// Write out the destination the proxy should connect to
+fn proxy_target(addr: &SocketAddr) -> (Option<String>, SocketAddr) {
+    (virtual_addr_host(addr), addr.clone())
+}
+
+/// Ask a SOCKS5 proxy to connect us to `addr`
+fn socks5_connect(sock: &mut TcpStream, addr: &SocketAddr) -> Result<(), Error> {
+    // no authentication
+    sock.write_all(&[0x05, 0x01, 0x00])?;
+    let mut method_reply = [0u8; 2];
+    sock.read_exact(&mut method_reply)?;
+    if method_reply != [0x05, 0x00] {
+        return Err(Error::RPCError(format!(
+            "SOCKS5 proxy rejected unauthenticated access (reply {:?})",
+            &method_reply
+        )));
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    match proxy_target(addr) {
+        (Some(host), _) => {
+            let host_bytes = host.as_bytes();
+            let host_len = u8::try_from(host_bytes.len())
+                .map_err(|_| Error::MalformedRequest(format!("Host name too long: {}", &host)))?;
+            request.push(0x03);
+            request.push(host_len);
+            request.extend_from_slice(host_bytes);
+        }
+        (None, SocketAddr::V4(addr_v4)) => {
+            request.push(0x01);
+            request.extend_from_slice(&addr_v4.ip().octets());
+        }
+        (None, SocketAddr::V6(addr_v6)) => {
+            request.push(0x04);
+            request.extend_from_slice(&addr_v6.ip().octets());
+        }
+    }
+    request.extend_from_slice(&addr.port().to_be_bytes());
+    sock.write_all(&request)?;
+
+    let mut reply = [0u8; 4];
+    sock.read_exact(&mut reply)?;
+    if reply[0] != 0x05 {
+        return Err(Error::MalformedResponse(format!(
+            "Invalid SOCKS5 reply version {}",
+            reply[0]
+        )));
+    }
+    match reply[1] {
+        0x00 => {}
+        0x05 => {
+            return Err(Error::ConnectionRefused(format!(
+                "SOCKS5 proxy: connection to {} refused",
+                addr
+            )));
+        }
+        0x06 => {
+            return Err(Error::Timeout(format!(
+                "SOCKS5 proxy: connection to {} timed out",
+                addr
+            )));
+        }
+        code => {
+            return Err(Error::RPCError(format!(
+                "SOCKS5 proxy failed to connect to {}: error code {}",
+                addr, code
+            )));
+        }
+    }
+
+    // consume the bound address, which we don't need
+    let bound_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => {
+            let mut len = [0u8; 1];
+            sock.read_exact(&mut len)?;
+            usize::from(len[0])
+        }
+        atyp => {
+            return Err(Error::MalformedResponse(format!(
+                "Invalid SOCKS5 address type {}",
+                atyp
+            )));
+        }
+    };
+    let mut bound = vec![0u8; bound_len + 2];
+    sock.read_exact(&mut bound)?;
+    Ok(())
+}
+
+/// Ask an HTTP proxy to connect us to `addr`
+fn http_connect(sock: &mut TcpStream, addr: &SocketAddr) -> Result<(), Error> {
+    let target = match proxy_target(addr) {
+        (Some(host), _) => format!("{}:{}", &host, addr.port()),
+        (None, addr) => addr.to_string(),
+    };
+    let request = format!(
+        "CONNECT {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: wrb/0.1\r\n\r\n",
+        &target, &target
+    );
+    sock.write_all(request.as_bytes())?;
+
+    let headers = read_http_response_headers(sock)?;
+    match decode_http_response(&headers) {
+        Ok(_) => Ok(()),
+        Err(Error::HttpError(504, ..)) => Err(Error::Timeout(format!(
+            "HTTP proxy: connection to {} timed out",
+            &target
+        ))),
+        Err(Error::HttpError(code, ..)) => Err(Error::ConnectionRefused(format!(
+            "HTTP proxy refused to connect to {}: HTTP {}",
+            &target, code
+        ))),
+        Err(e) => Err(e),
+    }
+}
+
+/// Connect to `addr` through a proxy.
+/// `connect_timeout` bounds both the connection to the proxy and the proxy's handshake.
+pub fn proxy_connect(
+    proxy: &ProxyConfig,
+    addr: &SocketAddr,
+    connect_timeout: Duration,
+) -> Result<TcpStream, Error> {
+    wrb_debug!("Connect to {} via {}", addr, proxy);
+    let mut sock = TcpStream::connect_timeout(&proxy.addr, connect_timeout)?;
+    sock.set_read_timeout(Some(connect_timeout))?;
+    sock.set_write_timeout(Some(connect_timeout))?;
+    match proxy.kind {
+        ProxyKind::Socks5 => socks5_connect(&mut sock, addr)?,
+        ProxyKind::HttpConnect => http_connect(&mut sock, addr)?,
+    }
+    Ok(sock)
+}
@@ -0,0 +1,433 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clarity::vm::types::QualifiedContractIdentifier;
+
+use libstackerdb::SlotMetadata;
+
+use rusqlite::Connection;
+use rusqlite::OpenFlags;
+use rusqlite::Row;
+
+use crate::runner::Error;
+use crate::runner::Runner;
+
+use crate::storage::StackerDBClient;
+
+use crate::util::sqlite::Error as DBError;
+use crate::util::sqlite::FromColumn;
+use crate::util::sqlite::FromRow;
+use crate::util::sqlite::{query_row, query_rows, sqlite_open, tx_begin_immediate, u64_to_sql};
+
+const REPLICA_STATS_SCHEMA: &'static [&'static str] = &[
+    r#"
+    CREATE TABLE IF NOT EXISTS replica_stats(
+        -- p2p address of the replica, as reported by the home node
+        addr TEXT PRIMARY KEY NOT NULL,
+        successes INTEGER NOT NULL,
+        failures INTEGER NOT NULL,
+        -- moving average of the time taken to fetch a verified chunk
+        avg_latency_ms INTEGER NOT NULL
+    );"#,
+    r#"
+    CREATE TABLE IF NOT EXISTS schema_version(
+        version INTEGER NOT NULL
+    );
+    "#,
+    r#"
+    INSERT INTO schema_version (version) VALUES (1);
+    "#,
+];
+
+/// How well a StackerDB replica has served us in the past
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicaStats {
+    /// p2p address of the replica
+    pub addr: SocketAddr,
+    /// number of verified chunk fetches
+    pub successes: u64,
+    /// number of failed or timed-out chunk fetches
+    pub failures: u64,
+    /// moving average of successful fetch latency
+    pub avg_latency_ms: u64,
+}
+
+impl ReplicaStats {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            successes: 0,
+            failures: 0,
+            avg_latency_ms: 0,
+        }
+    }
+
+    /// Sort key for this replica.  Lower is better.
+    /// Replicas are ordered first by failure rate (in percent), and then by latency.  Replicas
+    /// we have never successfully fetched from sort after the known-good ones with the same
+    /// failure rate.
+    pub fn sort_key(&self) -> (u64, u64) {
+        let attempts = self.successes.saturating_add(self.failures);
+        let failure_pct = if attempts == 0 {
+            0
+        } else {
+            self.failures.saturating_mul(100) / attempts
+        };
+        let latency = if self.successes == 0 {
+            u64::MAX
+        } else {
+            self.avg_latency_ms
+        };
+        (failure_pct, latency)
+    }
+
+    /// Record a successful, verified fetch
+    pub fn add_success(&mut self, latency: Duration) {
+        let latency_ms = u64::try_from(latency.as_millis())
+            .unwrap_or(u64::MAX)
+            .min(i64::MAX as u64);
+        if self.successes == 0 {
+            self.avg_latency_ms = latency_ms;
+        } else {
+            self.avg_latency_ms =
+                (self.avg_latency_ms.saturating_mul(3) / 4).saturating_add(latency_ms / 4);
+        }
+        self.successes = self.successes.saturating_add(1);
+    }
+
+    /// Record a failed fetch
+    pub fn add_failure(&mut self) {
+        self.failures = self.failures.saturating_add(1);
+    }
+}
+
+impl FromRow<ReplicaStats> for ReplicaStats {
+    fn from_row<'a>(row: &'a Row) -> Result<Self, DBError> {
+        let addr_str: String = row.get("addr")?;
+        let addr: SocketAddr = addr_str.parse().map_err(|_| DBError::ParseError)?;
+        let successes = u64::from_column(row, "successes")?;
+        let failures = u64::from_column(row, "failures")?;
+        let avg_latency_ms = u64::from_column(row, "avg_latency_ms")?;
+        Ok(Self {
+            addr,
+            successes,
+            failures,
+            avg_latency_ms,
+        })
+    }
+}
+
+/// Database of replica latency and failure stats, stored in the wrb storage directory.
+/// Used to decide which replicas to ask first for a wrbsite.
+pub struct ReplicaStatsDB {
+    pub path: String,
+    conn: Connection,
+}
+
+impl ReplicaStatsDB {
+    /// Open the stats DB, creating it if it doesn't exist
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let (create, open_flags) = if path != ":memory:" && fs::metadata(path).is_ok() {
+            (false, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        } else {
+            (
+                true,
+                OpenFlags::SQLITE_OPEN_CREATE | OpenFlags::SQLITE_OPEN_READ_WRITE,
+            )
+        };
+
+        let mut conn = sqlite_open(path, open_flags, true)?;
+        if create {
+            wrb_debug!("Instantiate ReplicaStatsDB at {}", path);
+            let tx = tx_begin_immediate(&mut conn)?;
+            for cmd in REPLICA_STATS_SCHEMA.iter() {
+                tx.execute(cmd, rusqlite::params![])?;
+            }
+            tx.commit()?;
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            conn,
+        })
+    }
+
+    /// Get the stats for a replica, if we have any
+    pub fn get_stats(&self, addr: &SocketAddr) -> Result<Option<ReplicaStats>, Error> {
+        let sql = "SELECT * FROM replica_stats WHERE addr = ?1";
+        Ok(query_row(
+            &self.conn,
+            sql,
+            rusqlite::params![&addr.to_string()],
+        )?)
+    }
+
+    /// Get stats for all replicas
+    pub fn get_all_stats(&self) -> Result<Vec<ReplicaStats>, Error> {
+        let sql = "SELECT * FROM replica_stats ORDER BY addr ASC";
+        Ok(query_rows(&self.conn, sql, rusqlite::params![])?)
+    }
+
+    /// Store stats, replacing whatever was there before
+    pub fn put_stats(&mut self, stats: &ReplicaStats) -> Result<(), Error> {
+        let tx = tx_begin_immediate(&mut self.conn)?;
+        let sql = "INSERT OR REPLACE INTO replica_stats (addr,successes,failures,avg_latency_ms) VALUES (?1,?2,?3,?4)";
+        let args = rusqlite::params![
+            &stats.addr.to_string(),
+            u64_to_sql(stats.successes)?,
+            u64_to_sql(stats.failures)?,
+            u64_to_sql(stats.avg_latency_ms)?
+        ];
+        tx.execute(sql, args)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record the outcome of a fetch from a replica.
+    /// `latency_opt` is the time taken to get a verified chunk, or None if the fetch failed.
+    pub fn record(
+        &mut self,
+        addr: &SocketAddr,
+        latency_opt: Option<Duration>,
+    ) -> Result<ReplicaStats, Error> {
+        let mut stats = self
+            .get_stats(addr)?
+            .unwrap_or_else(|| ReplicaStats::new(addr.clone()));
+        match latency_opt {
+            Some(latency) => stats.add_success(latency),
+            None => stats.add_failure(),
+        }
+        self.put_stats(&stats)?;
+        Ok(stats)
+    }
+
+    /// Sort replicas so that the ones which have served us best in the past come first.
+    /// Replicas we know nothing about keep their relative order.
+    pub fn order_replicas(&self, replicas: &mut Vec<SocketAddr>) -> Result<(), Error> {
+        let mut keys = HashMap::new();
+        for addr in replicas.iter() {
+            let stats = self
+                .get_stats(addr)?
+                .unwrap_or_else(|| ReplicaStats::new(addr.clone()));
+            keys.insert(addr.clone(), stats.sort_key());
+        }
+        replicas.sort_by_key(|addr| keys.get(addr).cloned().unwrap_or((0, u64::MAX)));
+        Ok(())
+    }
+}
+
+/// Outcome of one replica's attempt to fetch one chunk: the replica, the chunk's index, how long
+/// it took, and the verified chunk data.
+type ChunkFetchResult = (SocketAddr, usize, Duration, Result<Vec<u8>, Error>);
+
+/// A set of chunks which a ReplicaFetcher's workers race to fetch
+struct ChunkFetchJob {
+    contract_id: QualifiedContractIdentifier,
+    chunks: Vec<SlotMetadata>,
+    /// which chunks have been fetched and verified already
+    done: Vec<AtomicBool>,
+    /// set once the fetch is over, so workers stop starting new requests for it
+    cancel: AtomicBool,
+}
+
+/// Fetches wrbsite chunks from several replicas at once, with one worker thread per replica.
+/// Each worker fetches the chunks in turn, starting from a different chunk, and skips the chunks
+/// that another replica has already served, so the first verified copy of each chunk wins.
+/// Workers that are still busy when a fetch ends are told to stop, and their sockets time out
+/// within the replica timeout.  The workers exit once the fetcher is dropped.
+pub struct ReplicaFetcher {
+    contract_id: QualifiedContractIdentifier,
+    replica_timeout: Duration,
+    workers: Vec<(
+        SocketAddr,
+        Sender<(Arc<ChunkFetchJob>, Sender<ChunkFetchResult>)>,
+    )>,
+}
+
+impl ReplicaFetcher {
+    /// Start a worker for each replica client
+    pub fn new(
+        contract_id: QualifiedContractIdentifier,
+        replica_clients: Vec<(SocketAddr, Box<dyn StackerDBClient>)>,
+        replica_timeout: Duration,
+    ) -> Self {
+        let mut workers = vec![];
+        for (worker_idx, (replica_addr, mut replica_client)) in
+            replica_clients.into_iter().enumerate()
+        {
+            replica_client.set_timeout(replica_timeout);
+            let (job_tx, job_rx) = mpsc::channel();
+            let worker_addr = replica_addr.clone();
+            thread::spawn(move || {
+                Self::run_worker(worker_idx, worker_addr, replica_client, job_rx)
+            });
+            workers.push((replica_addr, job_tx));
+        }
+        Self {
+            contract_id,
+            replica_timeout,
+            workers,
+        }
+    }
+
+    /// Fetch each job's chunks from one replica, until the fetcher goes away
+    fn run_worker(
+        worker_idx: usize,
+        replica_addr: SocketAddr,
+        mut replica_client: Box<dyn StackerDBClient>,
+        job_rx: Receiver<(Arc<ChunkFetchJob>, Sender<ChunkFetchResult>)>,
+    ) {
+        while let Ok((job, result_tx)) = job_rx.recv() {
+            let num_chunks = job.chunks.len();
+            for i in 0..num_chunks {
+                if job.cancel.load(Ordering::SeqCst) {
+                    break;
+                }
+                let chunk_idx = (worker_idx + i) % num_chunks;
+                if job.done[chunk_idx].load(Ordering::SeqCst) {
+                    continue;
+                }
+                let start = Instant::now();
+                let res = Runner::wrbsite_load_chunk(
+                    &job.contract_id,
+                    &job.chunks[chunk_idx],
+                    &mut *replica_client,
+                );
+                // receiver goes away once the fetch is over
+                if result_tx
+                    .send((replica_addr.clone(), chunk_idx, start.elapsed(), res))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Fetch and verify the given chunks, returning their data in order.
+    /// Gives up once no replica has served a new chunk within the replica timeout.  Replica
+    /// successes and failures are recorded in `runner`'s replica stats.
+    pub fn fetch(
+        &mut self,
+        runner: &Runner,
+        chunks: &[SlotMetadata],
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let job = Arc::new(ChunkFetchJob {
+            contract_id: self.contract_id.clone(),
+            chunks: chunks.to_vec(),
+            done: chunks.iter().map(|_| AtomicBool::new(false)).collect(),
+            cancel: AtomicBool::new(false),
+        });
+        let (result_tx, result_rx) = mpsc::channel();
+        let mut pending = HashSet::new();
+        for (replica_addr, job_tx) in self.workers.iter() {
+            if job_tx.send((job.clone(), result_tx.clone())).is_ok() {
+                pending.insert(replica_addr.clone());
+            }
+        }
+        drop(result_tx);
+
+        let mut fetched: Vec<Option<Vec<u8>>> = vec![None; chunks.len()];
+        let mut num_fetched = 0;
+        let mut errors = vec![];
+        let mut deadline = Instant::now() + self.replica_timeout;
+        while num_fetched < chunks.len() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let Ok((replica_addr, chunk_idx, latency, res)) =
+                result_rx.recv_timeout(deadline - now)
+            else {
+                break;
+            };
+            pending.remove(&replica_addr);
+            let slot_metadata = &chunks[chunk_idx];
+            match res {
+                Ok(chunk_bytes) => {
+                    wrb_debug!(
+                        "Got {}[{}.{}] from {} in {:?}",
+                        &self.contract_id,
+                        slot_metadata.slot_id,
+                        slot_metadata.slot_version,
+                        &replica_addr,
+                        &latency
+                    );
+                    runner.record_replica_stats(&replica_addr, Some(latency));
+                    if fetched[chunk_idx].is_none() {
+                        job.done[chunk_idx].store(true, Ordering::SeqCst);
+                        fetched[chunk_idx] = Some(chunk_bytes);
+                        num_fetched += 1;
+
+                        // the replicas are making progress, so give them time for the next chunk
+                        deadline = Instant::now() + self.replica_timeout;
+                    }
+                }
+                Err(e) => {
+                    wrb_debug!(
+                        "Replica {} failed to serve {}[{}.{}]: {:?}",
+                        &replica_addr,
+                        &self.contract_id,
+                        slot_metadata.slot_id,
+                        slot_metadata.slot_version,
+                        &e
+                    );
+                    runner.record_replica_stats(&replica_addr, None);
+                    errors.push(format!(
+                        "{}: slot {}.{}: {:?}",
+                        &replica_addr, slot_metadata.slot_id, slot_metadata.slot_version, &e
+                    ));
+                }
+            }
+        }
+        job.cancel.store(true, Ordering::SeqCst);
+
+        if num_fetched == chunks.len() {
+            return Ok(fetched.into_iter().flatten().collect());
+        }
+
+        for replica_addr in pending.iter() {
+            wrb_debug!("Replica {} timed out", replica_addr);
+            runner.record_replica_stats(replica_addr, None);
+            errors.push(format!("{}: timed out", replica_addr));
+        }
+
+        let missing: Vec<_> = chunks
+            .iter()
+            .zip(fetched.iter())
+            .filter(|(_, chunk_bytes)| chunk_bytes.is_none())
+            .map(|(slot_metadata, _)| {
+                format!("{}.{}", slot_metadata.slot_id, slot_metadata.slot_version)
+            })
+            .collect();
+
+        Err(Error::Storage(format!(
+            "Failed to get StackerDB chunks {:?} for site {} from any replica: {:?}",
+            &missing, &self.contract_id, &errors
+        )))
+    }
+}
@@ -23,6 +23,7 @@ use std::env;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::Duration;
 
 use base64ct::{Base64, Encoding};
 
@@ -31,15 +32,21 @@ use clarity::vm::types::QualifiedContractIdentifier;
 
 use crate::core::Config;
 
+use crate::net::session::NodeSession;
 use crate::runner;
 use crate::runner::bns::BNSResolver;
-use crate::runner::stackerdb::StackerDBSession;
+use crate::runner::pins::{SitePin, SitePinDB, SitePinMismatch};
+use crate::runner::proxy::resolve_url;
+use crate::runner::replicas::ReplicaFetcher;
+use crate::runner::replicas::ReplicaStatsDB;
+use crate::runner::stackerdb::{StackerDBP2PSession, StackerDBSession};
 use crate::runner::Error;
 use crate::runner::Runner;
 
 use crate::storage::StackerDBClient;
 
 use stacks_common::codec::{read_next, write_next, Error as CodecError, StacksMessageCodec};
+use stacks_common::types::chainstate::StacksAddress;
 use stacks_common::util::hash::Sha512Trunc256Sum;
 use stacks_common::util::secp256k1::MessageSignature;
 
@@ -53,10 +60,41 @@ pub struct WrbTxtRecordV1 {
     pub slot_metadata: SlotMetadata,
 }
 
+/// Maximum number of chunks a multi-chunk wrbsite may span
+pub const WRBSITE_MANIFEST_MAX_PARTS: u32 = 4096;
+
+/// List of the StackerDB slots which hold the parts of a multi-chunk wrbsite.
+/// The wrbsite's (compressed) code is the concatenation of the parts' chunks, in order.
+/// The manifest itself is stored in a StackerDB slot.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WrbsiteManifest {
+    pub parts: Vec<SlotMetadata>,
+}
+
+/// Wrbsite TXT record for a multi-chunk wrbsite.  It points to the slot which holds the
+/// wrbsite's manifest.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WrbTxtRecordV2 {
+    pub contract_id: QualifiedContractIdentifier,
+    pub manifest_metadata: SlotMetadata,
+}
+
 /// Information embedded in a wrbsite TXT record in a BNS zonefile
 #[derive(Debug, PartialEq, Clone)]
 pub enum WrbTxtRecord {
     V1(WrbTxtRecordV1),
+    V2(WrbTxtRecordV2),
+}
+
+fn serialize_slot_metadata<W: Write>(
+    slot_metadata: &SlotMetadata,
+    fd: &mut W,
+) -> Result<(), CodecError> {
+    write_next(fd, &slot_metadata.slot_id)?;
+    write_next(fd, &slot_metadata.slot_version)?;
+    write_next(fd, &slot_metadata.data_hash)?;
+    write_next(fd, &slot_metadata.signature)?;
+    Ok(())
 }
 
 impl WrbTxtRecordV1 {
@@ -68,11 +106,7 @@ impl WrbTxtRecordV1 {
     }
 
     pub fn serialize_slot_metadata<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
-        write_next(fd, &self.slot_metadata.slot_id)?;
-        write_next(fd, &self.slot_metadata.slot_version)?;
-        write_next(fd, &self.slot_metadata.data_hash)?;
-        write_next(fd, &self.slot_metadata.signature)?;
-        Ok(())
+        serialize_slot_metadata(&self.slot_metadata, fd)
     }
 
     pub fn serialize_contract_id<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
@@ -125,6 +159,72 @@ impl StacksMessageCodec for WrbTxtRecordV1 {
     }
 }
 
+impl WrbTxtRecordV2 {
+    pub fn new(contract_id: QualifiedContractIdentifier, manifest_metadata: SlotMetadata) -> Self {
+        Self {
+            contract_id,
+            manifest_metadata,
+        }
+    }
+}
+
+impl StacksMessageCodec for WrbTxtRecordV2 {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
+        let principal = PrincipalData::Contract(self.contract_id.clone());
+        write_next(fd, &principal)?;
+        serialize_slot_metadata(&self.manifest_metadata, fd)?;
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<Self, CodecError> {
+        let contract_id = WrbTxtRecordV1::deserialize_contract_id(fd)?;
+        let manifest_metadata = WrbTxtRecordV1::deserialize_slot_metadata(fd)?;
+        Ok(WrbTxtRecordV2 {
+            contract_id,
+            manifest_metadata,
+        })
+    }
+}
+
+impl WrbsiteManifest {
+    pub fn new(parts: Vec<SlotMetadata>) -> Self {
+        Self { parts }
+    }
+}
+
+impl StacksMessageCodec for WrbsiteManifest {
+    fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
+        let num_parts = u32::try_from(self.parts.len())
+            .map_err(|_| CodecError::SerializeError("Too many manifest parts".into()))?;
+        if num_parts > WRBSITE_MANIFEST_MAX_PARTS {
+            return Err(CodecError::SerializeError(format!(
+                "Too many manifest parts: {} > {}",
+                num_parts, WRBSITE_MANIFEST_MAX_PARTS
+            )));
+        }
+        write_next(fd, &num_parts)?;
+        for part in self.parts.iter() {
+            serialize_slot_metadata(part, fd)?;
+        }
+        Ok(())
+    }
+
+    fn consensus_deserialize<R: Read>(fd: &mut R) -> Result<Self, CodecError> {
+        let num_parts: u32 = read_next(fd)?;
+        if num_parts > WRBSITE_MANIFEST_MAX_PARTS {
+            return Err(CodecError::DeserializeError(format!(
+                "Too many manifest parts: {} > {}",
+                num_parts, WRBSITE_MANIFEST_MAX_PARTS
+            )));
+        }
+        let mut parts = vec![];
+        for _ in 0..num_parts {
+            parts.push(WrbTxtRecordV1::deserialize_slot_metadata(fd)?);
+        }
+        Ok(Self { parts })
+    }
+}
+
 impl StacksMessageCodec for WrbTxtRecord {
     fn consensus_serialize<W: Write>(&self, fd: &mut W) -> Result<(), CodecError> {
         match self {
@@ -132,6 +232,10 @@ impl StacksMessageCodec for WrbTxtRecord {
                 write_next(fd, &1u8)?;
                 write_next(fd, payload)?;
             }
+            Self::V2(payload) => {
+                write_next(fd, &2u8)?;
+                write_next(fd, payload)?;
+            }
         }
         Ok(())
     }
@@ -143,6 +247,10 @@ impl StacksMessageCodec for WrbTxtRecord {
                 let payload: WrbTxtRecordV1 = read_next(fd)?;
                 Ok(Self::V1(payload))
             }
+            2u8 => {
+                let payload: WrbTxtRecordV2 = read_next(fd)?;
+                Ok(Self::V2(payload))
+            }
             _ => Err(CodecError::DeserializeError(format!(
                 "Unsupported version {}",
                 &version
@@ -224,6 +332,12 @@ impl From<WrbTxtRecordV1> for WrbTxtRecord {
     }
 }
 
+impl From<WrbTxtRecordV2> for WrbTxtRecord {
+    fn from(wrb_rec: WrbTxtRecordV2) -> WrbTxtRecord {
+        WrbTxtRecord::V2(wrb_rec)
+    }
+}
+
 impl ToString for ZonefileResourceRecord {
     fn to_string(&self) -> String {
         let payload_quoted = Self::escape_string(&self.rr_payload).unwrap_or("\"\"".to_string());
@@ -249,6 +363,23 @@ impl WrbTxtRecord {
             slot_metadata,
         })
     }
+
+    /// StackerDB which hosts the wrbsite
+    pub fn contract_id(&self) -> &QualifiedContractIdentifier {
+        match self {
+            Self::V1(rec) => &rec.contract_id,
+            Self::V2(rec) => &rec.contract_id,
+        }
+    }
+
+    /// Metadata for the slot this record points to.  For a V1 record, this is the slot with the
+    /// wrbsite's code.  For a V2 record, this is the slot with the wrbsite's manifest.
+    pub fn root_slot_metadata(&self) -> &SlotMetadata {
+        match self {
+            Self::V1(rec) => &rec.slot_metadata,
+            Self::V2(rec) => &rec.manifest_metadata,
+        }
+    }
 }
 
 /// Barebones zonefile resource record.
@@ -373,54 +504,156 @@ impl Runner {
         Ok(recs)
     }
 
-    /// Load a wrbsite, given the decoded wrb txt record.
-    /// `wrbrec.slot_metadata` must have been authenticated.
-    pub fn wrbsite_load_from_zonefile_rec(
-        wrbrec: &WrbTxtRecordV1,
+    /// Load and authenticate a single StackerDB chunk.
+    /// `slot_metadata` must have been authenticated.
+    pub fn wrbsite_load_chunk(
+        contract_id: &QualifiedContractIdentifier,
+        slot_metadata: &SlotMetadata,
         replica_stackerdb_client: &mut dyn StackerDBClient,
-    ) -> Result<Option<Vec<u8>>, Error> {
-        let mut chunks = replica_stackerdb_client.get_chunks(&[(
-            wrbrec.slot_metadata.slot_id,
-            wrbrec.slot_metadata.slot_version,
-        )])?;
+    ) -> Result<Vec<u8>, Error> {
+        let mut chunks = replica_stackerdb_client
+            .get_chunks(&[(slot_metadata.slot_id, slot_metadata.slot_version)])?;
         if chunks.len() != 1 {
             return Err(Error::Storage(format!(
                 "Failed to get StackerDB chunk for site {}[{}.{}]: did not get any slots",
-                &wrbrec.contract_id,
-                wrbrec.slot_metadata.slot_id,
-                wrbrec.slot_metadata.slot_version
+                contract_id, slot_metadata.slot_id, slot_metadata.slot_version
             )));
         }
 
         let Some(Some(chunk_bytes)) = chunks.pop() else {
             return Err(Error::Storage(format!(
                 "Failed to get StackerDB chunk for site {}[{}.{}]: no slot data returned",
-                &wrbrec.contract_id,
-                wrbrec.slot_metadata.slot_id,
-                wrbrec.slot_metadata.slot_version
+                contract_id, slot_metadata.slot_id, slot_metadata.slot_version
             )));
         };
 
         // authenticate
         let chunk_hash = Sha512Trunc256Sum::from_data(&chunk_bytes);
-        if chunk_hash != wrbrec.slot_metadata.data_hash {
+        if chunk_hash != slot_metadata.data_hash {
             return Err(Error::Storage(format!(
                 "Site hash mismatch for site {}[{}.{}]: {} != {}",
-                &wrbrec.contract_id,
-                wrbrec.slot_metadata.slot_id,
-                wrbrec.slot_metadata.slot_version,
-                &wrbrec.slot_metadata.data_hash,
+                contract_id,
+                slot_metadata.slot_id,
+                slot_metadata.slot_version,
+                &slot_metadata.data_hash,
                 &chunk_hash
             )));
         }
 
+        Ok(chunk_bytes)
+    }
+
+    /// Load a wrbsite, given the decoded wrb txt record.
+    /// `wrbrec.slot_metadata` must have been authenticated.
+    pub fn wrbsite_load_from_zonefile_rec(
+        wrbrec: &WrbTxtRecordV1,
+        replica_stackerdb_client: &mut dyn StackerDBClient,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let chunk_bytes = Self::wrbsite_load_chunk(
+            &wrbrec.contract_id,
+            &wrbrec.slot_metadata,
+            replica_stackerdb_client,
+        )?;
         Ok(Some(chunk_bytes))
     }
 
+    /// Verify that a slot's metadata was signed by the slot's signer.
+    /// Returns the signer's address on success.
+    pub fn verify_slot_signer(
+        slot_metadata: &SlotMetadata,
+        signers: &[StacksAddress],
+    ) -> Result<StacksAddress, Error> {
+        let Some(signer_addr) =
+            signers.get(usize::try_from(slot_metadata.slot_id).unwrap_or(usize::MAX))
+        else {
+            return Err(Error::Storage(format!(
+                "Slot {} exceeds number of signers ({})",
+                slot_metadata.slot_id,
+                signers.len()
+            )));
+        };
+
+        slot_metadata.verify(signer_addr).map_err(|e| {
+            Error::Storage(format!(
+                "Failed to authenticate slot {} with {}: {:?}",
+                slot_metadata.slot_id, signer_addr, &e
+            ))
+        })?;
+
+        Ok(signer_addr.clone())
+    }
+
+    /// Fetch the parts of a multi-chunk wrbsite and concatenate them.
+    /// The fetcher's replicas race to fetch the parts, and the first verified copy of each part
+    /// wins (see ReplicaFetcher).
+    /// Each part's metadata must have been authenticated.
+    pub fn wrbsite_fetch_parts(
+        &self,
+        fetcher: &mut ReplicaFetcher,
+        parts: &[SlotMetadata],
+    ) -> Result<Vec<u8>, Error> {
+        let wrbsite_bytes = fetcher.fetch(self, parts)?.concat();
+        Ok(wrbsite_bytes)
+    }
+
+    /// Load a multi-chunk wrbsite, given its decoded wrb txt record.
+    /// `wrbrec.manifest_metadata` must have been authenticated.
+    /// Each part listed in the manifest is authenticated against `signers`.
+    /// The manifest and the parts are fetched from all of `replica_clients` at once.
+    pub fn wrbsite_load_from_manifest(
+        &self,
+        wrbrec: &WrbTxtRecordV2,
+        signers: &[StacksAddress],
+        replica_clients: Vec<(SocketAddr, Box<dyn StackerDBClient>)>,
+    ) -> Result<Vec<u8>, Error> {
+        let mut fetcher = ReplicaFetcher::new(
+            wrbrec.contract_id.clone(),
+            replica_clients,
+            self.replica_timeout,
+        );
+        let manifest_bytes = fetcher
+            .fetch(self, &[wrbrec.manifest_metadata.clone()])?
+            .concat();
+
+        let manifest = WrbsiteManifest::consensus_deserialize(&mut &manifest_bytes[..])
+            .map_err(|e| Error::Deserialize(format!("Failed to decode manifest: {:?}", &e)))?;
+
+        for part in manifest.parts.iter() {
+            Self::verify_slot_signer(part, signers)?;
+        }
+
+        self.wrbsite_fetch_parts(&mut fetcher, &manifest.parts)
+    }
+
     /// Load a wrbsite, given the zonefile of a BNS name
     pub fn wrbsite_load_from_zonefile<F, G>(
         &mut self,
         zonefile: Vec<u8>,
+        home_connector: F,
+        replica_connector: G,
+    ) -> Result<Option<(Vec<u8>, u32)>, Error>
+    where
+        F: FnMut(
+            &QualifiedContractIdentifier,
+            &SocketAddr,
+        ) -> Result<Box<dyn StackerDBClient>, Error>,
+        G: FnMut(
+            &QualifiedContractIdentifier,
+            &SocketAddr,
+        ) -> Result<Box<dyn StackerDBClient>, Error>,
+    {
+        self.wrbsite_load_from_zonefile_pinned(None, zonefile, home_connector, replica_connector)
+    }
+
+    /// Load a wrbsite, given the zonefile of a BNS name.
+    /// If `name_opt` is given, then the site's publisher and code will be checked against the
+    /// site's pin (creating one if this is the first time we've seen the site).  Records which do
+    /// not match the pin are skipped; if no record can be loaded because of this, then
+    /// Error::SitePinMismatch(..) is returned.
+    pub fn wrbsite_load_from_zonefile_pinned<F, G>(
+        &mut self,
+        name_opt: Option<&str>,
+        zonefile: Vec<u8>,
         mut home_connector: F,
         mut replica_connector: G,
     ) -> Result<Option<(Vec<u8>, u32)>, Error>
@@ -440,6 +673,7 @@ impl Runner {
         let recs = Self::decode_zonefile_records(zonefile)?;
 
         let mut error_reasons = vec![];
+        let mut pin_mismatch = None;
         for (i, rec) in recs.into_iter().enumerate() {
             let rec_txt = rec.to_string();
             if rec.rr_name.as_str() != "wrb" {
@@ -468,107 +702,120 @@ impl Runner {
                 continue;
             };
 
-            let WrbTxtRecord::V1(wrbrec) = wrbrec;
+            let contract_id = wrbrec.contract_id().clone();
+            let root_slot_metadata = wrbrec.root_slot_metadata().clone();
 
             // query this replica's signers from the home node
-            let mut home_client = home_connector(&wrbrec.contract_id, &home_node_addr)?;
+            let mut home_client = home_connector(&contract_id, &home_node_addr)?;
             let signers = home_client.get_signers()?;
 
             // make sure the wrb txt record is consistent with the current signers
-            if wrbrec.slot_metadata.slot_id >= u32::try_from(signers.len()).unwrap_or(u32::MAX) {
-                wrb_info!(
-                    "WRB record is at slot {}, which exceeds the number of signers ({})",
-                    wrbrec.slot_metadata.slot_id,
-                    signers.len()
-                );
-                error_reasons.push(format!(
-                    "Slot {} exceeds number of signers ({}) in record {} '{}'",
-                    wrbrec.slot_metadata.slot_id,
-                    signers.len(),
-                    i,
-                    &rec_txt
-                ));
-                continue;
-            }
-
-            let Some(signer_addr) =
-                signers.get(usize::try_from(wrbrec.slot_metadata.slot_id).unwrap_or(usize::MAX))
-            else {
-                wrb_info!(
-                    "No such WRB signer for slot {}",
-                    wrbrec.slot_metadata.slot_id
-                );
-                error_reasons.push(format!(
-                    "No such signer for slot {} in record {} '{}'",
-                    wrbrec.slot_metadata.slot_id, i, &rec_txt
-                ));
-                continue;
+            let signer_addr = match Self::verify_slot_signer(&root_slot_metadata, &signers) {
+                Ok(signer_addr) => signer_addr,
+                Err(e) => {
+                    wrb_info!("Failed to authenticate WRB record: {:?}", &e);
+                    error_reasons.push(format!(
+                        "Failed to authenticate record {} '{}': {:?}",
+                        i, &rec_txt, &e
+                    ));
+                    continue;
+                }
             };
 
-            if let Err(e) = wrbrec.slot_metadata.verify(signer_addr) {
-                wrb_warn!(
-                    "Failed to authenticate slot {} with {}: {:?}",
-                    wrbrec.slot_metadata.slot_id,
-                    signer_addr,
-                    &e
-                );
-                error_reasons.push(format!(
-                    "Failed to authenticate slot {} with {} in record {} '{}'",
-                    wrbrec.slot_metadata.slot_id, &signer_addr, i, &rec_txt
-                ));
-                continue;
-            }
-
             // find nodes that replicate this stackerdb
-            let replicas = home_client.find_replicas()?;
+            let mut replicas = home_client.find_replicas()?;
             if replicas.len() == 0 {
-                wrb_warn!("No replicas found for StackerDB {}", &wrbrec.contract_id);
+                wrb_warn!("No replicas found for StackerDB {}", &contract_id);
                 error_reasons.push(format!(
                     "No replicas found for StackerDB {} in record {} '{}'",
-                    &wrbrec.contract_id, i, &rec_txt
+                    &contract_id, i, &rec_txt
                 ));
                 continue;
             }
-            for replica_addr in replicas.iter() {
-                let Ok(mut replica_client) = replica_connector(&wrbrec.contract_id, replica_addr)
-                    .inspect_err(|e| {
-                        wrb_warn!(
-                            "Failed to connect to replica {} of {}: {:?}",
-                            replica_addr,
-                            &wrbrec.contract_id,
-                            &e
-                        );
-                        error_reasons.push(format!(
-                            "Failed to connect to replica {} of {} in record {} '{}': {:?}",
-                            replica_addr, &wrbrec.contract_id, i, &rec_txt, &e
-                        ));
-                    })
-                else {
+
+            // try the replicas which have served us best first
+            self.order_replicas(&mut replicas);
+
+            // query batches of replicas concurrently, and take the first verified copy of
+            // each chunk
+            let mut wrbsite_bytes_opt = None;
+            for replica_batch in replicas.chunks(self.max_parallel_replicas) {
+                let mut replica_clients = vec![];
+                for replica_addr in replica_batch.iter() {
+                    match replica_connector(&contract_id, replica_addr) {
+                        Ok(replica_client) => {
+                            replica_clients.push((replica_addr.clone(), replica_client))
+                        }
+                        Err(e) => {
+                            wrb_warn!(
+                                "Failed to connect to replica {} of {}: {:?}",
+                                replica_addr,
+                                &contract_id,
+                                &e
+                            );
+                            error_reasons.push(format!(
+                                "Failed to connect to replica {} of {} in record {} '{}': {:?}",
+                                replica_addr, &contract_id, i, &rec_txt, &e
+                            ));
+                            self.record_replica_stats(replica_addr, None);
+                        }
+                    }
+                }
+                if replica_clients.len() == 0 {
                     continue;
-                };
+                }
 
-                match Self::wrbsite_load_from_zonefile_rec(&wrbrec, &mut *replica_client) {
-                    Ok(Some(wrbsite_bytes)) => {
-                        return Ok(Some((wrbsite_bytes, wrbrec.slot_metadata.slot_version)));
+                let res = match wrbrec {
+                    WrbTxtRecord::V1(ref wrbrec) => {
+                        self.wrbsite_fetch_fastest(wrbrec, replica_clients)
                     }
-                    Ok(None) => {
-                        // not found
-                        wrb_debug!("Skip WRB record {}", &rec_txt);
-                        error_reasons.push(format!("Failed to load wrbsite from zonefile record {} '{}' for replica {} at {}", i, &rec_txt, &wrbrec.contract_id, replica_addr));
-                        continue;
+                    WrbTxtRecord::V2(ref wrbrec) => {
+                        self.wrbsite_load_from_manifest(wrbrec, &signers, replica_clients)
+                    }
+                };
+                match res {
+                    Ok(wrbsite_bytes) => {
+                        wrbsite_bytes_opt = Some(wrbsite_bytes);
+                        break;
                     }
                     Err(e) => {
                         wrb_warn!(
-                            "Failed to load WRB site for StackerDB {} from {}: {:?}",
-                            &wrbrec.contract_id,
-                            replica_addr,
+                            "Failed to load WRB site for StackerDB {}: {:?}",
+                            &contract_id,
                             &e
                         );
-                        error_reasons.push(format!("Failed to load wrbsite from zonefile record {} '{}' for replica {} at {}: {:?}", i, &rec_txt, &wrbrec.contract_id, replica_addr, &e));
-                        continue;
+                        error_reasons.push(format!(
+                            "Failed to load wrbsite from zonefile record {} '{}': {:?}",
+                            i, &rec_txt, &e
+                        ));
                     }
                 }
             }
+
+            let Some(wrbsite_bytes) = wrbsite_bytes_opt else {
+                continue;
+            };
+
+            let Some(name) = name_opt else {
+                return Ok(Some((wrbsite_bytes, root_slot_metadata.slot_version)));
+            };
+            let observed_pin = SitePin::new(name, contract_id.clone(), signer_addr.clone(), None);
+            match self.check_site_pin(&observed_pin, &root_slot_metadata.data_hash)? {
+                Ok(()) => {
+                    return Ok(Some((wrbsite_bytes, root_slot_metadata.slot_version)));
+                }
+                Err(mismatch) => {
+                    wrb_warn!("Pin mismatch for '{}': {}", name, &mismatch);
+                    error_reasons.push(format!(
+                        "Pin mismatch for '{}' in record {} '{}': {}",
+                        name, i, &rec_txt, &mismatch
+                    ));
+                    pin_mismatch = Some(mismatch);
+                }
+            }
+        }
+        if let (Some(name), Some(mismatch)) = (name_opt, pin_mismatch) {
+            return Err(Error::SitePinMismatch(name.to_string(), mismatch));
         }
         return Err(Error::FailedToRun(
             "Failed to resolve WRB site".into(),
@@ -576,6 +823,106 @@ impl Runner {
         ));
     }
 
+    /// Fetch a wrbsite chunk from several replicas at once.
+    /// Returns the first chunk whose hash matches `wrbrec.slot_metadata.data_hash`.  Replicas
+    /// which do not answer within the replica timeout are abandoned, and count as failures.
+    pub fn wrbsite_fetch_fastest(
+        &self,
+        wrbrec: &WrbTxtRecordV1,
+        replica_clients: Vec<(SocketAddr, Box<dyn StackerDBClient>)>,
+    ) -> Result<Vec<u8>, Error> {
+        let mut fetcher = ReplicaFetcher::new(
+            wrbrec.contract_id.clone(),
+            replica_clients,
+            self.replica_timeout,
+        );
+        let chunk_bytes = fetcher
+            .fetch(self, &[wrbrec.slot_metadata.clone()])?
+            .concat();
+        Ok(chunk_bytes)
+    }
+
+    /// Sort replicas by how well they have served us in the past, if we're tracking that.
+    /// Failing to read the stats is not fatal; the replicas are simply left as-is.
+    pub fn order_replicas(&self, replicas: &mut Vec<SocketAddr>) {
+        let Some(stats_path) = self.replica_stats_path.as_ref() else {
+            return;
+        };
+        let res =
+            ReplicaStatsDB::open(stats_path).and_then(|stats_db| stats_db.order_replicas(replicas));
+        if let Err(e) = res {
+            wrb_warn!("Failed to order replicas by past performance: {:?}", &e);
+        }
+    }
+
+    /// Remember how a replica did, if we're tracking that.
+    /// `latency_opt` is None if the replica failed.
+    pub fn record_replica_stats(&self, replica_addr: &SocketAddr, latency_opt: Option<Duration>) {
+        let Some(stats_path) = self.replica_stats_path.as_ref() else {
+            return;
+        };
+        let res = ReplicaStatsDB::open(stats_path)
+            .and_then(|mut stats_db| stats_db.record(replica_addr, latency_opt));
+        if let Err(e) = res {
+            wrb_warn!("Failed to record stats for {}: {:?}", replica_addr, &e);
+        }
+    }
+
+    /// Check a freshly-loaded wrbsite against its pin.
+    /// * If the config pins the site to a code hash, then the code must match it.
+    /// * If we have never loaded this site before, then pin its publisher (trust on first use).
+    /// * Otherwise, the publisher (and code hash, if set) must match the stored pin.
+    ///
+    /// Returns Ok(Ok(())) if the site may be loaded
+    /// Returns Ok(Err(..)) if the site does not match its pin
+    /// Returns Err(..) if the pin DB could not be accessed
+    pub fn check_site_pin(
+        &mut self,
+        observed_pin: &SitePin,
+        code_hash: &Sha512Trunc256Sum,
+    ) -> Result<Result<(), SitePinMismatch>, Error> {
+        if let Some(pinned_hash) = self.pinned_code_hashes.get(&observed_pin.name) {
+            if pinned_hash != code_hash {
+                return Ok(Err(SitePinMismatch::CodeHash(
+                    pinned_hash.clone(),
+                    code_hash.clone(),
+                )));
+            }
+        }
+
+        let Some(pins_path) = self.site_pins_path.as_ref() else {
+            return Ok(Ok(()));
+        };
+        let mut pin_db = SitePinDB::open(pins_path)?;
+        let Some(pin) = pin_db.get_pin(&observed_pin.name)? else {
+            wrb_info!(
+                "Pinning '{}' to {} (signer {})",
+                &observed_pin.name,
+                &observed_pin.contract_id,
+                &observed_pin.signer
+            );
+            pin_db.put_pin(observed_pin)?;
+            return Ok(Ok(()));
+        };
+        Ok(pin.check(observed_pin, code_hash))
+    }
+
+    /// Trust a new publisher for a wrbsite, replacing its old pin.
+    /// Any code hash pinned for the site is preserved.
+    pub fn accept_site_pin(&mut self, new_pin: &SitePin) -> Result<(), Error> {
+        let Some(pins_path) = self.site_pins_path.as_ref() else {
+            return Ok(());
+        };
+        let mut pin_db = SitePinDB::open(pins_path)?;
+        let code_hash = pin_db
+            .get_pin(&new_pin.name)?
+            .and_then(|old_pin| old_pin.code_hash);
+
+        let mut pin = new_pin.clone();
+        pin.code_hash = code_hash;
+        pin_db.put_pin(&pin)
+    }
+
     /// Load a wrbsite, given the BNS name, resolver, and StackerDB connectors
     pub fn wrbsite_load_ext<F, G>(
         &mut self,
@@ -630,7 +977,13 @@ impl Runner {
             return Err(Error::FailedToRun("Name has no zonefile".into(), vec![]));
         };
 
-        self.wrbsite_load_from_zonefile(zonefile, home_connector, replica_connector)
+        let fqn = format!("{}.{}", name, namespace);
+        self.wrbsite_load_from_zonefile_pinned(
+            Some(&fqn),
+            zonefile,
+            home_connector,
+            replica_connector,
+        )
     }
 
     /// Home node connector
@@ -642,20 +995,96 @@ impl Runner {
         Ok(Box::new(session))
     }
 
-    /// Replica node connector
+    /// Replica node connector for reading chunks.
+    /// Talks to the replica over its RPC port if its handshake advertises a data URL that we can
+    /// connect to, and over its p2p port otherwise.  The p2p session is read-only.
     pub fn replica_node_connect(
         contract_id: &QualifiedContractIdentifier,
         home_node_addr: &SocketAddr,
         node_p2p_addr: &SocketAddr,
     ) -> Result<Box<dyn StackerDBClient>, Error> {
-        let node_addr = Self::run_resolve_stackerdb_host(home_node_addr, node_p2p_addr)?;
-        wrb_debug!(
-            "wrbsite_load: resolved replica node {} to {}",
-            node_p2p_addr,
-            &node_addr
-        );
+        Self::replica_node_connect_ext(contract_id, home_node_addr, node_p2p_addr, true)
+    }
+
+    /// Replica node connector for writing chunks.
+    /// Talks to the replica over its RPC port only, and fails if the replica doesn't advertise a
+    /// data URL that we can connect to.
+    pub fn replica_node_connect_rpc(
+        contract_id: &QualifiedContractIdentifier,
+        home_node_addr: &SocketAddr,
+        node_p2p_addr: &SocketAddr,
+    ) -> Result<Box<dyn StackerDBClient>, Error> {
+        Self::replica_node_connect_ext(contract_id, home_node_addr, node_p2p_addr, false)
+    }
+
+    /// Replica node connector.
+    /// If `allow_p2p` is true, then fall back to a read-only p2p session if the replica's RPC
+    /// port can't be reached.
+    fn replica_node_connect_ext(
+        contract_id: &QualifiedContractIdentifier,
+        home_node_addr: &SocketAddr,
+        node_p2p_addr: &SocketAddr,
+        allow_p2p: bool,
+    ) -> Result<Box<dyn StackerDBClient>, Error> {
+        let node_session = NodeSession::begin(home_node_addr.clone(), node_p2p_addr.clone())
+            .map_err(|e| {
+                Error::FailedToRun(
+                    format!("Failed to handshake with node {}", node_p2p_addr),
+                    vec![format!("Handshake error {}", &e)],
+                )
+            })?;
 
-        let session = StackerDBSession::new(node_addr, contract_id.clone());
+        let data_addr_opt = node_session
+            .handshake_accept_data
+            .as_ref()
+            .and_then(|handshake_data| resolve_url(&handshake_data.data_url.to_string()).ok())
+            .flatten();
+
+        if let Some(node_addr) = data_addr_opt {
+            wrb_debug!(
+                "wrbsite_load: resolved replica node {} to {}",
+                node_p2p_addr,
+                &node_addr
+            );
+            let mut session = StackerDBSession::new(node_addr.clone(), contract_id.clone());
+            match session.connect(node_addr.clone(), contract_id.clone()) {
+                Ok(()) => {
+                    return Ok(Box::new(session));
+                }
+                Err(e) => {
+                    if !allow_p2p {
+                        return Err(Error::FailedToRun(
+                            format!("Failed to connect to replica node {}", node_p2p_addr),
+                            vec![format!("Failed to connect to {}: {:?}", &node_addr, &e)],
+                        ));
+                    }
+                    wrb_debug!(
+                        "wrbsite_load: failed to connect to replica node {} at {} ({:?}); reading chunks over p2p",
+                        node_p2p_addr,
+                        &node_addr,
+                        &e
+                    );
+                }
+            }
+        } else {
+            if !allow_p2p {
+                return Err(Error::FailedToRun(
+                    format!("Replica node {} has no data URL", node_p2p_addr),
+                    vec![],
+                ));
+            }
+            wrb_debug!(
+                "wrbsite_load: replica node {} has no data URL; reading chunks over p2p",
+                node_p2p_addr
+            );
+        }
+
+        let session = StackerDBP2PSession::from_node_session(
+            home_node_addr.clone(),
+            node_p2p_addr.clone(),
+            contract_id.clone(),
+            node_session,
+        );
         Ok(Box::new(session))
     }
 
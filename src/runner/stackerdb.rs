@@ -21,6 +21,8 @@ use std::fmt;
 use std::io;
 use std::net::SocketAddr;
 use std::net::TcpStream;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use clarity::vm::errors::InterpreterError;
 use clarity::vm::types::PrincipalData;
@@ -30,6 +32,7 @@ use clarity::vm::Value;
 
 use crate::core::Config;
 use crate::runner::http::*;
+use crate::runner::proxy::resolve_url;
 use crate::runner::Error;
 use crate::runner::NeighborAddress;
 use crate::runner::Runner;
@@ -41,6 +44,7 @@ use stacks_common::types::chainstate::StacksAddress;
 use stacks_common::types::chainstate::StacksPrivateKey;
 use stacks_common::types::chainstate::StacksPublicKey;
 use stacks_common::util::hash::Hash160;
+use stacks_common::util::hash::Sha512Trunc256Sum;
 
 use crate::storage::mock::LocalStackerDBClient;
 use crate::storage::StackerDBClient;
@@ -65,6 +69,10 @@ pub struct StackerDBSession {
     pub stackerdb_contract_id: QualifiedContractIdentifier,
     /// connection to the replica
     sock: Option<TcpStream>,
+    /// idle keep-alive connections for replica and signer lookups
+    rpc_pool: Mutex<HttpConnectionPool>,
+    /// if set, chunk requests give up within this long, and are not retried
+    timeout_opt: Option<Duration>,
 }
 
 impl StackerDBSession {
@@ -77,22 +85,36 @@ impl StackerDBSession {
             host,
             stackerdb_contract_id,
             sock: None,
+            rpc_pool: Mutex::new(HttpConnectionPool::default()),
+            timeout_opt: None,
+        }
+    }
+
+    /// The configured RPC timeouts, bounded by our timeout if we have one
+    fn timeouts(&self) -> RPCTimeouts {
+        let timeouts = rpc_timeouts();
+        match self.timeout_opt {
+            Some(timeout) => timeouts.bounded_by(timeout),
+            None => timeouts,
         }
     }
 
     /// connect or reconnect to the node
     fn connect_or_reconnect(&mut self) -> Result<(), Error> {
         wrb_debug!("connect to {}", &self.host);
-        self.sock = Some(TcpStream::connect(self.host)?);
+        self.sock = Some(rpc_connect_ext(&self.host, &self.timeouts())?);
         Ok(())
     }
 
-    /// Do something with the connected socket
+    /// Do something with the connected socket, connecting first if we aren't already.
+    /// The connection is kept open afterwards so later requests can reuse it.
     fn with_socket<F, R>(&mut self, todo: F) -> Result<R, Error>
     where
         F: FnOnce(&mut StackerDBSession, &mut TcpStream) -> R,
     {
-        self.connect_or_reconnect()?;
+        if self.sock.is_none() {
+            self.connect_or_reconnect()?;
+        }
         let mut sock = if let Some(s) = self.sock.take() {
             s
         } else {
@@ -105,6 +127,58 @@ impl StackerDBSession {
         Ok(res)
     }
 
+    /// Send a single HTTP request over our keep-alive connection.
+    /// If a previously-used connection turns out to have been closed by the node, then
+    /// reconnect and try once more -- but only if the request is `idempotent`.  Other requests
+    /// always go over a fresh connection, and are never sent twice, since the node may have
+    /// already acted on the first one.
+    fn rpc_request_once(
+        &mut self,
+        verb: &str,
+        path: &str,
+        content_type: Option<&str>,
+        payload: &[u8],
+        idempotent: bool,
+    ) -> Result<Vec<u8>, Error> {
+        if !idempotent {
+            self.connect_or_reconnect()?;
+        }
+        let reused = idempotent && self.sock.is_some();
+        let mut res = self.with_socket(|session, sock| {
+            run_http_request_ext(sock, &session.host, verb, path, content_type, payload)
+        })?;
+        if reused {
+            if let Err(Error::IO(e)) = &res {
+                wrb_debug!(
+                    "Connection to {} is no longer usable ({:?}); reconnecting",
+                    &self.host,
+                    e
+                );
+                self.connect_or_reconnect()?;
+                res = self.with_socket(|session, sock| {
+                    run_http_request_ext(sock, &session.host, verb, path, content_type, payload)
+                })?;
+            }
+        }
+        match res {
+            Ok((body, keep_alive)) => {
+                if !keep_alive {
+                    self.sock = None;
+                }
+                Ok(body)
+            }
+            Err(Error::HttpError(code, headers, offset)) => {
+                // the error response was read in full, so the connection is still usable.
+                // If the node closed it anyway, we'll find out and reconnect next time.
+                Err(Error::HttpError(code, headers, offset))
+            }
+            Err(e) => {
+                self.sock = None;
+                Err(e)
+            }
+        }
+    }
+
     /// send an HTTP RPC request and receive a reply.
     /// Return the HTTP reply, decoded if it was chunked
     fn rpc_request(
@@ -114,9 +188,14 @@ impl StackerDBSession {
         content_type: Option<&str>,
         payload: &[u8],
     ) -> Result<Vec<u8>, Error> {
-        self.with_socket(|session, sock| {
-            run_http_request(sock, &session.host, verb, path, content_type, payload)
-        })?
+        let timeouts = self.timeouts();
+        let idempotent = verb == "GET";
+        with_rpc_retries(
+            &timeouts,
+            idempotent,
+            &format!("{} {} on {}", verb, path, &self.host),
+            || self.rpc_request_once(verb, path, content_type, payload, idempotent),
+        )
     }
 
     /// connect to the replica
@@ -213,13 +292,220 @@ impl StackerDBClient for StackerDBSession {
 
     /// Find the list of replicas
     fn find_replicas(&mut self) -> Result<Vec<SocketAddr>, Error> {
-        Runner::run_get_stackerdb_replicas(&self.host, &self.stackerdb_contract_id)
+        Runner::run_get_stackerdb_replicas(&self.rpc_pool, &self.host, &self.stackerdb_contract_id)
+    }
+
+    /// Get the signers for a StackerDB.
+    /// Return the list of addresses for each slot
+    fn get_signers(&mut self) -> Result<Vec<StacksAddress>, Error> {
+        Runner::run_get_stackerdb_signers(&self.rpc_pool, &self.host, &self.stackerdb_contract_id)
+    }
+
+    /// Bound the socket timeouts of chunk requests, including on an already-open connection
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout_opt = Some(timeout);
+        if let Some(sock) = self.sock.as_ref() {
+            let timeouts = self.timeouts();
+            let _ = sock.set_read_timeout(Some(timeouts.read_timeout));
+            let _ = sock.set_write_timeout(Some(timeouts.read_timeout));
+        }
+    }
+}
+
+/// StackerDB client which reads chunks from a replica over its p2p port, for replicas which do
+/// not expose a reachable RPC port.  Replica and signer lookups go through the home node.
+pub struct StackerDBP2PSession {
+    /// p2p address of the replica we're talking to
+    pub host: SocketAddr,
+    /// RPC address of the node we use for everything else
+    pub home_node_addr: SocketAddr,
+    /// contract we're talking to
+    pub stackerdb_contract_id: QualifiedContractIdentifier,
+    /// handshaked session with the replica
+    session: Option<NodeSession>,
+    /// idle keep-alive connections to the home node
+    rpc_pool: Mutex<HttpConnectionPool>,
+    /// if set, chunk requests give up within this long
+    timeout_opt: Option<Duration>,
+}
+
+impl StackerDBP2PSession {
+    /// instantiate but don't connect
+    pub fn new(
+        home_node_addr: SocketAddr,
+        host: SocketAddr,
+        stackerdb_contract_id: QualifiedContractIdentifier,
+    ) -> Self {
+        Self {
+            host,
+            home_node_addr,
+            stackerdb_contract_id,
+            session: None,
+            rpc_pool: Mutex::new(HttpConnectionPool::default()),
+            timeout_opt: None,
+        }
+    }
+
+    /// instantiate from an already-handshaked session
+    pub fn from_node_session(
+        home_node_addr: SocketAddr,
+        host: SocketAddr,
+        stackerdb_contract_id: QualifiedContractIdentifier,
+        session: NodeSession,
+    ) -> Self {
+        Self {
+            host,
+            home_node_addr,
+            stackerdb_contract_id,
+            session: Some(session),
+            rpc_pool: Mutex::new(HttpConnectionPool::default()),
+            timeout_opt: None,
+        }
+    }
+
+    /// Do something with the p2p session, handshaking first if need be.
+    /// The session is dropped on error, so the next call starts a new one.
+    fn with_node_session<F, R>(&mut self, todo: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut NodeSession, &QualifiedContractIdentifier) -> Result<R, String>,
+    {
+        let mut session = match self.session.take() {
+            Some(session) => session,
+            None => {
+                wrb_debug!("p2p connect to {}", &self.host);
+                NodeSession::begin(self.home_node_addr.clone(), self.host.clone()).map_err(|e| {
+                    Error::FailedToRun(
+                        format!("Failed to handshake with {}", &self.host),
+                        vec![format!("Handshake error {}", &e)],
+                    )
+                })?
+            }
+        };
+        if let Some(timeout) = self.timeout_opt {
+            session.set_timeout(timeout)?;
+        }
+        let res = todo(&mut session, &self.stackerdb_contract_id).map_err(|e| {
+            Error::RPCError(format!("p2p request to {} failed: {}", &self.host, &e))
+        })?;
+        self.session = Some(session);
+        Ok(res)
+    }
+
+    /// Get the replica's slot versions
+    fn get_slot_versions(&mut self) -> Result<Vec<u32>, Error> {
+        let inv = self.with_node_session(|session, contract_id| {
+            session.get_stackerdb_chunk_inv(contract_id)
+        })?;
+        Ok(inv.slot_versions)
+    }
+
+    /// Get a specific version of a chunk
+    fn get_chunk(
+        &mut self,
+        slot_id: u32,
+        slot_version: u32,
+    ) -> Result<Option<StackerDBChunkData>, Error> {
+        self.with_node_session(|session, contract_id| {
+            session.get_stackerdb_chunk(contract_id, slot_id, slot_version)
+        })
+    }
+}
+
+impl StackerDBClient for StackerDBP2PSession {
+    fn get_host(&self) -> SocketAddr {
+        self.host.clone()
+    }
+
+    /// Build the chunk list from the replica's inventory.  The inventory only carries versions,
+    /// so each non-empty chunk is fetched to recover its hash and signature.
+    fn list_chunks(&mut self) -> Result<Vec<SlotMetadata>, Error> {
+        let slot_versions = self.get_slot_versions()?;
+        let mut metadata = Vec::with_capacity(slot_versions.len());
+        for (slot_id, slot_version) in slot_versions.into_iter().enumerate() {
+            let slot_id = u32::try_from(slot_id).expect("infallible");
+            if slot_version == 0 {
+                metadata.push(SlotMetadata::new_unsigned(
+                    slot_id,
+                    0,
+                    Sha512Trunc256Sum([0u8; 32]),
+                ));
+                continue;
+            }
+            let chunk = self.get_chunk(slot_id, slot_version)?.ok_or_else(|| {
+                Error::RPCError(format!(
+                    "Chunk {}.{} changed on {} while listing chunks",
+                    slot_id, slot_version, &self.host
+                ))
+            })?;
+            metadata.push(chunk.get_slot_metadata());
+        }
+        Ok(metadata)
+    }
+
+    /// query the replica for zero or more chunks
+    fn get_chunks(
+        &mut self,
+        slots_and_versions: &[(u32, u32)],
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        let mut payloads = vec![];
+        for (slot_id, slot_version) in slots_and_versions.iter() {
+            let chunk = self.get_chunk(*slot_id, *slot_version)?;
+            payloads.push(chunk.map(|chunk| chunk.data));
+        }
+        Ok(payloads)
+    }
+
+    /// query the replica for zero or more latest chunks
+    fn get_latest_chunks(&mut self, slot_ids: &[u32]) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        let slot_versions = self.get_slot_versions()?;
+        let mut payloads = vec![];
+        for slot_id in slot_ids.iter() {
+            let slot_version = usize::try_from(*slot_id)
+                .ok()
+                .and_then(|idx| slot_versions.get(idx))
+                .cloned()
+                .unwrap_or(0);
+            if slot_version == 0 {
+                payloads.push(None);
+                continue;
+            }
+            let chunk = self.get_chunk(*slot_id, slot_version)?;
+            payloads.push(chunk.map(|chunk| chunk.data));
+        }
+        Ok(payloads)
+    }
+
+    /// The p2p protocol does not acknowledge pushed chunks, so uploads must go through a node's
+    /// RPC interface instead.
+    fn put_chunk(&mut self, chunk: StackerDBChunkData) -> Result<StackerDBChunkAckData, Error> {
+        Err(Error::RPCError(format!(
+            "Cannot upload chunk {}.{} to {}: p2p StackerDB sessions are read-only",
+            chunk.slot_id, chunk.slot_version, &self.host
+        )))
+    }
+
+    /// Find the list of replicas
+    fn find_replicas(&mut self) -> Result<Vec<SocketAddr>, Error> {
+        Runner::run_get_stackerdb_replicas(
+            &self.rpc_pool,
+            &self.home_node_addr,
+            &self.stackerdb_contract_id,
+        )
     }
 
     /// Get the signers for a StackerDB.
     /// Return the list of addresses for each slot
     fn get_signers(&mut self) -> Result<Vec<StacksAddress>, Error> {
-        Runner::run_get_stackerdb_signers(&self.host, &self.stackerdb_contract_id)
+        Runner::run_get_stackerdb_signers(
+            &self.rpc_pool,
+            &self.home_node_addr,
+            &self.stackerdb_contract_id,
+        )
+    }
+
+    /// Bound the socket timeouts of chunk requests to the replica
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout_opt = Some(timeout);
     }
 }
 
@@ -227,10 +513,10 @@ impl Runner {
     /// Get a list of hosts that replicate a particular StackerDB.
     /// These will be p2p addresses.
     pub fn run_get_stackerdb_replicas(
+        pool: &Mutex<HttpConnectionPool>,
         node_addr: &SocketAddr,
         contract_id: &QualifiedContractIdentifier,
     ) -> Result<Vec<SocketAddr>, Error> {
-        let mut sock = TcpStream::connect(node_addr)?;
         let stacks_address = StacksAddress::new(
             contract_id.issuer.version(),
             Hash160(contract_id.issuer.1.clone()),
@@ -242,8 +528,8 @@ impl Runner {
             contract_id,
             node_addr
         );
-        let bytes = run_http_request(
-            &mut sock,
+        let bytes = run_rpc_request(
+            pool,
             node_addr,
             "GET",
             &format!(
@@ -252,6 +538,7 @@ impl Runner {
             ),
             None,
             &[],
+            true,
         )?;
 
         let response: Vec<NeighborAddress> = serde_json::from_slice(&bytes)
@@ -268,10 +555,10 @@ impl Runner {
         &mut self,
         contract_id: &QualifiedContractIdentifier,
     ) -> Result<Vec<SocketAddr>, Error> {
-        let Some(node_addr) = self.resolve_node()? else {
-            return Err(Error::NotConnected);
-        };
-        Self::run_get_stackerdb_replicas(&node_addr, contract_id)
+        let pool = self.rpc_pool.clone();
+        self.with_node_failover("get StackerDB replicas", |node_addr| {
+            Self::run_get_stackerdb_replicas(&pool, node_addr, contract_id)
+        })
     }
 
     /// Decode `{signer: principal, num-slots: uint}`
@@ -380,11 +667,12 @@ impl Runner {
 
     /// Get the (uncompressed) list of signers for a stackerdb
     pub fn run_get_stackerdb_signers(
+        pool: &Mutex<HttpConnectionPool>,
         node_addr: &SocketAddr,
         contract_id: &QualifiedContractIdentifier,
     ) -> Result<Vec<StacksAddress>, Error> {
         let slots_val =
-            Self::run_call_readonly(node_addr, contract_id, STACKERDB_SLOTS_FUNCTION, &[])?;
+            Self::run_call_readonly(pool, node_addr, contract_id, STACKERDB_SLOTS_FUNCTION, &[])?;
         let slots_runs = Self::eval_signer_slots(contract_id, slots_val)?;
 
         // decompress
@@ -400,14 +688,14 @@ impl Runner {
     /// Get a single chunk with a given version from the StackerDB.
     /// Used to do one-off requests, like loading a wrbsite.
     pub fn run_get_stackerdb_chunk(
+        pool: &Mutex<HttpConnectionPool>,
         node_addr: &SocketAddr,
         contract_id: &QualifiedContractIdentifier,
         slot_id: u32,
         slot_version: u32,
     ) -> Result<Option<Vec<u8>>, Error> {
         let path = stackerdb_get_chunk_path(contract_id.clone(), slot_id, Some(slot_version));
-        let mut sock = TcpStream::connect(node_addr)?;
-        let chunk_opt = match run_http_request(&mut sock, &node_addr, "GET", &path, None, &[]) {
+        let chunk_opt = match run_rpc_request(pool, node_addr, "GET", &path, None, &[], true) {
             Ok(body_bytes) => Some(body_bytes),
             Err(Error::HttpError(code, headers, offset)) => {
                 if code != 404 {
@@ -428,10 +716,10 @@ impl Runner {
         &mut self,
         contract_id: &QualifiedContractIdentifier,
     ) -> Result<Vec<StacksAddress>, Error> {
-        let Some(node_addr) = self.resolve_node()? else {
-            return Err(Error::NotConnected);
-        };
-        Self::run_get_stackerdb_signers(&node_addr, contract_id)
+        let pool = self.rpc_pool.clone();
+        self.with_node_failover("get StackerDB signers", |node_addr| {
+            Self::run_get_stackerdb_signers(&pool, node_addr, contract_id)
+        })
     }
 
     #[cfg(test)]
@@ -472,9 +760,7 @@ impl Runner {
     ) -> Result<SocketAddr, Error> {
         // resolve its data port
         let handshake_data = Self::run_node_handshake(node_addr, &replica_p2p_addr)?;
-        let replica_addr = handshake_data
-            .data_url
-            .try_get_socketaddr()
+        let replica_addr = resolve_url(&handshake_data.data_url.to_string())
             .map_err(|e| {
                 Error::FailedToRun(
                     format!("Failed to resolve '{}'", &handshake_data.data_url),
@@ -495,22 +781,22 @@ impl Runner {
         &mut self,
         replica_p2p_addr: &SocketAddr,
     ) -> Result<SocketAddr, Error> {
-        let Some(node_addr) = self.resolve_node()? else {
-            return Err(Error::NotConnected);
-        };
-        Self::run_resolve_stackerdb_host(&node_addr, replica_p2p_addr)
+        self.with_node_failover("resolve StackerDB host", |node_addr| {
+            Self::run_resolve_stackerdb_host(node_addr, replica_p2p_addr)
+        })
     }
 
-    /// Given the address of a local Stacks node, find the address of a node that can serve a given
-    /// replica.
-    /// Note that the address will be the p2p address, not the rpc address.
-    /// Use `run_node_handshake()` to get the rpc address (among other things)
+    /// Given the address of a local Stacks node, find a node that can serve a given replica.
+    /// Returns Ok(None) if the local node replicates it itself.
+    /// Returns Ok(Some(..)) with the p2p address of a replica otherwise.
+    /// Use `replica_node_connect()` to talk to the replica.
     pub fn run_find_stackerdb(
+        pool: &Mutex<HttpConnectionPool>,
         node_addr: &SocketAddr,
         contract_id: &QualifiedContractIdentifier,
-    ) -> Result<SocketAddr, Error> {
+    ) -> Result<Option<SocketAddr>, Error> {
         // does this node replicate it?
-        let mut rpc_info = Self::run_get_info(node_addr)?;
+        let mut rpc_info = Self::run_get_info(pool, node_addr)?;
         let Some(stacker_dbs) = rpc_info.stackerdbs.take() else {
             // this node doesn't support stackerdbs
             return Err(Error::RPCError(format!(
@@ -523,7 +809,7 @@ impl Runner {
         for db in stacker_dbs {
             if db == contract_str {
                 // this node replicates this DB
-                return Ok(node_addr.clone());
+                return Ok(None);
             }
         }
 
@@ -534,26 +820,28 @@ impl Runner {
         );
 
         // this node does not replicate this DB, so ask it for one that does
-        let mut replicas = Self::run_get_stackerdb_replicas(node_addr, contract_id)?;
+        let mut replicas = Self::run_get_stackerdb_replicas(pool, node_addr, contract_id)?;
         let Some(replica) = replicas.pop() else {
             return Err(Error::RPCError(format!(
                 "Node {} cannot find a replica for StackerDB {}",
                 node_addr, contract_id
             )));
         };
-
-        // resolve its data port
-        Ok(Self::run_resolve_stackerdb_host(node_addr, &replica)?)
+        Ok(Some(replica))
     }
 
+    /// Find a node that can serve a given replica.
+    /// Returns the address of the node we asked, and the p2p address of the replica if it isn't
+    /// that node.
     pub fn find_stackerdb(
         &mut self,
         contract_id: &QualifiedContractIdentifier,
-    ) -> Result<SocketAddr, Error> {
-        let Some(node_addr) = self.resolve_node()? else {
-            return Err(Error::NotConnected);
-        };
-        Self::run_find_stackerdb(&node_addr, contract_id)
+    ) -> Result<(SocketAddr, Option<SocketAddr>), Error> {
+        let pool = self.rpc_pool.clone();
+        self.with_node_failover("find StackerDB", |node_addr| {
+            let replica_opt = Self::run_find_stackerdb(&pool, node_addr, contract_id)?;
+            Ok((node_addr.clone(), replica_opt))
+        })
     }
 
     #[cfg(test)]
@@ -574,6 +862,15 @@ impl Runner {
         Ok(Box::new(MockStackerDBClient::new(privkey, 16)))
     }
 
+    #[cfg(test)]
+    pub fn get_writable_replica_stackerdb_client(
+        &mut self,
+        _contract: QualifiedContractIdentifier,
+        privkey: StacksPrivateKey,
+    ) -> Result<Box<dyn StackerDBClient>, Error> {
+        Ok(Box::new(MockStackerDBClient::new(privkey, 16)))
+    }
+
     #[cfg(not(test))]
     pub fn get_home_stackerdb_client(
         &mut self,
@@ -595,27 +892,50 @@ impl Runner {
             return Ok(Box::new(mock_client));
         }
 
+        // make sure the node is reachable before handing out a session to it, so we can fail
+        // over to another node if it isn't
         let node_addr = self
-            .resolve_node()
+            .with_node_failover("connect to StackerDB node", |node_addr| {
+                rpc_connect(node_addr)?;
+                Ok(node_addr.clone())
+            })
             .map_err(|e| {
                 Error::FailedToRun(
                     "Unable to resolve node".into(),
                     vec![format!("Unable to resolve node: {:?}", &e)],
                 )
-            })?
-            .ok_or(Error::FailedToRun(
-                "Unable to resolve node".to_string(),
-                vec![],
-            ))?;
+            })?;
 
         Ok(Box::new(StackerDBSession::new(node_addr, contract)))
     }
 
+    /// Get a client for reading chunks from a replica of `contract`.
+    /// This may be a read-only p2p session if the replica's RPC port can't be reached.
     #[cfg(not(test))]
     pub fn get_replica_stackerdb_client(
         &mut self,
         contract: QualifiedContractIdentifier,
         _ignored: StacksPrivateKey,
+    ) -> Result<Box<dyn StackerDBClient>, Error> {
+        self.get_replica_stackerdb_client_ext(contract, false)
+    }
+
+    /// Get a client for reading and writing chunks on a replica of `contract`.
+    /// This is always an RPC session.
+    #[cfg(not(test))]
+    pub fn get_writable_replica_stackerdb_client(
+        &mut self,
+        contract: QualifiedContractIdentifier,
+        _ignored: StacksPrivateKey,
+    ) -> Result<Box<dyn StackerDBClient>, Error> {
+        self.get_replica_stackerdb_client_ext(contract, true)
+    }
+
+    #[cfg(not(test))]
+    fn get_replica_stackerdb_client_ext(
+        &mut self,
+        contract: QualifiedContractIdentifier,
+        writable: bool,
     ) -> Result<Box<dyn StackerDBClient>, Error> {
         if let Some(db_path) = self.mock_stackerdb_paths.get(&contract) {
             // use DB on disk instead
@@ -632,7 +952,7 @@ impl Runner {
             return Ok(Box::new(mock_client));
         }
 
-        let node_addr = self.find_stackerdb(&contract).map_err(|e| {
+        let (node_addr, replica_opt) = self.find_stackerdb(&contract).map_err(|e| {
             Error::FailedToRun(
                 format!("Unable to find replica for {}", &contract),
                 vec![format!(
@@ -642,6 +962,13 @@ impl Runner {
             )
         })?;
 
-        Ok(Box::new(StackerDBSession::new(node_addr, contract)))
+        let Some(replica_p2p_addr) = replica_opt else {
+            return Ok(Box::new(StackerDBSession::new(node_addr, contract)));
+        };
+        if writable {
+            Self::replica_node_connect_rpc(&contract, &node_addr, &replica_p2p_addr)
+        } else {
+            Self::replica_node_connect(&contract, &node_addr, &replica_p2p_addr)
+        }
     }
 }
@@ -17,14 +17,26 @@
 
 use std::collections::HashMap;
 use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 use std::{io, str};
 
+use clarity::vm::types::QualifiedContractIdentifier;
+use libstackerdb::StackerDBChunkData;
+
 use stacks_common::util::chunked_encoding::*;
 
 use crate::runner::http::{
-    decode_http_body, decode_http_request, decode_http_response, run_http_request,
+    decode_http_body, decode_http_request, decode_http_response, rpc_connect, run_http_request,
+    run_http_request_ext, run_pooled_http_request, with_rpc_retries, HttpConnectionPool,
+    RPCTimeouts, MAX_RPC_RETRY_BACKOFF_MS,
 };
+use crate::runner::stackerdb::StackerDBSession;
 use crate::runner::Error;
+use crate::storage::StackerDBClient;
 
 #[test]
 fn test_decode_http_request_ok() {
@@ -339,3 +351,303 @@ fn test_run_http_request_no_body() {
         assert_eq!(result_plain.len(), 0);
     }
 }
+
+#[test]
+fn test_rpc_retries() {
+    let timeouts = RPCTimeouts {
+        connect_timeout: Duration::from_millis(100),
+        read_timeout: Duration::from_millis(100),
+        max_retries: 3,
+        retry_backoff: Duration::from_millis(1),
+    };
+
+    // backoff doubles, and is capped
+    assert_eq!(timeouts.backoff(0), Duration::from_millis(1));
+    assert_eq!(timeouts.backoff(1), Duration::from_millis(2));
+    assert_eq!(timeouts.backoff(3), Duration::from_millis(8));
+    assert_eq!(
+        timeouts.backoff(u32::MAX),
+        Duration::from_millis(MAX_RPC_RETRY_BACKOFF_MS)
+    );
+
+    // idempotent requests are retried until they succeed
+    let mut attempts = 0;
+    let res = with_rpc_retries(&timeouts, true, "test", || {
+        attempts += 1;
+        if attempts < 3 {
+            Err(Error::Timeout("test".into()))
+        } else {
+            Ok(attempts)
+        }
+    });
+    assert_eq!(res.unwrap(), 3);
+
+    // ...or until we run out of retries
+    let mut attempts = 0;
+    let res: Result<(), Error> = with_rpc_retries(&timeouts, true, "test", || {
+        attempts += 1;
+        Err(Error::ConnectionRefused("test".into()))
+    });
+    assert!(matches!(res, Err(Error::ConnectionRefused(_))));
+    assert_eq!(attempts, 4);
+
+    // non-idempotent requests are not retried
+    let mut attempts = 0;
+    let res: Result<(), Error> = with_rpc_retries(&timeouts, false, "test", || {
+        attempts += 1;
+        Err(Error::Timeout("test".into()))
+    });
+    assert!(matches!(res, Err(Error::Timeout(_))));
+    assert_eq!(attempts, 1);
+
+    // non-network errors are not retried
+    let mut attempts = 0;
+    let res: Result<(), Error> = with_rpc_retries(&timeouts, true, "test", || {
+        attempts += 1;
+        Err(Error::HttpError(404, HashMap::new(), 0))
+    });
+    assert!(matches!(res, Err(Error::HttpError(404, ..))));
+    assert_eq!(attempts, 1);
+}
+
+#[test]
+fn test_rpc_timeout_and_refused_errors() {
+    assert!(matches!(
+        Error::from(io::Error::from(io::ErrorKind::TimedOut)),
+        Error::Timeout(_)
+    ));
+    assert!(matches!(
+        Error::from(io::Error::from(io::ErrorKind::WouldBlock)),
+        Error::Timeout(_)
+    ));
+    assert!(matches!(
+        Error::from(io::Error::from(io::ErrorKind::ConnectionRefused)),
+        Error::ConnectionRefused(_)
+    ));
+    assert!(matches!(
+        Error::from(io::Error::from(io::ErrorKind::BrokenPipe)),
+        Error::IO(_)
+    ));
+
+    // nothing listening
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed_addr = listener.local_addr().unwrap();
+    drop(listener);
+    assert!(matches!(
+        rpc_connect(&closed_addr),
+        Err(Error::ConnectionRefused(_))
+    ));
+
+    // listening, but never replies
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let hung_addr = listener.local_addr().unwrap();
+    let mut sock = TcpStream::connect(&hung_addr).unwrap();
+    sock.set_read_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+    let res = run_http_request(&mut sock, &hung_addr, "GET", "/v2/info", None, &[]);
+    assert!(matches!(res, Err(Error::Timeout(_))));
+}
+
+#[test]
+fn test_run_http_request_keep_alive() {
+    let host = "127.0.0.1:20443".parse().unwrap();
+
+    // framed responses leave the socket positioned at the next response
+    let mut msock = MockHTTPSocket::new(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5\r\n\r\nhello\
+         HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nwor\r\n2\r\nld\r\n0\r\n\r\n\
+         HTTP/1.1 404 Not Found\r\nContent-Length: 4\r\n\r\nnope\
+         HTTP/1.1 200 OK\r\nContent-Length: 3\r\nConnection: close\r\n\r\nbye"
+            .to_string(),
+    );
+    assert_eq!(
+        run_http_request_ext(&mut msock, &host, "GET", "/a", None, &[]).unwrap(),
+        (b"hello".to_vec(), true)
+    );
+    assert_eq!(
+        run_http_request_ext(&mut msock, &host, "GET", "/b", None, &[]).unwrap(),
+        (b"world".to_vec(), true)
+    );
+    assert!(matches!(
+        run_http_request_ext(&mut msock, &host, "GET", "/c", None, &[]),
+        Err(Error::HttpError(404, ..))
+    ));
+    assert_eq!(
+        run_http_request_ext(&mut msock, &host, "GET", "/d", None, &[]).unwrap(),
+        (b"bye".to_vec(), false)
+    );
+
+    let req_txt = String::from_utf8(msock.request.clone()).unwrap();
+    assert!(req_txt.starts_with("GET /a HTTP/1.1\r\n"));
+    assert!(req_txt.contains("Connection: keep-alive\r\n"));
+
+    // unframed and HTTP/1.0 responses can't be reused
+    let mut msock = MockHTTPSocket::new(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nuntil EOF".to_string(),
+    );
+    assert_eq!(
+        run_http_request_ext(&mut msock, &host, "GET", "/", None, &[]).unwrap(),
+        (b"until EOF".to_vec(), false)
+    );
+
+    let mut msock =
+        MockHTTPSocket::new("HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\nok".to_string());
+    assert_eq!(
+        run_http_request_ext(&mut msock, &host, "GET", "/", None, &[]).unwrap(),
+        (b"ok".to_vec(), false)
+    );
+}
+
+/// Read an HTTP request's headers off of a socket.  Returns false on EOF.
+fn read_request_headers(sock: &mut TcpStream) -> bool {
+    let mut buf = vec![];
+    let mut byte = [0u8; 1];
+    while !buf.ends_with(b"\r\n\r\n") {
+        match sock.read(&mut byte) {
+            Ok(1) => buf.push(byte[0]),
+            _ => {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+#[test]
+fn test_pooled_http_request_reuses_connections() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let accepts = Arc::new(AtomicUsize::new(0));
+
+    // serves three requests per connection, and then hangs up without saying so
+    let server_accepts = accepts.clone();
+    let server = thread::spawn(move || {
+        for _ in 0..2 {
+            let (mut sock, _) = listener.accept().unwrap();
+            server_accepts.fetch_add(1, Ordering::SeqCst);
+            for i in 0..3 {
+                if !read_request_headers(&mut sock) {
+                    break;
+                }
+                let body = format!("reply {}", i);
+                sock.write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        &body
+                    )
+                    .as_bytes(),
+                )
+                .unwrap();
+            }
+        }
+    });
+
+    let pool = Mutex::new(HttpConnectionPool::new(4));
+    for i in 0..3 {
+        let body =
+            run_pooled_http_request(&pool, &addr, "GET", "/v2/info", None, &[], true).unwrap();
+        assert_eq!(body, format!("reply {}", i).as_bytes().to_vec());
+        assert_eq!(pool.lock().unwrap().num_idle(&addr), 1);
+    }
+    assert_eq!(accepts.load(Ordering::SeqCst), 1);
+
+    // the idle connection was closed by the server, so we transparently reconnect
+    let body = run_pooled_http_request(&pool, &addr, "GET", "/v2/info", None, &[], true).unwrap();
+    assert_eq!(body, b"reply 0".to_vec());
+    assert_eq!(accepts.load(Ordering::SeqCst), 2);
+
+    pool.lock().unwrap().clear();
+    assert_eq!(pool.lock().unwrap().num_idle(&addr), 0);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_pooled_http_request_not_idempotent() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let accepts = Arc::new(AtomicUsize::new(0));
+
+    // serves one request per connection, and keeps it open
+    let server_accepts = accepts.clone();
+    let server = thread::spawn(move || {
+        let mut socks = vec![];
+        for i in 0..2 {
+            let (mut sock, _) = listener.accept().unwrap();
+            server_accepts.fetch_add(1, Ordering::SeqCst);
+            assert!(read_request_headers(&mut sock));
+            let body = format!("reply {}", i);
+            sock.write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    &body
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+            socks.push(sock);
+        }
+        socks
+    });
+
+    let pool = Mutex::new(HttpConnectionPool::new(4));
+    let body = run_pooled_http_request(&pool, &addr, "GET", "/v2/info", None, &[], true).unwrap();
+    assert_eq!(body, b"reply 0".to_vec());
+    assert_eq!(pool.lock().unwrap().num_idle(&addr), 1);
+
+    // a non-idempotent request leaves the idle connection alone, in case it's stale
+    let body = run_pooled_http_request(&pool, &addr, "POST", "/v2/transactions", None, &[], false)
+        .unwrap();
+    assert_eq!(body, b"reply 1".to_vec());
+    assert_eq!(accepts.load(Ordering::SeqCst), 2);
+    assert_eq!(pool.lock().unwrap().num_idle(&addr), 2);
+
+    server.join().unwrap();
+}
+
+#[test]
+fn test_stackerdb_session_post_not_resent() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let accepts = Arc::new(AtomicUsize::new(0));
+
+    // answers the first request and keeps the connection open, and hangs up on the second
+    // without answering.  Then it counts any more connections.
+    let server_accepts = accepts.clone();
+    let server = thread::spawn(move || {
+        let (mut sock, _) = listener.accept().unwrap();
+        server_accepts.fetch_add(1, Ordering::SeqCst);
+        assert!(read_request_headers(&mut sock));
+        sock.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n[]")
+            .unwrap();
+
+        let (mut post_sock, _) = listener.accept().unwrap();
+        server_accepts.fetch_add(1, Ordering::SeqCst);
+        assert!(read_request_headers(&mut post_sock));
+        drop(post_sock);
+
+        listener.set_nonblocking(true).unwrap();
+        for _ in 0..10 {
+            if listener.accept().is_ok() {
+                server_accepts.fetch_add(1, Ordering::SeqCst);
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        sock
+    });
+
+    let contract_id =
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2")
+            .unwrap();
+    let mut session = StackerDBSession::new(addr, contract_id);
+    assert_eq!(session.list_chunks().unwrap(), vec![]);
+
+    // the upload goes over a new connection, and isn't sent again when it fails
+    assert!(session
+        .put_chunk(StackerDBChunkData::new(0, 1, vec![1, 2, 3]))
+        .is_err());
+
+    server.join().unwrap();
+    assert_eq!(accepts.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,68 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2025 Stacks Open Internet Foundation
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+
+use crate::runner::identities::SiteIdentityDB;
+
+fn make_site_identity_db_path(test_name: &str) -> String {
+    let path = format!("/tmp/wrb-test-site-identities-{}.sqlite", test_name);
+    if fs::metadata(&path).is_ok() {
+        fs::remove_file(&path).unwrap();
+    }
+    path
+}
+
+#[test]
+fn test_site_identity_db() {
+    let path = make_site_identity_db_path("test_site_identity_db");
+    let mut db = SiteIdentityDB::open(&path).unwrap();
+
+    assert_eq!(db.get_site_identity("foo.btc").unwrap(), None);
+
+    db.put_site_identity("foo.btc", "work").unwrap();
+    db.put_site_identity("bar.btc", "play").unwrap();
+    assert_eq!(
+        db.get_site_identity("foo.btc").unwrap(),
+        Some("work".to_string())
+    );
+    assert_eq!(
+        db.get_site_identity("bar.btc").unwrap(),
+        Some("play".to_string())
+    );
+
+    // reopen and make sure it's still there
+    let mut db = SiteIdentityDB::open(&path).unwrap();
+    assert_eq!(
+        db.get_site_identity("foo.btc").unwrap(),
+        Some("work".to_string())
+    );
+
+    // change it
+    db.put_site_identity("foo.btc", "play").unwrap();
+    assert_eq!(
+        db.get_site_identity("foo.btc").unwrap(),
+        Some("play".to_string())
+    );
+
+    db.remove_site_identity("foo.btc").unwrap();
+    assert_eq!(db.get_site_identity("foo.btc").unwrap(), None);
+    assert_eq!(
+        db.get_site_identity("bar.btc").unwrap(),
+        Some("play".to_string())
+    );
+}
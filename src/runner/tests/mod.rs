@@ -18,8 +18,15 @@
 use std::collections::HashMap;
 
 pub mod http;
+pub mod identities;
+pub mod nodes;
+pub mod nonces;
+pub mod pins;
+pub mod proxy;
+pub mod replicas;
 pub mod runner;
 pub mod site;
+pub mod tx;
 
 use clarity::vm::types::QualifiedContractIdentifier;
 use libstackerdb::SlotMetadata;
@@ -0,0 +1,231 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::thread;
+use std::time::Duration;
+
+use clarity::vm::types::QualifiedContractIdentifier;
+
+use stacks_common::types::chainstate::{BlockHeaderHash, ConsensusHash};
+use stacks_common::util::hash::Sha256Sum;
+
+use crate::runner::nodes::{
+    check_network_id, get_node_health, record_node_failure, record_node_success, NodeHealth,
+    MAINNET_NETWORK_ID,
+};
+use crate::runner::Error;
+use crate::runner::RPCPeerInfoData;
+use crate::runner::Runner;
+
+fn make_peer_info(network_id: u32) -> RPCPeerInfoData {
+    RPCPeerInfoData {
+        peer_version: 0x18000000,
+        pox_consensus: ConsensusHash([0x01; 20]),
+        burn_block_height: 100,
+        stable_pox_consensus: ConsensusHash([0x02; 20]),
+        stable_burn_block_height: 94,
+        server_version: "test".into(),
+        network_id,
+        parent_network_id: 0,
+        stacks_tip_height: 50,
+        stacks_tip: BlockHeaderHash([0x03; 32]),
+        stacks_tip_consensus_hash: ConsensusHash([0x04; 20]),
+        genesis_chainstate_hash: Sha256Sum([0x05; 32]),
+        unanchored_tip: None,
+        unanchored_seq: None,
+        exit_at_block_height: None,
+        node_public_key: None,
+        node_public_key_hash: None,
+        stackerdbs: None,
+    }
+}
+
+/// Serve /v2/info for the given network ID on each connection, forever
+fn spawn_info_server(network_id: u32) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let body = serde_json::to_string(&make_peer_info(network_id)).unwrap();
+    thread::spawn(move || {
+        for sock_res in listener.incoming() {
+            let Ok(mut sock) = sock_res else {
+                continue;
+            };
+            let mut buf = vec![];
+            let mut byte = [0u8; 1];
+            while !buf.ends_with(b"\r\n\r\n") {
+                match sock.read(&mut byte) {
+                    Ok(1) => buf.push(byte[0]),
+                    _ => break,
+                }
+            }
+            let _ = sock.write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    &body
+                )
+                .as_bytes(),
+            );
+        }
+    });
+    addr
+}
+
+fn closed_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+fn make_runner(node_port: u16, fallback_ports: &[u16], mainnet: bool) -> Runner {
+    Runner::new(
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2")
+            .unwrap(),
+        QualifiedContractIdentifier::parse(
+            "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.zonefile-resolver",
+        )
+        .unwrap(),
+        "127.0.0.1".to_string(),
+        node_port,
+    )
+    .with_node_failover(
+        fallback_ports
+            .iter()
+            .map(|port| ("127.0.0.1".to_string(), *port))
+            .collect(),
+        mainnet,
+    )
+}
+
+#[test]
+fn test_node_health() {
+    assert!(check_network_id(true, MAINNET_NETWORK_ID));
+    assert!(!check_network_id(true, 0x80000000));
+    assert!(check_network_id(false, 0x80000000));
+    assert!(!check_network_id(false, MAINNET_NETWORK_ID));
+
+    let addr: SocketAddr = format!("127.0.0.1:{}", closed_port()).parse().unwrap();
+    assert_eq!(get_node_health(&addr), NodeHealth::new());
+
+    record_node_failure(&addr);
+    record_node_failure(&addr);
+    let health = get_node_health(&addr);
+    assert_eq!(health.failures, 2);
+    assert!(health.is_cooling_down(Duration::from_secs(60)));
+    assert!(!health.is_cooling_down(Duration::from_secs(0)));
+
+    record_node_success(&addr);
+    let health = get_node_health(&addr);
+    assert_eq!(health.failures, 0);
+    assert!(!health.is_cooling_down(Duration::from_secs(60)));
+}
+
+#[test]
+fn test_node_failover() {
+    let down_port = closed_port();
+    let testnet_addr = spawn_info_server(0x80000000);
+    let mainnet_addr_1 = spawn_info_server(MAINNET_NETWORK_ID);
+    let mainnet_addr_2 = spawn_info_server(MAINNET_NETWORK_ID);
+
+    // the down node and the testnet node are skipped
+    let mut runner = make_runner(
+        down_port,
+        &[
+            testnet_addr.port(),
+            mainnet_addr_1.port(),
+            mainnet_addr_2.port(),
+        ],
+        true,
+    );
+    assert_eq!(runner.resolve_node().unwrap(), Some(mainnet_addr_1.clone()));
+    assert_eq!(get_node_health(&testnet_addr).network_id, Some(0x80000000));
+    assert_eq!(
+        get_node_health(&mainnet_addr_1).network_id,
+        Some(MAINNET_NETWORK_ID)
+    );
+
+    // a network failure on one node fails over to the next
+    let mut tried = vec![];
+    let res = runner.with_node_failover("test", |node_addr| {
+        tried.push(node_addr.clone());
+        if *node_addr == mainnet_addr_1 {
+            Err(Error::Timeout("test".into()))
+        } else {
+            Ok(node_addr.clone())
+        }
+    });
+    assert_eq!(res.unwrap(), mainnet_addr_2);
+    assert_eq!(tried, vec![mainnet_addr_1.clone(), mainnet_addr_2.clone()]);
+    assert!(get_node_health(&mainnet_addr_1).is_cooling_down(Duration::from_secs(60)));
+
+    // later runners avoid the node that just failed
+    let mut runner = make_runner(mainnet_addr_1.port(), &[mainnet_addr_2.port()], true);
+    assert_eq!(runner.resolve_node().unwrap(), Some(mainnet_addr_2.clone()));
+
+    // errors from the node itself are not failed over
+    let mut tried = 0;
+    let res: Result<(), Error> = runner.with_node_failover("test", |_node_addr| {
+        tried += 1;
+        Err(Error::HttpError(404, Default::default(), 0))
+    });
+    assert!(matches!(res, Err(Error::HttpError(404, ..))));
+    assert_eq!(tried, 1);
+
+    // if every node is down, the last error comes back
+    let mut runner = make_runner(down_port, &[closed_port()], true);
+    let res: Result<(), Error> = runner.with_node_failover("test", |node_addr| {
+        Err(Error::ConnectionRefused(node_addr.to_string()))
+    });
+    assert!(matches!(res, Err(Error::ConnectionRefused(_))));
+
+    // if every node is cooling down, each one is still tried in order
+    let cooling_addr_1: SocketAddr = format!("127.0.0.1:{}", closed_port()).parse().unwrap();
+    let cooling_addr_2: SocketAddr = format!("127.0.0.1:{}", closed_port()).parse().unwrap();
+    let cooling_addr_3: SocketAddr = format!("127.0.0.1:{}", closed_port()).parse().unwrap();
+    record_node_failure(&cooling_addr_1);
+    record_node_failure(&cooling_addr_2);
+    record_node_failure(&cooling_addr_3);
+    let mut runner = make_runner(
+        cooling_addr_1.port(),
+        &[cooling_addr_2.port(), cooling_addr_3.port()],
+        true,
+    );
+    let mut tried = vec![];
+    let res = runner.with_node_failover("test", |node_addr| {
+        tried.push(node_addr.clone());
+        if *node_addr == cooling_addr_3 {
+            Ok(node_addr.clone())
+        } else {
+            Err(Error::ConnectionRefused(node_addr.to_string()))
+        }
+    });
+    assert_eq!(res.unwrap(), cooling_addr_3);
+    assert_eq!(
+        tried,
+        vec![
+            cooling_addr_1.clone(),
+            cooling_addr_2.clone(),
+            cooling_addr_3.clone()
+        ]
+    );
+    assert!(!get_node_health(&cooling_addr_3).is_cooling_down(Duration::from_secs(60)));
+
+    // testnet runners use the testnet node
+    let mut runner = make_runner(mainnet_addr_2.port(), &[testnet_addr.port()], false);
+    assert_eq!(runner.resolve_node().unwrap(), Some(testnet_addr));
+}
@@ -0,0 +1,226 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+
+use clarity::vm::types::PrincipalData;
+use clarity::vm::types::QualifiedContractIdentifier;
+use clarity::vm::types::StacksAddressExtensions;
+
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::chainstate::StacksPublicKey;
+use stacks_common::util::hash::Hash160;
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::runner::nonces::{NonceDB, NONCE_RESERVATION_TIMEOUT_SECS};
+use crate::runner::Error;
+use crate::runner::Runner;
+use crate::tx::{
+    make_contract_call, make_sponsored_tx, sponsor_tx, StacksTransaction,
+    TransactionPostConditionMode,
+};
+
+fn setup_nonce_db(test_name: &str) -> NonceDB {
+    let path = format!("/tmp/wrb-test-nonces-{}.sqlite", test_name);
+    if fs::metadata(&path).is_ok() {
+        fs::remove_file(&path).unwrap();
+    }
+    NonceDB::open(&path).unwrap()
+}
+
+fn make_tx(privk: &Secp256k1PrivateKey, nonce: u64, fee: u64) -> StacksTransaction {
+    make_contract_call(
+        false,
+        privk,
+        nonce,
+        fee,
+        &StacksAddress::new(26, Hash160([0x01; 20])).unwrap(),
+        "hello",
+        "world",
+        &[],
+        TransactionPostConditionMode::Deny,
+        vec![],
+    )
+    .unwrap()
+}
+
+fn principal_of(privk: &Secp256k1PrivateKey) -> PrincipalData {
+    StacksAddress::p2pkh(false, &StacksPublicKey::from_private(privk)).to_account_principal()
+}
+
+#[test]
+fn test_nonce_db_reserve() {
+    let mut nonce_db = setup_nonce_db("reserve");
+    let privk = Secp256k1PrivateKey::random();
+    let principal = principal_of(&privk);
+    let other = principal_of(&Secp256k1PrivateKey::random());
+
+    // sequential nonces, starting at the chain nonce
+    assert_eq!(nonce_db.reserve_nonce(&principal, 5, 1000).unwrap(), 5);
+    assert_eq!(nonce_db.reserve_nonce(&principal, 5, 1000).unwrap(), 6);
+    assert_eq!(nonce_db.reserve_nonce(&principal, 5, 1000).unwrap(), 7);
+
+    // accounts don't share nonces
+    assert_eq!(nonce_db.reserve_nonce(&other, 0, 1000).unwrap(), 0);
+
+    // released nonces get handed out again before higher ones
+    nonce_db.release_nonce(&principal, 6).unwrap();
+    assert_eq!(nonce_db.reserve_nonce(&principal, 5, 1000).unwrap(), 6);
+
+    // nonces used on-chain are forgotten
+    assert_eq!(nonce_db.reserve_nonce(&principal, 7, 1000).unwrap(), 8);
+    let pending: Vec<_> = nonce_db
+        .get_pending_nonces(&principal)
+        .unwrap()
+        .into_iter()
+        .map(|pending| pending.nonce)
+        .collect();
+    assert_eq!(pending, vec![7, 8]);
+
+    // broadcast transactions are remembered
+    let tx = make_tx(&privk, 7, 100);
+    nonce_db.record_tx(&principal, &tx, 2000).unwrap();
+    let pending = nonce_db.get_pending_nonce(&principal, 7).unwrap().unwrap();
+    assert_eq!(pending.tx, Some(tx.clone()));
+    assert_eq!(pending.txid(), Some(tx.txid()));
+    assert_eq!(pending.updated_at, 2000);
+
+    // abandoned reservations are pruned, but broadcast transactions are not
+    nonce_db
+        .prune(&principal, 7, 1000 + NONCE_RESERVATION_TIMEOUT_SECS)
+        .unwrap();
+    let pending: Vec<_> = nonce_db
+        .get_pending_nonces(&principal)
+        .unwrap()
+        .into_iter()
+        .map(|pending| pending.nonce)
+        .collect();
+    assert_eq!(pending, vec![7]);
+    assert_eq!(nonce_db.reserve_nonce(&principal, 7, 3000).unwrap(), 8);
+}
+
+#[test]
+fn test_nonce_db_gap() {
+    let mut nonce_db = setup_nonce_db("gap");
+    let privk = Secp256k1PrivateKey::random();
+    let principal = principal_of(&privk);
+
+    // nothing pending
+    assert!(!nonce_db.has_nonce_gap(&principal, 5).unwrap());
+
+    // contiguous from the chain nonce, whether or not a tx was recorded
+    assert_eq!(nonce_db.reserve_nonce(&principal, 5, 1000).unwrap(), 5);
+    assert_eq!(nonce_db.reserve_nonce(&principal, 5, 1000).unwrap(), 6);
+    nonce_db
+        .record_tx(&principal, &make_tx(&privk, 6, 100), 1000)
+        .unwrap();
+    assert!(!nonce_db.has_nonce_gap(&principal, 5).unwrap());
+
+    // a nonce in the middle went missing
+    assert_eq!(nonce_db.reserve_nonce(&principal, 5, 1000).unwrap(), 7);
+    nonce_db.release_nonce(&principal, 6).unwrap();
+    assert!(nonce_db.has_nonce_gap(&principal, 5).unwrap());
+
+    // the first nonce went missing
+    assert_eq!(nonce_db.reserve_nonce(&principal, 5, 1000).unwrap(), 6);
+    nonce_db.release_nonce(&principal, 5).unwrap();
+    assert!(nonce_db.has_nonce_gap(&principal, 5).unwrap());
+    assert!(!nonce_db.has_nonce_gap(&principal, 6).unwrap());
+}
+
+#[test]
+fn test_replace_by_fee() {
+    let mut nonce_db = setup_nonce_db("replace_by_fee");
+    let privk = Secp256k1PrivateKey::random();
+    let principal = principal_of(&privk);
+    let mut runner = Runner::new(
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2")
+            .unwrap(),
+        QualifiedContractIdentifier::parse(
+            "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.zonefile-resolver",
+        )
+        .unwrap(),
+        "127.0.0.1".to_string(),
+        20443,
+    );
+
+    // nothing to replace yet
+    assert!(matches!(
+        runner.make_replace_by_fee_tx(&nonce_db, &principal, 3, 200, &privk),
+        Err(Error::Database(_))
+    ));
+    assert_eq!(nonce_db.reserve_nonce(&principal, 3, 1000).unwrap(), 3);
+    assert!(matches!(
+        runner.make_replace_by_fee_tx(&nonce_db, &principal, 3, 200, &privk),
+        Err(Error::Database(_))
+    ));
+
+    let tx = make_tx(&privk, 3, 100);
+    nonce_db.record_tx(&principal, &tx, 1000).unwrap();
+
+    // fee must go up
+    assert!(matches!(
+        runner.make_replace_by_fee_tx(&nonce_db, &principal, 3, 100, &privk),
+        Err(Error::MalformedRequest(_))
+    ));
+
+    let replacement = runner
+        .make_replace_by_fee_tx(&nonce_db, &principal, 3, 200, &privk)
+        .unwrap();
+    assert_eq!(replacement.get_tx_fee(), 200);
+    assert_eq!(replacement.get_origin_nonce(), 3);
+    assert_eq!(replacement.payload, tx.payload);
+    assert_ne!(replacement.txid(), tx.txid());
+    replacement.verify().unwrap();
+
+    // same as building it from scratch with the higher fee
+    assert_eq!(replacement, make_tx(&privk, 3, 200));
+}
+
+#[test]
+fn test_nonce_db_sponsored() {
+    let mut nonce_db = setup_nonce_db("sponsored");
+    let origin_privk = Secp256k1PrivateKey::random();
+    let sponsor_privk = Secp256k1PrivateKey::random();
+    let origin = principal_of(&origin_privk);
+    let sponsor = principal_of(&sponsor_privk);
+
+    let origin_signed = make_sponsored_tx(&make_tx(&origin_privk, 4, 100), &origin_privk).unwrap();
+    assert!(origin_signed.auth.is_sponsored());
+    origin_signed.verify_origin().unwrap();
+    assert!(origin_signed.verify().is_err());
+
+    // can't sponsor twice over
+    assert!(make_sponsored_tx(&origin_signed, &origin_privk).is_err());
+
+    let tx = sponsor_tx(&origin_signed, &sponsor_privk, 9, 300).unwrap();
+    tx.verify().unwrap();
+    assert_eq!(tx.get_origin_nonce(), 4);
+    assert_eq!(tx.get_sponsor_nonce(), Some(9));
+    assert_eq!(tx.get_tx_fee(), 300);
+    assert_eq!(tx.origin_address(), origin_signed.origin_address());
+
+    // the sponsor's nonce is the one recorded for the sponsor
+    assert_eq!(nonce_db.reserve_nonce(&sponsor, 9, 1000).unwrap(), 9);
+    nonce_db.record_tx(&sponsor, &tx, 1000).unwrap();
+    let pending = nonce_db.get_pending_nonce(&sponsor, 9).unwrap().unwrap();
+    assert_eq!(pending.tx, Some(tx.clone()));
+
+    nonce_db.record_tx(&origin, &tx, 1000).unwrap();
+    let pending = nonce_db.get_pending_nonce(&origin, 4).unwrap().unwrap();
+    assert_eq!(pending.tx, Some(tx));
+}
@@ -0,0 +1,296 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::runner::pins::{SitePin, SitePinDB, SitePinMismatch};
+use crate::runner::tests::BNSNameRecord;
+use crate::runner::tests::MockBNSResolver;
+use crate::runner::Error;
+use crate::runner::Runner;
+
+use crate::storage::tests::MockStackerDBClient;
+use crate::storage::StackerDBClient;
+
+use crate::ui::render::Renderer;
+
+use libstackerdb::SlotMetadata;
+use libstackerdb::StackerDBChunkData;
+
+use clarity::vm::types::QualifiedContractIdentifier;
+
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::chainstate::StacksPrivateKey;
+use stacks_common::types::chainstate::StacksPublicKey;
+use stacks_common::types::Address;
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+fn make_pin_db_path(test_name: &str) -> String {
+    let path = format!("/tmp/wrb-test-pins-{}.sqlite", test_name);
+    if fs::metadata(&path).is_ok() {
+        fs::remove_file(&path).unwrap();
+    }
+    path
+}
+
+fn make_runner(pins_path: &str, pinned_code_hashes: HashMap<String, Sha512Trunc256Sum>) -> Runner {
+    Runner::new(
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2")
+            .unwrap(),
+        QualifiedContractIdentifier::parse(
+            "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.zonefile-resolver",
+        )
+        .unwrap(),
+        "127.0.0.1".to_string(),
+        12345,
+    )
+    .with_site_pins(pins_path.to_string(), pinned_code_hashes)
+}
+
+#[test]
+fn test_site_pin_db() {
+    let path = make_pin_db_path("test_site_pin_db");
+    let mut pin_db = SitePinDB::open(&path).unwrap();
+
+    assert_eq!(pin_db.get_pin("foo.btc").unwrap(), None);
+    assert_eq!(pin_db.get_all_pins().unwrap(), vec![]);
+
+    let pin = SitePin::new(
+        "foo.btc",
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.foo")
+            .unwrap(),
+        StacksAddress::from_string("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF").unwrap(),
+        None,
+    );
+    pin_db.put_pin(&pin).unwrap();
+    assert_eq!(pin_db.get_pin("foo.btc").unwrap(), Some(pin.clone()));
+
+    // reopen and make sure it's still there
+    let mut pin_db = SitePinDB::open(&path).unwrap();
+    assert_eq!(pin_db.get_pin("foo.btc").unwrap(), Some(pin.clone()));
+
+    // pin a code hash
+    let mut hash_pin = pin.clone();
+    hash_pin.code_hash = Some(Sha512Trunc256Sum([0x11; 32]));
+    pin_db.put_pin(&hash_pin).unwrap();
+    assert_eq!(pin_db.get_pin("foo.btc").unwrap(), Some(hash_pin.clone()));
+    assert_eq!(pin_db.get_all_pins().unwrap(), vec![hash_pin.clone()]);
+
+    pin_db.remove_pin("foo.btc").unwrap();
+    assert_eq!(pin_db.get_pin("foo.btc").unwrap(), None);
+}
+
+#[test]
+fn test_site_pin_check() {
+    let contract_id =
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.foo")
+            .unwrap();
+    let other_contract_id =
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.bar")
+            .unwrap();
+    let signer = StacksAddress::from_string("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF").unwrap();
+    let other_signer = StacksAddress::from_string("SP000000000000000000002Q6VF78").unwrap();
+
+    let pin = SitePin::new("foo.btc", contract_id.clone(), signer.clone(), None);
+    let code_hash = Sha512Trunc256Sum([0x11; 32]);
+
+    // same publisher
+    assert!(pin.check(&pin, &code_hash).is_ok());
+
+    // different StackerDB
+    let observed = SitePin::new("foo.btc", other_contract_id, signer.clone(), None);
+    assert_eq!(
+        pin.check(&observed, &code_hash),
+        Err(SitePinMismatch::Publisher(pin.clone(), observed.clone()))
+    );
+
+    // different signer
+    let observed = SitePin::new("foo.btc", contract_id.clone(), other_signer, None);
+    assert_eq!(
+        pin.check(&observed, &code_hash),
+        Err(SitePinMismatch::Publisher(pin.clone(), observed.clone()))
+    );
+
+    // pinned code hash
+    let hash_pin = SitePin::new(
+        "foo.btc",
+        contract_id.clone(),
+        signer.clone(),
+        Some(code_hash.clone()),
+    );
+    assert!(hash_pin.check(&pin, &code_hash).is_ok());
+    assert_eq!(
+        hash_pin.check(&pin, &Sha512Trunc256Sum([0x22; 32])),
+        Err(SitePinMismatch::CodeHash(
+            code_hash.clone(),
+            Sha512Trunc256Sum([0x22; 32])
+        ))
+    );
+}
+
+#[test]
+fn test_wrbsite_load_pinned() {
+    let pkey = StacksPrivateKey::random();
+    let other_pkey = StacksPrivateKey::random();
+
+    let code_body = b"(print \"hello world!\")";
+    let code_bytes = Renderer::encode_bytes(code_body).unwrap();
+    let chunk = StackerDBChunkData::new(1, 2, code_bytes.clone());
+    let code_hash = chunk.data_hash();
+
+    let stackerdb_id =
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.lolwut")
+            .unwrap();
+
+    let mut slot_metadata =
+        SlotMetadata::new_unsigned(chunk.slot_id, chunk.slot_version, code_hash.clone());
+    slot_metadata.sign(&pkey).unwrap();
+
+    let mut other_slot_metadata =
+        SlotMetadata::new_unsigned(chunk.slot_id, chunk.slot_version, code_hash.clone());
+    other_slot_metadata.sign(&other_pkey).unwrap();
+
+    let mut mock_stackerdb = MockStackerDBClient::new(pkey.clone(), 3);
+    mock_stackerdb.put_chunk(chunk.clone()).unwrap();
+
+    let mut other_mock_stackerdb = MockStackerDBClient::new(other_pkey.clone(), 3);
+    other_mock_stackerdb.put_chunk(chunk).unwrap();
+
+    let mut mock_bns_resolver = MockBNSResolver::new();
+    mock_bns_resolver.add_name_rec(
+        "happy",
+        "path",
+        BNSNameRecord::from_stackerdb_slot(stackerdb_id.clone(), slot_metadata.clone()),
+    );
+
+    let path = make_pin_db_path("test_wrbsite_load_pinned");
+    let mut runner = make_runner(&path, HashMap::new());
+
+    // first load pins the site
+    let (resolved_code, _) = runner
+        .wrbsite_load_ext(
+            &mut mock_bns_resolver,
+            "happy",
+            "path",
+            |_, _| Ok(Box::new(mock_stackerdb.clone())),
+            |_, _| Ok(Box::new(mock_stackerdb.clone())),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolved_code, code_bytes);
+
+    let mut pubkey = StacksPublicKey::from_private(&pkey);
+    pubkey.set_compressed(true);
+    let expected_pin = SitePin::new(
+        "happy.path",
+        stackerdb_id.clone(),
+        StacksAddress::p2pkh(true, &pubkey),
+        None,
+    );
+    let pin_db = SitePinDB::open(&path).unwrap();
+    assert_eq!(
+        pin_db.get_pin("happy.path").unwrap(),
+        Some(expected_pin.clone())
+    );
+
+    // second load succeeds, since the publisher is the same
+    let (resolved_code, _) = runner
+        .wrbsite_load_ext(
+            &mut mock_bns_resolver,
+            "happy",
+            "path",
+            |_, _| Ok(Box::new(mock_stackerdb.clone())),
+            |_, _| Ok(Box::new(mock_stackerdb.clone())),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolved_code, code_bytes);
+
+    // the name gets re-pointed to a different signer
+    mock_bns_resolver.add_name_rec(
+        "happy",
+        "path",
+        BNSNameRecord::from_stackerdb_slot(stackerdb_id.clone(), other_slot_metadata.clone()),
+    );
+
+    let err = runner
+        .wrbsite_load_ext(
+            &mut mock_bns_resolver,
+            "happy",
+            "path",
+            |_, _| Ok(Box::new(other_mock_stackerdb.clone())),
+            |_, _| Ok(Box::new(other_mock_stackerdb.clone())),
+        )
+        .unwrap_err();
+
+    let Error::SitePinMismatch(name, SitePinMismatch::Publisher(old_pin, new_pin)) = err else {
+        panic!("Did not get a publisher pin mismatch");
+    };
+    assert_eq!(name, "happy.path");
+    assert_eq!(old_pin, expected_pin);
+
+    // accept the new publisher
+    runner.accept_site_pin(&new_pin).unwrap();
+    let (resolved_code, _) = runner
+        .wrbsite_load_ext(
+            &mut mock_bns_resolver,
+            "happy",
+            "path",
+            |_, _| Ok(Box::new(other_mock_stackerdb.clone())),
+            |_, _| Ok(Box::new(other_mock_stackerdb.clone())),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolved_code, code_bytes);
+
+    // pin the site to a different code hash in the config
+    let mut pinned_code_hashes = HashMap::new();
+    pinned_code_hashes.insert("happy.path".to_string(), Sha512Trunc256Sum([0x11; 32]));
+    let mut runner = make_runner(&path, pinned_code_hashes);
+
+    let err = runner
+        .wrbsite_load_ext(
+            &mut mock_bns_resolver,
+            "happy",
+            "path",
+            |_, _| Ok(Box::new(other_mock_stackerdb.clone())),
+            |_, _| Ok(Box::new(other_mock_stackerdb.clone())),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::SitePinMismatch(_, SitePinMismatch::CodeHash(..))
+    ));
+
+    // pin the site to the right code hash in the config
+    let mut pinned_code_hashes = HashMap::new();
+    pinned_code_hashes.insert("happy.path".to_string(), code_hash.clone());
+    let mut runner = make_runner(&path, pinned_code_hashes);
+
+    let (resolved_code, _) = runner
+        .wrbsite_load_ext(
+            &mut mock_bns_resolver,
+            "happy",
+            "path",
+            |_, _| Ok(Box::new(other_mock_stackerdb.clone())),
+            |_, _| Ok(Box::new(other_mock_stackerdb.clone())),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(resolved_code, code_bytes);
+}
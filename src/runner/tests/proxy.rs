@@ -0,0 +1,216 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use crate::runner::http::run_http_request;
+use crate::runner::proxy::{
+    proxy_connect, virtual_addr, virtual_addr_host, ProxyConfig, ProxyKind,
+};
+use crate::runner::Error;
+
+fn read_until_blank_line(sock: &mut TcpStream) -> Vec<u8> {
+    let mut buf = vec![];
+    let mut byte = [0u8; 1];
+    while !buf.ends_with(b"\r\n\r\n") {
+        match sock.read(&mut byte) {
+            Ok(1) => buf.push(byte[0]),
+            _ => break,
+        }
+    }
+    buf
+}
+
+/// After the proxy handshake, act as the destination node and answer one HTTP request
+fn serve_http(sock: &mut TcpStream) {
+    read_until_blank_line(sock);
+    sock.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello")
+        .unwrap();
+}
+
+/// SOCKS5 stand-in.  Reports the requested destination (as the raw address bytes and port),
+/// and replies with the given status code.
+fn spawn_socks5_proxy(status: u8) -> (SocketAddr, Receiver<(u8, Vec<u8>, u16)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = sync_channel(1);
+    thread::spawn(move || {
+        let (mut sock, _) = listener.accept().unwrap();
+        let mut greeting = [0u8; 3];
+        sock.read_exact(&mut greeting).unwrap();
+        assert_eq!(greeting, [0x05, 0x01, 0x00]);
+        sock.write_all(&[0x05, 0x00]).unwrap();
+
+        let mut request = [0u8; 4];
+        sock.read_exact(&mut request).unwrap();
+        assert_eq!(&request[0..3], &[0x05, 0x01, 0x00]);
+        let atyp = request[3];
+        let addr_len = match atyp {
+            0x01 => 4,
+            0x04 => 16,
+            0x03 => {
+                let mut len = [0u8; 1];
+                sock.read_exact(&mut len).unwrap();
+                usize::from(len[0])
+            }
+            _ => panic!("bad atyp"),
+        };
+        let mut dest = vec![0u8; addr_len];
+        sock.read_exact(&mut dest).unwrap();
+        let mut port = [0u8; 2];
+        sock.read_exact(&mut port).unwrap();
+        tx.send((atyp, dest, u16::from_be_bytes(port))).unwrap();
+
+        sock.write_all(&[0x05, status, 0x00, 0x01, 127, 0, 0, 1, 0x4e, 0x20])
+            .unwrap();
+        if status == 0 {
+            serve_http(&mut sock);
+        }
+    });
+    (addr, rx)
+}
+
+/// HTTP CONNECT stand-in.  Reports the request line, and replies with the given status line.
+fn spawn_http_proxy(status_line: &'static str) -> (SocketAddr, Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = sync_channel(1);
+    thread::spawn(move || {
+        let (mut sock, _) = listener.accept().unwrap();
+        let request = String::from_utf8(read_until_blank_line(&mut sock)).unwrap();
+        tx.send(request.lines().next().unwrap_or("").to_string())
+            .unwrap();
+        sock.write_all(format!("{}\r\n\r\n", status_line).as_bytes())
+            .unwrap();
+        if status_line.contains(" 200 ") {
+            serve_http(&mut sock);
+        }
+    });
+    (addr, rx)
+}
+
+#[test]
+fn test_proxy_config_parse() {
+    let proxy = ProxyConfig::parse("socks5h://127.0.0.1:9050").unwrap();
+    assert_eq!(proxy.kind, ProxyKind::Socks5);
+    assert!(proxy.remote_dns);
+    assert_eq!(proxy.addr, "127.0.0.1:9050".parse().unwrap());
+    assert_eq!(proxy.to_string(), "socks5h://127.0.0.1:9050");
+
+    let proxy = ProxyConfig::parse("socks5://127.0.0.1").unwrap();
+    assert!(!proxy.remote_dns);
+    assert_eq!(proxy.addr.port(), 1080);
+
+    let proxy = ProxyConfig::parse("http://127.0.0.1:3128").unwrap();
+    assert_eq!(proxy.kind, ProxyKind::HttpConnect);
+    assert_eq!(ProxyConfig::parse(&proxy.to_string()).unwrap(), proxy);
+
+    assert!(ProxyConfig::parse("ftp://127.0.0.1").is_err());
+    assert!(ProxyConfig::parse("not a url").is_err());
+}
+
+#[test]
+fn test_virtual_addrs() {
+    let addr = virtual_addr("Node.Example.wrb", 20443);
+    assert_eq!(addr.port(), 20443);
+    assert_eq!(virtual_addr("node.example.wrb", 20444).ip(), addr.ip());
+    assert_ne!(virtual_addr("other.example.wrb", 20443).ip(), addr.ip());
+    assert_eq!(
+        virtual_addr_host(&addr),
+        Some("node.example.wrb".to_string())
+    );
+    assert_eq!(virtual_addr_host(&"127.0.0.1:20443".parse().unwrap()), None);
+}
+
+#[test]
+fn test_socks5_proxy_connect() {
+    let timeout = Duration::from_secs(5);
+
+    // host names are resolved by the proxy
+    let (proxy_addr, rx) = spawn_socks5_proxy(0x00);
+    let proxy = ProxyConfig {
+        kind: ProxyKind::Socks5,
+        addr: proxy_addr,
+        remote_dns: true,
+    };
+    let dest = virtual_addr("socks.example.wrb", 20443);
+    let mut sock = proxy_connect(&proxy, &dest, timeout).unwrap();
+    assert_eq!(
+        rx.recv().unwrap(),
+        (0x03, b"socks.example.wrb".to_vec(), 20443)
+    );
+    let body = run_http_request(&mut sock, &dest, "GET", "/v2/info", None, &[]).unwrap();
+    assert_eq!(body, b"hello".to_vec());
+
+    // IP addresses are passed as-is
+    let (proxy_addr, rx) = spawn_socks5_proxy(0x00);
+    let proxy = ProxyConfig {
+        kind: ProxyKind::Socks5,
+        addr: proxy_addr,
+        remote_dns: false,
+    };
+    let dest: SocketAddr = "10.1.2.3:20443".parse().unwrap();
+    proxy_connect(&proxy, &dest, timeout).unwrap();
+    assert_eq!(rx.recv().unwrap(), (0x01, vec![10, 1, 2, 3], 20443));
+
+    // refusals are reported as such
+    let (proxy_addr, _rx) = spawn_socks5_proxy(0x05);
+    let proxy = ProxyConfig {
+        kind: ProxyKind::Socks5,
+        addr: proxy_addr,
+        remote_dns: false,
+    };
+    assert!(matches!(
+        proxy_connect(&proxy, &dest, timeout),
+        Err(Error::ConnectionRefused(_))
+    ));
+}
+
+#[test]
+fn test_http_proxy_connect() {
+    let timeout = Duration::from_secs(5);
+
+    let (proxy_addr, rx) = spawn_http_proxy("HTTP/1.1 200 Connection established");
+    let proxy = ProxyConfig {
+        kind: ProxyKind::HttpConnect,
+        addr: proxy_addr,
+        remote_dns: true,
+    };
+    let dest = virtual_addr("http.example.wrb", 20443);
+    let mut sock = proxy_connect(&proxy, &dest, timeout).unwrap();
+    assert_eq!(
+        rx.recv().unwrap(),
+        "CONNECT http.example.wrb:20443 HTTP/1.1"
+    );
+    let body = run_http_request(&mut sock, &dest, "GET", "/v2/info", None, &[]).unwrap();
+    assert_eq!(body, b"hello".to_vec());
+
+    let (proxy_addr, _rx) = spawn_http_proxy("HTTP/1.1 403 Forbidden");
+    let proxy = ProxyConfig {
+        kind: ProxyKind::HttpConnect,
+        addr: proxy_addr,
+        remote_dns: true,
+    };
+    assert!(matches!(
+        proxy_connect(&proxy, &dest, timeout),
+        Err(Error::ConnectionRefused(_))
+    ));
+}
@@ -0,0 +1,352 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::runner::replicas::{ReplicaFetcher, ReplicaStats, ReplicaStatsDB};
+use crate::runner::site::WrbTxtRecordV1;
+use crate::runner::Error;
+use crate::runner::Runner;
+
+use crate::storage::tests::MockStackerDBClient;
+use crate::storage::StackerDBClient;
+
+use crate::ui::render::Renderer;
+
+use libstackerdb::{SlotMetadata, StackerDBChunkAckData, StackerDBChunkData};
+
+use clarity::vm::types::QualifiedContractIdentifier;
+
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::chainstate::StacksPrivateKey;
+
+/// StackerDB client which takes a while to answer.
+/// Like a socket, it gives up once its timeout (if set) passes.
+struct SlowStackerDBClient {
+    inner: MockStackerDBClient,
+    addr: SocketAddr,
+    delay: Duration,
+    timeout_opt: Option<Duration>,
+    /// number of reads which have finished, successfully or not
+    num_reads: Arc<AtomicUsize>,
+}
+
+impl SlowStackerDBClient {
+    fn wait(&self) -> Result<(), Error> {
+        let res = match self.timeout_opt {
+            Some(timeout) if timeout < self.delay => {
+                thread::sleep(timeout);
+                Err(Error::Timeout(format!("{} timed out", &self.addr)))
+            }
+            _ => {
+                thread::sleep(self.delay);
+                Ok(())
+            }
+        };
+        self.num_reads.fetch_add(1, Ordering::SeqCst);
+        res
+    }
+}
+
+impl StackerDBClient for SlowStackerDBClient {
+    fn get_host(&self) -> SocketAddr {
+        self.addr.clone()
+    }
+
+    fn list_chunks(&mut self) -> Result<Vec<SlotMetadata>, Error> {
+        self.inner.list_chunks()
+    }
+
+    fn get_chunks(
+        &mut self,
+        slots_and_versions: &[(u32, u32)],
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        self.wait()?;
+        self.inner.get_chunks(slots_and_versions)
+    }
+
+    fn get_latest_chunks(&mut self, slot_ids: &[u32]) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        self.wait()?;
+        self.inner.get_latest_chunks(slot_ids)
+    }
+
+    fn put_chunk(&mut self, chunk: StackerDBChunkData) -> Result<StackerDBChunkAckData, Error> {
+        self.inner.put_chunk(chunk)
+    }
+
+    fn find_replicas(&mut self) -> Result<Vec<SocketAddr>, Error> {
+        self.inner.find_replicas()
+    }
+
+    fn get_signers(&mut self) -> Result<Vec<StacksAddress>, Error> {
+        self.inner.get_signers()
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout_opt = Some(timeout);
+    }
+}
+
+fn make_slow_client(
+    stackerdb: &MockStackerDBClient,
+    addr: &SocketAddr,
+    delay_ms: u64,
+) -> (SocketAddr, Box<dyn StackerDBClient>, Arc<AtomicUsize>) {
+    let num_reads = Arc::new(AtomicUsize::new(0));
+    let client = SlowStackerDBClient {
+        inner: stackerdb.clone(),
+        addr: addr.clone(),
+        delay: Duration::from_millis(delay_ms),
+        timeout_opt: None,
+        num_reads: num_reads.clone(),
+    };
+    (addr.clone(), Box::new(client), num_reads)
+}
+
+fn make_stats_db_path(test_name: &str) -> String {
+    let path = format!("/tmp/wrb-test-replicas-{}.sqlite", test_name);
+    if fs::metadata(&path).is_ok() {
+        fs::remove_file(&path).unwrap();
+    }
+    path
+}
+
+fn make_runner(stats_path: &str, timeout: Duration) -> Runner {
+    Runner::new(
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2")
+            .unwrap(),
+        QualifiedContractIdentifier::parse(
+            "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.zonefile-resolver",
+        )
+        .unwrap(),
+        "127.0.0.1".to_string(),
+        12345,
+    )
+    .with_replica_stats(stats_path.to_string(), timeout, 4)
+}
+
+#[test]
+fn test_replica_stats_db() {
+    let path = make_stats_db_path("test_replica_stats_db");
+    let mut stats_db = ReplicaStatsDB::open(&path).unwrap();
+
+    let fast: SocketAddr = "127.0.0.1:20001".parse().unwrap();
+    let slow: SocketAddr = "127.0.0.1:20002".parse().unwrap();
+    let flaky: SocketAddr = "127.0.0.1:20003".parse().unwrap();
+    let unknown: SocketAddr = "127.0.0.1:20004".parse().unwrap();
+
+    assert_eq!(stats_db.get_stats(&fast).unwrap(), None);
+
+    stats_db
+        .record(&fast, Some(Duration::from_millis(10)))
+        .unwrap();
+    stats_db
+        .record(&slow, Some(Duration::from_millis(1000)))
+        .unwrap();
+    stats_db
+        .record(&flaky, Some(Duration::from_millis(1)))
+        .unwrap();
+    stats_db.record(&flaky, None).unwrap();
+
+    let fast_stats = stats_db.get_stats(&fast).unwrap().unwrap();
+    assert_eq!(fast_stats.successes, 1);
+    assert_eq!(fast_stats.failures, 0);
+    assert_eq!(fast_stats.avg_latency_ms, 10);
+
+    let flaky_stats = stats_db.get_stats(&flaky).unwrap().unwrap();
+    assert_eq!(flaky_stats.successes, 1);
+    assert_eq!(flaky_stats.failures, 1);
+    assert_eq!(flaky_stats.sort_key(), (50, 1));
+
+    // stats survive reopening
+    let stats_db = ReplicaStatsDB::open(&path).unwrap();
+    assert_eq!(stats_db.get_all_stats().unwrap().len(), 3);
+
+    let mut replicas = vec![flaky.clone(), unknown.clone(), slow.clone(), fast.clone()];
+    stats_db.order_replicas(&mut replicas).unwrap();
+    assert_eq!(replicas, vec![fast, slow, unknown, flaky]);
+
+    // moving average
+    let mut stats = ReplicaStats::new(fast.clone());
+    stats.add_success(Duration::from_millis(100));
+    assert_eq!(stats.avg_latency_ms, 100);
+    stats.add_success(Duration::from_millis(20));
+    assert_eq!(stats.avg_latency_ms, 80);
+}
+
+#[test]
+fn test_wrbsite_fetch_fastest() {
+    let path = make_stats_db_path("test_wrbsite_fetch_fastest");
+    let pkey = StacksPrivateKey::random();
+
+    let code_bytes = Renderer::encode_bytes(b"(print \"hello world!\")").unwrap();
+    let mut chunk = StackerDBChunkData::new(1, 2, code_bytes.clone());
+    chunk.sign(&pkey).unwrap();
+    let wrbrec = WrbTxtRecordV1::new(
+        QualifiedContractIdentifier::parse("S1G2081040G2081040G2081040G208105NK8PE5.test").unwrap(),
+        chunk.get_slot_metadata(),
+    );
+
+    let mut good_stackerdb = MockStackerDBClient::new(pkey.clone(), 3);
+    good_stackerdb.put_chunk(chunk).unwrap();
+
+    // same slot and version, but different data
+    let mut bad_chunk = StackerDBChunkData::new(1, 2, vec![0x00; 16]);
+    bad_chunk.sign(&pkey).unwrap();
+    let mut bad_stackerdb = MockStackerDBClient::new(pkey.clone(), 3);
+    bad_stackerdb.put_chunk(bad_chunk).unwrap();
+
+    let fast_good: SocketAddr = "127.0.0.1:20001".parse().unwrap();
+    let slow_good: SocketAddr = "127.0.0.1:20002".parse().unwrap();
+    let fast_bad: SocketAddr = "127.0.0.1:20003".parse().unwrap();
+    let hung: SocketAddr = "127.0.0.1:20004".parse().unwrap();
+
+    let make_client = |stackerdb: &MockStackerDBClient,
+                       addr: &SocketAddr,
+                       delay_ms: u64|
+     -> (SocketAddr, Box<dyn StackerDBClient>) {
+        let (addr, client, _) = make_slow_client(stackerdb, addr, delay_ms);
+        (addr, client)
+    };
+
+    let runner = make_runner(&path, Duration::from_millis(2000));
+
+    // the fast replica with bad data is skipped, and the fastest verified chunk wins
+    let start = Instant::now();
+    let bytes = runner
+        .wrbsite_fetch_fastest(
+            &wrbrec,
+            vec![
+                make_client(&bad_stackerdb, &fast_bad, 0),
+                make_client(&good_stackerdb, &slow_good, 1000),
+                make_client(&good_stackerdb, &fast_good, 100),
+            ],
+        )
+        .unwrap();
+    assert_eq!(bytes, code_bytes);
+    assert!(start.elapsed() < Duration::from_millis(1000));
+
+    let stats_db = ReplicaStatsDB::open(&path).unwrap();
+    let fast_good_stats = stats_db.get_stats(&fast_good).unwrap().unwrap();
+    assert_eq!(fast_good_stats.successes, 1);
+    let fast_bad_stats = stats_db.get_stats(&fast_bad).unwrap().unwrap();
+    assert_eq!(fast_bad_stats.failures, 1);
+
+    // replicas which don't answer in time are abandoned
+    let runner = make_runner(&path, Duration::from_millis(200));
+    let start = Instant::now();
+    let err = runner
+        .wrbsite_fetch_fastest(
+            &wrbrec,
+            vec![
+                make_client(&bad_stackerdb, &fast_bad, 0),
+                make_client(&good_stackerdb, &hung, 5000),
+            ],
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::Storage(_)));
+    assert!(start.elapsed() < Duration::from_millis(5000));
+
+    let hung_stats = stats_db.get_stats(&hung).unwrap().unwrap();
+    assert_eq!(hung_stats.successes, 0);
+    assert_eq!(hung_stats.failures, 1);
+
+    // the known-good replica now sorts first
+    let mut replicas = vec![
+        hung.clone(),
+        fast_bad.clone(),
+        slow_good.clone(),
+        fast_good.clone(),
+    ];
+    runner.order_replicas(&mut replicas);
+    assert_eq!(replicas[0], fast_good);
+}
+
+#[test]
+fn test_replica_fetcher_races_parts() {
+    let path = make_stats_db_path("test_replica_fetcher_races_parts");
+    let pkey = StacksPrivateKey::random();
+    let contract_id =
+        QualifiedContractIdentifier::parse("S1G2081040G2081040G2081040G208105NK8PE5.test").unwrap();
+
+    let mut good_stackerdb = MockStackerDBClient::new(pkey.clone(), 16);
+    let mut parts = vec![];
+    for i in 0..8 {
+        let mut chunk = StackerDBChunkData::new(i + 1, 1, vec![i as u8; 16]);
+        chunk.sign(&pkey).unwrap();
+        parts.push(chunk.get_slot_metadata());
+        good_stackerdb.put_chunk(chunk).unwrap();
+    }
+    let empty_stackerdb = MockStackerDBClient::new(pkey.clone(), 16);
+
+    let fast: SocketAddr = "127.0.0.1:20001".parse().unwrap();
+    let slow: SocketAddr = "127.0.0.1:20002".parse().unwrap();
+    let hung: SocketAddr = "127.0.0.1:20003".parse().unwrap();
+    let bad: SocketAddr = "127.0.0.1:20004".parse().unwrap();
+
+    // the fast replica serves most of the parts, and the others' copies are not waited for
+    let runner = make_runner(&path, Duration::from_millis(500));
+    let (fast_addr, fast_client, _) = make_slow_client(&good_stackerdb, &fast, 10);
+    let (slow_addr, slow_client, slow_reads) = make_slow_client(&good_stackerdb, &slow, 150);
+    let (hung_addr, hung_client, hung_reads) = make_slow_client(&good_stackerdb, &hung, 5000);
+    let mut fetcher = ReplicaFetcher::new(
+        contract_id.clone(),
+        vec![
+            (fast_addr, fast_client),
+            (slow_addr, slow_client),
+            (hung_addr, hung_client),
+        ],
+        Duration::from_millis(500),
+    );
+
+    let start = Instant::now();
+    let chunks = fetcher.fetch(&runner, &parts).unwrap();
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert_eq!(chunks.len(), parts.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        assert_eq!(chunk, &vec![i as u8; 16]);
+    }
+
+    // the abandoned workers stop once their in-flight reads finish, and the hung replica's read
+    // gives up at the replica timeout
+    thread::sleep(Duration::from_millis(1000));
+    assert_eq!(slow_reads.load(Ordering::SeqCst), 1);
+    assert_eq!(hung_reads.load(Ordering::SeqCst), 1);
+
+    let stats_db = ReplicaStatsDB::open(&path).unwrap();
+    let fast_stats = stats_db.get_stats(&fast).unwrap().unwrap();
+    assert!(fast_stats.successes >= 6);
+
+    // a failed fetch stops its workers from starting new reads
+    let (bad_addr, bad_client, bad_reads) = make_slow_client(&empty_stackerdb, &bad, 200);
+    let mut fetcher = ReplicaFetcher::new(
+        contract_id.clone(),
+        vec![(bad_addr, bad_client)],
+        Duration::from_millis(500),
+    );
+    let err = fetcher.fetch(&runner, &parts).unwrap_err();
+    assert!(matches!(err, Error::Storage(_)));
+
+    thread::sleep(Duration::from_millis(1000));
+    assert!(bad_reads.load(Ordering::SeqCst) < parts.len());
+}
@@ -17,7 +17,11 @@
 
 use std::net::SocketAddr;
 
-use crate::runner::site::{WrbTxtRecord, WrbTxtRecordV1, ZonefileResourceRecord};
+use crate::runner::replicas::ReplicaFetcher;
+use crate::runner::site::{
+    WrbTxtRecord, WrbTxtRecordV1, WrbTxtRecordV2, WrbsiteManifest, ZonefileResourceRecord,
+    WRBSITE_MANIFEST_MAX_PARTS,
+};
 use crate::runner::tests::BNSNameRecord;
 use crate::runner::Error;
 use crate::runner::Runner;
@@ -547,3 +551,209 @@ fn test_wrbsite_load_ext() {
         .unwrap_err();
     assert!(matches!(err, Error::FailedToRun(..)));
 }
+
+#[test]
+fn test_dns_wrb_txt_v2_codec() {
+    let manifest_metadata = SlotMetadata {
+        slot_id: 1,
+        slot_version: 2,
+        data_hash: Sha512Trunc256Sum([0x33; 32]),
+        signature: MessageSignature::empty(),
+    };
+    let wrbrec = WrbTxtRecord::V2(WrbTxtRecordV2::new(
+        QualifiedContractIdentifier::parse("S1G2081040G2081040G2081040G208105NK8PE5.tokens")
+            .unwrap(),
+        manifest_metadata.clone(),
+    ));
+
+    let wrbrec_bytes = wrbrec.serialize_to_vec();
+    assert_eq!(wrbrec_bytes[0], 0x02);
+    assert_eq!(
+        WrbTxtRecord::consensus_deserialize(&mut &wrbrec_bytes[..]).unwrap(),
+        wrbrec
+    );
+    assert_eq!(wrbrec.root_slot_metadata(), &manifest_metadata);
+
+    // V1 and V2 records for the same slot differ only in their version byte
+    let wrbrec_v1 = WrbTxtRecord::new(wrbrec.contract_id().clone(), manifest_metadata.clone());
+    assert_eq!(wrbrec_v1.serialize_to_vec()[1..], wrbrec_bytes[1..]);
+
+    // fits in a TXT record
+    let rr = ZonefileResourceRecord::try_from(wrbrec.clone()).unwrap();
+    assert_eq!(WrbTxtRecord::try_from(rr).unwrap(), wrbrec);
+
+    // manifest codec
+    let manifest = WrbsiteManifest::new(vec![
+        manifest_metadata.clone(),
+        SlotMetadata::new_unsigned(2, 3, Sha512Trunc256Sum([0x44; 32])),
+    ]);
+    let manifest_bytes = manifest.serialize_to_vec();
+    assert_eq!(manifest_bytes[0..4], [0x00, 0x00, 0x00, 0x02]);
+    assert_eq!(
+        WrbsiteManifest::consensus_deserialize(&mut &manifest_bytes[..]).unwrap(),
+        manifest
+    );
+
+    // too many parts
+    let mut bad_manifest_bytes = manifest_bytes.clone();
+    bad_manifest_bytes[0..4].copy_from_slice(&(WRBSITE_MANIFEST_MAX_PARTS + 1).to_be_bytes());
+    assert!(WrbsiteManifest::consensus_deserialize(&mut &bad_manifest_bytes[..]).is_err());
+}
+
+/// Store `code_bytes` as a multi-chunk site in `mock_stackerdb`, with the manifest in slot 0 and
+/// `num_parts` parts in slots 1, 2, .... Returns the signed manifest slot metadata and the parts'
+/// slot metadata.
+fn store_multichunk_site(
+    pkey: &StacksPrivateKey,
+    mock_stackerdb: &mut MockStackerDBClient,
+    code_bytes: &[u8],
+    num_parts: usize,
+) -> (SlotMetadata, Vec<SlotMetadata>) {
+    let part_len = (code_bytes.len() + num_parts - 1) / num_parts;
+    let mut parts_metadata = vec![];
+    for (i, part) in code_bytes.chunks(part_len).enumerate() {
+        let mut chunk = StackerDBChunkData::new((i + 1) as u32, 1, part.to_vec());
+        chunk.sign(pkey).unwrap();
+        parts_metadata.push(chunk.get_slot_metadata());
+        mock_stackerdb.put_chunk(chunk).unwrap();
+    }
+
+    let manifest = WrbsiteManifest::new(parts_metadata.clone());
+    let mut chunk = StackerDBChunkData::new(0, 1, manifest.serialize_to_vec());
+    chunk.sign(pkey).unwrap();
+    let manifest_metadata = chunk.get_slot_metadata();
+    mock_stackerdb.put_chunk(chunk).unwrap();
+
+    (manifest_metadata, parts_metadata)
+}
+
+#[test]
+fn test_wrbsite_load_multichunk() {
+    let pkey = StacksPrivateKey::random();
+
+    let code_body = b"(print \"hello world!\") (print \"this site spans several chunks\")";
+    let code_bytes = Renderer::encode_bytes(code_body).unwrap();
+
+    let mut mock_stackerdb = MockStackerDBClient::new(pkey.clone(), 16);
+    let (manifest_metadata, parts_metadata) =
+        store_multichunk_site(&pkey, &mut mock_stackerdb, &code_bytes, 3);
+    assert_eq!(parts_metadata.len(), 3);
+
+    let contract_id =
+        QualifiedContractIdentifier::parse("S1G2081040G2081040G2081040G208105NK8PE5.test").unwrap();
+
+    let mut runner = Runner::new(
+        QualifiedContractIdentifier::parse("SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.BNS-V2")
+            .unwrap(),
+        QualifiedContractIdentifier::parse(
+            "SP2QEZ06AGJ3RKJPBV14SY1V5BBFNAW33D96YPGZF.zonefile-resolver",
+        )
+        .unwrap(),
+        "127.0.0.1".to_string(),
+        12345,
+    );
+
+    let wrbrec: WrbTxtRecord = WrbTxtRecordV2::new(contract_id.clone(), manifest_metadata).into();
+    let rr_text = ZonefileResourceRecord::try_from(wrbrec)
+        .unwrap()
+        .to_string();
+    let zonefile = format!("$ORIGIN test.test\n\n{}\n\n", &rr_text)
+        .as_bytes()
+        .to_vec();
+
+    // happy path
+    let (bytes, ver) = runner
+        .wrbsite_load_from_zonefile(
+            zonefile.clone(),
+            |_, _| Ok(Box::new(mock_stackerdb.clone())),
+            |_, _| Ok(Box::new(mock_stackerdb.clone())),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(bytes, code_bytes);
+    assert_eq!(ver, 1);
+    assert_eq!(Renderer::decode_bytes(&bytes).unwrap(), code_body.to_vec());
+
+    // replicas race for the parts, and parts missing from some replicas come from the others
+    let mut partial_stackerdb = MockStackerDBClient::new(pkey.clone(), 16);
+    store_multichunk_site(&pkey, &mut partial_stackerdb, &code_bytes, 3);
+    let empty_stackerdb = MockStackerDBClient::new(pkey.clone(), 16);
+    let replica_clients: Vec<(SocketAddr, Box<dyn StackerDBClient>)> = vec![
+        (
+            "127.0.0.1:20001".parse().unwrap(),
+            Box::new(empty_stackerdb.clone()),
+        ),
+        (
+            "127.0.0.1:20002".parse().unwrap(),
+            Box::new(partial_stackerdb.clone()),
+        ),
+        (
+            "127.0.0.1:20003".parse().unwrap(),
+            Box::new(empty_stackerdb.clone()),
+        ),
+    ];
+    let mut fetcher =
+        ReplicaFetcher::new(contract_id.clone(), replica_clients, runner.replica_timeout);
+    let bytes = runner
+        .wrbsite_fetch_parts(&mut fetcher, &parts_metadata)
+        .unwrap();
+    assert_eq!(bytes, code_bytes);
+
+    // sad path -- no replica has the parts
+    let replica_clients: Vec<(SocketAddr, Box<dyn StackerDBClient>)> = vec![
+        (
+            "127.0.0.1:20001".parse().unwrap(),
+            Box::new(empty_stackerdb.clone()),
+        ),
+        (
+            "127.0.0.1:20003".parse().unwrap(),
+            Box::new(empty_stackerdb.clone()),
+        ),
+    ];
+    let mut fetcher =
+        ReplicaFetcher::new(contract_id.clone(), replica_clients, runner.replica_timeout);
+    assert!(matches!(
+        runner.wrbsite_fetch_parts(&mut fetcher, &parts_metadata),
+        Err(Error::Storage(_))
+    ));
+
+    // sad path -- a part's data does not match the manifest
+    let mut bad_chunk = StackerDBChunkData::new(2, 1, vec![0x00; 16]);
+    bad_chunk.sign(&pkey).unwrap();
+    mock_stackerdb.put_chunk(bad_chunk).unwrap();
+
+    let err = runner
+        .wrbsite_load_from_zonefile(
+            zonefile.clone(),
+            |_, _| Ok(Box::new(mock_stackerdb.clone())),
+            |_, _| Ok(Box::new(mock_stackerdb.clone())),
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::FailedToRun(..)));
+
+    // sad path -- a part was signed by someone other than the slot's signer
+    let other_pkey = StacksPrivateKey::random();
+    let mut bad_stackerdb = MockStackerDBClient::new(pkey.clone(), 16);
+    let (_, mut bad_parts_metadata) =
+        store_multichunk_site(&pkey, &mut bad_stackerdb, &code_bytes, 3);
+    bad_parts_metadata[1].sign(&other_pkey).unwrap();
+    let bad_manifest = WrbsiteManifest::new(bad_parts_metadata);
+    let mut chunk = StackerDBChunkData::new(0, 2, bad_manifest.serialize_to_vec());
+    chunk.sign(&pkey).unwrap();
+    let bad_manifest_metadata = chunk.get_slot_metadata();
+    bad_stackerdb.put_chunk(chunk).unwrap();
+
+    let bad_wrbrec = WrbTxtRecordV2::new(contract_id.clone(), bad_manifest_metadata);
+    let replica_clients: Vec<(SocketAddr, Box<dyn StackerDBClient>)> = vec![(
+        "127.0.0.1:20001".parse().unwrap(),
+        Box::new(bad_stackerdb.clone()),
+    )];
+    assert!(matches!(
+        runner.wrbsite_load_from_manifest(
+            &bad_wrbrec,
+            &bad_stackerdb.get_signers().unwrap(),
+            replica_clients
+        ),
+        Err(Error::Storage(_))
+    ));
+}
@@ -0,0 +1,233 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022-2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Mutex;
+use std::thread;
+
+use clarity::vm::Value;
+
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::util::hash::to_hex;
+use stacks_common::util::hash::Hash160;
+use stacks_common::util::secp256k1::Secp256k1PrivateKey;
+
+use crate::runner::http::HttpConnectionPool;
+use crate::runner::tx::{RPCTransactionStatusResponse, TxRejectionReason, TxStatus, TxTracker};
+use crate::runner::Error;
+use crate::runner::Runner;
+use crate::tx::{
+    make_contract_call, FungibleConditionCode, PostConditionPrincipal, StacksTransaction,
+    TransactionPostCondition, TransactionPostConditionMode,
+};
+
+fn make_tx(nonce: u64, post_conditions: Vec<TransactionPostCondition>) -> StacksTransaction {
+    let privk = Secp256k1PrivateKey::from_hex(
+        "e89bb394ecd5161007a84b34ac98d4f7239016c91d3e0c7c3b97aa499693288301",
+    )
+    .unwrap();
+    make_contract_call(
+        false,
+        &privk,
+        nonce,
+        1000,
+        &StacksAddress::new(26, Hash160([0x01; 20])).unwrap(),
+        "hello",
+        "world",
+        &[],
+        TransactionPostConditionMode::Deny,
+        post_conditions,
+    )
+    .unwrap()
+}
+
+fn make_status(
+    tx: &StacksTransaction,
+    result: Value,
+    post_condition_aborted: Option<bool>,
+) -> RPCTransactionStatusResponse {
+    RPCTransactionStatusResponse {
+        index_block_hash: "11".repeat(32),
+        tx: to_hex(&tx.serialize_to_vec()),
+        result: Some(format!("0x{}", result.serialize_to_hex().unwrap())),
+        block_height: Some(123),
+        post_condition_aborted,
+    }
+}
+
+/// Answer one request on each connection with the next canned (status, body) response
+fn spawn_canned_server(responses: Vec<(u32, String)>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for (code, body) in responses.into_iter() {
+            let Ok((mut sock, _)) = listener.accept() else {
+                return;
+            };
+            let mut buf = vec![];
+            let mut byte = [0u8; 1];
+            while !buf.ends_with(b"\r\n\r\n") {
+                match sock.read(&mut byte) {
+                    Ok(1) => buf.push(byte[0]),
+                    _ => break,
+                }
+            }
+            // drain the request body, if there is one
+            let headers = String::from_utf8_lossy(&buf).to_lowercase();
+            if let Some(len_hdr) = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length: "))
+            {
+                let mut body = vec![0u8; len_hdr.trim().parse::<usize>().unwrap()];
+                sock.read_exact(&mut body).unwrap();
+            }
+            let _ = sock.write_all(
+                format!(
+                    "HTTP/1.1 {} Whatever\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    code,
+                    body.len(),
+                    &body
+                )
+                .as_bytes(),
+            );
+        }
+    });
+    addr
+}
+
+#[test]
+fn test_tx_status_decode() {
+    let tx = make_tx(1, vec![]);
+    let tx_with_pcs = make_tx(
+        1,
+        vec![TransactionPostCondition::STX(
+            PostConditionPrincipal::Origin,
+            FungibleConditionCode::SentEq,
+            0,
+        )],
+    );
+
+    let TxStatus::Mined(receipt) = make_status(&tx, Value::okay_true(), None)
+        .into_status()
+        .unwrap()
+    else {
+        panic!("not mined");
+    };
+    assert_eq!(receipt.block_height, Some(123));
+    assert_eq!(receipt.result, Some(Value::okay_true()));
+
+    // if the node doesn't say why an (err ..) happened, then it's still rolled back
+    for (tx, result) in [
+        (&tx, Value::err_uint(1)),
+        (&tx, Value::err_none()),
+        (&tx_with_pcs, Value::err_none()),
+    ] {
+        let TxStatus::AbortedByResponse(receipt) =
+            make_status(tx, result.clone(), None).into_status().unwrap()
+        else {
+            panic!("not aborted");
+        };
+        assert_eq!(receipt.result, Some(result));
+    }
+
+    // otherwise, the node's flag says whether a post-condition did it
+    assert!(matches!(
+        make_status(&tx_with_pcs, Value::err_none(), Some(false))
+            .into_status()
+            .unwrap(),
+        TxStatus::AbortedByResponse(_)
+    ));
+    assert!(matches!(
+        make_status(&tx, Value::okay_true(), Some(false))
+            .into_status()
+            .unwrap(),
+        TxStatus::Mined(_)
+    ));
+    assert!(matches!(
+        make_status(&tx, Value::err_none(), Some(true))
+            .into_status()
+            .unwrap(),
+        TxStatus::AbortedByPostCondition(_)
+    ));
+
+    let tracker = TxTracker::new(&tx);
+    assert_eq!(tracker.txid, tx.txid());
+    assert_eq!(tracker.origin_nonce, 1);
+    assert!(tracker.sponsor.is_none());
+}
+
+#[test]
+fn test_tx_status_rpc() {
+    let tx = make_tx(1, vec![]);
+    let status = make_status(&tx, Value::okay_true(), None);
+
+    let addr = spawn_canned_server(vec![
+        (404, "".to_string()),
+        (200, serde_json::to_string(&status).unwrap()),
+        (501, "Transaction indexing is not enabled".to_string()),
+    ]);
+    let pool = Mutex::new(HttpConnectionPool::default());
+
+    assert_eq!(
+        Runner::run_get_tx_status(&pool, &addr, &tx.txid()).unwrap(),
+        None
+    );
+    assert_eq!(
+        Runner::run_get_tx_status(&pool, &addr, &tx.txid()).unwrap(),
+        Some(status)
+    );
+    assert!(matches!(
+        Runner::run_get_tx_status(&pool, &addr, &tx.txid()),
+        Err(Error::HttpError(501, ..))
+    ));
+}
+
+#[test]
+fn test_post_tx_rejected() {
+    let tx = make_tx(1, vec![]);
+    let addr = spawn_canned_server(vec![
+        (
+            400,
+            r#"{"error":"transaction rejected","reason":"ConflictingNonceInMempool","reason_data":null,"txid":"00"}"#
+                .to_string(),
+        ),
+        (400, "not json".to_string()),
+        (200, format!("\"{}\"", &tx.txid())),
+    ]);
+    let pool = Mutex::new(HttpConnectionPool::default());
+
+    match Runner::run_post_tx(&pool, &addr, &tx) {
+        Err(Error::TxRejected(rejection)) => {
+            assert_eq!(
+                rejection.reason_code(),
+                TxRejectionReason::ConflictingNonceInMempool
+            );
+            assert_eq!(rejection.to_string(), "ConflictingNonceInMempool");
+        }
+        res => panic!("unexpected {:?}", &res),
+    }
+    match Runner::run_post_tx(&pool, &addr, &tx) {
+        Err(Error::TxRejected(rejection)) => {
+            assert_eq!(rejection.reason_code(), TxRejectionReason::Other(None));
+            assert_eq!(rejection.to_string(), "not json");
+        }
+        res => panic!("unexpected {:?}", &res),
+    }
+    assert_eq!(Runner::run_post_tx(&pool, &addr, &tx).unwrap(), tx.txid());
+}
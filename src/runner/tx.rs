@@ -16,11 +16,16 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::convert::TryFrom;
+use std::fmt;
 use std::net::SocketAddr;
 use std::net::TcpStream;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crate::core::Config;
-use crate::runner::run_http_request;
+use crate::runner::http::is_retryable_error;
+use crate::runner::http::{run_rpc_request, run_rpc_request_full, HttpConnectionPool};
 use crate::runner::Error;
 use crate::runner::Runner;
 
@@ -32,6 +37,7 @@ use clarity::vm::types::PrincipalData;
 use clarity::vm::types::QualifiedContractIdentifier;
 use clarity::vm::types::ResponseData;
 use clarity::vm::types::SequenceData;
+use clarity::vm::types::StacksAddressExtensions;
 use clarity::vm::types::Value;
 
 use clarity::vm::costs::ExecutionCost;
@@ -42,6 +48,11 @@ use stacks_common::util::hash::{to_hex, Hash160};
 use serde::{Deserialize, Serialize};
 use serde_json;
 
+/// Default time to wait for a broadcast transaction to be mined
+pub const DEFAULT_TX_CONFIRM_TIMEOUT_SECS: u64 = 600;
+/// How often to ask the node about a transaction we're waiting on
+pub const TX_STATUS_POLL_INTERVAL_MS: u64 = 1_000;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct AccountEntryResponse {
     pub balance: String,
@@ -84,6 +95,217 @@ pub struct RPCFeeEstimateResponse {
     pub cost_scalar_change_by_byte: f64,
 }
 
+/// Body of a 400 response to a transaction broadcast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RPCTxRejection {
+    pub error: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub reason_data: Option<serde_json::Value>,
+    #[serde(default)]
+    pub txid: Option<String>,
+}
+
+impl RPCTxRejection {
+    /// A rejection whose body isn't the node's JSON rejection object
+    pub fn from_body(body: &[u8]) -> Self {
+        Self {
+            error: String::from_utf8_lossy(body).to_string(),
+            reason: None,
+            reason_data: None,
+            txid: None,
+        }
+    }
+
+    pub fn reason_code(&self) -> TxRejectionReason {
+        match self.reason.as_deref() {
+            Some("ConflictingNonceInMempool") => TxRejectionReason::ConflictingNonceInMempool,
+            Some("BadNonce") => TxRejectionReason::BadNonce,
+            Some("FeeTooLow") => TxRejectionReason::FeeTooLow,
+            Some("NotEnoughFunds") => TxRejectionReason::NotEnoughFunds,
+            reason => TxRejectionReason::Other(reason.map(|r| r.to_string())),
+        }
+    }
+}
+
+/// Why the node refused a transaction, as given in the `reason` field of its rejection
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxRejectionReason {
+    /// another transaction with the same nonce is already in the mempool, and this one doesn't
+    /// pay enough more to replace it
+    ConflictingNonceInMempool,
+    /// the nonce has already been used, or is too far ahead of the account's nonce
+    BadNonce,
+    FeeTooLow,
+    NotEnoughFunds,
+    /// any other reason, if the node gave one
+    Other(Option<String>),
+}
+
+impl fmt::Display for RPCTxRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.reason, &self.reason_data) {
+            (Some(reason), Some(reason_data)) => write!(f, "{} ({})", reason, reason_data),
+            (Some(reason), None) => write!(f, "{}", reason),
+            (None, _) => write!(f, "{}", &self.error),
+        }
+    }
+}
+
+/// Response to `GET /v3/transactions/{txid}`, which the node only serves for transactions that
+/// have been mined
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RPCTransactionStatusResponse {
+    pub index_block_hash: String,
+    /// hex-encoded transaction
+    pub tx: String,
+    /// hex-encoded Clarity value the transaction evaluated to
+    #[serde(default)]
+    pub result: Option<String>,
+    #[serde(default)]
+    pub block_height: Option<u64>,
+    #[serde(default)]
+    pub post_condition_aborted: Option<bool>,
+}
+
+/// Where a mined transaction landed, and what it evaluated to
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxReceipt {
+    pub index_block_hash: String,
+    pub block_height: Option<u64>,
+    pub result: Option<Value>,
+}
+
+impl fmt::Display for TxReceipt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "block {}", &self.index_block_hash)?;
+        if let Some(height) = self.block_height {
+            write!(f, " (height {})", height)?;
+        }
+        if let Some(result) = self.result.as_ref() {
+            write!(f, ", result {}", result)?;
+        }
+        Ok(())
+    }
+}
+
+/// What has become of a transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxStatus {
+    /// Not mined yet, and its nonce is still unused
+    Pending,
+    /// The node has no record of this transaction in any block.  We can't tell whether or not
+    /// it is pending without knowing its nonce.
+    NotFound,
+    /// Mined, and its effects took hold.  Its result is `(ok ..)`, or not a response at all.
+    Mined(TxReceipt),
+    /// Mined, but evaluated to `(err ..)`, so its effects were rolled back.  Also used if the
+    /// node did not say whether or not a post-condition aborted it.
+    AbortedByResponse(TxReceipt),
+    /// Mined, but a post-condition failed, so its effects were rolled back
+    AbortedByPostCondition(TxReceipt),
+    /// The node has no record of this transaction, but a different transaction used its nonce
+    Replaced,
+    /// The node does not index transactions, but the transaction's nonce has been used.  It was
+    /// most likely mined, but it may have been replaced.
+    NonceConsumed,
+}
+
+impl TxStatus {
+    /// Is this a final status?
+    pub fn is_final(&self) -> bool {
+        match self {
+            TxStatus::Pending | TxStatus::NotFound => false,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TxStatus::Pending => write!(f, "pending"),
+            TxStatus::NotFound => write!(f, "not found in any block"),
+            TxStatus::Mined(receipt) => write!(f, "mined in {}", receipt),
+            TxStatus::AbortedByResponse(receipt) => {
+                write!(f, "aborted by its (err ..) response in {}", receipt)
+            }
+            TxStatus::AbortedByPostCondition(receipt) => {
+                write!(f, "aborted by a failed post-condition in {}", receipt)
+            }
+            TxStatus::Replaced => write!(f, "replaced by another transaction with its nonce"),
+            TxStatus::NonceConsumed => write!(
+                f,
+                "nonce consumed (the node does not index transactions, so it is not known whether this transaction or another one used it)"
+            ),
+        }
+    }
+}
+
+/// What we need to know about a broadcast transaction in order to follow it to a block
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxTracker {
+    pub txid: Txid,
+    pub origin: PrincipalData,
+    pub origin_nonce: u64,
+    pub sponsor: Option<(PrincipalData, u64)>,
+}
+
+impl TxTracker {
+    pub fn new(tx: &StacksTransaction) -> Self {
+        Self {
+            txid: tx.txid(),
+            origin: tx.origin_address().to_account_principal(),
+            origin_nonce: tx.get_origin_nonce(),
+            sponsor: tx.sponsor_address().map(|sponsor_addr| {
+                (
+                    sponsor_addr.to_account_principal(),
+                    tx.get_sponsor_nonce().unwrap_or(0),
+                )
+            }),
+        }
+    }
+}
+
+impl RPCTransactionStatusResponse {
+    /// Work out whether the transaction took effect.
+    /// Any `(err ..)` result means its effects were rolled back.  If the node does not report
+    /// `post_condition_aborted` (e.g. `/v3/transactions`), then it is blamed on the response.
+    pub fn into_status(self) -> Result<TxStatus, Error> {
+        let result = self
+            .result
+            .as_ref()
+            .map(|result| {
+                let result = result.strip_prefix("0x").unwrap_or(result);
+                Value::try_deserialize_hex_untyped(result).map_err(|_| {
+                    Error::Deserialize(format!(
+                        "Failed to decode hex string into clarity value: {}",
+                        result
+                    ))
+                })
+            })
+            .transpose()?;
+
+        let aborted = match result.as_ref() {
+            Some(Value::Response(ResponseData { committed, .. })) => !*committed,
+            _ => false,
+        };
+
+        let receipt = TxReceipt {
+            index_block_hash: self.index_block_hash,
+            block_height: self.block_height,
+            result,
+        };
+
+        match self.post_condition_aborted {
+            Some(true) => Ok(TxStatus::AbortedByPostCondition(receipt)),
+            _ if aborted => Ok(TxStatus::AbortedByResponse(receipt)),
+            _ => Ok(TxStatus::Mined(receipt)),
+        }
+    }
+}
+
 impl TryFrom<AccountEntryResponse> for StacksAccount {
     type Error = Error;
     fn try_from(a: AccountEntryResponse) -> Result<Self, Error> {
@@ -103,17 +325,18 @@ impl TryFrom<AccountEntryResponse> for StacksAccount {
 
 impl Runner {
     pub fn run_get_account(
+        pool: &Mutex<HttpConnectionPool>,
         node_addr: &SocketAddr,
         account: &PrincipalData,
     ) -> Result<StacksAccount, Error> {
-        let mut sock = TcpStream::connect(node_addr)?;
-        let bytes = run_http_request(
-            &mut sock,
+        let bytes = run_rpc_request(
+            pool,
             node_addr,
             "GET",
             &format!("/v2/accounts/{}?proof=0", &account.to_string()),
             None,
             &[],
+            true,
         )?;
 
         let response: AccountEntryResponse = serde_json::from_slice(&bytes)
@@ -123,13 +346,14 @@ impl Runner {
     }
 
     pub fn get_account(&mut self, account: &PrincipalData) -> Result<StacksAccount, Error> {
-        let Some(node_addr) = self.resolve_node()? else {
-            return Err(Error::NotConnected);
-        };
-        Self::run_get_account(&node_addr, account)
+        let pool = self.rpc_pool.clone();
+        self.with_node_failover("get account", |node_addr| {
+            Self::run_get_account(&pool, node_addr, account)
+        })
     }
 
     pub fn run_get_tx_fee(
+        pool: &Mutex<HttpConnectionPool>,
         node_addr: &SocketAddr,
         tx: &StacksTransaction,
     ) -> Result<RPCFeeEstimateResponse, Error> {
@@ -145,14 +369,14 @@ impl Runner {
         let request_body_json = serde_json::to_string(&request_body)
             .map_err(|_| Error::Serialize("Failed to encode request to JSON".into()))?;
 
-        let mut sock = TcpStream::connect(node_addr)?;
-        let bytes = match run_http_request(
-            &mut sock,
+        let bytes = match run_rpc_request(
+            pool,
             node_addr,
             "POST",
             "/v2/fees/transaction",
             Some("application/json"),
             request_body_json.as_bytes(),
+            true,
         ) {
             Ok(bytes) => bytes,
             Err(Error::HttpError(code, headers, offset)) => {
@@ -176,25 +400,38 @@ impl Runner {
     }
 
     pub fn get_tx_fee(&mut self, tx: &StacksTransaction) -> Result<RPCFeeEstimateResponse, Error> {
-        let Some(node_addr) = self.resolve_node()? else {
-            return Err(Error::NotConnected);
-        };
-        Self::run_get_tx_fee(&node_addr, tx)
+        let pool = self.rpc_pool.clone();
+        self.with_node_failover("fee estimate", |node_addr| {
+            Self::run_get_tx_fee(&pool, node_addr, tx)
+        })
     }
 
-    pub fn run_post_tx(node_addr: &SocketAddr, tx: &StacksTransaction) -> Result<Txid, Error> {
+    /// Broadcast a transaction.
+    /// If the node won't take it, then the reason is reported as an Error::TxRejected.
+    pub fn run_post_tx(
+        pool: &Mutex<HttpConnectionPool>,
+        node_addr: &SocketAddr,
+        tx: &StacksTransaction,
+    ) -> Result<Txid, Error> {
         let tx_bytes = tx.serialize_to_vec();
 
-        let mut sock = TcpStream::connect(node_addr)?;
-        let bytes = run_http_request(
-            &mut sock,
+        let response = run_rpc_request_full(
+            pool,
             node_addr,
             "POST",
             "/v2/transactions",
             Some("application/octet-stream"),
             &tx_bytes,
+            false,
         )?;
 
+        if response.code == 400 {
+            let rejection = serde_json::from_slice::<RPCTxRejection>(&response.body)
+                .unwrap_or_else(|_| RPCTxRejection::from_body(&response.body));
+            return Err(Error::TxRejected(rejection));
+        }
+
+        let bytes = response.into_body()?;
         let response: Txid = serde_json::from_slice(&bytes)
             .map_err(|_| Error::Deserialize("Failed to decode txid".into()))?;
 
@@ -202,9 +439,119 @@ impl Runner {
     }
 
     pub fn post_tx(&mut self, tx: &StacksTransaction) -> Result<Txid, Error> {
-        let Some(node_addr) = self.resolve_node()? else {
-            return Err(Error::NotConnected);
-        };
-        Self::run_post_tx(&node_addr, tx)
+        let pool = self.rpc_pool.clone();
+        self.with_node_failover("broadcast", |node_addr| {
+            Self::run_post_tx(&pool, node_addr, tx)
+        })
+    }
+
+    /// Look up a mined transaction.
+    /// Returns Ok(None) if the node has no record of it in any block.
+    /// Returns Error::HttpError(501, ..) if the node does not index transactions.
+    pub fn run_get_tx_status(
+        pool: &Mutex<HttpConnectionPool>,
+        node_addr: &SocketAddr,
+        txid: &Txid,
+    ) -> Result<Option<RPCTransactionStatusResponse>, Error> {
+        let response = run_rpc_request_full(
+            pool,
+            node_addr,
+            "GET",
+            &format!("/v3/transactions/{}", txid),
+            None,
+            &[],
+            true,
+        )?;
+
+        if response.code == 404 {
+            return Ok(None);
+        }
+
+        let bytes = response.into_body()?;
+        let response: RPCTransactionStatusResponse = serde_json::from_slice(&bytes)
+            .map_err(|_| Error::Deserialize("Failed to decode transaction status".into()))?;
+
+        Ok(Some(response))
+    }
+
+    /// Find out what became of a transaction, knowing only its txid.
+    /// Returns TxStatus::NotFound if it has not been mined.
+    pub fn get_tx_status(&mut self, txid: &Txid) -> Result<TxStatus, Error> {
+        let pool = self.rpc_pool.clone();
+        let response = self.with_node_failover("transaction status", |node_addr| {
+            Self::run_get_tx_status(&pool, node_addr, txid)
+        })?;
+        match response {
+            Some(response) => response.into_status(),
+            None => Ok(TxStatus::NotFound),
+        }
+    }
+
+    /// Find out what became of a transaction we broadcast.
+    /// The nonces are read before the transaction is looked up, so that a transaction which is
+    /// mined in between is not mistaken for a replaced one.
+    pub fn check_tx(&mut self, tracker: &TxTracker) -> Result<TxStatus, Error> {
+        let mut nonce_consumed = self.get_account(&tracker.origin)?.nonce > tracker.origin_nonce;
+        if let Some((sponsor, sponsor_nonce)) = tracker.sponsor.as_ref() {
+            nonce_consumed = nonce_consumed || self.get_account(sponsor)?.nonce > *sponsor_nonce;
+        }
+
+        match self.get_tx_status(&tracker.txid) {
+            Ok(TxStatus::NotFound) => {
+                if nonce_consumed {
+                    Ok(TxStatus::Replaced)
+                } else {
+                    Ok(TxStatus::Pending)
+                }
+            }
+            Err(Error::HttpError(501, ..)) => {
+                wrb_debug!("Node does not index transactions; falling back to nonce checks");
+                if nonce_consumed {
+                    Ok(TxStatus::NonceConsumed)
+                } else {
+                    Ok(TxStatus::Pending)
+                }
+            }
+            res => res,
+        }
+    }
+
+    /// Wait for a transaction we broadcast to reach a final status, calling `on_poll` after each
+    /// check.  Network errors while polling are logged and the check is retried.
+    /// Returns Error::Timeout if it is still pending after `timeout`.
+    pub fn wait_for_tx<F>(
+        &mut self,
+        tracker: &TxTracker,
+        timeout: Duration,
+        mut on_poll: F,
+    ) -> Result<TxStatus, Error>
+    where
+        F: FnMut(&TxStatus),
+    {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.check_tx(tracker) {
+                Ok(status) => {
+                    on_poll(&status);
+                    if status.is_final() {
+                        return Ok(status);
+                    }
+                }
+                Err(e) => {
+                    if !is_retryable_error(&e) {
+                        return Err(e);
+                    }
+                    wrb_warn!("Failed to check on transaction {}: {:?}", &tracker.txid, &e);
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout(format!(
+                    "transaction {} was not mined within {} seconds",
+                    &tracker.txid,
+                    timeout.as_secs()
+                )));
+            }
+            thread::sleep(Duration::from_millis(TX_STATUS_POLL_INTERVAL_MS));
+        }
     }
 }
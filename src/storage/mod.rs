@@ -19,6 +19,7 @@ use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::io::{Read, Write};
 use std::net::SocketAddr;
+use std::time::Duration;
 
 use rusqlite::Error as sqlite_error;
 
@@ -238,6 +239,10 @@ pub trait StackerDBClient: Send {
 
     /// Get the list of signers for the replica.
     fn get_signers(&mut self) -> Result<Vec<StacksAddress>, RuntimeError>;
+
+    /// Make each later network operation give up within `timeout`, without retrying.
+    /// Clients which don't talk to the network ignore this.
+    fn set_timeout(&mut self, _timeout: Duration) {}
 }
 
 /// Instantiated handle to a Wrbpod
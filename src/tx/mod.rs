@@ -52,9 +52,13 @@ use crate::tx::string::StacksString;
 use stacks_common::types::chainstate::StacksPrivateKey;
 
 pub mod auth;
+pub mod multisig;
 pub mod string;
 pub mod transaction;
 
+#[cfg(test)]
+pub mod tests;
+
 #[derive(Debug)]
 pub enum Error {
     SigningError(String),
@@ -686,6 +690,68 @@ pub fn serialize_sign_tx(
     tx_signer.get_tx().ok_or(Error::Incomplete)
 }
 
+/// Make a copy of a standard single-signature transaction with a different fee, signed again by
+/// `sender`.  Used to replace a stuck transaction with one that pays more for the same nonce.
+pub fn replace_tx_fee(
+    tx: &StacksTransaction,
+    tx_fee: u64,
+    sender: &StacksPrivateKey,
+) -> Result<StacksTransaction, Error> {
+    if tx.auth.is_sponsored() {
+        return Err(Error::IncompatibleSpendingConditionError);
+    }
+
+    let mut unsigned_tx = tx.clone();
+    unsigned_tx.auth = unsigned_tx.auth.into_initial_sighash_auth();
+    unsigned_tx.set_origin_nonce(tx.get_origin_nonce());
+    unsigned_tx.set_tx_fee(tx_fee);
+
+    let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
+    tx_signer.sign_origin(sender)?;
+    tx_signer.get_tx().ok_or(Error::Incomplete)
+}
+
+/// Re-sign a transaction so that another account pays its fee.
+/// Only the origin signs it; the sponsor fills in its own spending condition with `sponsor_tx()`.
+pub fn make_sponsored_tx(
+    tx: &StacksTransaction,
+    sender: &StacksPrivateKey,
+) -> Result<StacksTransaction, Error> {
+    if tx.auth.is_sponsored() {
+        return Err(Error::IncompatibleSpendingConditionError);
+    }
+
+    let mut origin = tx.auth.origin().clone();
+    origin.clear();
+    origin.set_nonce(tx.get_origin_nonce());
+
+    let mut unsigned_tx = tx.clone();
+    unsigned_tx.auth =
+        TransactionAuth::Sponsored(origin, TransactionSpendingCondition::new_initial_sighash());
+
+    let mut tx_signer = StacksTransactionSigner::new(&unsigned_tx);
+    tx_signer.sign_origin(sender)?;
+    Ok(tx_signer.get_tx_incomplete())
+}
+
+/// Pay for an origin-signed sponsored transaction, and sign it as the sponsor
+pub fn sponsor_tx(
+    tx: &StacksTransaction,
+    sponsor: &StacksPrivateKey,
+    sponsor_nonce: u64,
+    tx_fee: u64,
+) -> Result<StacksTransaction, Error> {
+    let mut sponsor_spending_condition =
+        TransactionSpendingCondition::new_singlesig_p2pkh(StacksPublicKey::from_private(sponsor))
+            .ok_or(Error::IncompatibleSpendingConditionError)?;
+    sponsor_spending_condition.set_nonce(sponsor_nonce);
+    sponsor_spending_condition.set_tx_fee(tx_fee);
+
+    let mut tx_signer = StacksTransactionSigner::new_sponsor(tx, sponsor_spending_condition)?;
+    tx_signer.sign_sponsor(sponsor)?;
+    tx_signer.get_tx().ok_or(Error::Incomplete)
+}
+
 pub fn make_contract_call(
     mainnet: bool,
     sender: &StacksPrivateKey,
@@ -0,0 +1,470 @@
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::chainstate::StacksPrivateKey;
+use stacks_common::types::chainstate::StacksPublicKey;
+use stacks_common::util::hash::{hex_bytes, to_hex};
+use stacks_common::util::secp256k1::MessageSignature;
+
+use crate::tx::{
+    Error, StacksTransaction, TransactionAuth, TransactionAuthFlags, TransactionPublicKeyEncoding,
+    TransactionSpendingCondition, Txid,
+};
+
+/// How a multisig origin's public keys are hashed to its address, and whether or not its
+/// cosigners must sign in order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MultisigMode {
+    /// cosigners sign one after the other, in public key order
+    P2SH,
+    /// like P2SH, but with segwit-compatible hashing.  Compressed keys only.
+    P2WSH,
+    /// cosigners sign in any order.  Requires epoch 3.0.
+    OrderIndependentP2SH,
+    /// like OrderIndependentP2SH, but with segwit-compatible hashing.  Compressed keys only.
+    OrderIndependentP2WSH,
+}
+
+impl MultisigMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MultisigMode::P2SH => "p2sh",
+            MultisigMode::P2WSH => "p2wsh",
+            MultisigMode::OrderIndependentP2SH => "p2sh-order-independent",
+            MultisigMode::OrderIndependentP2WSH => "p2wsh-order-independent",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "p2sh" => Some(MultisigMode::P2SH),
+            "p2wsh" => Some(MultisigMode::P2WSH),
+            "p2sh-order-independent" => Some(MultisigMode::OrderIndependentP2SH),
+            "p2wsh-order-independent" => Some(MultisigMode::OrderIndependentP2WSH),
+            _ => None,
+        }
+    }
+
+    pub fn is_order_independent(&self) -> bool {
+        match self {
+            MultisigMode::OrderIndependentP2SH | MultisigMode::OrderIndependentP2WSH => true,
+            MultisigMode::P2SH | MultisigMode::P2WSH => false,
+        }
+    }
+}
+
+/// The cosigners of a multisig account
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultisigOrigin {
+    pub mode: MultisigMode,
+    pub signatures_required: u16,
+    /// cosigners' public keys, in the order they were given when the account was made
+    pub public_keys: Vec<StacksPublicKey>,
+}
+
+impl MultisigOrigin {
+    pub fn new(
+        mode: MultisigMode,
+        signatures_required: u16,
+        public_keys: Vec<StacksPublicKey>,
+    ) -> Result<Self, String> {
+        if signatures_required == 0 {
+            return Err("At least one signature must be required".into());
+        }
+        if usize::from(signatures_required) > public_keys.len() {
+            return Err(format!(
+                "{} signatures required, but only {} public keys given",
+                signatures_required,
+                public_keys.len()
+            ));
+        }
+        let origin = Self {
+            mode,
+            signatures_required,
+            public_keys,
+        };
+        if origin.spending_condition().is_none() {
+            return Err(format!(
+                "Public keys cannot be used in a {} account",
+                mode.as_str()
+            ));
+        }
+        Ok(origin)
+    }
+
+    /// Parse `M:PUBKEY,PUBKEY,...`, where M is the number of signatures required
+    pub fn parse(mode: MultisigMode, spec: &str) -> Result<Self, String> {
+        let Some((required_str, keys_str)) = spec.split_once(':') else {
+            return Err(format!(
+                "Invalid multisig '{}': expected M:PUBKEY,PUBKEY,...",
+                spec
+            ));
+        };
+        let signatures_required: u16 = required_str
+            .parse()
+            .map_err(|e| format!("Invalid signature count '{}': {:?}", required_str, &e))?;
+        let mut public_keys = vec![];
+        for key_str in keys_str.split(',') {
+            let public_key = StacksPublicKey::from_hex(key_str)
+                .map_err(|e| format!("Invalid public key '{}': {:?}", key_str, &e))?;
+            public_keys.push(public_key);
+        }
+        Self::new(mode, signatures_required, public_keys)
+    }
+
+    /// Make an unsigned spending condition for this account
+    pub fn spending_condition(&self) -> Option<TransactionSpendingCondition> {
+        let num_sigs = self.signatures_required;
+        let public_keys = self.public_keys.clone();
+        match self.mode {
+            MultisigMode::P2SH => {
+                TransactionSpendingCondition::new_multisig_p2sh(num_sigs, public_keys)
+            }
+            MultisigMode::P2WSH => {
+                TransactionSpendingCondition::new_multisig_p2wsh(num_sigs, public_keys)
+            }
+            MultisigMode::OrderIndependentP2SH => {
+                TransactionSpendingCondition::new_multisig_order_independent_p2sh(
+                    num_sigs,
+                    public_keys,
+                )
+            }
+            MultisigMode::OrderIndependentP2WSH => {
+                TransactionSpendingCondition::new_multisig_order_independent_p2wsh(
+                    num_sigs,
+                    public_keys,
+                )
+            }
+        }
+    }
+
+    /// Get this account's address
+    pub fn address(&self, mainnet: bool) -> StacksAddress {
+        self.spending_condition()
+            .expect("FATAL: multisig origin was validated on construction")
+            .get_address(mainnet)
+    }
+}
+
+/// On-disk form of a PartiallySignedTransaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PartiallySignedTransactionJSON {
+    /// hex-encoded transaction, whose origin is a placeholder carrying only the nonce and fee
+    tx: String,
+    mode: String,
+    signatures_required: u16,
+    /// hex-encoded public keys, in order
+    public_keys: Vec<String>,
+    /// hex-encoded signatures, one per public key, or null if that key hasn't signed
+    signatures: Vec<Option<String>>,
+}
+
+/// A transaction from a multisig account, passed from cosigner to cosigner to collect signatures.
+/// Once enough cosigners have signed, it can be turned into a broadcastable transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartiallySignedTransaction {
+    /// the transaction, with an unsigned multisig origin
+    pub tx: StacksTransaction,
+    pub origin: MultisigOrigin,
+    /// signatures collected so far, one per public key
+    pub signatures: Vec<Option<MessageSignature>>,
+}
+
+impl PartiallySignedTransaction {
+    /// Make a transaction to be signed by `origin`'s cosigners out of `tx`.  Its payload,
+    /// post-conditions, nonce, fee, and sponsor (if any) are kept, and its origin is replaced.
+    pub fn new(tx: &StacksTransaction, origin: MultisigOrigin) -> Result<Self, Error> {
+        let mut condition = origin
+            .spending_condition()
+            .ok_or(Error::IncompatibleSpendingConditionError)?;
+        condition.set_nonce(tx.auth.origin().nonce());
+        condition.set_tx_fee(tx.auth.origin().tx_fee());
+
+        let mut tx = tx.clone();
+        tx.auth = match tx.auth {
+            TransactionAuth::Standard(_) => TransactionAuth::Standard(condition),
+            TransactionAuth::Sponsored(_, sponsor) => {
+                TransactionAuth::Sponsored(condition, sponsor)
+            }
+        };
+        let signatures = vec![None; origin.public_keys.len()];
+        Ok(Self {
+            tx,
+            origin,
+            signatures,
+        })
+    }
+
+    pub fn num_signatures(&self) -> u16 {
+        let num_sigs = self.signatures.iter().filter(|sig| sig.is_some()).count();
+        u16::try_from(num_sigs).unwrap_or(u16::MAX)
+    }
+
+    pub fn signatures_required(&self) -> u16 {
+        self.origin.signatures_required
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.num_signatures() >= self.signatures_required()
+    }
+
+    /// Which public keys have signed so far?
+    pub fn signers(&self) -> Vec<&StacksPublicKey> {
+        self.origin
+            .public_keys
+            .iter()
+            .zip(self.signatures.iter())
+            .filter_map(|(public_key, sig)| sig.as_ref().map(|_| public_key))
+            .collect()
+    }
+
+    fn key_encoding(public_key: &StacksPublicKey) -> TransactionPublicKeyEncoding {
+        if public_key.compressed() {
+            TransactionPublicKeyEncoding::Compressed
+        } else {
+            TransactionPublicKeyEncoding::Uncompressed
+        }
+    }
+
+    /// The sighash that the cosigner at `index` must sign
+    pub(crate) fn sighash_for(&self, index: usize) -> Result<Txid, Error> {
+        let mut initial_tx = self.tx.clone();
+        initial_tx.auth = initial_tx.auth.into_initial_sighash_auth();
+        let mut cur_sighash = initial_tx.txid();
+        if self.origin.mode.is_order_independent() {
+            return Ok(cur_sighash);
+        }
+
+        // each signature commits to the ones before it
+        let condition = self.tx.auth.origin();
+        for (public_key, sig) in self.origin.public_keys[..index]
+            .iter()
+            .zip(self.signatures[..index].iter())
+        {
+            let Some(sig) = sig else {
+                continue;
+            };
+            let (_, next_sighash) = TransactionSpendingCondition::next_verification(
+                &cur_sighash,
+                &TransactionAuthFlags::AuthStandard,
+                condition.tx_fee(),
+                condition.nonce(),
+                &Self::key_encoding(public_key),
+                sig,
+            )?;
+            cur_sighash = next_sighash;
+        }
+        Ok(cur_sighash)
+    }
+
+    /// Add a cosigner's signature.
+    /// Cosigners of an order-dependent account must sign in public key order.
+    pub fn sign(&mut self, privk: &StacksPrivateKey) -> Result<(), Error> {
+        let public_key = StacksPublicKey::from_private(privk);
+        let Some(index) = self
+            .origin
+            .public_keys
+            .iter()
+            .position(|key| key == &public_key)
+        else {
+            return Err(Error::SigningError(format!(
+                "{} is not a cosigner",
+                &public_key.to_hex()
+            )));
+        };
+        if self.signatures[index].is_some() {
+            return Err(Error::SigningError(format!(
+                "{} has already signed",
+                &public_key.to_hex()
+            )));
+        }
+        if self.is_complete() {
+            return Err(Error::SigningError(
+                "Transaction already has enough signatures".into(),
+            ));
+        }
+        if !self.origin.mode.is_order_independent() {
+            if let Some(later) = self.signatures[index + 1..]
+                .iter()
+                .position(|sig| sig.is_some())
+            {
+                return Err(Error::SigningError(format!(
+                    "Cosigners must sign in order, but {} has already signed",
+                    &self.origin.public_keys[index + 1 + later].to_hex()
+                )));
+            }
+        }
+
+        let sighash = self.sighash_for(index)?;
+        let condition = self.tx.auth.origin();
+        let (sig, _) = TransactionSpendingCondition::next_signature(
+            &sighash,
+            &TransactionAuthFlags::AuthStandard,
+            condition.tx_fee(),
+            condition.nonce(),
+            privk,
+        )?;
+        self.signatures[index] = Some(sig);
+        Ok(())
+    }
+
+    /// Put the signatures into the transaction's origin.
+    /// Fails if there aren't enough signatures, or if any of them are invalid.
+    pub fn to_transaction(&self) -> Result<StacksTransaction, Error> {
+        if !self.is_complete() {
+            return Err(Error::Incomplete);
+        }
+        let tx = self.with_signatures(&self.signatures)?;
+        tx.verify_origin()?;
+        Ok(tx)
+    }
+
+    /// Make a stand-in for the finished transaction, in which the first M cosigners have signed
+    /// with empty signatures.  It is the same size as the transaction that will be broadcast, so
+    /// its fee can be estimated before anyone signs.  It does not verify.
+    pub fn to_fee_estimate_transaction(&self) -> Result<StacksTransaction, Error> {
+        let signatures_required = usize::from(self.signatures_required());
+        let signatures: Vec<_> = (0..self.origin.public_keys.len())
+            .map(|i| (i < signatures_required).then(MessageSignature::empty))
+            .collect();
+        self.with_signatures(&signatures)
+    }
+
+    /// Copy the transaction, with `signatures` (one per public key) put into its origin
+    fn with_signatures(
+        &self,
+        signatures: &[Option<MessageSignature>],
+    ) -> Result<StacksTransaction, Error> {
+        let mut tx = self.tx.clone();
+        let condition = match tx.auth {
+            TransactionAuth::Standard(ref mut condition) => condition,
+            TransactionAuth::Sponsored(ref mut condition, _) => condition,
+        };
+        for (public_key, sig) in self.origin.public_keys.iter().zip(signatures.iter()) {
+            match (&mut *condition, sig) {
+                (TransactionSpendingCondition::Multisig(cond), Some(sig)) => {
+                    cond.push_signature(Self::key_encoding(public_key), sig.clone())
+                }
+                (TransactionSpendingCondition::Multisig(cond), None) => {
+                    cond.push_public_key(public_key.clone())
+                }
+                (TransactionSpendingCondition::OrderIndependentMultisig(cond), Some(sig)) => {
+                    cond.push_signature(Self::key_encoding(public_key), sig.clone())
+                }
+                (TransactionSpendingCondition::OrderIndependentMultisig(cond), None) => {
+                    cond.push_public_key(public_key.clone())
+                }
+                (TransactionSpendingCondition::Singlesig(_), _) => {
+                    return Err(Error::IncompatibleSpendingConditionError);
+                }
+            }
+        }
+        Ok(tx)
+    }
+
+    /// Encode to JSON, for handing to the next cosigner
+    pub fn to_json(&self) -> String {
+        // multisig conditions with too few signatures can't be decoded, so the transaction is
+        // stored with a stand-in origin
+        let mut placeholder = TransactionSpendingCondition::new_initial_sighash();
+        placeholder.set_nonce(self.tx.auth.origin().nonce());
+        placeholder.set_tx_fee(self.tx.auth.origin().tx_fee());
+        let mut tx = self.tx.clone();
+        tx.auth = match tx.auth {
+            TransactionAuth::Standard(_) => TransactionAuth::Standard(placeholder),
+            TransactionAuth::Sponsored(_, sponsor) => {
+                TransactionAuth::Sponsored(placeholder, sponsor)
+            }
+        };
+
+        let json = PartiallySignedTransactionJSON {
+            tx: to_hex(&tx.serialize_to_vec()),
+            mode: self.origin.mode.as_str().to_string(),
+            signatures_required: self.origin.signatures_required,
+            public_keys: self
+                .origin
+                .public_keys
+                .iter()
+                .map(|public_key| public_key.to_hex())
+                .collect(),
+            signatures: self
+                .signatures
+                .iter()
+                .map(|sig| sig.as_ref().map(|sig| sig.to_hex()))
+                .collect(),
+        };
+        serde_json::to_string_pretty(&json).expect("FATAL: failed to encode JSON")
+    }
+
+    /// Decode from JSON
+    pub fn from_json(json_str: &str) -> Result<Self, String> {
+        let json: PartiallySignedTransactionJSON =
+            serde_json::from_str(json_str).map_err(|e| format!("Invalid JSON: {}", &e))?;
+        let mode = MultisigMode::from_name(&json.mode)
+            .ok_or_else(|| format!("Unrecognized multisig mode '{}'", &json.mode))?;
+        let mut public_keys = vec![];
+        for key_str in json.public_keys.iter() {
+            let public_key = StacksPublicKey::from_hex(key_str)
+                .map_err(|e| format!("Invalid public key '{}': {:?}", key_str, &e))?;
+            public_keys.push(public_key);
+        }
+        if json.signatures.len() != public_keys.len() {
+            return Err(format!(
+                "Expected {} signature slots, got {}",
+                public_keys.len(),
+                json.signatures.len()
+            ));
+        }
+        let mut signatures = vec![];
+        for sig_str_opt in json.signatures.iter() {
+            let sig_opt = sig_str_opt
+                .as_ref()
+                .map(|sig_str| {
+                    MessageSignature::from_hex(sig_str)
+                        .map_err(|e| format!("Invalid signature '{}': {:?}", sig_str, &e))
+                })
+                .transpose()?;
+            signatures.push(sig_opt);
+        }
+
+        let origin = MultisigOrigin::new(mode, json.signatures_required, public_keys)?;
+        let tx_bytes =
+            hex_bytes(&json.tx).map_err(|e| format!("Invalid transaction hex: {:?}", &e))?;
+        let tx = StacksTransaction::consensus_deserialize(&mut &tx_bytes[..])
+            .map_err(|e| format!("Invalid transaction: {:?}", &e))?;
+        let mut pst = Self::new(&tx, origin).map_err(|e| format!("{}", &e))?;
+        pst.signatures = signatures;
+        Ok(pst)
+    }
+}
+
+impl fmt::Display for PartiallySignedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} required signatures ({} cosigners, {})",
+            self.num_signatures(),
+            self.signatures_required(),
+            self.origin.public_keys.len(),
+            self.origin.mode.as_str()
+        )
+    }
+}
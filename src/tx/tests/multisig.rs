@@ -0,0 +1,159 @@
+// Copyright (C) 2025 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::chainstate::StacksPrivateKey;
+use stacks_common::types::chainstate::StacksPublicKey;
+use stacks_common::util::hash::Hash160;
+
+use crate::tx::multisig::{MultisigMode, MultisigOrigin, PartiallySignedTransaction};
+use crate::tx::{make_contract_call, Error, StacksTransaction, TransactionPostConditionMode};
+
+fn make_tx(privk: &StacksPrivateKey) -> StacksTransaction {
+    make_contract_call(
+        false,
+        privk,
+        3,
+        100,
+        &StacksAddress::new(26, Hash160([0x01; 20])).unwrap(),
+        "hello",
+        "world",
+        &[],
+        TransactionPostConditionMode::Deny,
+        vec![],
+    )
+    .unwrap()
+}
+
+/// Make a 2-of-3 transaction, and its cosigners' keys in public key order
+fn make_pst(mode: MultisigMode) -> (PartiallySignedTransaction, Vec<StacksPrivateKey>) {
+    let privks: Vec<_> = (0..3).map(|_| StacksPrivateKey::random()).collect();
+    let public_keys = privks.iter().map(StacksPublicKey::from_private).collect();
+    let origin = MultisigOrigin::new(mode, 2, public_keys).unwrap();
+    let pst = PartiallySignedTransaction::new(&make_tx(&privks[0]), origin).unwrap();
+    (pst, privks)
+}
+
+#[test]
+fn test_multisig_sighash_chaining() {
+    let (mut pst, privks) = make_pst(MultisigMode::P2SH);
+    let initial_sighash = pst.sighash_for(0).unwrap();
+
+    // nobody has signed, so everyone signs the initial sighash
+    assert_eq!(pst.sighash_for(1).unwrap(), initial_sighash);
+    assert_eq!(pst.sighash_for(2).unwrap(), initial_sighash);
+
+    // the second cosigner signs over the first cosigner's signature
+    pst.sign(&privks[0]).unwrap();
+    assert_eq!(pst.sighash_for(0).unwrap(), initial_sighash);
+    let second_sighash = pst.sighash_for(1).unwrap();
+    assert_ne!(second_sighash, initial_sighash);
+
+    // cosigners who didn't sign are skipped
+    assert_eq!(pst.sighash_for(2).unwrap(), second_sighash);
+
+    // order-independent cosigners all sign the initial sighash
+    let (mut pst, privks) = make_pst(MultisigMode::OrderIndependentP2SH);
+    let initial_sighash = pst.sighash_for(0).unwrap();
+    pst.sign(&privks[0]).unwrap();
+    assert_eq!(pst.sighash_for(1).unwrap(), initial_sighash);
+    assert_eq!(pst.sighash_for(2).unwrap(), initial_sighash);
+}
+
+#[test]
+fn test_multisig_sign_out_of_order() {
+    // order-dependent cosigners can skip ahead, but can't go back
+    let (mut pst, privks) = make_pst(MultisigMode::P2SH);
+    pst.sign(&privks[1]).unwrap();
+    assert!(pst.sign(&privks[0]).is_err());
+    pst.sign(&privks[2]).unwrap();
+    assert!(pst.is_complete());
+    pst.to_transaction().unwrap();
+
+    // order-independent cosigners can sign in any order
+    let (mut pst, privks) = make_pst(MultisigMode::OrderIndependentP2SH);
+    pst.sign(&privks[2]).unwrap();
+    pst.sign(&privks[0]).unwrap();
+    assert!(pst.is_complete());
+    pst.to_transaction().unwrap();
+
+    // no signing twice, or by strangers, or past M
+    let (mut pst, privks) = make_pst(MultisigMode::OrderIndependentP2WSH);
+    pst.sign(&privks[0]).unwrap();
+    assert!(pst.sign(&privks[0]).is_err());
+    assert!(pst.sign(&StacksPrivateKey::random()).is_err());
+    pst.sign(&privks[1]).unwrap();
+    assert!(pst.sign(&privks[2]).is_err());
+    assert_eq!(pst.num_signatures(), 2);
+}
+
+#[test]
+fn test_multisig_json_roundtrip() {
+    for mode in [
+        MultisigMode::P2SH,
+        MultisigMode::P2WSH,
+        MultisigMode::OrderIndependentP2SH,
+        MultisigMode::OrderIndependentP2WSH,
+    ] {
+        let (mut pst, privks) = make_pst(mode);
+        assert_eq!(
+            PartiallySignedTransaction::from_json(&pst.to_json()).unwrap(),
+            pst
+        );
+
+        // the next cosigner can pick up where the last one left off
+        pst.sign(&privks[0]).unwrap();
+        let mut pst_2 = PartiallySignedTransaction::from_json(&pst.to_json()).unwrap();
+        assert_eq!(pst_2, pst);
+        pst_2.sign(&privks[1]).unwrap();
+        pst_2.to_transaction().unwrap();
+    }
+
+    // signature slots must match the public keys
+    let (pst, _) = make_pst(MultisigMode::P2SH);
+    let json = pst.to_json().replacen("null,", "", 1);
+    assert!(PartiallySignedTransaction::from_json(&json).is_err());
+}
+
+#[test]
+fn test_multisig_to_transaction() {
+    let (mut pst, privks) = make_pst(MultisigMode::P2SH);
+    let fee_estimate_tx = pst.to_fee_estimate_transaction().unwrap();
+
+    // fewer than M signatures
+    assert!(matches!(pst.to_transaction(), Err(Error::Incomplete)));
+    pst.sign(&privks[0]).unwrap();
+    assert!(matches!(pst.to_transaction(), Err(Error::Incomplete)));
+
+    // exactly M signatures
+    pst.sign(&privks[1]).unwrap();
+    let tx = pst.to_transaction().unwrap();
+    tx.verify().unwrap();
+    assert_eq!(
+        tx.auth.origin().address_testnet(),
+        pst.origin.address(false)
+    );
+    assert_eq!(tx.get_origin_nonce(), 3);
+    assert_eq!(tx.get_tx_fee(), 100);
+    assert_eq!(tx.payload, pst.tx.payload);
+
+    // the fee is estimated on a transaction of the same size
+    assert!(fee_estimate_tx.verify_origin().is_err());
+    assert_eq!(
+        fee_estimate_tx.serialize_to_vec().len(),
+        tx.serialize_to_vec().len()
+    );
+}
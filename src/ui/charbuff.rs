@@ -16,6 +16,8 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::fmt;
+use std::ops::BitOr;
+use std::ops::BitXor;
 
 use clarity::vm::Value;
 
@@ -45,14 +47,165 @@ impl Color {
     pub fn rgb(r: u8, g: u8, b: u8) -> Self {
         Self { r, g, b }
     }
+
+    /// Squared distance between two colors in RGB space
+    fn distance(&self, other: &Color) -> u32 {
+        let dr = i32::from(self.r) - i32::from(other.r);
+        let dg = i32::from(self.g) - i32::from(other.g);
+        let db = i32::from(self.b) - i32::from(other.b);
+        u32::try_from(dr * dr + dg * dg + db * db).expect("infallible")
+    }
+
+    /// Perceived brightness, from 0 to 255
+    pub fn luminance(&self) -> u8 {
+        let lum =
+            (299 * u32::from(self.r) + 587 * u32::from(self.g) + 114 * u32::from(self.b)) / 1000;
+        u8::try_from(lum).expect("infallible")
+    }
+
+    /// The RGB value of an entry in the xterm 256-color palette
+    pub fn from_ansi256(idx: u8) -> Self {
+        match idx {
+            0..=15 => Self::from_ansi16(idx),
+            16..=231 => {
+                let idx = idx - 16;
+                Self::rgb(
+                    ANSI256_CUBE_LEVELS[usize::from(idx / 36)],
+                    ANSI256_CUBE_LEVELS[usize::from((idx / 6) % 6)],
+                    ANSI256_CUBE_LEVELS[usize::from(idx % 6)],
+                )
+            }
+            232..=255 => {
+                let v = 8 + 10 * (idx - 232);
+                Self::rgb(v, v, v)
+            }
+        }
+    }
+
+    /// The RGB value of an entry in the (xterm) 16-color palette.  Only the low 4 bits are used.
+    pub fn from_ansi16(idx: u8) -> Self {
+        let (r, g, b) = ANSI16_PALETTE[usize::from(idx & 0x0f)];
+        Self::rgb(r, g, b)
+    }
+
+    /// Nearest entry in the xterm 256-color palette.  Only the color cube and the grayscale ramp
+    /// are considered, since the first 16 entries vary from terminal to terminal.
+    pub fn to_ansi256(&self) -> u8 {
+        fn cube_index(v: u8) -> u8 {
+            if v < 48 {
+                0
+            } else if v < 115 {
+                1
+            } else {
+                (v - 35) / 40
+            }
+        }
+        let cube_idx = 16 + 36 * cube_index(self.r) + 6 * cube_index(self.g) + cube_index(self.b);
+
+        let avg = (u16::from(self.r) + u16::from(self.g) + u16::from(self.b)) / 3;
+        let gray_idx = if avg < 8 {
+            232
+        } else {
+            232 + u8::try_from(((avg - 8) / 10).min(23)).expect("infallible")
+        };
+
+        if Self::from_ansi256(gray_idx).distance(self) < Self::from_ansi256(cube_idx).distance(self)
+        {
+            gray_idx
+        } else {
+            cube_idx
+        }
+    }
+
+    /// Nearest entry in the 16-color palette
+    pub fn to_ansi16(&self) -> u8 {
+        (0..16u8)
+            .min_by_key(|idx| Self::from_ansi16(*idx).distance(self))
+            .expect("infallible")
+    }
 }
 
+/// Channel values of the 6x6x6 color cube in the xterm 256-color palette
+const ANSI256_CUBE_LEVELS: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+
+/// RGB values of the 16 ANSI colors, as xterm draws them
+const ANSI16_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
     }
 }
 
+/// Text attributes (bold, underline, etc.) of a character cell, as a bitfield.
+/// The bits match the `WRB_STYLE_*` constants in `wrb.clar`.
+#[derive(Clone, PartialEq, Debug, Copy, Default)]
+pub struct TextAttrs(u8);
+
+impl TextAttrs {
+    pub const NONE: Self = Self(0x00);
+    pub const BOLD: Self = Self(0x01);
+    pub const UNDERLINE: Self = Self(0x02);
+    pub const ITALIC: Self = Self(0x04);
+    pub const REVERSE: Self = Self(0x08);
+    pub const ALL: Self = Self(0x0f);
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Are all of the attributes in `other` set?
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn to_clarity_value(&self) -> Value {
+        Value::UInt(u128::from(self.0))
+    }
+}
+
+/// Unknown bits are dropped
+impl From<u128> for TextAttrs {
+    fn from(bits: u128) -> Self {
+        Self(u8::try_from(bits & u128::from(Self::ALL.0)).expect("infallible"))
+    }
+}
+
+impl BitOr for TextAttrs {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitXor for TextAttrs {
+    type Output = Self;
+    fn bitxor(self, other: Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+}
+
 /// A single character cell in a character buffer
 #[derive(Clone, PartialEq, Debug)]
 pub enum CharCell {
@@ -61,16 +214,28 @@ pub enum CharCell {
         value: char,
         bg: Color,
         fg: Color,
+        attrs: TextAttrs,
         element_id: u128,
     },
 }
 
 impl CharCell {
     pub fn new(element_id: u128, value: char, bg: Color, fg: Color) -> Self {
+        Self::new_styled(element_id, value, bg, fg, TextAttrs::NONE)
+    }
+
+    pub fn new_styled(
+        element_id: u128,
+        value: char,
+        bg: Color,
+        fg: Color,
+        attrs: TextAttrs,
+    ) -> Self {
         Self::Fill {
             value,
             bg,
             fg,
+            attrs,
             element_id,
         }
     }
@@ -84,8 +249,23 @@ impl fmt::Display for CharCell {
                 value,
                 bg,
                 fg,
+                attrs,
                 element_id,
-            } => write!(f, "({})({})[{}]<{}>", &bg, &fg, value, element_id),
+            } => {
+                if attrs.is_empty() {
+                    write!(f, "({})({})[{}]<{}>", &bg, &fg, value, element_id)
+                } else {
+                    write!(
+                        f,
+                        "({})({}){{{:x}}}[{}]<{}>",
+                        &bg,
+                        &fg,
+                        attrs.bits(),
+                        value,
+                        element_id
+                    )
+                }
+            }
         }
     }
 }
@@ -130,6 +310,28 @@ impl CharBuff {
         bg: Color,
         fg: Color,
         text_iter: impl Iterator<Item = char>,
+    ) -> (u64, u64) {
+        self.print_at_iter_styled(
+            element_id,
+            start_row,
+            start_col,
+            bg,
+            fg,
+            TextAttrs::NONE,
+            text_iter,
+        )
+    }
+
+    /// Like `print_at_iter`, but with text attributes
+    pub fn print_at_iter_styled(
+        &mut self,
+        element_id: u128,
+        start_row: u64,
+        start_col: u64,
+        bg: Color,
+        fg: Color,
+        attrs: TextAttrs,
+        text_iter: impl Iterator<Item = char>,
     ) -> (u64, u64) {
         // do we need to pad?
         let Ok(mut offset) = usize::try_from(self.num_cols * start_row + start_col) else {
@@ -144,9 +346,9 @@ impl CharBuff {
         for c in text_iter {
             let ccell = if c <= '\x1f' || c.is_control() {
                 // escape code or control character
-                CharCell::new(element_id, char::REPLACEMENT_CHARACTER, bg, fg)
+                CharCell::new_styled(element_id, char::REPLACEMENT_CHARACTER, bg, fg, attrs)
             } else {
-                CharCell::new(element_id, c, bg, fg)
+                CharCell::new_styled(element_id, c, bg, fg, attrs)
             };
 
             if offset < self.cells.len() {
@@ -173,6 +375,28 @@ impl CharBuff {
         self.print_at_iter(element_id, start_row, start_col, bg, fg, &mut text.chars())
     }
 
+    /// Wrapper around print_at_iter_styled
+    pub fn print_at_styled(
+        &mut self,
+        element_id: u128,
+        start_row: u64,
+        start_col: u64,
+        bg: Color,
+        fg: Color,
+        attrs: TextAttrs,
+        text: &str,
+    ) -> (u64, u64) {
+        self.print_at_iter_styled(
+            element_id,
+            start_row,
+            start_col,
+            bg,
+            fg,
+            attrs,
+            &mut text.chars(),
+        )
+    }
+
     /// Print word-wrapped text.
     /// Returns (end-row, end-col) where printing finished
     pub fn print(
@@ -190,6 +414,31 @@ impl CharBuff {
             start_col,
             bg,
             fg,
+            TextAttrs::NONE,
+            text.chars(),
+            false,
+        )
+    }
+
+    /// Print word-wrapped text with text attributes.
+    /// Returns (end-row, end-col) where printing finished
+    pub fn print_styled(
+        &mut self,
+        element_id: u128,
+        start_row: u64,
+        start_col: u64,
+        bg: Color,
+        fg: Color,
+        attrs: TextAttrs,
+        text: &str,
+    ) -> (u64, u64) {
+        self.inner_print(
+            element_id,
+            start_row,
+            start_col,
+            bg,
+            fg,
+            attrs,
             text.chars(),
             false,
         )
@@ -206,7 +455,16 @@ impl CharBuff {
         fg: Color,
         text: impl Iterator<Item = char>,
     ) -> (u64, u64) {
-        self.inner_print(element_id, start_row, start_col, bg, fg, text, false)
+        self.inner_print(
+            element_id,
+            start_row,
+            start_col,
+            bg,
+            fg,
+            TextAttrs::NONE,
+            text,
+            false,
+        )
     }
 
     /// Print word-wrapped text with a newline at the end.
@@ -220,7 +478,40 @@ impl CharBuff {
         fg: Color,
         text: &str,
     ) -> (u64, u64) {
-        self.inner_print(element_id, start_row, start_col, bg, fg, text.chars(), true)
+        self.inner_print(
+            element_id,
+            start_row,
+            start_col,
+            bg,
+            fg,
+            TextAttrs::NONE,
+            text.chars(),
+            true,
+        )
+    }
+
+    /// Print word-wrapped text with text attributes and a newline at the end.
+    /// Returns (end-row, end-col) where printing finished
+    pub fn println_styled(
+        &mut self,
+        element_id: u128,
+        start_row: u64,
+        start_col: u64,
+        bg: Color,
+        fg: Color,
+        attrs: TextAttrs,
+        text: &str,
+    ) -> (u64, u64) {
+        self.inner_print(
+            element_id,
+            start_row,
+            start_col,
+            bg,
+            fg,
+            attrs,
+            text.chars(),
+            true,
+        )
     }
 
     /// Print word-wrapped text, optionally with a terminating newline.
@@ -233,6 +524,7 @@ impl CharBuff {
         start_col: u64,
         bg: Color,
         fg: Color,
+        attrs: TextAttrs,
         text: impl Iterator<Item = char>,
         newline: bool,
     ) -> (u64, u64) {
@@ -270,12 +562,12 @@ impl CharBuff {
                 PrintPartType::Word(part, charlen) => {
                     if idx + charlen < self.num_cols {
                         // can write without wrap
-                        ret = self.print_at(element_id, row, idx, bg, fg, &part);
+                        ret = self.print_at_styled(element_id, row, idx, bg, fg, attrs, &part);
                         idx += charlen;
                     } else {
                         // need to wrap
                         row += 1;
-                        ret = self.print_at(element_id, row, 0, bg, fg, &part);
+                        ret = self.print_at_styled(element_id, row, 0, bg, fg, attrs, &part);
                         idx = charlen % self.num_cols;
                     }
 
@@ -64,6 +64,8 @@ use stacks_common::util::hash::to_hex;
 use stacks_common::util::hash::Hash160;
 use stacks_common::util::sleep_ms;
 
+use termion::event::MouseButton;
+
 use crate::ui::forms::WrbFormTypes;
 use crate::ui::root::FrameUpdate;
 use crate::ui::root::Root;
@@ -87,8 +89,36 @@ pub enum WrbEvent {
         element_id: u128,
         event_payload: Value,
     },
+    /// A mouse button was pressed at the given absolute (row, col) in the root pane.
+    /// Sent regardless of which UI element (if any) is under the pointer, so pages can implement
+    /// their own clickable areas (e.g. canvases).
+    Mouse {
+        button: MouseButton,
+        row: u64,
+        col: u64,
+    },
+    /// The user scrolled a viewport, so that content row `row` is now at its top.
+    Scroll { viewport_id: u128, row: u64 },
+}
+
+/// Mouse button codes.
+/// These match the constants in `wrb.clar`
+pub fn mouse_button_code(button: MouseButton) -> u128 {
+    match button {
+        MouseButton::Left => 0,
+        MouseButton::Right => 1,
+        MouseButton::Middle => 2,
+        MouseButton::WheelUp => 3,
+        MouseButton::WheelDown => 4,
+        MouseButton::WheelLeft => 5,
+        MouseButton::WheelRight => 6,
+    }
 }
 
+/// Element type of events which are not about a UI element.  It's outside the range of the
+/// `WRB_UI_TYPE_*` constants, so a page's UI element handlers never match these events.
+pub const WRB_NON_UI_ELEMENT_TYPE: u128 = u128::MAX;
+
 impl WrbEvent {
     pub fn element_type(&self) -> u128 {
         match self {
@@ -97,6 +127,8 @@ impl WrbEvent {
             Self::Timer => 1,
             Self::Resize(_, _) => 2,
             Self::UI { element_type, .. } => element_type.as_u128(),
+            Self::Mouse { .. } => WRB_NON_UI_ELEMENT_TYPE,
+            Self::Scroll { .. } => WRB_NON_UI_ELEMENT_TYPE,
         }
     }
 
@@ -106,6 +138,8 @@ impl WrbEvent {
             Self::Close => u128::MAX,
             Self::Timer => u128::MAX,
             Self::Resize(_, _) => u128::MAX,
+            Self::Mouse { .. } => u128::MAX,
+            Self::Scroll { viewport_id, .. } => *viewport_id,
             Self::UI {
                 element_type: _,
                 element_id,
@@ -121,6 +155,8 @@ impl WrbEvent {
             Self::Timer => 1,
             Self::Resize(_, _) => 2,
             Self::UI { .. } => 4,
+            Self::Mouse { .. } => 5,
+            Self::Scroll { .. } => 6,
         }
     }
 
@@ -138,6 +174,25 @@ impl WrbEvent {
             )
             .serialize_to_vec()
             .expect("FATAL: could not serialize rows/cols tuple"),
+            Self::Mouse { button, row, col } => Value::Tuple(
+                TupleData::from_data(vec![
+                    ("button".into(), Value::UInt(mouse_button_code(*button))),
+                    ("row".into(), Value::UInt(u128::from(*row))),
+                    ("col".into(), Value::UInt(u128::from(*col))),
+                ])
+                .expect("FATAL: could not produce mouse tuple data"),
+            )
+            .serialize_to_vec()
+            .expect("FATAL: could not serialize mouse tuple"),
+            Self::Scroll { viewport_id, row } => Value::Tuple(
+                TupleData::from_data(vec![
+                    ("viewport".into(), Value::UInt(*viewport_id)),
+                    ("row".into(), Value::UInt(u128::from(*row))),
+                ])
+                .expect("FATAL: could not produce scroll tuple data"),
+            )
+            .serialize_to_vec()
+            .expect("FATAL: could not serialize scroll tuple"),
             Self::UI {
                 element_type,
                 element_id,
@@ -476,6 +531,33 @@ impl Renderer {
         Ok(res)
     }
 
+    /// Record that the user scrolled a viewport, so the page sees its new scroll position
+    pub(crate) fn record_viewport_scroll(
+        &mut self,
+        wrb_tx: &mut WritableWrbStore,
+        headers_db: &dyn HeadersDB,
+        main_code_id: &QualifiedContractIdentifier,
+        viewport_id: u128,
+        row: u64,
+    ) -> Result<(), Error> {
+        let mut db = wrb_tx.get_clarity_db(headers_db, &NULL_BURN_STATE_DB);
+        db.begin();
+        let mut vm_env = OwnedEnvironment::new_free(true, DEFAULT_CHAIN_ID, db, DEFAULT_WRB_EPOCH);
+
+        let qry = format!(
+            "(print (wrb-internal-viewport-scrolled u{} u{}))",
+            viewport_id, row
+        );
+        self.run_query_code(&mut vm_env, main_code_id, &qry)?;
+
+        let (mut db, _) = vm_env
+            .destruct()
+            .expect("Failed to recover database reference after executing transaction");
+
+        db.commit()?;
+        Ok(())
+    }
+
     /// Run the main loop in an interactive setting
     /// Returns the last thing the event loop returns.
     /// Returns None if there's no event loop function defined.
@@ -535,6 +617,24 @@ impl Renderer {
             // if this was a request to close, then exit
             will_close = matches!(next_event, WrbEvent::Close);
 
+            // if the user scrolled a viewport, then record it even if the page doesn't
+            // subscribe to scroll events
+            if let WrbEvent::Scroll { viewport_id, row } = &next_event {
+                if let Err(e) = self.record_viewport_scroll(
+                    &mut wrb_tx,
+                    &headers_db,
+                    &main_code_id,
+                    *viewport_id,
+                    *row,
+                ) {
+                    wrb_warn!(
+                        "Failed to record scroll of viewport {}: {:?}",
+                        viewport_id,
+                        &e
+                    );
+                }
+            }
+
             if event_subscriptions.len() > 0 {
                 let event_type_u128 = next_event.event_type();
                 if !event_subscriptions.contains(&event_type_u128)
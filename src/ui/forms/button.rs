@@ -21,6 +21,8 @@ use crate::ui::Error;
 use crate::ui::ValueExtensions;
 use clarity::vm::Value;
 
+use termion::event::MouseButton;
+
 use crate::ui::forms::WrbForm;
 use crate::ui::forms::WrbFormEvent;
 use crate::ui::forms::WrbFormTypes;
@@ -194,11 +196,12 @@ impl WrbForm for Button {
         event: WrbFormEvent,
     ) -> Result<Option<Value>, Error> {
         self.focus(root, root.is_focused(self.element_id))?;
-        let WrbFormEvent::Keypress(keycode) = event else {
-            return Ok(None);
+        let pressed = match event {
+            WrbFormEvent::Keypress(keycode) => keycode == root.keycode_enter(),
+            WrbFormEvent::Mouse(button, _, _) => button == MouseButton::Left,
         };
-        if keycode != root.keycode_enter() {
-            // not the "submit" keycode
+        if !pressed {
+            // not the "submit" keycode or a click
             return Ok(None);
         }
 
@@ -18,6 +18,8 @@
 use clarity::vm::types::TupleData;
 use clarity::vm::Value;
 
+use termion::event::MouseButton;
+
 use crate::ui::charbuff::Color;
 use crate::ui::root::Root;
 use crate::ui::Error;
@@ -31,6 +33,7 @@ use crate::DEFAULT_WRB_EPOCH;
 struct CheckboxOption {
     text: String,
     selected: bool,
+    disabled: bool,
 }
 
 impl CheckboxOption {
@@ -48,7 +51,11 @@ impl CheckboxOption {
             .expect("FATAL: no `selected`")
             .expect_bool()?;
 
-        Ok(Self { text, selected })
+        Ok(Self {
+            text,
+            selected,
+            disabled: false,
+        })
     }
 
     pub fn to_clarity_value(&self) -> Value {
@@ -65,8 +72,19 @@ impl CheckboxOption {
         )
     }
 
-    pub fn to_string(&self) -> String {
-        format!("[{}] {}", if self.selected { "*" } else { " " }, &self.text)
+    pub fn to_string(&self, radio: bool) -> String {
+        let mark = if self.selected {
+            "*"
+        } else if self.disabled {
+            "-"
+        } else {
+            " "
+        };
+        if radio {
+            format!("({}) {}", mark, &self.text)
+        } else {
+            format!("[{}] {}", mark, &self.text)
+        }
     }
 }
 
@@ -84,10 +102,125 @@ pub struct Checkbox {
     selector_color: Color,
     options: Vec<CheckboxOption>,
     selector: usize,
+    /// If true, then exactly one option is selected at a time
+    radio: bool,
+    /// If true, then the whole checkbox group is greyed out and cannot be focused
+    disabled: bool,
 }
 
 pub const CHECKBOX_MAX_LEN: usize = 256;
 
+/// Foreground color of disabled checkboxes and options
+pub const CHECKBOX_DISABLED_FG_COLOR: u32 = 0x808080;
+
+impl Checkbox {
+    pub fn new_detached(options: Vec<(String, bool)>, radio: bool) -> Self {
+        let mut checkbox = Self {
+            element_id: 0,
+            viewport_id: 0,
+            row: 0,
+            col: 0,
+            bg_color: 0u32.into(),
+            fg_color: 0xffffffu32.into(),
+            focused_bg_color: 0xffffffu32.into(),
+            focused_fg_color: 0u32.into(),
+            selector_color: 0xff0000u32.into(),
+            options: options
+                .into_iter()
+                .map(|(text, selected)| CheckboxOption {
+                    text,
+                    selected,
+                    disabled: false,
+                })
+                .collect(),
+            selector: 0,
+            radio,
+            disabled: false,
+        };
+        checkbox.normalize();
+        checkbox
+    }
+
+    /// Indexes of the selected options
+    pub fn selected(&self) -> Vec<usize> {
+        self.options
+            .iter()
+            .enumerate()
+            .filter_map(|(i, option)| if option.selected { Some(i) } else { None })
+            .collect()
+    }
+
+    /// Index of the option under the selector
+    pub fn selector(&self) -> usize {
+        self.selector
+    }
+
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
+    pub fn set_option_disabled(&mut self, index: usize, disabled: bool) {
+        if let Some(option) = self.options.get_mut(index) {
+            option.disabled = disabled;
+        }
+        self.normalize();
+    }
+
+    /// Keep the selector off of disabled options, and make sure that a radio group has exactly
+    /// one selected option (the first one, if the page selected several or none).
+    fn normalize(&mut self) {
+        if self
+            .options
+            .get(self.selector)
+            .map(|option| option.disabled)
+            .unwrap_or(false)
+        {
+            self.selector = self.next_enabled(0, true).unwrap_or(0);
+        }
+        if !self.radio {
+            return;
+        }
+        let first_selected = self
+            .options
+            .iter()
+            .position(|option| option.selected)
+            .or_else(|| self.next_enabled(0, true));
+        for (i, option) in self.options.iter_mut().enumerate() {
+            option.selected = Some(i) == first_selected;
+        }
+    }
+
+    /// Find the first enabled option at or after `start` (if `forward`), or at or before `start`
+    /// (if not).
+    fn next_enabled(&self, start: usize, forward: bool) -> Option<usize> {
+        if forward {
+            (start..self.options.len()).find(|i| !self.options[*i].disabled)
+        } else {
+            (0..=start.min(self.options.len().saturating_sub(1)))
+                .rev()
+                .find(|i| !self.options.get(*i).map(|o| o.disabled).unwrap_or(true))
+        }
+    }
+
+    /// Toggle the option under the selector.  In a radio group, this selects it and deselects
+    /// all others.
+    fn toggle(&mut self) {
+        let Some(option) = self.options.get(self.selector) else {
+            return;
+        };
+        if option.disabled {
+            return;
+        }
+        if self.radio {
+            for (i, option) in self.options.iter_mut().enumerate() {
+                option.selected = i == self.selector;
+            }
+        } else {
+            self.options[self.selector].selected = !self.options[self.selector].selected;
+        }
+    }
+}
+
 impl WrbForm for Checkbox {
     /// type
     fn type_id(&self) -> WrbFormTypes {
@@ -102,6 +235,10 @@ impl WrbForm for Checkbox {
         self.viewport_id
     }
 
+    fn disabled(&self) -> bool {
+        self.disabled
+    }
+
     fn focus(&mut self, root: &mut Root, focused: bool) -> Result<(), Error> {
         if focused {
             root.set_form_cursor(
@@ -194,9 +331,30 @@ impl WrbForm for Checkbox {
             )));
         }
 
+        let radio = checkbox_tuple
+            .get("radio")
+            .cloned()
+            .expect("FATAL: no `radio`")
+            .expect_bool()?;
+
+        let disabled = checkbox_tuple
+            .get("disabled")
+            .cloned()
+            .expect("FATAL: no `disabled`")
+            .expect_bool()?;
+
+        let disabled_options_list = checkbox_tuple
+            .get("disabled-options")
+            .cloned()
+            .expect("FATAL: no `disabled-options`")
+            .expect_list()?;
+
         let mut options = vec![];
-        for option_value in text_options_list.into_iter() {
-            let checkbox_option = CheckboxOption::from_clarity_value(option_value)?;
+        for (i, option_value) in text_options_list.into_iter().enumerate() {
+            let mut checkbox_option = CheckboxOption::from_clarity_value(option_value)?;
+            if let Some(option_disabled) = disabled_options_list.get(i) {
+                checkbox_option.disabled = option_disabled.clone().expect_bool()?;
+            }
             options.push(checkbox_option);
         }
 
@@ -212,7 +370,7 @@ impl WrbForm for Checkbox {
             .expect("infallible")
             .into();
 
-        Ok(Self {
+        let mut checkbox = Self {
             element_id,
             viewport_id,
             row,
@@ -224,7 +382,11 @@ impl WrbForm for Checkbox {
             focused_fg_color,
             options,
             selector: 0,
-        })
+            radio,
+            disabled,
+        };
+        checkbox.normalize();
+        Ok(checkbox)
     }
 
     /// Store back to a Clarity value.
@@ -242,6 +404,18 @@ impl WrbForm for Checkbox {
         Ok(Some(value))
     }
 
+    /// Take the radio and disabled settings from the wrbsite, but keep the user's selections
+    fn merge_update(&mut self, value: Value) -> Result<(), Error> {
+        let update = Self::from_clarity_value(self.viewport_id, value)?;
+        self.radio = update.radio;
+        self.disabled = update.disabled;
+        for (option, updated_option) in self.options.iter_mut().zip(update.options.iter()) {
+            option.disabled = updated_option.disabled;
+        }
+        self.normalize();
+        Ok(())
+    }
+
     /// Render the button
     fn render(&mut self, root: &mut Root, cursor: (u64, u64)) -> Result<(u64, u64), Error> {
         let focused = root.is_focused(self.element_id);
@@ -260,18 +434,24 @@ impl WrbForm for Checkbox {
         } else {
             self.fg_color.clone()
         };
+        let disabled_fg_color: Color = CHECKBOX_DISABLED_FG_COLOR.into();
 
         let mut next_cursor = cursor;
         for (i, option) in self.options.iter().enumerate() {
             let row = self.row + u64::try_from(i).expect("infallible: too many options");
-            next_cursor = if i == self.selector {
+            let option_fg_color = if self.disabled || option.disabled {
+                disabled_fg_color
+            } else {
+                fg_color
+            };
+            next_cursor = if i == self.selector && !self.disabled {
                 viewport.print_to(
                     self.element_id,
                     row,
                     self.col,
                     self.selector_color,
-                    fg_color,
-                    &option.to_string(),
+                    option_fg_color,
+                    &option.to_string(self.radio),
                 )
             } else {
                 viewport.print_to(
@@ -279,8 +459,8 @@ impl WrbForm for Checkbox {
                     row,
                     self.col,
                     bg_color,
-                    fg_color,
-                    &option.to_string(),
+                    option_fg_color,
+                    &option.to_string(self.radio),
                 )
             };
         }
@@ -302,29 +482,55 @@ impl WrbForm for Checkbox {
         root: &mut Root,
         event: WrbFormEvent,
     ) -> Result<Option<Value>, Error> {
-        self.focus(root, root.is_focused(self.element_id))?;
-        let WrbFormEvent::Keypress(keycode) = event else {
+        if self.disabled {
             return Ok(None);
+        }
+        self.focus(root, root.is_focused(self.element_id))?;
+        let keycode = match event {
+            WrbFormEvent::Keypress(keycode) => keycode,
+            WrbFormEvent::Mouse(MouseButton::Left, row, _) => {
+                // clicking an option selects and toggles it
+                if self.options.len() == 0 {
+                    return Ok(None);
+                }
+                let index = usize::try_from(row)
+                    .unwrap_or(usize::MAX)
+                    .min(self.options.len() - 1);
+                if self.options[index].disabled {
+                    return Ok(None);
+                }
+                self.selector = index;
+                root.keycode_space()
+            }
+            // the wheel moves the selector
+            WrbFormEvent::Mouse(MouseButton::WheelUp, _, _) => root.keycode_up(),
+            WrbFormEvent::Mouse(MouseButton::WheelDown, _, _) => root.keycode_down(),
+            WrbFormEvent::Mouse(..) => {
+                return Ok(None);
+            }
         };
 
-        // up and down move the selector
+        // up and down move the selector, skipping disabled options
         if keycode == root.keycode_up() {
-            self.selector = self.selector.saturating_sub(1);
+            if self.selector > 0 {
+                self.selector = self
+                    .next_enabled(self.selector - 1, false)
+                    .unwrap_or(self.selector);
+            }
             self.focus(root, root.is_focused(self.element_id))?;
             return Ok(None);
         }
 
         if keycode == root.keycode_down() {
             self.selector = self
-                .selector
-                .saturating_add(1)
-                .min(self.options.len().saturating_sub(1));
+                .next_enabled(self.selector.saturating_add(1), true)
+                .unwrap_or(self.selector);
             self.focus(root, root.is_focused(self.element_id))?;
             return Ok(None);
         }
 
         if keycode == root.keycode_enter() || keycode == root.keycode_space() {
-            self.options[self.selector].selected = !self.options[self.selector].selected;
+            self.toggle();
         }
 
         return Ok(None);
@@ -19,6 +19,8 @@ pub mod button;
 pub mod checkbox;
 pub mod print_text;
 pub mod raw_text;
+pub mod select;
+pub mod table;
 pub mod textarea;
 pub mod textline;
 
@@ -31,10 +33,13 @@ pub use button::Button;
 pub use checkbox::Checkbox;
 pub use print_text::PrintText;
 pub use raw_text::RawText;
+pub use select::Select;
+pub use table::Table;
 pub use textarea::TextArea;
 pub use textline::TextLine;
 
 use termion::event::Key;
+use termion::event::MouseButton;
 
 use crate::ui::root::Root;
 use crate::ui::Error;
@@ -53,6 +58,8 @@ pub enum WrbFormTypes {
     Checkbox,
     TextLine,
     TextArea,
+    Select,
+    Table,
 }
 
 impl WrbFormTypes {
@@ -64,12 +71,19 @@ impl WrbFormTypes {
             Self::Checkbox => 7,
             Self::TextLine => 8,
             Self::TextArea => 9,
+            Self::Select => 10,
+            Self::Table => 11,
         }
     }
 
     pub fn focusable(&self) -> bool {
         match *self {
-            Self::Button | Self::Checkbox | Self::TextLine | Self::TextArea => true,
+            Self::Button
+            | Self::Checkbox
+            | Self::TextLine
+            | Self::TextArea
+            | Self::Select
+            | Self::Table => true,
             _ => false,
         }
     }
@@ -85,6 +99,8 @@ impl TryFrom<u128> for WrbFormTypes {
             7 => Ok(Self::Checkbox),
             8 => Ok(Self::TextLine),
             9 => Ok(Self::TextArea),
+            10 => Ok(Self::Select),
+            11 => Ok(Self::Table),
             _ => Err(()),
         }
     }
@@ -94,6 +110,9 @@ impl TryFrom<u128> for WrbFormTypes {
 #[derive(Debug, Clone, PartialEq)]
 pub enum WrbFormEvent {
     Keypress(Key),
+    /// A mouse button was pressed at the given (row, col), relative to the element's upper-left
+    /// corner
+    Mouse(MouseButton, u64, u64),
 }
 
 /// Work around Clone blanket implementations not being object-safe
@@ -149,6 +168,19 @@ pub trait WrbForm: Send + WrbFormClone + WrbFormDebug {
     fn element_id(&self) -> u128;
     /// What viewport are we attached to?
     fn viewport_id(&self) -> u128;
+    /// Is this element disabled?  Disabled elements are skipped in the focus order.
+    fn disabled(&self) -> bool {
+        false
+    }
+    /// Is this element's content secret?  Events passed to masked elements are not logged.
+    fn masked(&self) -> bool {
+        false
+    }
+    /// Does this element use `key` when it's focused?  Keys like page-up and page-down scroll the
+    /// element's viewport instead, unless the element uses them itself.
+    fn handles_key(&self, _key: &Key) -> bool {
+        false
+    }
     /// Set or unset focused
     fn focus(&mut self, root: &mut Root, focused: bool) -> Result<(), Error>;
     /// Render this
@@ -167,4 +199,10 @@ pub trait WrbForm: Send + WrbFormClone + WrbFormDebug {
     /// Serialize the state to a Clarity value, so it can be stored to the wrbsite.
     /// If not applicable to this UI element, then the implementation should return None.
     fn to_clarity_value(&self) -> Result<Option<Value>, Error>;
+    /// Apply settings that the wrbsite changed after this element was first drawn.  `value` is
+    /// the element's record, as given to `from_clarity_value()`.  State that the user controls is
+    /// kept.
+    fn merge_update(&mut self, _value: Value) -> Result<(), Error> {
+        Ok(())
+    }
 }
@@ -16,6 +16,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::ui::charbuff::Color;
+use crate::ui::charbuff::TextAttrs;
 use crate::ui::root::Root;
 use crate::ui::Error;
 use crate::ui::ValueExtensions;
@@ -34,6 +35,7 @@ pub struct PrintText {
     cursor: Option<(u64, u64)>,
     bg_color: Color,
     fg_color: Color,
+    attrs: TextAttrs,
     text: String,
     newline: bool,
 }
@@ -123,6 +125,13 @@ impl WrbForm for PrintText {
             .expect("FATAL: no `element-id`")
             .expect_u128()?;
 
+        let attrs: TextAttrs = text_tuple
+            .get("style")
+            .cloned()
+            .expect("FATAL: no `style`")
+            .expect_u128()?
+            .into();
+
         let newline = text_tuple
             .get("newline")
             .cloned()
@@ -138,6 +147,7 @@ impl WrbForm for PrintText {
             cursor,
             bg_color,
             fg_color,
+            attrs,
             text,
             newline,
         })
@@ -157,21 +167,23 @@ impl WrbForm for PrintText {
         let cursor = self.cursor.clone().unwrap_or(cursor);
         wrb_test_debug!("Print '{}' at {:?}", &self.text, &cursor);
         if self.newline {
-            Ok(viewport.println(
+            Ok(viewport.println_styled(
                 self.element_id,
                 cursor.0,
                 cursor.1,
                 self.bg_color,
                 self.fg_color,
+                self.attrs,
                 &self.text,
             ))
         } else {
-            Ok(viewport.print(
+            Ok(viewport.print_styled(
                 self.element_id,
                 cursor.0,
                 cursor.1,
                 self.bg_color,
                 self.fg_color,
+                self.attrs,
                 &self.text,
             ))
         }
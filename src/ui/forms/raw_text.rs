@@ -16,6 +16,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::ui::charbuff::Color;
+use crate::ui::charbuff::TextAttrs;
 use crate::ui::root::Root;
 use crate::ui::Error;
 use crate::ui::ValueExtensions;
@@ -34,6 +35,7 @@ pub struct RawText {
     col: u64,
     bg_color: Color,
     fg_color: Color,
+    attrs: TextAttrs,
     text: String,
 }
 
@@ -107,6 +109,13 @@ impl WrbForm for RawText {
             .expect("FATAL: no `element-id`")
             .expect_u128()?;
 
+        let attrs: TextAttrs = text_tuple
+            .get("style")
+            .cloned()
+            .expect("FATAL: no `style`")
+            .expect_u128()?
+            .into();
+
         let bg_color: Color = u32::try_from(bg_color_u128).expect("infallible").into();
         let fg_color: Color = u32::try_from(fg_color_u128).expect("infallible").into();
 
@@ -117,6 +126,7 @@ impl WrbForm for RawText {
             col: u64::try_from(col).map_err(|_| Error::Codec("col too big".into()))?,
             bg_color,
             fg_color,
+            attrs,
             text,
         })
     }
@@ -136,12 +146,13 @@ impl WrbForm for RawText {
             self.viewport_id,
             &self.text
         );
-        let new_cursor = viewport.print_to(
+        let new_cursor = viewport.print_to_styled(
             self.element_id,
             self.row,
             self.col,
             self.bg_color,
             self.fg_color,
+            self.attrs,
             &self.text,
         );
         Ok(new_cursor)
@@ -0,0 +1,655 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2023 Stacks Open Internet Foundation
+// Copyright (C) 2024 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use clarity::vm::types::TupleData;
+use clarity::vm::Value;
+
+use termion::event::Key;
+use termion::event::MouseButton;
+
+use crate::ui::charbuff::Color;
+use crate::ui::root::Root;
+use crate::ui::Error;
+use crate::ui::ValueExtensions;
+
+use crate::ui::forms::{WrbForm, WrbFormEvent, WrbFormTypes};
+
+use crate::DEFAULT_WRB_EPOCH;
+
+#[derive(Clone, PartialEq, Debug)]
+struct SelectOption {
+    text: String,
+    selected: bool,
+}
+
+impl SelectOption {
+    pub fn from_clarity_value(value: Value) -> Result<Self, Error> {
+        let select_option_tuple = value.expect_tuple()?;
+        let text = select_option_tuple
+            .get("text")
+            .cloned()
+            .expect("FATAL: no `text`")
+            .expect_utf8()?;
+
+        let selected = select_option_tuple
+            .get("selected")
+            .cloned()
+            .expect("FATAL: no `selected`")
+            .expect_bool()?;
+
+        Ok(Self { text, selected })
+    }
+
+    pub fn to_clarity_value(&self) -> Value {
+        Value::Tuple(
+            TupleData::from_data(vec![
+                (
+                    "text".into(),
+                    Value::string_utf8_from_string_utf8_literal(self.text.clone())
+                        .expect("FATAL: could not convert UTF-8 literal back to Clarity string"),
+                ),
+                ("selected".into(), Value::Bool(self.selected)),
+            ])
+            .expect("FATAL: could not build tuple from select option"),
+        )
+    }
+}
+
+/// UI command to pick one or more of a list of options.
+/// The options are shown `num_rows` at a time, and scroll to keep the selector visible.
+/// In dropdown mode, only the selected option is shown until the select is opened.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Select {
+    element_id: u128,
+    viewport_id: u128,
+    row: u64,
+    col: u64,
+    num_rows: u64,
+    num_cols: u64,
+    bg_color: Color,
+    fg_color: Color,
+    focused_bg_color: Color,
+    focused_fg_color: Color,
+    selector_color: Color,
+    /// can more than one option be selected?
+    multi: bool,
+    /// collapse to a single row when not opened?
+    dropdown: bool,
+    options: Vec<SelectOption>,
+    /// option under the selector
+    selector: usize,
+    /// first option shown
+    scroll: usize,
+    /// whether or not the dropdown is open
+    open: bool,
+    /// type-ahead search text
+    search: String,
+}
+
+pub const SELECT_MAX_LEN: usize = 256;
+
+/// Shown at the end of a dropdown's collapsed row
+const DROPDOWN_ARROW: &str = " ▼";
+
+impl Select {
+    pub fn new_detached(
+        options: Vec<(String, bool)>,
+        num_rows: u64,
+        num_cols: u64,
+        multi: bool,
+        dropdown: bool,
+    ) -> Self {
+        Self {
+            element_id: 0,
+            viewport_id: 0,
+            row: 0,
+            col: 0,
+            num_rows,
+            num_cols,
+            bg_color: 0u32.into(),
+            fg_color: 0xffffffu32.into(),
+            focused_bg_color: 0xffffffu32.into(),
+            focused_fg_color: 0u32.into(),
+            selector_color: 0xff0000u32.into(),
+            multi,
+            dropdown,
+            options: options
+                .into_iter()
+                .map(|(text, selected)| SelectOption { text, selected })
+                .collect(),
+            selector: 0,
+            scroll: 0,
+            open: false,
+            search: String::new(),
+        }
+    }
+
+    /// Indexes of the selected options
+    pub fn selected(&self) -> Vec<usize> {
+        self.options
+            .iter()
+            .enumerate()
+            .filter_map(|(i, option)| if option.selected { Some(i) } else { None })
+            .collect()
+    }
+
+    pub fn selector(&self) -> usize {
+        self.selector
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Are the options shown?
+    fn options_shown(&self) -> bool {
+        !self.dropdown || self.open
+    }
+
+    /// Row (relative to the select) of the first shown option
+    fn options_row(&self) -> u64 {
+        if self.dropdown {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Number of options shown at once
+    fn page_len(&self) -> usize {
+        usize::try_from(self.num_rows.max(1)).unwrap_or(usize::MAX)
+    }
+
+    /// Move the selector, and scroll so it's visible
+    fn move_selector(&mut self, selector: usize) {
+        self.selector = selector.min(self.options.len().saturating_sub(1));
+        if self.selector < self.scroll {
+            self.scroll = self.selector;
+        } else if self.selector >= self.scroll + self.page_len() {
+            self.scroll = self.selector + 1 - self.page_len();
+        }
+    }
+
+    /// Relative row of the cursor
+    fn cursor_row(&self) -> u64 {
+        if self.options_shown() {
+            self.options_row()
+                + u64::try_from(self.selector.saturating_sub(self.scroll)).unwrap_or(0)
+        } else {
+            0
+        }
+    }
+
+    /// Find the first option at or after `start` (wrapping around) whose text starts with the
+    /// search text
+    fn find_search_text(&self, start: usize) -> Option<usize> {
+        let search = self.search.to_lowercase();
+        let num_options = self.options.len();
+        (0..num_options)
+            .map(|i| (start + i) % num_options)
+            .find(|i| self.options[*i].text.to_lowercase().starts_with(&search))
+    }
+
+    /// Type-ahead: add a character to the search text and move to the first matching option.
+    /// If nothing matches, then search for just this character after the selector, so typing
+    /// the same character again cycles through the options that start with it.
+    fn type_ahead(&mut self, c: char) {
+        self.search.push(c);
+        let found = self.find_search_text(self.selector).or_else(|| {
+            self.search = c.to_string();
+            self.find_search_text(self.selector + 1)
+        });
+        if let Some(found) = found {
+            self.move_selector(found);
+        }
+    }
+
+    /// Select or toggle the option under the selector, and make the event payload
+    fn choose(&mut self) -> Option<Value> {
+        if self.options.is_empty() {
+            return None;
+        }
+        if self.multi {
+            self.options[self.selector].selected = !self.options[self.selector].selected;
+        } else {
+            for (i, option) in self.options.iter_mut().enumerate() {
+                option.selected = i == self.selector;
+            }
+        }
+        Some(self.event_payload())
+    }
+
+    /// The UI event payload: the option that was chosen, and all selected options
+    fn event_payload(&self) -> Value {
+        let selected = Value::cons_list(
+            self.selected()
+                .into_iter()
+                .map(|i| Value::UInt(u128::try_from(i).expect("infallible")))
+                .collect(),
+            &DEFAULT_WRB_EPOCH,
+        )
+        .expect("FATAL: failed to encode selected options list");
+
+        Value::Tuple(
+            TupleData::from_data(vec![
+                (
+                    "index".into(),
+                    Value::UInt(u128::try_from(self.selector).expect("infallible")),
+                ),
+                ("selected".into(), selected),
+            ])
+            .expect("FATAL: could not build select event tuple"),
+        )
+    }
+
+    /// Open or close the dropdown.  Closing it clears the viewport so the options it covered
+    /// get redrawn.
+    fn set_open(&mut self, root: &mut Root, open: bool) {
+        if !self.dropdown || self.open == open {
+            return;
+        }
+        self.open = open;
+        if !open {
+            if let Some(viewport) = root.viewport_mut(self.viewport_id) {
+                viewport.clear();
+            }
+        }
+    }
+
+    /// Put the cursor on the selector, if focused
+    fn update_cursor(&self, root: &mut Root) {
+        if root.is_focused(self.element_id) {
+            root.set_form_cursor(self.viewport_id, self.row + self.cursor_row(), self.col + 1);
+        }
+    }
+
+    /// Pad or truncate text to the select's width
+    fn fit_text(&self, text: &str, num_cols: u64) -> String {
+        let num_cols = usize::try_from(num_cols).unwrap_or(usize::MAX);
+        let mut fitted: String = text.chars().take(num_cols).collect();
+        while fitted.chars().count() < num_cols {
+            fitted.push(' ');
+        }
+        fitted
+    }
+
+    /// Text of the dropdown's collapsed row
+    fn dropdown_text(&self) -> String {
+        let selected_text = self
+            .options
+            .iter()
+            .filter(|option| option.selected)
+            .map(|option| option.text.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let arrow_len = u64::try_from(DROPDOWN_ARROW.chars().count()).expect("infallible");
+        format!(
+            "{}{}",
+            self.fit_text(&selected_text, self.num_cols.saturating_sub(arrow_len)),
+            DROPDOWN_ARROW
+        )
+    }
+
+    /// Text of an option's row
+    fn option_text(&self, option: &SelectOption) -> String {
+        let mark = match (self.multi, option.selected) {
+            (true, true) => "[*] ",
+            (true, false) => "[ ] ",
+            (false, true) => "(*) ",
+            (false, false) => "( ) ",
+        };
+        self.fit_text(&format!("{}{}", mark, &option.text), self.num_cols)
+    }
+}
+
+impl WrbForm for Select {
+    /// type
+    fn type_id(&self) -> WrbFormTypes {
+        WrbFormTypes::Select
+    }
+
+    fn element_id(&self) -> u128 {
+        self.element_id
+    }
+
+    fn viewport_id(&self) -> u128 {
+        self.viewport_id
+    }
+
+    fn focus(&mut self, root: &mut Root, focused: bool) -> Result<(), Error> {
+        if focused {
+            self.update_cursor(root);
+        } else {
+            self.search.clear();
+            self.set_open(root, false);
+        }
+        Ok(())
+    }
+
+    /// Load from a Clarity value
+    fn from_clarity_value(viewport_id: u128, v: Value) -> Result<Self, Error> {
+        let select_tuple = v.expect_tuple()?;
+        let row = u64::try_from(
+            select_tuple
+                .get("row")
+                .cloned()
+                .expect("FATAL: no `row`")
+                .expect_u128()?,
+        )
+        .map_err(|_| Error::Codec("row is too big".into()))?;
+
+        let col = u64::try_from(
+            select_tuple
+                .get("col")
+                .cloned()
+                .expect("FATAL: no `col`")
+                .expect_u128()?,
+        )
+        .map_err(|_| Error::Codec("col is too big".into()))?;
+
+        let num_rows = u64::try_from(
+            select_tuple
+                .get("num-rows")
+                .cloned()
+                .expect("FATAL: no `num-rows`")
+                .expect_u128()?,
+        )
+        .map_err(|_| Error::Codec("num-rows is too big".into()))?;
+
+        let num_cols = u64::try_from(
+            select_tuple
+                .get("num-cols")
+                .cloned()
+                .expect("FATAL: no `num-cols`")
+                .expect_u128()?,
+        )
+        .map_err(|_| Error::Codec("num-cols is too big".into()))?;
+
+        let bg_color_u128 = select_tuple
+            .get("bg-color")
+            .cloned()
+            .expect("FATAL: no `bg-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let fg_color_u128 = select_tuple
+            .get("fg-color")
+            .cloned()
+            .expect("FATAL: no `fg-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let focused_bg_color_u128 = select_tuple
+            .get("focused-bg-color")
+            .cloned()
+            .expect("FATAL: no `focused-bg-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let focused_fg_color_u128 = select_tuple
+            .get("focused-fg-color")
+            .cloned()
+            .expect("FATAL: no `focused-fg-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let selector_color_u128 = select_tuple
+            .get("selector-color")
+            .cloned()
+            .expect("FATAL: no `selector-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let element_id = select_tuple
+            .get("element-id")
+            .cloned()
+            .expect("FATAL: no `element-id`")
+            .expect_u128()?;
+
+        let multi = select_tuple
+            .get("multi")
+            .cloned()
+            .expect("FATAL: no `multi`")
+            .expect_bool()?;
+
+        let dropdown = select_tuple
+            .get("dropdown")
+            .cloned()
+            .expect("FATAL: no `dropdown`")
+            .expect_bool()?;
+
+        let options_list = select_tuple
+            .get("options")
+            .cloned()
+            .expect("FATAL: no `options`")
+            .expect_list()?;
+
+        if options_list.len() > SELECT_MAX_LEN {
+            return Err(Error::Page(format!(
+                "Too many select options (max is {}, but got {})",
+                SELECT_MAX_LEN,
+                options_list.len()
+            )));
+        }
+
+        let mut options = vec![];
+        for option_value in options_list.into_iter() {
+            let select_option = SelectOption::from_clarity_value(option_value)?;
+            options.push(select_option);
+        }
+
+        let bg_color: Color = u32::try_from(bg_color_u128).expect("infallible").into();
+        let fg_color: Color = u32::try_from(fg_color_u128).expect("infallible").into();
+        let focused_bg_color: Color = u32::try_from(focused_bg_color_u128)
+            .expect("infallible")
+            .into();
+        let focused_fg_color: Color = u32::try_from(focused_fg_color_u128)
+            .expect("infallible")
+            .into();
+        let selector_color: Color = u32::try_from(selector_color_u128)
+            .expect("infallible")
+            .into();
+
+        // start at the first selected option
+        let mut select = Self {
+            element_id,
+            viewport_id,
+            row,
+            col,
+            num_rows,
+            num_cols,
+            bg_color,
+            fg_color,
+            focused_bg_color,
+            focused_fg_color,
+            selector_color,
+            multi,
+            dropdown,
+            options,
+            selector: 0,
+            scroll: 0,
+            open: false,
+            search: String::new(),
+        };
+        if let Some(first_selected) = select.selected().first() {
+            select.move_selector(*first_selected);
+        }
+        Ok(select)
+    }
+
+    /// Store back to a Clarity value.
+    /// Only returns the actionable data.
+    fn to_clarity_value(&self) -> Result<Option<Value>, Error> {
+        let value = Value::cons_list(
+            self.options
+                .iter()
+                .map(|option| option.to_clarity_value())
+                .collect(),
+            &DEFAULT_WRB_EPOCH,
+        )
+        .expect("FATAL: failed to encode select options list");
+        Ok(Some(value))
+    }
+
+    /// Render the select
+    fn render(&mut self, root: &mut Root, cursor: (u64, u64)) -> Result<(u64, u64), Error> {
+        let focused = root.is_focused(self.element_id);
+        let dropdown_text = self.dropdown_text();
+        let option_texts: Vec<_> = self
+            .options
+            .iter()
+            .skip(self.scroll)
+            .take(self.page_len())
+            .map(|option| self.option_text(option))
+            .collect();
+
+        let Some(viewport) = root.viewport_mut(self.viewport_id) else {
+            return Err(Error::NoViewport(self.viewport_id));
+        };
+        wrb_test_debug!("Select at ({},{})", self.row, self.col);
+
+        let bg_color = if focused {
+            self.focused_bg_color.clone()
+        } else {
+            self.bg_color.clone()
+        };
+        let fg_color = if focused {
+            self.focused_fg_color.clone()
+        } else {
+            self.fg_color.clone()
+        };
+
+        let mut next_cursor = cursor;
+        if self.dropdown {
+            next_cursor = viewport.print_to(
+                self.element_id,
+                self.row,
+                self.col,
+                bg_color,
+                fg_color,
+                &dropdown_text,
+            );
+        }
+
+        if self.options_shown() {
+            for (i, option_text) in option_texts.iter().enumerate() {
+                let row = self.row
+                    + self.options_row()
+                    + u64::try_from(i).expect("infallible: too many options");
+                let option_bg_color = if self.scroll + i == self.selector {
+                    self.selector_color
+                } else {
+                    bg_color
+                };
+                next_cursor = viewport.print_to(
+                    self.element_id,
+                    row,
+                    self.col,
+                    option_bg_color,
+                    fg_color,
+                    option_text,
+                );
+            }
+        }
+
+        // set the cursor to be the option at the selector
+        self.update_cursor(root);
+        Ok(next_cursor)
+    }
+
+    /// Handle an event
+    fn handle_event(
+        &mut self,
+        root: &mut Root,
+        event: WrbFormEvent,
+    ) -> Result<Option<Value>, Error> {
+        let keycode = match event {
+            WrbFormEvent::Keypress(keycode) => keycode,
+            WrbFormEvent::Mouse(MouseButton::Left, row, _) => {
+                // clicking a collapsed dropdown opens it, and clicking an option chooses it
+                if !self.options_shown() {
+                    self.set_open(root, true);
+                    self.update_cursor(root);
+                    return Ok(None);
+                }
+                let Some(option_row) = row.checked_sub(self.options_row()) else {
+                    // clicked the dropdown's collapsed row
+                    self.set_open(root, false);
+                    self.update_cursor(root);
+                    return Ok(None);
+                };
+                let option = self
+                    .scroll
+                    .saturating_add(usize::try_from(option_row).unwrap_or(usize::MAX));
+                if option >= self.options.len() {
+                    return Ok(None);
+                }
+                self.move_selector(option);
+                root.keycode_enter()
+            }
+            // the wheel moves the selector
+            WrbFormEvent::Mouse(MouseButton::WheelUp, _, _) => root.keycode_up(),
+            WrbFormEvent::Mouse(MouseButton::WheelDown, _, _) => root.keycode_down(),
+            WrbFormEvent::Mouse(..) => {
+                return Ok(None);
+            }
+        };
+
+        if self.options.is_empty() {
+            return Ok(None);
+        }
+
+        let mut payload = None;
+        match keycode {
+            Key::Char(c) if c != '\n' && c != ' ' => {
+                self.type_ahead(c);
+                self.set_open(root, true);
+            }
+            _ => {
+                self.search.clear();
+                if keycode == root.keycode_up() {
+                    self.move_selector(self.selector.saturating_sub(1));
+                    self.set_open(root, true);
+                } else if keycode == root.keycode_down() {
+                    self.move_selector(self.selector.saturating_add(1));
+                    self.set_open(root, true);
+                } else if keycode == Key::Home {
+                    self.move_selector(0);
+                } else if keycode == Key::End {
+                    self.move_selector(self.options.len() - 1);
+                } else if keycode == root.keycode_enter() || keycode == root.keycode_space() {
+                    if self.options_shown() {
+                        payload = self.choose();
+                        if !self.multi {
+                            self.set_open(root, false);
+                        }
+                    } else {
+                        self.set_open(root, true);
+                    }
+                }
+            }
+        }
+
+        self.update_cursor(root);
+        Ok(payload)
+    }
+}
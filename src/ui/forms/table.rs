@@ -0,0 +1,630 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2023 Stacks Open Internet Foundation
+// Copyright (C) 2024 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp::Ordering;
+
+use clarity::vm::Value;
+
+use termion::event::Key;
+use termion::event::MouseButton;
+
+use crate::ui::charbuff::Color;
+use crate::ui::root::Root;
+use crate::ui::Error;
+use crate::ui::ValueExtensions;
+
+use crate::ui::forms::{WrbForm, WrbFormEvent, WrbFormTypes};
+
+#[derive(Clone, PartialEq, Debug)]
+struct TableColumn {
+    header: String,
+    width: u64,
+}
+
+impl TableColumn {
+    pub fn from_clarity_value(value: Value) -> Result<Self, Error> {
+        let column_tuple = value.expect_tuple()?;
+        let header = column_tuple
+            .get("header")
+            .cloned()
+            .expect("FATAL: no `header`")
+            .expect_utf8()?;
+
+        let width = u64::try_from(
+            column_tuple
+                .get("width")
+                .cloned()
+                .expect("FATAL: no `width`")
+                .expect_u128()?,
+        )
+        .map_err(|_| Error::Codec("width is too big".into()))?;
+
+        Ok(Self { header, width })
+    }
+}
+
+/// UI command to show rows of cells under column headers.
+/// Up to `num_rows` rows are shown at a time, and scroll to keep the selector visible.
+/// The rows can be sorted by any column, and pressing enter on (or clicking) a row activates it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Table {
+    element_id: u128,
+    viewport_id: u128,
+    row: u64,
+    col: u64,
+    num_rows: u64,
+    bg_color: Color,
+    fg_color: Color,
+    focused_bg_color: Color,
+    focused_fg_color: Color,
+    selector_color: Color,
+    columns: Vec<TableColumn>,
+    rows: Vec<Vec<String>>,
+    /// indexes into `rows`, in the order shown
+    order: Vec<usize>,
+    /// column to sort by, and whether or not the sort is descending
+    sort: Option<(usize, bool)>,
+    /// position in `order` of the selected row
+    selector: usize,
+    /// position in `order` of the first row shown
+    scroll: usize,
+}
+
+pub const TABLE_MAX_COLUMNS: usize = 8;
+pub const TABLE_MAX_ROWS: usize = 128;
+
+/// Rows taken up by the top border, header, and header separator
+const TABLE_HEADER_ROWS: u64 = 3;
+
+const SORT_ASCENDING: char = '▲';
+const SORT_DESCENDING: char = '▼';
+
+impl Table {
+    pub fn new_detached(
+        columns: Vec<(String, u64)>,
+        rows: Vec<Vec<String>>,
+        num_rows: u64,
+    ) -> Self {
+        let num_rows = Self::clamp_num_rows(num_rows, &rows);
+        let order = (0..rows.len()).collect();
+        Self {
+            element_id: 0,
+            viewport_id: 0,
+            row: 0,
+            col: 0,
+            num_rows,
+            bg_color: 0u32.into(),
+            fg_color: 0xffffffu32.into(),
+            focused_bg_color: 0xffffffu32.into(),
+            focused_fg_color: 0u32.into(),
+            selector_color: 0xff0000u32.into(),
+            columns: columns
+                .into_iter()
+                .map(|(header, width)| TableColumn { header, width })
+                .collect(),
+            rows,
+            order,
+            sort: None,
+            selector: 0,
+            scroll: 0,
+        }
+    }
+
+    /// Index (in the page's row list) of the selected row, if there are any rows
+    pub fn selected_row(&self) -> Option<usize> {
+        self.order.get(self.selector).cloned()
+    }
+
+    /// Indexes (in the page's row list) of the rows, in the order shown
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// The table is no taller than its rows, so it never pads them with blank lines.
+    /// An empty table still shows one blank row.
+    fn clamp_num_rows(num_rows: u64, rows: &[Vec<String>]) -> u64 {
+        num_rows.min(u64::try_from(rows.len()).unwrap_or(u64::MAX))
+    }
+
+    /// Number of rows shown at once
+    fn page_len(&self) -> usize {
+        usize::try_from(self.num_rows.max(1)).unwrap_or(usize::MAX)
+    }
+
+    /// Move the selector, and scroll so it's visible
+    fn move_selector(&mut self, selector: usize) {
+        self.selector = selector.min(self.order.len().saturating_sub(1));
+        if self.selector < self.scroll {
+            self.scroll = self.selector;
+        } else if self.selector >= self.scroll + self.page_len() {
+            self.scroll = self.selector + 1 - self.page_len();
+        }
+    }
+
+    /// Compare two cells.  Cells which are both numbers compare as numbers; otherwise, they
+    /// compare as case-insensitive text.
+    fn compare_cells(a: &str, b: &str) -> Ordering {
+        match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+            (Ok(a_num), Ok(b_num)) => a_num.partial_cmp(&b_num).unwrap_or(Ordering::Equal),
+            _ => a.to_lowercase().cmp(&b.to_lowercase()),
+        }
+    }
+
+    /// Sort by the given column.  Sorting by the same column again reverses the order.
+    /// Sorting by `None` restores the page's order.
+    /// The same row stays selected.
+    pub fn sort_by(&mut self, column: Option<usize>) {
+        let selected_row = self.selected_row();
+        self.sort = match (column, self.sort) {
+            (None, _) => None,
+            (Some(column), _) if column >= self.columns.len() => self.sort,
+            (Some(column), Some((sort_column, descending))) if column == sort_column => {
+                Some((column, !descending))
+            }
+            (Some(column), _) => Some((column, false)),
+        };
+
+        let mut order: Vec<usize> = (0..self.rows.len()).collect();
+        if let Some((column, descending)) = self.sort {
+            let empty = String::new();
+            order.sort_by(|a, b| {
+                let a_cell = self.rows[*a].get(column).unwrap_or(&empty);
+                let b_cell = self.rows[*b].get(column).unwrap_or(&empty);
+                let ordering = Self::compare_cells(a_cell, b_cell);
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        self.order = order;
+
+        if let Some(selected_row) = selected_row {
+            let selector = self
+                .order
+                .iter()
+                .position(|row| *row == selected_row)
+                .unwrap_or(0);
+            self.move_selector(selector);
+        }
+    }
+
+    /// Which column is at the given column offset from the table's left edge?
+    fn column_at(&self, rel_col: u64) -> Option<usize> {
+        // skip the left border
+        let mut col_start = 1;
+        for (i, column) in self.columns.iter().enumerate() {
+            if col_start <= rel_col && rel_col < col_start + column.width {
+                return Some(i);
+            }
+            // skip the column and its right border
+            col_start += column.width + 1;
+        }
+        None
+    }
+
+    /// Pad or truncate text to a cell's width.  Truncated text ends in an ellipsis.
+    fn fit_text(text: &str, width: u64) -> String {
+        let width = usize::try_from(width).unwrap_or(usize::MAX);
+        let mut fitted: String = if text.chars().count() > width {
+            let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+            if width > 0 {
+                truncated.push('…');
+            }
+            truncated
+        } else {
+            text.to_string()
+        };
+        while fitted.chars().count() < width {
+            fitted.push(' ');
+        }
+        fitted
+    }
+
+    /// A horizontal border line, with the given left, middle, and right corners
+    fn border_line(&self, left: char, middle: char, right: char) -> String {
+        let mut line = String::new();
+        line.push(left);
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                line.push(middle);
+            }
+            for _ in 0..column.width {
+                line.push('─');
+            }
+        }
+        line.push(right);
+        line
+    }
+
+    /// A line of cells, with borders
+    fn cells_line<'a>(&self, mut cells: impl Iterator<Item = &'a str>) -> String {
+        let mut line = String::from("│");
+        for column in self.columns.iter() {
+            let cell = cells.next().unwrap_or("");
+            line.push_str(&Self::fit_text(cell, column.width));
+            line.push('│');
+        }
+        line
+    }
+
+    /// The header line, with the sort indicator on the sorted column
+    fn header_line(&self) -> String {
+        let headers: Vec<String> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| match self.sort {
+                Some((sort_column, descending)) if sort_column == i => {
+                    let indicator = if descending {
+                        SORT_DESCENDING
+                    } else {
+                        SORT_ASCENDING
+                    };
+                    let width = column.width.saturating_sub(1);
+                    format!("{}{}", Self::fit_text(&column.header, width), indicator)
+                }
+                _ => column.header.clone(),
+            })
+            .collect();
+        self.cells_line(headers.iter().map(|header| header.as_str()))
+    }
+
+    /// Put the cursor on the selected row, if focused
+    fn update_cursor(&self, root: &mut Root) {
+        if root.is_focused(self.element_id) {
+            let selector_row =
+                u64::try_from(self.selector.saturating_sub(self.scroll)).unwrap_or(0);
+            root.set_form_cursor(
+                self.viewport_id,
+                self.row + TABLE_HEADER_ROWS + selector_row,
+                self.col + 1,
+            );
+        }
+    }
+
+    /// The UI event payload for activating the selected row: its index in the page's row list
+    fn activate(&self) -> Option<Value> {
+        self.selected_row()
+            .map(|row| Value::UInt(u128::try_from(row).expect("infallible")))
+    }
+}
+
+impl WrbForm for Table {
+    /// type
+    fn type_id(&self) -> WrbFormTypes {
+        WrbFormTypes::Table
+    }
+
+    fn element_id(&self) -> u128 {
+        self.element_id
+    }
+
+    fn viewport_id(&self) -> u128 {
+        self.viewport_id
+    }
+
+    /// Page-up and page-down move the selector a page at a time
+    fn handles_key(&self, key: &Key) -> bool {
+        matches!(key, Key::PageUp | Key::PageDown)
+    }
+
+    fn focus(&mut self, root: &mut Root, focused: bool) -> Result<(), Error> {
+        if focused {
+            self.update_cursor(root);
+        }
+        Ok(())
+    }
+
+    /// Load from a Clarity value
+    fn from_clarity_value(viewport_id: u128, v: Value) -> Result<Self, Error> {
+        let table_tuple = v.expect_tuple()?;
+        let row = u64::try_from(
+            table_tuple
+                .get("row")
+                .cloned()
+                .expect("FATAL: no `row`")
+                .expect_u128()?,
+        )
+        .map_err(|_| Error::Codec("row is too big".into()))?;
+
+        let col = u64::try_from(
+            table_tuple
+                .get("col")
+                .cloned()
+                .expect("FATAL: no `col`")
+                .expect_u128()?,
+        )
+        .map_err(|_| Error::Codec("col is too big".into()))?;
+
+        let num_rows = u64::try_from(
+            table_tuple
+                .get("num-rows")
+                .cloned()
+                .expect("FATAL: no `num-rows`")
+                .expect_u128()?,
+        )
+        .map_err(|_| Error::Codec("num-rows is too big".into()))?;
+
+        let bg_color_u128 = table_tuple
+            .get("bg-color")
+            .cloned()
+            .expect("FATAL: no `bg-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let fg_color_u128 = table_tuple
+            .get("fg-color")
+            .cloned()
+            .expect("FATAL: no `fg-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let focused_bg_color_u128 = table_tuple
+            .get("focused-bg-color")
+            .cloned()
+            .expect("FATAL: no `focused-bg-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let focused_fg_color_u128 = table_tuple
+            .get("focused-fg-color")
+            .cloned()
+            .expect("FATAL: no `focused-fg-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let selector_color_u128 = table_tuple
+            .get("selector-color")
+            .cloned()
+            .expect("FATAL: no `selector-color`")
+            .expect_u128()?
+            // truncate
+            & 0xffffffffu128;
+
+        let element_id = table_tuple
+            .get("element-id")
+            .cloned()
+            .expect("FATAL: no `element-id`")
+            .expect_u128()?;
+
+        let columns_list = table_tuple
+            .get("columns")
+            .cloned()
+            .expect("FATAL: no `columns`")
+            .expect_list()?;
+
+        if columns_list.len() > TABLE_MAX_COLUMNS {
+            return Err(Error::Page(format!(
+                "Too many table columns (max is {}, but got {})",
+                TABLE_MAX_COLUMNS,
+                columns_list.len()
+            )));
+        }
+
+        let rows_list = table_tuple
+            .get("rows")
+            .cloned()
+            .expect("FATAL: no `rows`")
+            .expect_list()?;
+
+        if rows_list.len() > TABLE_MAX_ROWS {
+            return Err(Error::Page(format!(
+                "Too many table rows (max is {}, but got {})",
+                TABLE_MAX_ROWS,
+                rows_list.len()
+            )));
+        }
+
+        let mut columns = vec![];
+        for column_value in columns_list.into_iter() {
+            columns.push(TableColumn::from_clarity_value(column_value)?);
+        }
+
+        let mut rows = vec![];
+        for row_value in rows_list.into_iter() {
+            let mut cells = vec![];
+            for cell_value in row_value.expect_list()?.into_iter() {
+                cells.push(cell_value.expect_utf8()?);
+            }
+            rows.push(cells);
+        }
+
+        let bg_color: Color = u32::try_from(bg_color_u128).expect("infallible").into();
+        let fg_color: Color = u32::try_from(fg_color_u128).expect("infallible").into();
+        let focused_bg_color: Color = u32::try_from(focused_bg_color_u128)
+            .expect("infallible")
+            .into();
+        let focused_fg_color: Color = u32::try_from(focused_fg_color_u128)
+            .expect("infallible")
+            .into();
+        let selector_color: Color = u32::try_from(selector_color_u128)
+            .expect("infallible")
+            .into();
+
+        let num_rows = Self::clamp_num_rows(num_rows, &rows);
+        let order = (0..rows.len()).collect();
+        Ok(Self {
+            element_id,
+            viewport_id,
+            row,
+            col,
+            num_rows,
+            bg_color,
+            fg_color,
+            focused_bg_color,
+            focused_fg_color,
+            selector_color,
+            columns,
+            rows,
+            order,
+            sort: None,
+            selector: 0,
+            scroll: 0,
+        })
+    }
+
+    /// Store back to a Clarity value.
+    /// Only returns the actionable data -- the selected row, if there is one.
+    fn to_clarity_value(&self) -> Result<Option<Value>, Error> {
+        Ok(self.activate())
+    }
+
+    /// Render the table
+    fn render(&mut self, root: &mut Root, cursor: (u64, u64)) -> Result<(u64, u64), Error> {
+        let focused = root.is_focused(self.element_id);
+
+        let top_line = self.border_line('┌', '┬', '┐');
+        let header_line = self.header_line();
+        let separator_line = self.border_line('├', '┼', '┤');
+        let bottom_line = self.border_line('└', '┴', '┘');
+        let row_lines: Vec<_> = (0..self.page_len())
+            .map(|i| match self.order.get(self.scroll + i) {
+                Some(row_index) => {
+                    self.cells_line(self.rows[*row_index].iter().map(|cell| cell.as_str()))
+                }
+                None => self.cells_line(std::iter::empty()),
+            })
+            .collect();
+
+        let Some(viewport) = root.viewport_mut(self.viewport_id) else {
+            return Err(Error::NoViewport(self.viewport_id));
+        };
+        wrb_test_debug!("Table at ({},{})", self.row, self.col);
+
+        let bg_color = if focused {
+            self.focused_bg_color.clone()
+        } else {
+            self.bg_color.clone()
+        };
+        let fg_color = if focused {
+            self.focused_fg_color.clone()
+        } else {
+            self.fg_color.clone()
+        };
+
+        for (i, line) in [top_line, header_line, separator_line].iter().enumerate() {
+            viewport.print_to(
+                self.element_id,
+                self.row + u64::try_from(i).expect("infallible"),
+                self.col,
+                bg_color,
+                fg_color,
+                line,
+            );
+        }
+
+        for (i, line) in row_lines.iter().enumerate() {
+            let row_bg_color = if self.scroll + i == self.selector && !self.order.is_empty() {
+                self.selector_color
+            } else {
+                bg_color
+            };
+            viewport.print_to(
+                self.element_id,
+                self.row + TABLE_HEADER_ROWS + u64::try_from(i).expect("infallible"),
+                self.col,
+                row_bg_color,
+                fg_color,
+                line,
+            );
+        }
+
+        let next_cursor = viewport.print_to(
+            self.element_id,
+            self.row + TABLE_HEADER_ROWS + self.num_rows.max(1),
+            self.col,
+            bg_color,
+            fg_color,
+            &bottom_line,
+        );
+
+        // set the cursor to be the selected row
+        self.update_cursor(root);
+        Ok(next_cursor)
+    }
+
+    /// Handle an event
+    fn handle_event(
+        &mut self,
+        root: &mut Root,
+        event: WrbFormEvent,
+    ) -> Result<Option<Value>, Error> {
+        let mut payload = None;
+        match event {
+            WrbFormEvent::Keypress(keycode) => {
+                if keycode == root.keycode_up() {
+                    self.move_selector(self.selector.saturating_sub(1));
+                } else if keycode == root.keycode_down() {
+                    self.move_selector(self.selector.saturating_add(1));
+                } else if keycode == Key::Home {
+                    self.move_selector(0);
+                } else if keycode == Key::End {
+                    self.move_selector(self.order.len().saturating_sub(1));
+                } else if keycode == Key::PageUp {
+                    self.move_selector(self.selector.saturating_sub(self.page_len()));
+                } else if keycode == Key::PageDown {
+                    self.move_selector(self.selector.saturating_add(self.page_len()));
+                } else if keycode == root.keycode_enter() || keycode == root.keycode_space() {
+                    payload = self.activate();
+                } else if let Key::Char(c) = keycode {
+                    // digits sort by a column (1 is the first); 0 restores the page's order
+                    match c.to_digit(10) {
+                        Some(0) => self.sort_by(None),
+                        Some(column) => {
+                            self.sort_by(Some(usize::try_from(column - 1).expect("infallible")))
+                        }
+                        None => {}
+                    }
+                }
+            }
+            WrbFormEvent::Mouse(MouseButton::Left, row, col) => {
+                if row == 1 {
+                    // clicking a header sorts by its column
+                    if let Some(column) = self.column_at(col) {
+                        self.sort_by(Some(column));
+                    }
+                } else if let Some(data_row) = row.checked_sub(TABLE_HEADER_ROWS) {
+                    // clicking a row selects and activates it
+                    let position = self
+                        .scroll
+                        .saturating_add(usize::try_from(data_row).unwrap_or(usize::MAX));
+                    if data_row < self.num_rows.max(1) && position < self.order.len() {
+                        self.move_selector(position);
+                        payload = self.activate();
+                    }
+                }
+            }
+            // the wheel moves the selector
+            WrbFormEvent::Mouse(MouseButton::WheelUp, _, _) => {
+                self.move_selector(self.selector.saturating_sub(1));
+            }
+            WrbFormEvent::Mouse(MouseButton::WheelDown, _, _) => {
+                self.move_selector(self.selector.saturating_add(1));
+            }
+            WrbFormEvent::Mouse(..) => {}
+        }
+
+        self.update_cursor(root);
+        Ok(payload)
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::ui::forms::Checkbox;
+use crate::ui::forms::WrbForm;
+use crate::ui::forms::WrbFormEvent;
+
+use crate::ui::Root;
+
+use termion::event::Key;
+use termion::event::MouseButton;
+
+fn options(texts: &[&str]) -> Vec<(String, bool)> {
+    texts.iter().map(|text| (text.to_string(), false)).collect()
+}
+
+#[test]
+fn test_checkbox_handle_event() {
+    let mut root = Root::null();
+    let mut checkbox = Checkbox::new_detached(options(&["one", "two", "three"]), false);
+    assert!(checkbox.selected().is_empty());
+
+    // any number of options can be selected
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char(' ')))
+        .unwrap();
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Down))
+        .unwrap();
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('\n')))
+        .unwrap();
+    assert_eq!(checkbox.selected(), vec![0, 1]);
+
+    // and toggled off
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char(' ')))
+        .unwrap();
+    assert_eq!(checkbox.selected(), vec![0]);
+}
+
+#[test]
+fn test_checkbox_radio() {
+    let mut root = Root::null();
+
+    // a radio group with nothing selected selects its first option
+    let checkbox = Checkbox::new_detached(options(&["one", "two", "three"]), true);
+    assert_eq!(checkbox.selected(), vec![0]);
+
+    // a radio group with several selected options keeps only the first
+    let mut checkbox = Checkbox::new_detached(
+        vec![
+            ("one".to_string(), false),
+            ("two".to_string(), true),
+            ("three".to_string(), true),
+        ],
+        true,
+    );
+    assert_eq!(checkbox.selected(), vec![1]);
+
+    // selecting an option deselects the others
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 2, 1))
+        .unwrap();
+    assert_eq!(checkbox.selector(), 2);
+    assert_eq!(checkbox.selected(), vec![2]);
+
+    // the selected option cannot be deselected
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char(' ')))
+        .unwrap();
+    assert_eq!(checkbox.selected(), vec![2]);
+}
+
+#[test]
+fn test_checkbox_disabled() {
+    let mut root = Root::null();
+    let mut checkbox = Checkbox::new_detached(options(&["one", "two", "three", "four"]), false);
+    checkbox.set_option_disabled(0, true);
+    checkbox.set_option_disabled(2, true);
+
+    // the selector starts on the first enabled option
+    assert_eq!(checkbox.selector(), 1);
+
+    // the selector skips disabled options
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Down))
+        .unwrap();
+    assert_eq!(checkbox.selector(), 3);
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Down))
+        .unwrap();
+    assert_eq!(checkbox.selector(), 3);
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Up))
+        .unwrap();
+    assert_eq!(checkbox.selector(), 1);
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Up))
+        .unwrap();
+    assert_eq!(checkbox.selector(), 1);
+
+    // disabled options can't be clicked
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 2, 1))
+        .unwrap();
+    assert_eq!(checkbox.selector(), 1);
+    assert!(checkbox.selected().is_empty());
+
+    // a disabled checkbox ignores everything
+    assert!(!checkbox.disabled());
+    checkbox.set_disabled(true);
+    assert!(checkbox.disabled());
+    checkbox
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char(' ')))
+        .unwrap();
+    assert!(checkbox.selected().is_empty());
+}
@@ -15,5 +15,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+pub mod checkbox;
+pub mod select;
+pub mod table;
 pub mod textarea;
 pub mod textline;
@@ -0,0 +1,207 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::ui::forms::Select;
+use crate::ui::forms::WrbForm;
+use crate::ui::forms::WrbFormEvent;
+
+use crate::ui::Root;
+
+use crate::DEFAULT_WRB_EPOCH;
+
+use clarity::vm::types::TupleData;
+use clarity::vm::Value;
+
+use termion::event::Key;
+use termion::event::MouseButton;
+
+fn options(texts: &[&str]) -> Vec<(String, bool)> {
+    texts.iter().map(|text| (text.to_string(), false)).collect()
+}
+
+fn select_payload(index: u128, selected: &[u128]) -> Option<Value> {
+    Some(Value::Tuple(
+        TupleData::from_data(vec![
+            ("index".into(), Value::UInt(index)),
+            (
+                "selected".into(),
+                Value::cons_list(
+                    selected.iter().map(|i| Value::UInt(*i)).collect(),
+                    &DEFAULT_WRB_EPOCH,
+                )
+                .unwrap(),
+            ),
+        ])
+        .unwrap(),
+    ))
+}
+
+#[test]
+fn test_select_handle_event() {
+    let mut root = Root::null();
+    let mut select = Select::new_detached(
+        options(&["apple", "banana", "blueberry", "cherry"]),
+        2,
+        20,
+        false,
+        false,
+    );
+    assert_eq!(select.selector(), 0);
+    assert!(select.selected().is_empty());
+
+    // moving the selector doesn't choose anything
+    for _ in 0..2 {
+        assert_eq!(
+            select
+                .handle_event(&mut root, WrbFormEvent::Keypress(Key::Down))
+                .unwrap(),
+            None
+        );
+    }
+    assert_eq!(select.selector(), 2);
+
+    assert_eq!(
+        select
+            .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('\n')))
+            .unwrap(),
+        select_payload(2, &[2])
+    );
+    assert_eq!(select.selected(), vec![2]);
+
+    // type-ahead
+    select
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('a')))
+        .unwrap();
+    assert_eq!(select.selector(), 0);
+
+    // no option starts with "ab", so this finds the next option starting with "b"
+    select
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('b')))
+        .unwrap();
+    assert_eq!(select.selector(), 1);
+
+    // typing the same character again cycles through the matches
+    select
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('b')))
+        .unwrap();
+    assert_eq!(select.selector(), 2);
+
+    select
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('l')))
+        .unwrap();
+    assert_eq!(select.selector(), 2);
+
+    // single-select: choosing an option deselects the others
+    select
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Home))
+        .unwrap();
+    assert_eq!(select.selector(), 0);
+    assert_eq!(
+        select
+            .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char(' ')))
+            .unwrap(),
+        select_payload(0, &[0])
+    );
+    assert_eq!(select.selected(), vec![0]);
+
+    // the list scrolls to keep the selector visible, and clicks are relative to the scroll
+    select
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::End))
+        .unwrap();
+    assert_eq!(select.selector(), 3);
+    assert_eq!(
+        select
+            .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 0, 4))
+            .unwrap(),
+        select_payload(2, &[2])
+    );
+
+    // clicking past the last option does nothing
+    let mut select = Select::new_detached(options(&["apple", "banana"]), 4, 20, false, false);
+    assert_eq!(
+        select
+            .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 3, 0))
+            .unwrap(),
+        None
+    );
+    assert!(select.selected().is_empty());
+}
+
+#[test]
+fn test_select_dropdown_multi() {
+    let mut root = Root::null();
+    let mut select = Select::new_detached(options(&["a", "b", "c"]), 3, 20, true, true);
+    assert!(!select.is_open());
+
+    // enter opens the dropdown
+    assert_eq!(
+        select
+            .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('\n')))
+            .unwrap(),
+        None
+    );
+    assert!(select.is_open());
+
+    // multi-select: choosing toggles, and the dropdown stays open
+    select
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Down))
+        .unwrap();
+    assert_eq!(
+        select
+            .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('\n')))
+            .unwrap(),
+        select_payload(1, &[1])
+    );
+    select
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Up))
+        .unwrap();
+    assert_eq!(
+        select
+            .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char(' ')))
+            .unwrap(),
+        select_payload(0, &[0, 1])
+    );
+    assert!(select.is_open());
+
+    // clicking the collapsed row closes and opens it
+    select
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 0, 0))
+        .unwrap();
+    assert!(!select.is_open());
+    select
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 0, 0))
+        .unwrap();
+    assert!(select.is_open());
+
+    // options start on the row below the collapsed row
+    assert_eq!(
+        select
+            .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 3, 0))
+            .unwrap(),
+        select_payload(2, &[0, 1, 2])
+    );
+    assert_eq!(
+        select
+            .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 1, 0))
+            .unwrap(),
+        select_payload(0, &[1, 2])
+    );
+
+    // losing focus closes it
+    select.focus(&mut root, false).unwrap();
+    assert!(!select.is_open());
+}
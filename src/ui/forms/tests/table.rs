@@ -0,0 +1,205 @@
+// Copyright (C) 2013-2020 Blockstack PBC, a public benefit corporation
+// Copyright (C) 2020-2022 Stacks Open Internet Foundation
+// Copyright (C) 2022 Jude Nelson
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::ui::charbuff::CharBuff;
+use crate::ui::forms::Table;
+use crate::ui::forms::WrbForm;
+use crate::ui::forms::WrbFormEvent;
+use crate::ui::viewport::Viewport;
+
+use crate::ui::Root;
+use crate::ui::SceneGraph;
+
+use clarity::vm::Value;
+
+use termion::event::Key;
+use termion::event::MouseButton;
+
+fn make_table(num_rows: u64) -> Table {
+    Table::new_detached(
+        vec![("Name".to_string(), 6), ("Slot".to_string(), 4)],
+        vec![
+            vec!["carol".to_string(), "3".to_string()],
+            vec!["alice".to_string(), "10".to_string()],
+            vec!["bob".to_string(), "2".to_string()],
+        ],
+        num_rows,
+    )
+}
+
+#[test]
+fn test_table_handle_event() {
+    let mut root = Root::null();
+    let mut table = make_table(2);
+    assert_eq!(table.selected_row(), Some(0));
+
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Down))
+        .unwrap();
+    assert_eq!(table.selected_row(), Some(1));
+    assert_eq!(
+        table
+            .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('\n')))
+            .unwrap(),
+        Some(Value::UInt(1))
+    );
+
+    // sort numerically by the second column; the same row stays selected
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('2')))
+        .unwrap();
+    assert_eq!(table.order(), &[2, 0, 1]);
+    assert_eq!(table.selected_row(), Some(1));
+
+    // sorting again reverses
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('2')))
+        .unwrap();
+    assert_eq!(table.order(), &[1, 0, 2]);
+    assert_eq!(table.selected_row(), Some(1));
+
+    // sort by text
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('1')))
+        .unwrap();
+    assert_eq!(table.order(), &[1, 2, 0]);
+
+    // there's no ninth column
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('9')))
+        .unwrap();
+    assert_eq!(table.order(), &[1, 2, 0]);
+
+    // restore the page's order
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('0')))
+        .unwrap();
+    assert_eq!(table.order(), &[0, 1, 2]);
+
+    // clicking a header sorts by its column
+    table
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 1, 9))
+        .unwrap();
+    assert_eq!(table.order(), &[2, 0, 1]);
+    assert_eq!(table.selected_row(), Some(1));
+
+    // the table scrolled to show the selected (last) row, so the first row shown is the second
+    // row in sorted order
+    assert_eq!(
+        table
+            .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 3, 2))
+            .unwrap(),
+        Some(Value::UInt(0))
+    );
+    assert_eq!(table.selected_row(), Some(0));
+
+    // clicking the bottom border does nothing
+    assert_eq!(
+        table
+            .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 5, 2))
+            .unwrap(),
+        None
+    );
+
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Home))
+        .unwrap();
+    assert_eq!(table.selected_row(), Some(2));
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::End))
+        .unwrap();
+    assert_eq!(table.selected_row(), Some(1));
+    table
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::WheelUp, 3, 2))
+        .unwrap();
+    assert_eq!(table.selected_row(), Some(0));
+}
+
+#[test]
+fn test_table_render() {
+    let mut root = Root::new(8, 20, SceneGraph::new(vec![Viewport::new(0, 0, 0, 8, 20)]));
+    let mut table = Table::new_detached(
+        vec![("Name".to_string(), 6), ("Slot".to_string(), 4)],
+        vec![
+            vec!["alexandria".to_string(), "10".to_string()],
+            vec!["bob".to_string(), "2".to_string()],
+        ],
+        3,
+    );
+    table.render(&mut root, (0, 0)).unwrap();
+    let buff = root.refresh();
+    let text = CharBuff::dump_charbuff(&buff, 8);
+    eprintln!("{}", &text);
+    let rows: Vec<_> = text.lines().collect();
+    assert!(rows[0].starts_with("┌──────┬────┐"));
+    assert!(rows[1].starts_with("│Name  │Slot│"));
+    assert!(rows[2].starts_with("├──────┼────┤"));
+    assert!(rows[3].starts_with("│alexa…│10  │"));
+    assert!(rows[4].starts_with("│bob   │2   │"));
+
+    // the table is only as tall as its rows
+    assert!(rows[5].starts_with("└──────┴────┘"));
+
+    // the sorted column is marked
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char('2')))
+        .unwrap();
+    table.render(&mut root, (0, 0)).unwrap();
+    let buff = root.refresh();
+    let text = CharBuff::dump_charbuff(&buff, 8);
+    eprintln!("{}", &text);
+    let rows: Vec<_> = text.lines().collect();
+    assert!(rows[1].starts_with("│Name  │Sl…▲│"));
+    assert!(rows[3].starts_with("│bob   │2   │"));
+    assert!(rows[4].starts_with("│alexa…│10  │"));
+}
+
+#[test]
+fn test_table_render_huge_num_rows() {
+    let mut root = Root::new(8, 20, SceneGraph::new(vec![Viewport::new(0, 0, 0, 8, 20)]));
+
+    // a huge page size only shows the rows there are
+    let mut table = make_table(u64::MAX);
+    table.render(&mut root, (0, 0)).unwrap();
+    let buff = root.refresh();
+    let text = CharBuff::dump_charbuff(&buff, 8);
+    eprintln!("{}", &text);
+    let rows: Vec<_> = text.lines().collect();
+    assert!(rows[5].starts_with("│bob   │2   │"));
+    assert!(rows[6].starts_with("└──────┴────┘"));
+
+    // page-down moves to the last row
+    table
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::PageDown))
+        .unwrap();
+    assert_eq!(table.selected_row(), Some(2));
+
+    // an empty table shows one blank row
+    let mut root = Root::new(8, 20, SceneGraph::new(vec![Viewport::new(0, 0, 0, 8, 20)]));
+    let mut table = Table::new_detached(
+        vec![("Name".to_string(), 6), ("Slot".to_string(), 4)],
+        vec![],
+        u64::MAX,
+    );
+    table.render(&mut root, (0, 0)).unwrap();
+    let buff = root.refresh();
+    let text = CharBuff::dump_charbuff(&buff, 8);
+    eprintln!("{}", &text);
+    let rows: Vec<_> = text.lines().collect();
+    assert!(rows[3].starts_with("│      │    │"));
+    assert!(rows[4].starts_with("└──────┴────┘"));
+}
@@ -25,6 +25,7 @@ use crate::ui::Root;
 use crate::ui::SceneGraph;
 
 use termion::event::Key;
+use termion::event::MouseButton;
 
 #[test]
 fn test_gapbuffer_insert_backspace_delete_replace_left_right_ops() {
@@ -881,6 +882,77 @@ fn test_textarea_handle_event() {
     assert_eq!(textarea.scroll(), 12);
 }
 
+#[test]
+fn test_gapbuffer_index_at() {
+    let gb = GapBuffer::new("hello\nworld\nabcdefghijklm", 10);
+
+    // (row, col) to index
+    assert_eq!(gb.index_at(0, 10, 0, 0), 0);
+    assert_eq!(gb.index_at(0, 10, 0, 4), 4);
+    assert_eq!(gb.index_at(0, 10, 1, 2), 8);
+
+    // past the end of a line is the end of the line
+    assert_eq!(gb.index_at(0, 10, 0, 9), 5);
+    assert_eq!(gb.index_at(0, 10, 1, 7), 11);
+
+    // long lines wrap
+    assert_eq!(gb.index_at(0, 10, 2, 9), 21);
+    assert_eq!(gb.index_at(0, 10, 3, 1), 23);
+
+    // past the end of the text is the end of the text
+    assert_eq!(gb.index_at(0, 10, 3, 5), gb.len());
+    assert_eq!(gb.index_at(0, 10, 10, 0), gb.len());
+
+    // scrolling offsets the rows
+    assert_eq!(gb.index_at(6, 10, 0, 2), 8);
+
+    // inverse of cursor_location
+    let mut gb = gb;
+    for (row, col) in [(0, 3), (1, 0), (2, 9), (3, 2)] {
+        let idx = gb.index_at(0, 10, row, col);
+        gb.move_to(idx);
+        assert_eq!(gb.get_cursor(), idx);
+        assert_eq!(gb.cursor_location(0, 10, 10), Some((row, col)));
+    }
+    assert_eq!(gb.to_string(), "hello\nworld\nabcdefghijklm");
+}
+
+#[test]
+fn test_textarea_handle_mouse_event() {
+    let mut root = Root::null();
+    let mut textarea = TextArea::new_detached("hello\nworld\nfoo".to_string(), 3, 10, 2_000);
+    assert_eq!(textarea.cursor(), textarea.text().len());
+
+    // clicking places the cursor
+    textarea
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 1, 2))
+        .unwrap();
+    assert_eq!(textarea.cursor(), 8);
+
+    // clicking past the end of a line places the cursor at the end of the line
+    textarea
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 0, 9))
+        .unwrap();
+    assert_eq!(textarea.cursor(), 5);
+
+    // the wheel moves the cursor a line at a time
+    textarea
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 1, 2))
+        .unwrap();
+    textarea
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::WheelUp, 0, 0))
+        .unwrap();
+    assert_eq!(textarea.cursor(), 2);
+
+    textarea
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::WheelDown, 0, 0))
+        .unwrap();
+    assert_eq!(textarea.cursor(), 8);
+
+    // text is unchanged
+    assert_eq!(textarea.text(), "hello\nworld\nfoo");
+}
+
 #[test]
 fn test_textarea_gap_buffer_iter() {
     let mut gb = GapBuffer::new("", 10);
@@ -23,6 +23,7 @@ use crate::ui::Root;
 use crate::ui::SceneGraph;
 
 use termion::event::Key;
+use termion::event::MouseButton;
 
 #[test]
 fn test_textline_handle_event() {
@@ -145,3 +146,63 @@ fn test_textline_handle_event() {
     assert_eq!(textline.cursor(), textline.text().len());
     assert_eq!(textline.insert(), false);
 }
+
+#[test]
+fn test_textline_handle_mouse_event() {
+    let mut root = Root::null();
+    let mut textline = TextLine::new_detached("hello world".to_string(), 20);
+    assert_eq!(textline.cursor(), 0);
+
+    // clicking places the cursor
+    textline
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 0, 3))
+        .unwrap();
+    assert_eq!(textline.text(), "hello world");
+    assert_eq!(textline.cursor(), 3);
+
+    // clicking past the end places the cursor at the end
+    textline
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Left, 0, 15))
+        .unwrap();
+    assert_eq!(textline.cursor(), textline.text().len());
+
+    // other buttons do nothing
+    textline
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::Right, 0, 1))
+        .unwrap();
+    textline
+        .handle_event(&mut root, WrbFormEvent::Mouse(MouseButton::WheelUp, 0, 1))
+        .unwrap();
+    assert_eq!(textline.text(), "hello world");
+    assert_eq!(textline.cursor(), textline.text().len());
+}
+
+#[test]
+fn test_textline_masked() {
+    let mut root = Root::null();
+    let mut textline = TextLine::new_detached_masked("".to_string(), 20);
+    assert!(textline.masked());
+
+    for c in "hunter2".chars() {
+        textline
+            .handle_event(&mut root, WrbFormEvent::Keypress(Key::Char(c)))
+            .unwrap();
+    }
+    textline
+        .handle_event(&mut root, WrbFormEvent::Keypress(Key::Backspace))
+        .unwrap();
+
+    // the text is edited as usual, but only shown as mask characters
+    assert_eq!(textline.text(), "hunter");
+    assert_eq!(textline.display_text(), "******");
+
+    // the text never shows up in logs
+    let debug_str = format!("{:?}", &textline);
+    assert!(!debug_str.contains("hunter"));
+
+    // unmasked textlines are shown verbatim
+    let textline = TextLine::new_detached("hunter2".to_string(), 20);
+    assert!(!textline.masked());
+    assert_eq!(textline.display_text(), "hunter2");
+    assert!(format!("{:?}", &textline).contains("hunter2"));
+}
@@ -24,6 +24,7 @@ use clarity::vm::Value;
 use crate::ui::forms::{WrbForm, WrbFormEvent, WrbFormTypes};
 
 use termion::event::Key;
+use termion::event::MouseButton;
 
 /// gap buffer for long text
 #[derive(Debug, Clone, PartialEq)]
@@ -361,6 +362,20 @@ impl GapBuffer {
         }
     }
 
+    /// move the cursor to the given index, and shift the gap with it
+    pub fn move_to(&mut self, idx: usize) {
+        while self.cursor > idx {
+            if !self.left() {
+                break;
+            }
+        }
+        while self.cursor < idx {
+            if !self.right() {
+                break;
+            }
+        }
+    }
+
     /// Set the inner text
     /// Resets all state
     pub fn set_text(&mut self, text: String) {
@@ -600,6 +615,37 @@ impl GapBuffer {
         self.debug(|| wrb_debug!("cursor_location: cursor at ({},{})", row, col));
         Some((row, col))
     }
+
+    /// find the index of the character at the (row,col) location in the text area, given the
+    /// scroll offset index.  This is the inverse of `cursor_location`.
+    /// If the location is past the end of its line, then the index of the end of the line is
+    /// returned.
+    pub fn index_at(&self, scroll: u64, num_cols: u64, row: u64, col: u64) -> usize {
+        let mut i = usize::try_from(scroll).unwrap_or(usize::MAX);
+        if num_cols == 0 {
+            return i;
+        }
+
+        let mut cur_row = 0;
+        let mut cur_col = 0;
+        while let Some(c) = self.get(i) {
+            if cur_row > row || (cur_row == row && (cur_col == col || c == '\n')) {
+                return i;
+            }
+            i += 1;
+            cur_col += 1;
+            if c == '\n' {
+                cur_row += 1;
+                cur_col = 0;
+            }
+            if cur_col >= num_cols {
+                cur_row += 1;
+                cur_col = 0;
+            }
+        }
+        self.debug(|| wrb_debug!("index_at: ({},{}) is past the end at {}", row, col, i));
+        i
+    }
 }
 
 pub struct GapBufferIterator<'a> {
@@ -1104,6 +1150,30 @@ impl WrbForm for TextArea {
                 }
                 _ => {}
             },
+            WrbFormEvent::Mouse(button, row, col) => match button {
+                MouseButton::Left => {
+                    // clicking places the cursor
+                    let idx = self.inner_text.index_at(
+                        u64::try_from(self.scroll).unwrap_or(u64::MAX),
+                        self.num_cols,
+                        row,
+                        col,
+                    );
+                    self.inner_text.move_to(idx);
+                    self.cursor_col = self
+                        .inner_text
+                        .cursor
+                        .saturating_sub(self.inner_text.line_start);
+                }
+                // the wheel moves the cursor a line at a time, scrolling as needed
+                MouseButton::WheelUp => {
+                    return self.handle_event(root, WrbFormEvent::Keypress(Key::Up));
+                }
+                MouseButton::WheelDown => {
+                    return self.handle_event(root, WrbFormEvent::Keypress(Key::Down));
+                }
+                _ => {}
+            },
         }
         // update cursor
         self.focus(root, root.is_focused(self.element_id))?;
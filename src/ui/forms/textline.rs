@@ -21,12 +21,18 @@ use crate::ui::Error;
 use crate::ui::ValueExtensions;
 use clarity::vm::Value;
 
+use std::fmt;
+
 use crate::ui::forms::{WrbForm, WrbFormEvent, WrbFormTypes};
 
 use termion::event::Key;
+use termion::event::MouseButton;
+
+/// Character shown in place of each character of a masked textline
+pub const TEXTLINE_MASK_CHAR: char = '*';
 
 /// UI command to add an editable line of text
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq)]
 pub struct TextLine {
     element_id: u128,
     viewport_id: u128,
@@ -40,6 +46,37 @@ pub struct TextLine {
     inner_text: String,
     max_len: usize,
     insert: bool,
+    /// If true, then the text is a secret (like a passphrase).  It is rendered as
+    /// `TEXTLINE_MASK_CHAR`s, and is never logged.
+    masked: bool,
+}
+
+/// Never print a masked textline's text
+impl fmt::Debug for TextLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TextLine")
+            .field("element_id", &self.element_id)
+            .field("viewport_id", &self.viewport_id)
+            .field("row", &self.row)
+            .field("col", &self.col)
+            .field("cursor", &self.cursor)
+            .field("bg_color", &self.bg_color)
+            .field("fg_color", &self.fg_color)
+            .field("focused_bg_color", &self.focused_bg_color)
+            .field("focused_fg_color", &self.focused_fg_color)
+            .field(
+                "inner_text",
+                if self.masked {
+                    &"<masked>" as &dyn fmt::Debug
+                } else {
+                    &self.inner_text
+                },
+            )
+            .field("max_len", &self.max_len)
+            .field("insert", &self.insert)
+            .field("masked", &self.masked)
+            .finish()
+    }
 }
 
 impl TextLine {
@@ -58,6 +95,25 @@ impl TextLine {
             inner_text: text,
             max_len,
             insert: true,
+            masked: false,
+        }
+    }
+
+    /// Constructor for a detached textline whose text is masked
+    pub fn new_detached_masked(text: String, max_len: usize) -> Self {
+        let mut textline = Self::new_detached(text, max_len);
+        textline.masked = true;
+        textline
+    }
+
+    /// What the user sees in place of the text
+    pub fn display_text(&self) -> String {
+        if self.masked {
+            std::iter::repeat(TEXTLINE_MASK_CHAR)
+                .take(self.inner_text.chars().count())
+                .collect()
+        } else {
+            self.inner_text.clone()
         }
     }
 
@@ -91,6 +147,10 @@ impl WrbForm for TextLine {
         self.viewport_id
     }
 
+    fn masked(&self) -> bool {
+        self.masked
+    }
+
     fn focus(&mut self, root: &mut Root, focused: bool) -> Result<(), Error> {
         if focused {
             root.set_form_cursor(
@@ -167,6 +227,12 @@ impl WrbForm for TextLine {
             .expect("FATAL: no `element-id`")
             .expect_u128()?;
 
+        let masked = text_tuple
+            .get("masked")
+            .cloned()
+            .expect("FATAL: no `masked`")
+            .expect_bool()?;
+
         let bg_color: Color = u32::try_from(bg_color_u128).expect("infallible").into();
         let fg_color: Color = u32::try_from(fg_color_u128).expect("infallible").into();
         let focused_bg_color: Color = u32::try_from(focused_bg_color_u128)
@@ -190,6 +256,7 @@ impl WrbForm for TextLine {
             focused_fg_color,
             inner_text: text,
             insert: true,
+            masked,
         })
     }
 
@@ -227,7 +294,7 @@ impl WrbForm for TextLine {
             .min(u64::try_from(self.max_len).unwrap_or(u64::MAX));
         let padded_text = format!(
             "{:width$}",
-            &self.inner_text,
+            &self.display_text(),
             width = usize::try_from(max_viewable_cols).unwrap_or(0)
         );
         let new_cursor = viewport.print_to(
@@ -328,6 +395,15 @@ impl WrbForm for TextLine {
                 }
                 _ => {}
             },
+            WrbFormEvent::Mouse(button, _row, col) => {
+                // clicking places the cursor
+                if button == MouseButton::Left {
+                    self.cursor = usize::try_from(col)
+                        .unwrap_or(usize::MAX)
+                        .min(self.inner_text.len())
+                        .min(self.max_len);
+                }
+            }
         }
         self.focus(root, root.is_focused(self.element_id))?;
         Ok(None)
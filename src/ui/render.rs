@@ -68,7 +68,9 @@ use crate::ui::scanline::Scanline;
 use crate::ui::viewport::Viewport;
 
 use crate::ui::forms::WrbForm;
-use crate::ui::forms::{Button, Checkbox, PrintText, RawText, TextArea, TextLine, WrbFormTypes};
+use crate::ui::forms::{
+    Button, Checkbox, PrintText, RawText, Select, Table, TextArea, TextLine, WrbFormTypes,
+};
 
 pub struct Renderer {
     /// maximum wrbsite size -- a decoded string can't be longer than this
@@ -360,6 +362,32 @@ impl Renderer {
                         TextArea::from_clarity_value(viewport_id, viewport_textarea_value)?;
                     ui_contents.push(Box::new(textarea));
                 }
+                WrbFormTypes::Select => {
+                    // go get the select
+                    let qry = format!("(print (wrb-ui-get-select-element u{}))", index);
+                    let viewport_select_value = self
+                        .run_query_code(vm_env, main_code_id, &qry)?
+                        .pop()
+                        .expect("FATAL: expected one result")
+                        .expect_optional()?
+                        .expect("FATAL: select UI element not defined at defined index");
+
+                    let select = Select::from_clarity_value(viewport_id, viewport_select_value)?;
+                    ui_contents.push(Box::new(select));
+                }
+                WrbFormTypes::Table => {
+                    // go get the table
+                    let qry = format!("(print (wrb-ui-get-table-element u{}))", index);
+                    let viewport_table_value = self
+                        .run_query_code(vm_env, main_code_id, &qry)?
+                        .pop()
+                        .expect("FATAL: expected one result")
+                        .expect_optional()?
+                        .expect("FATAL: table UI element not defined at defined index");
+
+                    let table = Table::from_clarity_value(viewport_id, viewport_table_value)?;
+                    ui_contents.push(Box::new(table));
+                }
             }
         }
         Ok(ui_contents)
@@ -503,6 +531,41 @@ impl Renderer {
         Ok(updated_viewports)
     }
 
+    /// Get the records of forms whose settings changed since they were first drawn
+    fn get_updated_forms(
+        &mut self,
+        vm_env: &mut OwnedEnvironment,
+        main_code_id: &QualifiedContractIdentifier,
+    ) -> Result<Vec<(u128, Value)>, Error> {
+        let mut updated_forms = vec![];
+
+        // get updated checkbox IDs
+        let qry = "(print (wrb-take-checkbox-updates))";
+        let checkbox_id_list: Vec<u128> = self
+            .run_query_code(vm_env, main_code_id, &qry)?
+            .pop()
+            .expect("FATAL: expected one result")
+            .expect_list()?
+            .into_iter()
+            .filter_map(|val| val.expect_u128().ok())
+            .collect();
+
+        for element_id in checkbox_id_list.into_iter() {
+            let qry = format!("(print (wrb-ui-get-checkbox-element u{}))", element_id);
+            let checkbox_value_opt = self
+                .run_query_code(vm_env, main_code_id, &qry)?
+                .pop()
+                .expect("FATAL: expected one value")
+                .expect_optional()?;
+
+            let Some(checkbox_value) = checkbox_value_opt else {
+                continue;
+            };
+            updated_forms.push((element_id, checkbox_value));
+        }
+        Ok(updated_forms)
+    }
+
     /// Compute the root pane from scratch
     pub(crate) fn make_root(
         &mut self,
@@ -558,9 +621,11 @@ impl Renderer {
     ) -> Result<FrameUpdate, Error> {
         let dynamic_ui_contents = self.get_dynamic_ui_contents(vm_env, main_code_id)?;
         let updated_viewports = self.get_updated_viewports(vm_env, main_code_id)?;
+        let updated_forms = self.get_updated_forms(vm_env, main_code_id)?;
         Ok(FrameUpdate {
             new_contents: dynamic_ui_contents,
             updated_viewports,
+            updated_forms,
         })
     }
 
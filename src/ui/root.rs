@@ -16,6 +16,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use termion::event::Key;
+use termion::event::MouseButton;
 
 use std::collections::BTreeMap;
 use std::collections::HashMap;
@@ -32,6 +33,9 @@ use crate::ui::events::WrbEvent;
 
 use clarity::vm::Value;
 
+/// How many rows one turn of the mouse wheel scrolls a viewport
+const WHEEL_SCROLL_ROWS: i64 = 3;
+
 #[derive(Debug, Clone)]
 pub enum ZBuffEntry {
     Root,
@@ -234,6 +238,9 @@ pub struct Root {
     debug_frames: bool,
     /// runtime event buffer
     runtime_events: Vec<WrbEvent>,
+    /// Which viewport (if any) the user last clicked on.  Used to decide which viewport to
+    /// scroll from the keyboard when no form has focus.
+    pub(crate) clicked_viewport: Option<u128>,
 }
 
 /// An update to the root pane
@@ -241,6 +248,8 @@ pub struct Root {
 pub struct FrameUpdate {
     pub new_contents: Vec<Box<dyn WrbForm>>,
     pub updated_viewports: Vec<Viewport>,
+    /// records of existing forms whose settings changed, keyed by element ID
+    pub updated_forms: Vec<(u128, Value)>,
 }
 
 impl Root {
@@ -260,6 +269,7 @@ impl Root {
             dynamic_forms: HashSet::new(),
             debug_frames: true,
             runtime_events: vec![],
+            clicked_viewport: None,
         }
     }
 
@@ -441,6 +451,15 @@ impl Root {
             viewport.merge_update(&vp);
         }
 
+        // merge form settings
+        for (element_id, value) in frame_update.updated_forms.into_iter() {
+            let Some(ui_content) = forms.get_mut(&element_id) else {
+                continue;
+            };
+            wrb_debug!("Update form {}", element_id);
+            ui_content.merge_update(value)?;
+        }
+
         // clear dirty viewports
         for (_element_id, ui_content) in forms.iter() {
             let viewport_id = ui_content.viewport_id();
@@ -500,9 +519,13 @@ impl Root {
             dynamic_form_ids.insert(element_id);
         }
 
-        // clear focused if it references a nonexistant form
+        // clear focused if it references a nonexistant or disabled form
         let focused = if let Some(focused) = self.focused.take() {
-            if forms.get(&focused).is_none() {
+            if forms
+                .get(&focused)
+                .map(|form| form.disabled())
+                .unwrap_or(true)
+            {
                 None
             } else {
                 Some(focused)
@@ -560,6 +583,20 @@ impl Root {
         }
     }
 
+    /// Look up the UI element visible at a given absolute (row,col) coordinate (uses the zbuff).
+    /// Returns Some((element_id, row, col)), where (row, col) is relative to the element's
+    /// upper-left corner.
+    /// Returns None if there's no UI element there.
+    pub fn find_element(&self, row: u64, col: u64) -> Option<(u128, u64, u64)> {
+        let viewport_id = self.find_viewport_id(row, col)?;
+        let viewport = self.scenegraph.ref_viewport(viewport_id)?;
+        let (viewport_row, viewport_col) = self.scenegraph.viewport_coords(viewport_id)?;
+        viewport.element_at(
+            row.checked_sub(viewport_row)?,
+            col.checked_sub(viewport_col)?,
+        )
+    }
+
     /// Find the order of UI elements to shift focus to
     fn make_focus_order(&mut self, buff: &CharBuff) {
         let mut cur_ui_element = None;
@@ -569,6 +606,7 @@ impl Root {
                 value: _value,
                 bg: _bg,
                 fg: _fg,
+                attrs: _attrs,
                 element_id,
             } = cell
             else {
@@ -577,7 +615,7 @@ impl Root {
             let Some(form) = self.forms.get(element_id) else {
                 continue;
             };
-            if !form.type_id().focusable() {
+            if !form.type_id().focusable() || form.disabled() {
                 continue;
             }
             if let Some(cur_ui_element) = cur_ui_element.as_mut() {
@@ -614,17 +652,25 @@ impl Root {
 
     /// Update the focus pointer
     fn inner_next_focus(&mut self, reverse: bool) -> Result<(), Error> {
-        let old_focused = self.focused.clone();
-        if let Some(focused) = self.focused {
-            let next_focused = if reverse {
+        let next_focused = if let Some(focused) = self.focused {
+            if reverse {
                 self.reverse_focus_order.get(&focused).cloned()
             } else {
                 self.focus_order.get(&focused).cloned()
-            };
-            self.focused = next_focused;
+            }
         } else {
-            self.focused = self.focus_first.clone();
-        }
+            None
+        };
+        // the focused form may have been scrolled out of view, in which case start over
+        let next_focused = next_focused.or(self.focus_first.clone());
+        self.set_focus(next_focused)
+    }
+
+    /// Move focus to the given element (or to no element), and tell the old and new focused
+    /// forms.
+    fn set_focus(&mut self, next_focused: Option<u128>) -> Result<(), Error> {
+        let old_focused = self.focused.clone();
+        self.focused = next_focused;
         if let Some(old_focused) = old_focused {
             if let Some(mut form) = self.forms.remove(&old_focused) {
                 form.focus(self, false)?;
@@ -663,26 +709,207 @@ impl Root {
         self.focused == Some(element_id)
     }
 
+    /// Is the focused element's content secret?  If so, then keypresses must not be logged.
+    pub fn focused_is_masked(&self) -> bool {
+        self.focused
+            .map(|focused| self.forms.get(&focused).map(|form| form.masked()))
+            .flatten()
+            .unwrap_or(false)
+    }
+
     /// Is the element ID focusable
     pub fn is_focusable(&self, element_id: u128) -> bool {
         self.focus_order.contains_key(&element_id)
     }
 
-    /// Handle a form event. Pass it to the focused form.
+    /// Which viewport do scrolling keys apply to?  This is the focused form's viewport, or if no
+    /// form is focused, the viewport the user last clicked on.
+    pub fn focused_viewport(&self) -> Option<u128> {
+        self.focused
+            .map(|focused| self.forms.get(&focused).map(|form| form.viewport_id()))
+            .flatten()
+            .or(self.clicked_viewport)
+    }
+
+    /// Scroll a viewport down by `delta` rows (or up, if `delta` is negative), and tell the page.
+    /// Returns true if the viewport scrolled.
+    pub fn scroll_viewport(&mut self, viewport_id: u128, delta: i64) -> bool {
+        let Some(viewport) = self.viewport_mut(viewport_id) else {
+            return false;
+        };
+        if !viewport.scroll_by(delta) {
+            return false;
+        }
+        let row = viewport.scroll_offset();
+        wrb_debug!("Scrolled viewport {} to row {}", viewport_id, row);
+        self.runtime_events
+            .push(WrbEvent::Scroll { viewport_id, row });
+        true
+    }
+
+    /// How many rows a page-up or page-down scrolls a viewport
+    fn page_rows(&self, viewport_id: u128) -> i64 {
+        self.scenegraph
+            .ref_viewport(viewport_id)
+            .map(|viewport| viewport.dims().0.saturating_sub(1).max(1))
+            .map(|rows| i64::try_from(rows).unwrap_or(i64::MAX))
+            .unwrap_or(1)
+    }
+
+    /// If this is a scrolling key and the focused viewport can scroll, then scroll it.
+    /// Returns true if the key was consumed.
+    fn handle_scroll_key(&mut self, key: &Key) -> bool {
+        let Some(viewport_id) = self.focused_viewport() else {
+            return false;
+        };
+        if !self
+            .scenegraph
+            .ref_viewport(viewport_id)
+            .map(|viewport| viewport.scrollable())
+            .unwrap_or(false)
+        {
+            return false;
+        }
+        let delta = match key {
+            Key::PageUp => -self.page_rows(viewport_id),
+            Key::PageDown => self.page_rows(viewport_id),
+            _ => {
+                return false;
+            }
+        };
+        self.scroll_viewport(viewport_id, delta);
+        true
+    }
+
+    /// Handle a mouse button press on a scrollable viewport: the wheel scrolls it (unless the
+    /// pointer is on the focused form, which gets the wheel instead), and clicking the scrollbar
+    /// pages it up or down.
+    /// Returns true if the press was consumed.
+    fn handle_viewport_mouse_event(
+        &mut self,
+        viewport_id: u128,
+        button: MouseButton,
+        row: u64,
+        col: u64,
+    ) -> bool {
+        let Some(viewport) = self.scenegraph.ref_viewport(viewport_id) else {
+            return false;
+        };
+        let Some((viewport_row, viewport_col)) = self.scenegraph.viewport_coords(viewport_id)
+        else {
+            return false;
+        };
+        if !viewport.scrollable() {
+            return false;
+        }
+        let (Some(rel_row), Some(rel_col)) =
+            (row.checked_sub(viewport_row), col.checked_sub(viewport_col))
+        else {
+            return false;
+        };
+
+        if viewport.scrollbar_at(rel_col) {
+            let (thumb_start, thumb_rows) = viewport.scrollbar_thumb();
+            let page_rows = self.page_rows(viewport_id);
+            if button == MouseButton::Left && rel_row < thumb_start {
+                self.scroll_viewport(viewport_id, -page_rows);
+            } else if button == MouseButton::Left && rel_row >= thumb_start + thumb_rows {
+                self.scroll_viewport(viewport_id, page_rows);
+            }
+            return true;
+        }
+
+        let delta = match button {
+            MouseButton::WheelUp => -WHEEL_SCROLL_ROWS,
+            MouseButton::WheelDown => WHEEL_SCROLL_ROWS,
+            _ => {
+                return false;
+            }
+        };
+        if let Some((element_id, ..)) = viewport.element_at(rel_row, rel_col) {
+            if self.is_focused(element_id) {
+                return false;
+            }
+        }
+        self.scroll_viewport(viewport_id, delta);
+        true
+    }
+
+    /// Handle a form event. Pass it to the focused form, unless the focused form doesn't use it
+    /// and it scrolls the focused viewport.
     /// Coalesce when possible.
     pub fn handle_event(&mut self, event: WrbFormEvent) -> Result<(), Error> {
+        if let WrbFormEvent::Keypress(key) = &event {
+            let focused_handles_key = self
+                .focused
+                .and_then(|focused| self.forms.get(&focused))
+                .map(|form| form.handles_key(key))
+                .unwrap_or(false);
+            if !focused_handles_key && self.handle_scroll_key(key) {
+                return Ok(());
+            }
+        }
         let Some(focused) = self.focused else {
             wrb_debug!("No form focused; dropping event {:?}", &event);
             return Ok(());
         };
+        self.dispatch_event(focused, event)
+    }
+
+    /// Handle a mouse button press at the given absolute (row, col).
+    /// The page always gets a `WrbEvent::Mouse`.  If the press scrolls a scrollable viewport, then
+    /// that's all that happens.  Otherwise, if the press lands on a focusable form, then a
+    /// left-click focuses it, and the press is passed to it relative to its upper-left corner.
+    pub fn handle_mouse_event(
+        &mut self,
+        button: MouseButton,
+        row: u64,
+        col: u64,
+    ) -> Result<(), Error> {
+        self.runtime_events
+            .push(WrbEvent::Mouse { button, row, col });
+
+        if let Some(viewport_id) = self.find_viewport_id(row, col) {
+            self.clicked_viewport = Some(viewport_id);
+            if self.handle_viewport_mouse_event(viewport_id, button, row, col) {
+                return Ok(());
+            }
+        }
+
+        let Some((element_id, element_row, element_col)) = self.find_element(row, col) else {
+            wrb_debug!("No form at ({},{}); dropping {:?}", row, col, &button);
+            return Ok(());
+        };
+        if !self.is_focusable(element_id) {
+            wrb_debug!(
+                "Form {} is not focusable; dropping {:?}",
+                element_id,
+                &button
+            );
+            return Ok(());
+        }
+        if button == MouseButton::Left && !self.is_focused(element_id) {
+            self.set_focus(Some(element_id))?;
+        }
+        self.dispatch_event(
+            element_id,
+            WrbFormEvent::Mouse(button, element_row, element_col),
+        )
+    }
 
+    /// Pass a form event to the given form, and buffer any runtime event it emits
+    fn dispatch_event(&mut self, element_id: u128, event: WrbFormEvent) -> Result<(), Error> {
         // take ownership to avoid multiple mutable references
-        let Some(mut form) = self.forms.remove(&focused) else {
-            wrb_debug!("No such form {}; dropping event {:?}", focused, &event);
+        let Some(mut form) = self.forms.remove(&element_id) else {
+            wrb_debug!("No such form {}; dropping event {:?}", element_id, &event);
             return Ok(());
         };
 
-        wrb_debug!("Pass event to form {}: {:?}", focused, &event);
+        if form.masked() {
+            wrb_debug!("Pass event to form {}: <masked>", element_id);
+        } else {
+            wrb_debug!("Pass event to form {}: {:?}", element_id, &event);
+        }
         let runtime_event_payload = form.handle_event(self, event)?;
 
         let runtime_event_opt = runtime_event_payload.map(|event_payload| {
@@ -693,14 +920,14 @@ impl Root {
             };
             wrb_debug!(
                 "Received runtime event from form {}: {:?}",
-                focused,
+                element_id,
                 &runtime_event
             );
             runtime_event
         });
 
         // restore form ownership to root
-        self.forms.insert(focused, form);
+        self.forms.insert(element_id, form);
 
         if let Some(runtime_event) = runtime_event_opt {
             self.runtime_events.push(runtime_event);
@@ -726,6 +953,13 @@ impl Root {
             return None;
         };
 
+        // the cursor may be scrolled out of view
+        let viewport = self.scenegraph.ref_viewport(form_viewport_id)?;
+        let form_cursor_row = form_cursor_row.checked_sub(viewport.scroll_offset())?;
+        if form_cursor_row >= viewport.dims().0 {
+            return None;
+        }
+
         Some((
             viewport_row + form_cursor_row,
             viewport_col + form_cursor_col,
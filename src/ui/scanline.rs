@@ -18,11 +18,116 @@
 use crate::ui::charbuff::CharBuff;
 use crate::ui::charbuff::CharCell;
 use crate::ui::charbuff::Color;
+use crate::ui::charbuff::TextAttrs;
+
+use std::env;
+use std::fmt;
 
 use termion::clear as termclear;
 use termion::color as termcolor;
 use termion::cursor as termcursor;
 
+/// How many colors the terminal can draw
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorDepth {
+    /// 24-bit RGB
+    TrueColor,
+    /// the xterm 256-color palette
+    Ansi256,
+    /// the 8 ANSI colors and their bright variants
+    Ansi16,
+    /// no colors at all; only text attributes
+    Mono,
+}
+
+impl ColorDepth {
+    pub fn parse(depth: &str) -> Result<Self, String> {
+        match depth {
+            "truecolor" | "24bit" => Ok(Self::TrueColor),
+            "256" => Ok(Self::Ansi256),
+            "16" => Ok(Self::Ansi16),
+            "mono" | "none" => Ok(Self::Mono),
+            _ => Err(format!(
+                "Invalid color depth '{}': expected truecolor, 256, 16, or mono",
+                depth
+            )),
+        }
+    }
+
+    /// Guess the terminal's color depth from `NO_COLOR`, `COLORTERM`, and `TERM`
+    pub fn from_env() -> Self {
+        let no_color = env::var("NO_COLOR").ok();
+        let colorterm = env::var("COLORTERM").ok();
+        let term = env::var("TERM").ok();
+        Self::detect(no_color.as_deref(), colorterm.as_deref(), term.as_deref())
+    }
+
+    /// Guess the terminal's color depth from the values of `NO_COLOR`, `COLORTERM`, and `TERM`
+    pub fn detect(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if no_color.map(|v| !v.is_empty()).unwrap_or(false) {
+            return Self::Mono;
+        }
+        if let Some("truecolor" | "24bit") = colorterm {
+            return Self::TrueColor;
+        }
+        let Some(term) = term else {
+            return Self::Ansi256;
+        };
+        if term == "dumb" {
+            Self::Mono
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else if term == "linux"
+            || term.starts_with("vt")
+            || term.starts_with("ansi")
+            || term.starts_with("cons")
+        {
+            Self::Ansi16
+        } else {
+            Self::Ansi256
+        }
+    }
+
+    /// Map a cell's colors and attributes to what this terminal can draw.  Colors are snapped to
+    /// the nearest palette entry.  In monochrome, colors are dropped, and a cell whose background
+    /// is brighter than its foreground is drawn in reverse video instead.
+    pub fn downgrade(&self, fg: Color, bg: Color, attrs: TextAttrs) -> (Color, Color, TextAttrs) {
+        match self {
+            Self::TrueColor => (fg, bg, attrs),
+            Self::Ansi256 => (
+                Color::from_ansi256(fg.to_ansi256()),
+                Color::from_ansi256(bg.to_ansi256()),
+                attrs,
+            ),
+            Self::Ansi16 => (
+                Color::from_ansi16(fg.to_ansi16()),
+                Color::from_ansi16(bg.to_ansi16()),
+                attrs,
+            ),
+            Self::Mono => {
+                let attrs = if bg.luminance() > fg.luminance() {
+                    attrs ^ TextAttrs::REVERSE
+                } else {
+                    attrs
+                };
+                (Color::rgb(0xff, 0xff, 0xff), Color::rgb(0, 0, 0), attrs)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let depth = match self {
+            Self::TrueColor => "truecolor",
+            Self::Ansi256 => "256",
+            Self::Ansi16 => "16",
+            Self::Mono => "mono",
+        };
+        write!(f, "{}", depth)
+    }
+}
+
 /// Rendering commands for the whole screen
 #[derive(Clone, PartialEq, Debug)]
 pub enum Scanline {
@@ -30,6 +135,14 @@ pub enum Scanline {
     FgColor(Color),
     /// set background color
     BgColor(Color),
+    /// set foreground color from the 256-color palette
+    FgColor256(u8),
+    /// set background color from the 256-color palette
+    BgColor256(u8),
+    /// set foreground color from the 16-color palette
+    FgColor16(u8),
+    /// set background color from the 16-color palette
+    BgColor16(u8),
     /// A run of text
     Text(String),
     /// A newline
@@ -38,14 +151,48 @@ pub enum Scanline {
     ClearLine,
     /// Reset the color
     ResetColor,
+    /// Set the text attributes.  Attributes not in the set are turned off; colors are unaffected.
+    Attrs(TextAttrs),
 }
 
 impl Scanline {
-    /// Translate a charbuff into a sequence of scanline directives
+    /// Command to set the foreground color at the given color depth, if the terminal has colors
+    pub fn fg_color(color: &Color, depth: ColorDepth) -> Option<Scanline> {
+        match depth {
+            ColorDepth::TrueColor => Some(Self::FgColor(color.clone())),
+            ColorDepth::Ansi256 => Some(Self::FgColor256(color.to_ansi256())),
+            ColorDepth::Ansi16 => Some(Self::FgColor16(color.to_ansi16())),
+            ColorDepth::Mono => None,
+        }
+    }
+
+    /// Command to set the background color at the given color depth, if the terminal has colors
+    pub fn bg_color(color: &Color, depth: ColorDepth) -> Option<Scanline> {
+        match depth {
+            ColorDepth::TrueColor => Some(Self::BgColor(color.clone())),
+            ColorDepth::Ansi256 => Some(Self::BgColor256(color.to_ansi256())),
+            ColorDepth::Ansi16 => Some(Self::BgColor16(color.to_ansi16())),
+            ColorDepth::Mono => None,
+        }
+    }
+
+    /// Translate a charbuff into a sequence of scanline directives, in 24-bit color
     pub fn compile_rows(buff: &CharBuff, start_row: u64, num_rows: u64) -> Vec<Scanline> {
+        Self::compile_rows_with_depth(buff, start_row, num_rows, ColorDepth::TrueColor)
+    }
+
+    /// Translate a charbuff into a sequence of scanline directives, using only the colors
+    /// available at `depth`
+    pub fn compile_rows_with_depth(
+        buff: &CharBuff,
+        start_row: u64,
+        num_rows: u64,
+        depth: ColorDepth,
+    ) -> Vec<Scanline> {
         let mut cmds = vec![];
         let mut cur_fg_color: Option<Color> = None;
         let mut cur_bg_color: Option<Color> = None;
+        let mut cur_attrs = TextAttrs::NONE;
         let mut cur_str: Vec<char> = vec![];
         let mut in_blank = true;
 
@@ -62,19 +209,26 @@ impl Scanline {
                 // finish up
                 finish_string(&mut cur_str, &mut cmds);
 
-                // next line
+                // next line.  Each line turns off its own attributes, so that any range of lines
+                // can be drawn on its own.
                 if i > 0 {
+                    if !cur_attrs.is_empty() {
+                        cmds.push(Self::Attrs(TextAttrs::NONE));
+                    }
                     cmds.push(Self::Newline);
                     cmds.push(Self::ResetColor);
                 }
                 cmds.push(Self::ClearLine);
 
-                // carry over colors
+                // carry over colors and attributes
                 if let Some(fg) = cur_fg_color.as_ref() {
-                    cmds.push(Self::FgColor(fg.clone()));
+                    cmds.extend(Self::fg_color(fg, depth));
                 }
                 if let Some(bg) = cur_bg_color.as_ref() {
-                    cmds.push(Self::BgColor(bg.clone()));
+                    cmds.extend(Self::bg_color(bg, depth));
+                }
+                if !cur_attrs.is_empty() {
+                    cmds.push(Self::Attrs(cur_attrs));
                 }
             }
             match cell {
@@ -83,6 +237,10 @@ impl Scanline {
                         in_blank = true;
                         finish_string(&mut cur_str, &mut cmds);
                         cmds.push(Self::ResetColor);
+                        if !cur_attrs.is_empty() {
+                            cmds.push(Self::Attrs(TextAttrs::NONE));
+                            cur_attrs = TextAttrs::NONE;
+                        }
                     }
                     cur_str.push(' ');
                     cur_fg_color = None;
@@ -92,8 +250,10 @@ impl Scanline {
                     element_id: _element_id,
                     fg,
                     bg,
+                    attrs,
                     value,
                 } => {
+                    let (fg, bg, attrs) = depth.downgrade(*fg, *bg, *attrs);
                     if in_blank {
                         finish_string(&mut cur_str, &mut cmds);
                         in_blank = false;
@@ -101,38 +261,46 @@ impl Scanline {
 
                     match cur_fg_color.take() {
                         Some(fg_color) => {
-                            if fg != &fg_color {
+                            if fg != fg_color {
                                 finish_string(&mut cur_str, &mut cmds);
-                                cmds.push(Self::FgColor(fg.clone()));
+                                cmds.extend(Self::fg_color(&fg, depth));
                             }
-                            cur_fg_color = Some(fg.clone());
+                            cur_fg_color = Some(fg);
                         }
                         None => {
                             finish_string(&mut cur_str, &mut cmds);
-                            cmds.push(Self::FgColor(fg.clone()));
-                            cur_fg_color = Some(fg.clone());
+                            cmds.extend(Self::fg_color(&fg, depth));
+                            cur_fg_color = Some(fg);
                         }
                     }
                     match cur_bg_color.take() {
                         Some(bg_color) => {
-                            if bg != &bg_color {
+                            if bg != bg_color {
                                 finish_string(&mut cur_str, &mut cmds);
-                                cmds.push(Self::BgColor(bg.clone()));
+                                cmds.extend(Self::bg_color(&bg, depth));
                             }
-                            cur_bg_color = Some(bg.clone());
+                            cur_bg_color = Some(bg);
                         }
                         None => {
                             finish_string(&mut cur_str, &mut cmds);
-                            cmds.push(Self::BgColor(bg.clone()));
-                            cur_bg_color = Some(bg.clone());
+                            cmds.extend(Self::bg_color(&bg, depth));
+                            cur_bg_color = Some(bg);
                         }
                     }
+                    if attrs != cur_attrs {
+                        finish_string(&mut cur_str, &mut cmds);
+                        cmds.push(Self::Attrs(attrs));
+                        cur_attrs = attrs;
+                    }
 
                     cur_str.push(*value);
                 }
             }
         }
         finish_string(&mut cur_str, &mut cmds);
+        if !cur_attrs.is_empty() {
+            cmds.push(Self::Attrs(TextAttrs::NONE));
+        }
         cmds.push(Self::ResetColor);
 
         let mut in_range = Vec::with_capacity(cmds.len());
@@ -164,6 +332,20 @@ impl Scanline {
                 "{}",
                 termcolor::Bg(termcolor::Rgb(color.r, color.g, color.b))
             ),
+            Self::FgColor256(idx) => format!("{}", termcolor::Fg(termcolor::AnsiValue(idx))),
+            Self::BgColor256(idx) => format!("{}", termcolor::Bg(termcolor::AnsiValue(idx))),
+            // 16-color terminals may not understand the 256-color `38;5;n` codes, so use the
+            // original SGR codes: 30-37/40-47, and 90-97/100-107 for the bright colors
+            Self::FgColor16(idx) => {
+                let idx = idx & 0x0f;
+                let code = if idx < 8 { 30 + idx } else { 90 + idx - 8 };
+                format!("\x1b[{}m", code)
+            }
+            Self::BgColor16(idx) => {
+                let idx = idx & 0x0f;
+                let code = if idx < 8 { 40 + idx } else { 100 + idx - 8 };
+                format!("\x1b[{}m", code)
+            }
             Self::Text(s) => s,
             Self::Newline => "\r\n".into(),
             Self::ClearLine => format!("{}", termclear::CurrentLine),
@@ -172,13 +354,40 @@ impl Scanline {
                 termcolor::Fg(termcolor::Reset),
                 termcolor::Bg(termcolor::Reset)
             ),
+            Self::Attrs(attrs) => {
+                // turn off bold/faint, italic, underline, and reverse, and then turn on the
+                // requested ones
+                let mut code = "\x1b[22;23;24;27".to_string();
+                if attrs.contains(TextAttrs::BOLD) {
+                    code.push_str(";1");
+                }
+                if attrs.contains(TextAttrs::ITALIC) {
+                    code.push_str(";3");
+                }
+                if attrs.contains(TextAttrs::UNDERLINE) {
+                    code.push_str(";4");
+                }
+                if attrs.contains(TextAttrs::REVERSE) {
+                    code.push_str(";7");
+                }
+                code.push('m');
+                code
+            }
         }
     }
 
     /// Translate a scanline command into just text
     pub fn into_text(self) -> String {
         match self {
-            Self::FgColor(..) | Self::BgColor(..) | Self::ClearLine | Self::ResetColor => "".into(),
+            Self::FgColor(..)
+            | Self::BgColor(..)
+            | Self::FgColor256(..)
+            | Self::BgColor256(..)
+            | Self::FgColor16(..)
+            | Self::BgColor16(..)
+            | Self::ClearLine
+            | Self::ResetColor
+            | Self::Attrs(..) => "".into(),
             Self::Text(s) => s,
             Self::Newline => "\n".into(),
         }
@@ -16,8 +16,8 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::core;
-use crate::ui::charbuff::{CharBuff, CharCell, Color};
-use crate::ui::scanline::Scanline;
+use crate::ui::charbuff::{CharBuff, CharCell, Color, TextAttrs};
+use crate::ui::scanline::{ColorDepth, Scanline};
 use crate::ui::Renderer;
 use crate::vm::ClarityVM;
 use std::fs;
@@ -57,6 +57,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Fill {
@@ -67,6 +68,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Fill {
@@ -77,6 +79,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Fill {
@@ -87,6 +90,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Fill {
@@ -97,6 +101,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Blank,
@@ -112,6 +117,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Fill {
@@ -122,6 +128,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Fill {
@@ -132,6 +139,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Fill {
@@ -142,6 +150,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Fill {
@@ -152,6 +161,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 },
                 CharCell::Blank,
@@ -167,6 +177,7 @@ fn test_render_charbuff_multiline() {
                         g: 255,
                         b: 255
                     },
+                    attrs: TextAttrs::NONE,
                     element_id: 100
                 }
             ]
@@ -233,6 +244,7 @@ fn test_render_charbuff() {
             num_cols: 80,
             cells: vec![
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'H',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -243,6 +255,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'e',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -253,6 +266,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -263,6 +277,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -273,6 +288,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -283,6 +299,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -293,6 +310,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -303,6 +321,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -313,6 +332,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -323,6 +343,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -333,6 +354,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -343,6 +365,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 100,
                     value: '!',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -431,120 +454,140 @@ fn test_render_charbuff() {
                 CharCell::Blank,
                 CharCell::Blank,
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'H',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'e',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'i',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'n',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'b',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'u',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: 'e',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 255 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 101,
                     value: '!',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -621,126 +664,147 @@ fn test_render_charbuff() {
                 CharCell::Blank,
                 CharCell::Blank,
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'H',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'e',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'i',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'n',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'g',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'e',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'e',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: 'n',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 0, g: 255, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 102,
                     value: '!',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -811,144 +875,168 @@ fn test_render_charbuff() {
                 CharCell::Blank,
                 CharCell::Blank,
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 105,
                     value: 'o',
                     bg: Color { r: 255, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 105,
                     value: 'v',
                     bg: Color { r: 255, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 105,
                     value: 'e',
                     bg: Color { r: 255, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 105,
                     value: 'r',
                     bg: Color { r: 255, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 105,
                     value: 'w',
                     bg: Color { r: 255, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 105,
                     value: 'r',
                     bg: Color { r: 255, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 105,
                     value: 'i',
                     bg: Color { r: 255, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 105,
                     value: 't',
                     bg: Color { r: 255, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 105,
                     value: 'e',
                     bg: Color { r: 255, g: 0, b: 0 },
                     fg: Color { r: 0, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'l',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'i',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'n',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: ' ',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'e',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
                     fg: Color { r: 255, g: 0, b: 0 }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 103,
                     value: '!',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1066,6 +1154,7 @@ fn test_render_charbuff() {
                 CharCell::Blank,
                 CharCell::Blank,
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1076,6 +1165,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1086,6 +1176,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1096,6 +1187,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1106,6 +1198,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1116,6 +1209,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1126,6 +1220,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1136,6 +1231,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1146,6 +1242,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1156,6 +1253,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1166,6 +1264,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1176,6 +1275,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1186,6 +1286,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1196,6 +1297,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1206,6 +1308,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1216,6 +1319,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1226,6 +1330,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1236,6 +1341,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1246,6 +1352,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1256,6 +1363,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1266,6 +1374,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1276,6 +1385,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1286,6 +1396,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1296,6 +1407,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1306,6 +1418,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1316,6 +1429,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1326,6 +1440,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1336,6 +1451,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1346,6 +1462,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1356,6 +1473,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1366,6 +1484,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1376,6 +1495,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1386,6 +1506,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1396,6 +1517,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1406,6 +1528,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1416,6 +1539,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1426,6 +1550,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1436,6 +1561,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1446,6 +1572,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1456,6 +1583,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1466,6 +1594,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1476,6 +1605,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1486,6 +1616,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1496,6 +1627,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1506,6 +1638,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1516,6 +1649,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1526,6 +1660,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1536,6 +1671,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1546,6 +1682,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1556,6 +1693,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1566,6 +1704,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1576,6 +1715,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1586,6 +1726,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1596,6 +1737,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1606,6 +1748,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1616,6 +1759,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1626,6 +1770,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1636,6 +1781,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1646,6 +1792,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1656,6 +1803,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1666,6 +1814,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1676,6 +1825,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1686,6 +1836,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1696,6 +1847,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1706,6 +1858,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1716,6 +1869,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1726,6 +1880,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1736,6 +1891,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1746,6 +1902,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1756,6 +1913,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1766,6 +1924,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1776,6 +1935,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1786,6 +1946,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1796,6 +1957,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1806,6 +1968,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1816,6 +1979,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1826,6 +1990,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1836,6 +2001,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1846,6 +2012,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1856,6 +2023,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1866,6 +2034,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1876,6 +2045,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'o',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1886,6 +2056,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1896,6 +2067,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'd',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1906,6 +2078,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'w',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1916,6 +2089,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'r',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1926,6 +2100,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'a',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -1936,6 +2111,7 @@ fn test_render_charbuff() {
                     }
                 },
                 CharCell::Fill {
+                    attrs: TextAttrs::NONE,
                     element_id: 104,
                     value: 'p',
                     bg: Color { r: 0, g: 0, b: 0 },
@@ -2033,3 +2209,214 @@ fn test_render_charbuff() {
     println!("{}", &output);
     */
 }
+
+#[test]
+fn test_charbuff_text_attrs() {
+    let black = Color::rgb(0, 0, 0);
+    let white = Color::rgb(255, 255, 255);
+
+    // unknown style bits are dropped
+    assert_eq!(TextAttrs::from(0xffu128), TextAttrs::ALL);
+    assert!((TextAttrs::BOLD | TextAttrs::UNDERLINE).contains(TextAttrs::UNDERLINE));
+    assert!(!(TextAttrs::BOLD | TextAttrs::UNDERLINE).contains(TextAttrs::ITALIC));
+
+    let mut charbuff = CharBuff::new(8);
+    charbuff.print_at_styled(
+        1,
+        0,
+        0,
+        black,
+        white,
+        TextAttrs::BOLD | TextAttrs::UNDERLINE,
+        "ab",
+    );
+    charbuff.print_at(1, 0, 2, black, white, "cd");
+    charbuff.print_at_styled(2, 1, 0, black, white, TextAttrs::REVERSE, "ef");
+
+    assert_eq!(
+        charbuff.charcell_at(0, 0),
+        Some(CharCell::new_styled(
+            1,
+            'a',
+            black,
+            white,
+            TextAttrs::BOLD | TextAttrs::UNDERLINE
+        ))
+    );
+    assert_eq!(
+        charbuff.charcell_at(0, 2),
+        Some(CharCell::new(1, 'c', black, white))
+    );
+
+    // runs are split on attribute changes, and attributes are turned off before blanks
+    let scanlines = Scanline::compile(&charbuff);
+    assert_eq!(
+        scanlines,
+        vec![
+            Scanline::ClearLine,
+            Scanline::FgColor(white),
+            Scanline::BgColor(black),
+            Scanline::Attrs(TextAttrs::BOLD | TextAttrs::UNDERLINE),
+            Scanline::Text("ab".into()),
+            Scanline::Attrs(TextAttrs::NONE),
+            Scanline::Text("cd".into()),
+            Scanline::ResetColor,
+            Scanline::Text("    ".into()),
+            Scanline::Newline,
+            Scanline::ResetColor,
+            Scanline::ClearLine,
+            Scanline::FgColor(white),
+            Scanline::BgColor(black),
+            Scanline::Attrs(TextAttrs::REVERSE),
+            Scanline::Text("ef".into()),
+            Scanline::Attrs(TextAttrs::NONE),
+            Scanline::ResetColor,
+        ]
+    );
+
+    // attributes are carried over wrapped lines, so each line can be drawn on its own
+    let mut charbuff = CharBuff::new(4);
+    charbuff.print_at_styled(1, 0, 0, black, white, TextAttrs::ITALIC, "abcdef");
+    assert_eq!(
+        Scanline::compile_rows(&charbuff, 1, 1),
+        vec![
+            Scanline::ResetColor,
+            Scanline::ClearLine,
+            Scanline::FgColor(white),
+            Scanline::BgColor(black),
+            Scanline::Attrs(TextAttrs::ITALIC),
+            Scanline::Text("ef".into()),
+            Scanline::Attrs(TextAttrs::NONE),
+            Scanline::ResetColor,
+        ]
+    );
+
+    assert_eq!(
+        Scanline::Attrs(TextAttrs::BOLD | TextAttrs::UNDERLINE).into_term_code(),
+        "\x1b[22;23;24;27;1;4m"
+    );
+    assert_eq!(
+        Scanline::Attrs(TextAttrs::ALL).into_term_code(),
+        "\x1b[22;23;24;27;1;3;4;7m"
+    );
+    assert_eq!(
+        Scanline::Attrs(TextAttrs::NONE).into_term_code(),
+        "\x1b[22;23;24;27m"
+    );
+    assert_eq!(Scanline::Attrs(TextAttrs::ALL).into_text(), "");
+}
+
+#[test]
+fn test_charbuff_color_depth() {
+    let black = Color::rgb(0, 0, 0);
+    let white = Color::rgb(255, 255, 255);
+    let gray = Color::rgb(0x80, 0x80, 0x80);
+    let pink = Color::rgb(0xff, 0, 0x80);
+
+    // palette lookups
+    assert_eq!(Color::from_ansi256(196), Color::rgb(255, 0, 0));
+    assert_eq!(Color::from_ansi256(244), gray);
+    assert_eq!(Color::from_ansi256(9), Color::rgb(255, 0, 0));
+    assert_eq!(Color::from_ansi16(4), Color::rgb(0, 0, 238));
+
+    // nearest palette entries
+    assert_eq!(black.to_ansi256(), 16);
+    assert_eq!(white.to_ansi256(), 231);
+    assert_eq!(gray.to_ansi256(), 244);
+    assert_eq!(pink.to_ansi256(), 198);
+    assert_eq!(black.to_ansi16(), 0);
+    assert_eq!(white.to_ansi16(), 15);
+    assert_eq!(gray.to_ansi16(), 8);
+    assert_eq!(pink.to_ansi16(), 5);
+
+    // colors that snap to the same palette entry don't split runs
+    let mut charbuff = CharBuff::new(4);
+    charbuff.print_at(1, 0, 0, black, white, "ab");
+    charbuff.print_at(2, 0, 2, black, Color::rgb(0xfe, 0xfe, 0xfe), "cd");
+
+    assert_eq!(
+        Scanline::compile_rows_with_depth(&charbuff, 0, u64::MAX, ColorDepth::Ansi256),
+        vec![
+            Scanline::ClearLine,
+            Scanline::FgColor256(231),
+            Scanline::BgColor256(16),
+            Scanline::Text("abcd".into()),
+            Scanline::ResetColor,
+        ]
+    );
+    assert_eq!(
+        Scanline::compile_rows_with_depth(&charbuff, 0, u64::MAX, ColorDepth::Ansi16),
+        vec![
+            Scanline::ClearLine,
+            Scanline::FgColor16(15),
+            Scanline::BgColor16(0),
+            Scanline::Text("abcd".into()),
+            Scanline::ResetColor,
+        ]
+    );
+    assert_eq!(
+        Scanline::compile_rows_with_depth(&charbuff, 0, u64::MAX, ColorDepth::TrueColor),
+        Scanline::compile(&charbuff)
+    );
+
+    // in monochrome, dark-on-light text is drawn in reverse video
+    let mut charbuff = CharBuff::new(4);
+    charbuff.print_at(1, 0, 0, white, black, "ab");
+    charbuff.print_at(2, 0, 2, black, white, "cd");
+    assert_eq!(
+        Scanline::compile_rows_with_depth(&charbuff, 0, u64::MAX, ColorDepth::Mono),
+        vec![
+            Scanline::ClearLine,
+            Scanline::Attrs(TextAttrs::REVERSE),
+            Scanline::Text("ab".into()),
+            Scanline::Attrs(TextAttrs::NONE),
+            Scanline::Text("cd".into()),
+            Scanline::ResetColor,
+        ]
+    );
+
+    assert_eq!(Scanline::FgColor256(231).into_term_code(), "\x1b[38;5;231m");
+    assert_eq!(Scanline::BgColor256(16).into_term_code(), "\x1b[48;5;16m");
+    assert_eq!(Scanline::FgColor16(1).into_term_code(), "\x1b[31m");
+    assert_eq!(Scanline::FgColor16(9).into_term_code(), "\x1b[91m");
+    assert_eq!(Scanline::BgColor16(4).into_term_code(), "\x1b[44m");
+    assert_eq!(Scanline::BgColor16(12).into_term_code(), "\x1b[104m");
+    assert_eq!(Scanline::FgColor16(1).into_text(), "");
+
+    // detection from the environment
+    assert_eq!(
+        ColorDepth::detect(Some("1"), Some("truecolor"), Some("xterm")),
+        ColorDepth::Mono
+    );
+    assert_eq!(
+        ColorDepth::detect(Some(""), Some("truecolor"), Some("linux")),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        ColorDepth::detect(None, None, Some("xterm-256color")),
+        ColorDepth::Ansi256
+    );
+    assert_eq!(
+        ColorDepth::detect(None, None, Some("linux")),
+        ColorDepth::Ansi16
+    );
+    assert_eq!(
+        ColorDepth::detect(None, None, Some("vt100")),
+        ColorDepth::Ansi16
+    );
+    assert_eq!(
+        ColorDepth::detect(None, None, Some("dumb")),
+        ColorDepth::Mono
+    );
+    assert_eq!(ColorDepth::detect(None, None, None), ColorDepth::Ansi256);
+
+    for depth in [
+        ColorDepth::TrueColor,
+        ColorDepth::Ansi256,
+        ColorDepth::Ansi16,
+        ColorDepth::Mono,
+    ] {
+        assert_eq!(ColorDepth::parse(&depth.to_string()), Ok(depth));
+    }
+    assert!(ColorDepth::parse("8").is_err());
+}
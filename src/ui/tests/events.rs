@@ -18,6 +18,7 @@
 use crate::core;
 use crate::ui;
 use crate::ui::events::*;
+use crate::ui::forms::WrbFormTypes;
 use crate::ui::root::Root;
 use crate::ui::scanline::Scanline;
 use crate::ui::Renderer;
@@ -32,6 +33,8 @@ use clarity::vm::contexts::OwnedEnvironment;
 use clarity::vm::database::NULL_BURN_STATE_DB;
 use clarity::vm::Value;
 
+use termion::event::MouseButton;
+
 use crate::util::DEFAULT_CHAIN_ID;
 use crate::util::DEFAULT_WRB_EPOCH;
 
@@ -164,6 +167,76 @@ fn test_wrb_stateful_event_loop() {
     );
 }
 
+#[test]
+fn test_wrb_scroll_event_is_not_a_ui_event() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-scroll-event-is-not-a-ui-event";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    // viewport 1 and button 1 share an ID
+    let code = r#"
+(wrb-root u10 u60)
+(wrb-viewport u1 u0 u0 u10 u60)
+
+(define-data-var button-presses uint u0)
+(define-public (main (element-type uint) (element-id uint) (event-type uint) (event-payload (buff 1024)))
+    (begin
+        (if (and (is-eq element-type WRB_UI_TYPE_BUTTON) (is-eq element-id u1))
+            (var-set button-presses (+ u1 (var-get button-presses)))
+            true)
+        (ok (var-get button-presses))))
+
+(wrb-event-loop "main")
+(wrb-event-subscribe WRB_EVENT_CLOSE)
+(wrb-event-subscribe WRB_EVENT_UI)
+(wrb-event-subscribe WRB_EVENT_MOUSE)
+(wrb-event-subscribe WRB_EVENT_SCROLL)
+"#;
+
+    let scroll = WrbEvent::Scroll {
+        viewport_id: 1,
+        row: 2,
+    };
+    let mouse = WrbEvent::Mouse {
+        button: MouseButton::Left,
+        row: 0,
+        col: 0,
+    };
+    let button = WrbEvent::UI {
+        element_type: WrbFormTypes::Button,
+        element_id: 1,
+        event_payload: Value::Bool(true),
+    };
+    assert_eq!(scroll.element_type(), WRB_NON_UI_ELEMENT_TYPE);
+    assert_eq!(mouse.element_type(), WRB_NON_UI_ELEMENT_TYPE);
+    assert_eq!(scroll.element_id(), button.element_id());
+
+    let vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let renderer = Renderer::new(1_000_000_000);
+
+    let (_frames, value_opt) = run_page(
+        vm,
+        renderer,
+        code,
+        vec![scroll, mouse, button, WrbEvent::Close],
+    )
+    .unwrap();
+
+    // only the button press counted
+    assert_eq!(
+        value_opt
+            .unwrap()
+            .expect_result_ok()
+            .unwrap()
+            .expect_u128()
+            .unwrap(),
+        1
+    );
+}
+
 #[test]
 fn test_render_dynamic_text() {
     core::init(true, "localhost", 20443);
@@ -245,3 +318,80 @@ fn test_render_dynamic_text() {
         }
     }
 }
+
+#[test]
+fn test_checkbox_settings_update_on_next_frame() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-checkbox-settings-update";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    let code = r#"
+(wrb-root u2 u40)
+(wrb-viewport u0 u0 u0 u2 u40)
+
+(define-constant WRB_CHECKBOX (wrb-checkbox u0 u0 u0 (list
+    {
+        text: u"option 1",
+        selected: false
+    }
+    {
+        text: u"option 2",
+        selected: false
+    })))
+
+(define-data-var event-count uint u0)
+(define-public (main (element-type uint) (element-id uint) (event-type uint) (event-payload (buff 1024)))
+    (let (
+        (count (var-get event-count))
+    )
+    (var-set event-count (+ u1 count))
+    (if (is-eq count u0)
+        (unwrap-panic (wrb-checkbox-set-option-disabled WRB_CHECKBOX u1 true))
+        (unwrap-panic (wrb-checkbox-set-radio WRB_CHECKBOX true)))
+    (ok (var-get event-count))))
+
+(wrb-event-loop "main")
+(wrb-event-subscribe WRB_EVENT_CLOSE)
+(wrb-event-subscribe WRB_EVENT_TIMER)
+"#;
+
+    let vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let renderer = Renderer::new(1_000_000_000);
+
+    let (frames, _value_opt) = run_page(
+        vm,
+        renderer,
+        code,
+        vec![WrbEvent::Timer, WrbEvent::Timer, WrbEvent::Close],
+    )
+    .unwrap();
+
+    // each setter takes effect on the frame after the event that called it
+    let expected_texts = vec![
+        "[ ] option 1\n[ ] option 2",
+        "[ ] option 1\n[-] option 2",
+        "(*) option 1\n(-) option 2",
+    ];
+    let mut root: Option<Root> = None;
+    for (i, frame) in frames.into_iter().enumerate() {
+        let mut frame = match frame {
+            WrbFrameData::Root(frame) => frame,
+            WrbFrameData::Update(update) => {
+                let mut frame = root.unwrap();
+                frame.update_forms(update).unwrap();
+                frame
+            }
+        };
+        let chars = frame.render();
+        let scanlines = Scanline::compile(&chars);
+        let test_text = Renderer::scanlines_into_text(scanlines);
+        let lines: Vec<_> = test_text.lines().map(|line| line.trim_end()).collect();
+
+        println!("{}", &test_text);
+        assert_eq!(lines.join("\n"), expected_texts[i]);
+        root = Some(frame);
+    }
+}
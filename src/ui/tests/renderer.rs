@@ -16,6 +16,7 @@
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::core;
+use crate::ui::charbuff::{CharCell, TextAttrs};
 use crate::ui::Renderer;
 use crate::vm::ClarityStorage;
 use crate::vm::ClarityVM;
@@ -344,6 +345,49 @@ fn test_render_viewport_checkbox() {
     assert_eq!(s, "                    \n                    \n                    \n                    \n                    \n                    \n                    \n                    \n                    \n                    \n          [ ] option\n          [*] option\n          [ ] looooo\n          oooooooooo\n          oooooooong\n                    \n                    \n                    \n                    \n                    ");
 }
 
+#[test]
+fn test_render_styled_text() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-render-styled-text";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    let code = r#"
+(wrb-root u4 u20)
+(wrb-viewport u0 u0 u0 u4 u20)
+(wrb-static-txt-styled u0 u0 u0 (bit-or WRB_STYLE_BOLD WRB_STYLE_UNDERLINE) u"link")
+(wrb-txt-styled u0 u1 u0 WRB_STYLE_ITALIC u"dynamic")
+(unwrap-panic (wrb-static-println-styled u0 (some { row: u2, col: u0 }) WRB_STYLE_REVERSE u"printed"))
+(wrb-static-txt u0 u3 u0 u"plain")
+"#;
+    let bytes = Renderer::encode_bytes(code.as_bytes()).unwrap();
+
+    let mut vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let mut renderer = Renderer::new(1_000_000_000);
+    let mut root = renderer.eval_root(&mut vm, &bytes).unwrap();
+    let buff = root.refresh();
+
+    let attrs_at = |row: u64, col: u64| match buff.charcell_at(row, col) {
+        Some(CharCell::Fill { attrs, .. }) => Some(attrs),
+        _ => None,
+    };
+    assert_eq!(attrs_at(0, 0), Some(TextAttrs::BOLD | TextAttrs::UNDERLINE));
+    assert_eq!(attrs_at(1, 6), Some(TextAttrs::ITALIC));
+    assert_eq!(attrs_at(2, 0), Some(TextAttrs::REVERSE));
+    assert_eq!(attrs_at(3, 0), Some(TextAttrs::NONE));
+
+    // styles don't change the text
+    let mut vm = ClarityVM::new(db_path, "foo-test.btc", 1).unwrap();
+    let mut renderer = Renderer::new(1_000_000_000);
+    let s = renderer.eval_to_text(&mut vm, &bytes).unwrap();
+    assert_eq!(
+        s,
+        "link                \ndynamic             \nprinted             \nplain               "
+    );
+}
+
 #[test]
 fn test_render_load_store_large_strings() {
     core::init(true, "localhost", 20443);
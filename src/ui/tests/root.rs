@@ -20,12 +20,18 @@ use std::fs;
 
 use crate::core;
 use crate::ui::charbuff::{CharBuff, CharCell, Color};
+use crate::ui::events::WrbEvent;
+use crate::ui::forms::{WrbFormEvent, WrbFormTypes};
 use crate::ui::root::{Root, SceneGraph};
 use crate::ui::scanline::Scanline;
 use crate::ui::viewport::Viewport;
 use crate::ClarityVM;
 use crate::Renderer;
 
+use clarity::vm::Value;
+
+use termion::event::{Key, MouseButton};
+
 #[test]
 fn test_zbuff() {
     let mut red_viewport = Viewport::new(0, 5, 5, 20, 20);
@@ -466,3 +472,551 @@ fn test_root_focus_order() {
         assert!(root.forms.get(element_id).is_some());
     }
 }
+
+#[test]
+fn test_root_mouse_events() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-root-mouse-events";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    let code = r#"
+(wrb-root u20 u40)
+(wrb-viewport u0 u2 u5 u10 u30)
+
+(define-constant WRB_BUTTON (wrb-button u0 u0 u0 u"button"))
+(define-constant WRB_CHECKBOX (wrb-checkbox u0 u2 u0 (list
+    {
+        text: u"option 1",
+        selected: false
+    }
+    {
+        text: u"option 2",
+        selected: false
+    })))
+"#;
+    let bytes = Renderer::encode_bytes(code.as_bytes()).unwrap();
+
+    let mut vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let mut renderer = Renderer::new(1_000_000_000);
+    let mut root = renderer.eval_root(&mut vm, &bytes).unwrap();
+    root.refresh();
+
+    let qry = r#"
+    (begin
+        (print WRB_BUTTON)
+        (print WRB_CHECKBOX))"#;
+
+    let element_ids: Vec<_> = renderer
+        .run_test_query_code(&mut vm, qry)
+        .unwrap()
+        .into_iter()
+        .map(|val| val.expect_u128().unwrap())
+        .collect();
+    let button_id = element_ids[0];
+    let checkbox_id = element_ids[1];
+
+    // element lookup is relative to the element
+    assert_eq!(root.find_element(2, 7), Some((button_id, 0, 2)));
+    assert_eq!(root.find_element(5, 6), Some((checkbox_id, 1, 1)));
+    assert_eq!(root.find_element(0, 0), None);
+    assert_eq!(root.find_element(9, 20), None);
+
+    // clicking on nothing only produces a page-level event
+    root.handle_mouse_event(MouseButton::Left, 0, 0).unwrap();
+    assert_eq!(root.focused, None);
+    assert_eq!(
+        root.consume_runtime_events(),
+        vec![WrbEvent::Mouse {
+            button: MouseButton::Left,
+            row: 0,
+            col: 0
+        }]
+    );
+
+    // clicking the button focuses and presses it
+    root.handle_mouse_event(MouseButton::Left, 2, 7).unwrap();
+    assert_eq!(root.focused, Some(button_id));
+    assert_eq!(
+        root.consume_runtime_events(),
+        vec![
+            WrbEvent::Mouse {
+                button: MouseButton::Left,
+                row: 2,
+                col: 7
+            },
+            WrbEvent::UI {
+                element_type: WrbFormTypes::Button,
+                element_id: button_id,
+                event_payload: Value::Bool(true)
+            }
+        ]
+    );
+
+    // right-clicking the checkbox neither focuses nor toggles it
+    root.handle_mouse_event(MouseButton::Right, 5, 6).unwrap();
+    assert_eq!(root.focused, Some(button_id));
+    assert_eq!(root.consume_runtime_events().len(), 1);
+
+    // clicking the second checkbox option focuses the checkbox and toggles the option
+    root.handle_mouse_event(MouseButton::Left, 5, 6).unwrap();
+    assert_eq!(root.focused, Some(checkbox_id));
+    assert_eq!(root.consume_runtime_events().len(), 1);
+
+    root.redraw().unwrap();
+    let buff = root.refresh();
+    let text = CharBuff::dump_charbuff(&buff, 20);
+    eprintln!("{}", &text);
+    assert!(text.contains("[ ] option 1"));
+    assert!(text.contains("[*] option 2"));
+}
+
+#[test]
+fn test_root_disabled_checkbox() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-root-disabled-checkbox";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    let code = r#"
+(wrb-root u20 u40)
+(wrb-viewport u0 u0 u0 u20 u40)
+
+(define-constant WRB_BUTTON (wrb-button u0 u0 u0 u"button"))
+(define-constant WRB_DISABLED (wrb-checkbox u0 u2 u0 (list
+    {
+        text: u"disabled 1",
+        selected: true
+    }
+    {
+        text: u"disabled 2",
+        selected: false
+    })))
+(define-constant WRB_RADIO (wrb-checkbox u0 u5 u0 (list
+    {
+        text: u"radio 1",
+        selected: false
+    }
+    {
+        text: u"radio 2",
+        selected: false
+    }
+    {
+        text: u"radio 3",
+        selected: false
+    })))
+(unwrap-panic (wrb-checkbox-set-disabled WRB_DISABLED true))
+(unwrap-panic (wrb-checkbox-set-radio WRB_RADIO true))
+(unwrap-panic (wrb-checkbox-set-option-disabled WRB_RADIO u1 true))
+(asserts! (is-err (wrb-checkbox-set-option-disabled WRB_RADIO u3 true)) (err u0))
+"#;
+    let bytes = Renderer::encode_bytes(code.as_bytes()).unwrap();
+
+    let mut vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let mut renderer = Renderer::new(1_000_000_000);
+    let mut root = renderer.eval_root(&mut vm, &bytes).unwrap();
+    let buff = root.refresh();
+    let text = CharBuff::dump_charbuff(&buff, 20);
+    eprintln!("{}", &text);
+
+    let qry = r#"
+    (begin
+        (print WRB_BUTTON)
+        (print WRB_DISABLED)
+        (print WRB_RADIO))"#;
+
+    let element_ids: Vec<_> = renderer
+        .run_test_query_code(&mut vm, qry)
+        .unwrap()
+        .into_iter()
+        .map(|val| val.expect_u128().unwrap())
+        .collect();
+    let button_id = element_ids[0];
+    let disabled_id = element_ids[1];
+    let radio_id = element_ids[2];
+
+    // disabled options are marked, and radio groups start with one option selected
+    assert!(text.contains("[*] disabled 1"));
+    assert!(text.contains("[ ] disabled 2"));
+    assert!(text.contains("(*) radio 1"));
+    assert!(text.contains("(-) radio 2"));
+    assert!(text.contains("( ) radio 3"));
+
+    // the disabled checkbox is skipped in the focus order
+    let mut expected_focus_order = HashMap::new();
+    expected_focus_order.insert(button_id, radio_id);
+    expected_focus_order.insert(radio_id, button_id);
+    assert_eq!(root.focus_order, expected_focus_order);
+    assert!(!root.is_focusable(disabled_id));
+
+    // clicking the disabled checkbox neither focuses nor toggles it
+    root.handle_mouse_event(MouseButton::Left, 3, 1).unwrap();
+    assert_eq!(root.focused, None);
+    assert_eq!(root.consume_runtime_events().len(), 1);
+
+    // choosing a radio option deselects the others, and the selector skips the disabled option
+    root.handle_mouse_event(MouseButton::Left, 5, 1).unwrap();
+    assert_eq!(root.focused, Some(radio_id));
+    root.handle_event(WrbFormEvent::Keypress(Key::Down))
+        .unwrap();
+    root.handle_event(WrbFormEvent::Keypress(Key::Char(' ')))
+        .unwrap();
+
+    root.redraw().unwrap();
+    let buff = root.refresh();
+    let text = CharBuff::dump_charbuff(&buff, 20);
+    eprintln!("{}", &text);
+    assert!(text.contains("[*] disabled 1"));
+    assert!(text.contains("( ) radio 1"));
+    assert!(text.contains("(-) radio 2"));
+    assert!(text.contains("(*) radio 3"));
+}
+
+#[test]
+fn test_root_password_textline() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-root-password-textline";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    let code = r#"
+(wrb-root u10 u40)
+(wrb-viewport u0 u0 u0 u10 u40)
+
+(define-constant WRB_TEXTLINE (wrb-textline u0 u0 u0 u20 u"visible"))
+(define-constant WRB_PASSWORD (wrb-password-textline u0 u2 u0 u20))
+"#;
+    let bytes = Renderer::encode_bytes(code.as_bytes()).unwrap();
+
+    let mut vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let mut renderer = Renderer::new(1_000_000_000);
+    let mut root = renderer.eval_root(&mut vm, &bytes).unwrap();
+    root.refresh();
+
+    let qry = r#"
+    (begin
+        (print WRB_TEXTLINE)
+        (print WRB_PASSWORD))"#;
+
+    let element_ids: Vec<_> = renderer
+        .run_test_query_code(&mut vm, qry)
+        .unwrap()
+        .into_iter()
+        .map(|val| val.expect_u128().unwrap())
+        .collect();
+    let textline_id = element_ids[0];
+    let password_id = element_ids[1];
+
+    root.handle_mouse_event(MouseButton::Left, 0, 1).unwrap();
+    assert_eq!(root.focused, Some(textline_id));
+    assert!(!root.focused_is_masked());
+
+    root.handle_mouse_event(MouseButton::Left, 2, 1).unwrap();
+    assert_eq!(root.focused, Some(password_id));
+    assert!(root.focused_is_masked());
+    for c in "hunter2".chars() {
+        root.handle_event(WrbFormEvent::Keypress(Key::Char(c)))
+            .unwrap();
+    }
+
+    // only the mask is drawn
+    root.redraw().unwrap();
+    let buff = root.refresh();
+    let text = CharBuff::dump_charbuff(&buff, 10);
+    eprintln!("{}", &text);
+    assert!(text.contains("visible"));
+    assert!(text.contains("*******"));
+    assert!(!text.contains("hunter2"));
+
+    // the page still gets the real text
+    assert_eq!(
+        root.forms
+            .get(&password_id)
+            .unwrap()
+            .to_clarity_value()
+            .unwrap(),
+        Some(Value::string_utf8_from_string_utf8_literal("hunter2".to_string()).unwrap())
+    );
+}
+
+#[test]
+fn test_root_scroll_viewport() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-root-scroll-viewport";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    let code = r#"
+(wrb-root u10 u40)
+(wrb-viewport u0 u1 u2 u4 u20)
+(wrb-static-txt u0 u0 u0 u"line 0")
+(wrb-static-txt u0 u1 u0 u"line 1")
+(wrb-static-txt u0 u2 u0 u"line 2")
+(wrb-static-txt u0 u3 u0 u"line 3")
+(wrb-static-txt u0 u4 u0 u"line 4")
+(wrb-static-txt u0 u5 u0 u"line 5")
+(wrb-static-txt u0 u6 u0 u"line 6")
+(wrb-static-txt u0 u7 u0 u"line 7")
+(wrb-static-txt u0 u8 u0 u"line 8")
+(wrb-static-txt u0 u9 u0 u"line 9")
+(unwrap-panic (wrb-viewport-set-scrollable u0 u10 true))
+(unwrap-panic (wrb-viewport-set-scroll u0 u2))
+"#;
+    let bytes = Renderer::encode_bytes(code.as_bytes()).unwrap();
+
+    let mut vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let mut renderer = Renderer::new(1_000_000_000);
+    let mut root = renderer.eval_root(&mut vm, &bytes).unwrap();
+
+    let rows = |root: &mut Root| -> Vec<String> {
+        let buff = root.refresh();
+        let text = CharBuff::dump_charbuff(&buff, 10);
+        eprintln!("{}", &text);
+        text.lines().map(|line| line.to_string()).collect()
+    };
+
+    // scrolled to the third line, with the scrollbar's thumb in the second row
+    let text = rows(&mut root);
+    assert!(text[1].starts_with("__line 2"));
+    assert!(text[4].starts_with("__line 5"));
+    assert_eq!(text[1].chars().nth(21), Some('│'));
+    assert_eq!(text[2].chars().nth(21), Some('█'));
+    assert_eq!(root.find_element(2, 21), None);
+
+    // the page sees the scroll position, clamped to the content
+    let scroll = renderer
+        .run_test_query_code(&mut vm, "(print (wrb-viewport-get-scroll u0))")
+        .unwrap();
+    assert_eq!(scroll, vec![Value::some(Value::UInt(2)).unwrap()]);
+
+    let scroll = renderer
+        .run_test_query_code(
+            &mut vm,
+            "(begin (unwrap-panic (wrb-viewport-set-scroll u0 u20)) (print (wrb-viewport-get-scroll u0)))",
+        )
+        .unwrap();
+    assert_eq!(scroll, vec![Value::some(Value::UInt(6)).unwrap()]);
+
+    let scroll = renderer
+        .run_test_query_code(
+            &mut vm,
+            "(begin (wrb-internal-viewport-scrolled u0 u4) (print (wrb-viewport-get-scroll u0)))",
+        )
+        .unwrap();
+    assert_eq!(scroll, vec![Value::some(Value::UInt(4)).unwrap()]);
+
+    // the mouse wheel scrolls the viewport under the pointer
+    root.handle_mouse_event(MouseButton::WheelDown, 2, 5)
+        .unwrap();
+    assert_eq!(
+        root.consume_runtime_events(),
+        vec![
+            WrbEvent::Mouse {
+                button: MouseButton::WheelDown,
+                row: 2,
+                col: 5
+            },
+            WrbEvent::Scroll {
+                viewport_id: 0,
+                row: 5
+            }
+        ]
+    );
+    let text = rows(&mut root);
+    assert!(text[1].starts_with("__line 5"));
+
+    // page-down scrolls the last-clicked viewport, up to the end of its content
+    root.handle_event(WrbFormEvent::Keypress(Key::PageDown))
+        .unwrap();
+    assert_eq!(
+        root.consume_runtime_events(),
+        vec![WrbEvent::Scroll {
+            viewport_id: 0,
+            row: 6
+        }]
+    );
+    let text = rows(&mut root);
+    assert!(text[1].starts_with("__line 6"));
+    assert!(text[4].starts_with("__line 9"));
+    assert_eq!(text[4].chars().nth(21), Some('█'));
+
+    // no more scrolling, so no event
+    root.handle_event(WrbFormEvent::Keypress(Key::PageDown))
+        .unwrap();
+    assert_eq!(root.consume_runtime_events(), vec![]);
+
+    // clicking the scrollbar above the thumb pages up
+    root.handle_mouse_event(MouseButton::Left, 1, 21).unwrap();
+    assert_eq!(
+        root.consume_runtime_events(),
+        vec![
+            WrbEvent::Mouse {
+                button: MouseButton::Left,
+                row: 1,
+                col: 21
+            },
+            WrbEvent::Scroll {
+                viewport_id: 0,
+                row: 3
+            }
+        ]
+    );
+    let text = rows(&mut root);
+    assert!(text[1].starts_with("__line 3"));
+}
+
+#[test]
+fn test_root_scroll_viewport_focused_form_keys() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-root-scroll-viewport-focused-form-keys";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    let code = r#"
+(wrb-root u10 u40)
+(wrb-viewport u0 u1 u2 u4 u30)
+(define-constant WRB_TABLE (wrb-table u0 u0 u0 u2
+    (list { header: u"name", width: u10 })
+    (list (list u"a") (list u"b") (list u"c") (list u"d") (list u"e") (list u"f"))))
+(unwrap-panic (wrb-viewport-set-scrollable u0 u10 true))
+"#;
+    let bytes = Renderer::encode_bytes(code.as_bytes()).unwrap();
+
+    let mut vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let mut renderer = Renderer::new(1_000_000_000);
+    let mut root = renderer.eval_root(&mut vm, &bytes).unwrap();
+    root.refresh();
+
+    let table_id = renderer
+        .run_test_query_code(&mut vm, "(print WRB_TABLE)")
+        .unwrap()
+        .pop()
+        .unwrap()
+        .expect_u128()
+        .unwrap();
+
+    // the focused table pages its own rows, so the viewport doesn't scroll
+    root.next_focus().unwrap();
+    assert_eq!(root.focused, Some(table_id));
+    root.handle_event(WrbFormEvent::Keypress(Key::PageDown))
+        .unwrap();
+    assert!(!root
+        .consume_runtime_events()
+        .iter()
+        .any(|event| matches!(event, WrbEvent::Scroll { .. })));
+    assert_eq!(root.viewports()[0].scroll_offset(), 0);
+
+    // with nothing focused, page-down scrolls the last-clicked viewport
+    root.clear_focus().unwrap();
+    root.handle_mouse_event(MouseButton::Right, 1, 3).unwrap();
+    assert_eq!(root.focused, None);
+    root.consume_runtime_events();
+
+    root.handle_event(WrbFormEvent::Keypress(Key::PageDown))
+        .unwrap();
+    assert_eq!(
+        root.consume_runtime_events(),
+        vec![WrbEvent::Scroll {
+            viewport_id: 0,
+            row: 3
+        }]
+    );
+}
+
+#[test]
+fn test_root_select_dropdown() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-root-select-dropdown";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    let code = r#"
+(wrb-root u10 u40)
+(wrb-viewport u0 u0 u0 u10 u40)
+
+(define-constant WRB_SELECT (wrb-select u0 u0 u0 u3 u20 false true (list
+    {
+        text: u"one",
+        selected: false
+    }
+    {
+        text: u"two",
+        selected: true
+    })))
+"#;
+    let bytes = Renderer::encode_bytes(code.as_bytes()).unwrap();
+
+    let mut vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let mut renderer = Renderer::new(1_000_000_000);
+    let mut root = renderer.eval_root(&mut vm, &bytes).unwrap();
+    root.refresh();
+
+    let select_id = renderer
+        .run_test_query_code(&mut vm, "(print WRB_SELECT)")
+        .unwrap()
+        .pop()
+        .unwrap()
+        .expect_u128()
+        .unwrap();
+
+    let rows = |root: &mut Root| -> Vec<String> {
+        root.redraw().unwrap();
+        let buff = root.refresh();
+        let text = CharBuff::dump_charbuff(&buff, 10);
+        eprintln!("{}", &text);
+        text.lines().map(|line| line.to_string()).collect()
+    };
+
+    // collapsed, showing the selected option
+    let text = rows(&mut root);
+    assert!(text[0].starts_with("two"));
+    assert!(text[0].contains("▼"));
+    assert!(text[1].starts_with("____"));
+
+    // clicking it focuses and opens it
+    root.handle_mouse_event(MouseButton::Left, 0, 2).unwrap();
+    assert_eq!(root.focused, Some(select_id));
+    assert_eq!(root.consume_runtime_events().len(), 1);
+
+    let text = rows(&mut root);
+    assert!(text[1].starts_with("( ) one"));
+    assert!(text[2].starts_with("(*) two"));
+
+    // clicking an option chooses it and closes the dropdown
+    root.handle_mouse_event(MouseButton::Left, 1, 2).unwrap();
+    let events = root.consume_runtime_events();
+    assert_eq!(events.len(), 2);
+    assert_eq!(
+        events[1],
+        WrbEvent::UI {
+            element_type: WrbFormTypes::Select,
+            element_id: select_id,
+            event_payload: Value::Tuple(
+                clarity::vm::types::TupleData::from_data(vec![
+                    ("index".into(), Value::UInt(0)),
+                    (
+                        "selected".into(),
+                        Value::cons_list(vec![Value::UInt(0)], &crate::DEFAULT_WRB_EPOCH).unwrap()
+                    ),
+                ])
+                .unwrap()
+            )
+        }
+    );
+
+    let text = rows(&mut root);
+    assert!(text[0].starts_with("one"));
+    assert!(text[1].starts_with("____"));
+}
@@ -22,8 +22,17 @@ use std::fmt;
 use crate::ui::charbuff::CharBuff;
 use crate::ui::charbuff::CharCell;
 use crate::ui::charbuff::Color;
+use crate::ui::charbuff::TextAttrs;
 use crate::ui::Error;
 
+/// Element ID given to the charcells of a viewport's scrollbar
+pub const SCROLLBAR_ELEMENT_ID: u128 = u128::MAX;
+
+const SCROLLBAR_TRACK: char = '│';
+const SCROLLBAR_THUMB: char = '█';
+const SCROLLBAR_BG_COLOR: u32 = 0x303030;
+const SCROLLBAR_FG_COLOR: u32 = 0xc0c0c0;
+
 #[derive(Clone, PartialEq)]
 pub struct Viewport {
     pub id: u128,
@@ -33,8 +42,13 @@ pub struct Viewport {
     /// number of rows that will be visible
     /// (num_cols in in `buff`)
     num_rows: u64,
+    /// number of rows of content, of which `num_rows` are visible at a time.
+    /// If this is more than `num_rows`, then the viewport can scroll.
+    content_rows: u64,
     /// viewport row offset
     scroll_offset: u64,
+    /// draw a scrollbar in the last column if the viewport can scroll?
+    scrollbar: bool,
     /// is it available for rendering?
     visible: bool,
     /// parent viewport
@@ -51,12 +65,13 @@ impl fmt::Debug for Viewport {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Viewport({},({},{}),({},{}),scroll={},visible={},buff={})",
+            "Viewport({},({},{}),({},{}),content={},scroll={},visible={},buff={})",
             self.id,
             self.start_row,
             self.start_col,
             self.start_row + self.num_rows,
             self.start_col + self.buff.num_cols,
+            self.content_rows,
             self.scroll_offset,
             self.visible,
             self.buff.cells.len()
@@ -110,6 +125,30 @@ impl Viewport {
         )
         .expect("too many columns");
 
+        let content_rows = u64::try_from(
+            viewport_tuple
+                .get("content-rows")
+                .cloned()
+                .expect("FATAL: no content-rows")
+                .expect_u128()?,
+        )
+        .expect("too many content rows");
+
+        let scroll_offset = u64::try_from(
+            viewport_tuple
+                .get("scroll-row")
+                .cloned()
+                .expect("FATAL: no scroll-row")
+                .expect_u128()?,
+        )
+        .expect("scroll row too big");
+
+        let scrollbar = viewport_tuple
+            .get("scrollbar")
+            .cloned()
+            .expect("FATAL: no scrollbar")
+            .expect_bool()?;
+
         let visible = viewport_tuple
             .get("visible")
             .cloned()
@@ -132,18 +171,22 @@ impl Viewport {
             .map(|last_value| last_value.expect_u128())
             .transpose()?;
 
-        Ok(Viewport {
+        let mut viewport = Viewport {
             id,
             start_row,
             start_col,
             num_rows,
+            content_rows,
             scroll_offset: 0,
+            scrollbar,
             visible,
             prev_viewport: last_opt,
             parent: parent_opt,
             buff: CharBuff::new(num_cols),
             element_coords: HashMap::new(),
-        })
+        };
+        viewport.set_scroll(scroll_offset);
+        Ok(viewport)
     }
 
     pub fn new(id: u128, start_row: u64, start_col: u64, num_rows: u64, num_cols: u64) -> Viewport {
@@ -152,7 +195,9 @@ impl Viewport {
             start_row,
             start_col,
             num_rows,
+            content_rows: num_rows,
             scroll_offset: 0,
+            scrollbar: false,
             visible: true,
             prev_viewport: None,
             parent: None,
@@ -174,7 +219,9 @@ impl Viewport {
             start_row,
             start_col,
             num_rows,
+            content_rows: num_rows,
             scroll_offset: 0,
+            scrollbar: false,
             visible: true,
             prev_viewport: None,
             parent: Some(parent_id),
@@ -193,6 +240,89 @@ impl Viewport {
         (self.num_rows, self.buff.num_cols)
     }
 
+    /// How many rows of content does this viewport hold?  This is at least its number of rows.
+    pub fn content_rows(&self) -> u64 {
+        self.content_rows.max(self.num_rows)
+    }
+
+    /// Set the number of rows of content, and whether or not to draw a scrollbar.
+    /// The scroll offset is clamped to the new content size.
+    pub fn set_content_rows(&mut self, content_rows: u64, scrollbar: bool) {
+        self.content_rows = content_rows;
+        self.scrollbar = scrollbar;
+        self.set_scroll(self.scroll_offset);
+    }
+
+    /// Does this viewport have more content than it can show at once?
+    pub fn scrollable(&self) -> bool {
+        self.content_rows() > self.num_rows
+    }
+
+    /// What's the content row at the top of the viewport?
+    pub fn scroll_offset(&self) -> u64 {
+        self.scroll_offset
+    }
+
+    /// What's the largest scroll offset?
+    pub fn max_scroll(&self) -> u64 {
+        self.content_rows() - self.num_rows
+    }
+
+    /// Scroll so that the given content row is at the top, as far as the content allows.
+    /// Returns true if the scroll offset changed
+    pub fn set_scroll(&mut self, scroll_offset: u64) -> bool {
+        let old_scroll_offset = self.scroll_offset;
+        self.scroll_offset = scroll_offset.min(self.max_scroll());
+        self.scroll_offset != old_scroll_offset
+    }
+
+    /// Scroll down by `delta` rows (or up, if `delta` is negative), as far as the content allows.
+    /// Returns true if the scroll offset changed
+    pub fn scroll_by(&mut self, delta: i64) -> bool {
+        let scroll_offset = if delta < 0 {
+            self.scroll_offset.saturating_sub(delta.unsigned_abs())
+        } else {
+            self.scroll_offset.saturating_add(delta.unsigned_abs())
+        };
+        self.set_scroll(scroll_offset)
+    }
+
+    /// Is the scrollbar drawn in the given relative column?
+    pub fn scrollbar_at(&self, rel_col: u64) -> bool {
+        self.scrollbar && self.scrollable() && rel_col + 1 == self.buff.num_cols
+    }
+
+    /// Where is the scrollbar's thumb?  Returns (first row, number of rows), relative to the
+    /// viewport.
+    pub fn scrollbar_thumb(&self) -> (u64, u64) {
+        let content_rows = self.content_rows();
+        let thumb_rows = (self.num_rows * self.num_rows / content_rows.max(1))
+            .max(1)
+            .min(self.num_rows);
+        let thumb_start = if self.max_scroll() > 0 {
+            self.scroll_offset * (self.num_rows - thumb_rows) / self.max_scroll()
+        } else {
+            0
+        };
+        (thumb_start, thumb_rows)
+    }
+
+    /// The scrollbar's charcell at the given relative row
+    fn scrollbar_charcell(&self, rel_row: u64) -> CharCell {
+        let (thumb_start, thumb_rows) = self.scrollbar_thumb();
+        let value = if thumb_start <= rel_row && rel_row < thumb_start + thumb_rows {
+            SCROLLBAR_THUMB
+        } else {
+            SCROLLBAR_TRACK
+        };
+        CharCell::new(
+            SCROLLBAR_ELEMENT_ID,
+            value,
+            SCROLLBAR_BG_COLOR.into(),
+            SCROLLBAR_FG_COLOR.into(),
+        )
+    }
+
     /// Update the coordinate of a UI element
     fn update_element_coord(&mut self, element_id: u128, start_row: u64, start_col: u64) {
         if let Some((r, c)) = self.element_coords.get_mut(&element_id) {
@@ -220,6 +350,24 @@ impl Viewport {
             .print_at(element_id, start_row, start_col, bg_color, fg_color, text)
     }
 
+    /// Write text to this viewport, with text attributes.
+    /// `start_col` and `start_row` are coordinates within the viewport.
+    pub fn print_to_styled(
+        &mut self,
+        element_id: u128,
+        start_row: u64,
+        start_col: u64,
+        bg_color: Color,
+        fg_color: Color,
+        attrs: TextAttrs,
+        text: &str,
+    ) -> (u64, u64) {
+        self.update_element_coord(element_id, start_row, start_col);
+        self.buff.print_at_styled(
+            element_id, start_row, start_col, bg_color, fg_color, attrs, text,
+        )
+    }
+
     /// Write word-wrapped text to this viewport
     /// `start_col` and `start_row` are coordinates within the viewport
     pub fn print(
@@ -236,6 +384,24 @@ impl Viewport {
             .print(element_id, start_row, start_col, bg_color, fg_color, text)
     }
 
+    /// Write word-wrapped text to this viewport, with text attributes
+    /// `start_col` and `start_row` are coordinates within the viewport
+    pub fn print_styled(
+        &mut self,
+        element_id: u128,
+        start_row: u64,
+        start_col: u64,
+        bg_color: Color,
+        fg_color: Color,
+        attrs: TextAttrs,
+        text: &str,
+    ) -> (u64, u64) {
+        self.update_element_coord(element_id, start_row, start_col);
+        self.buff.print_styled(
+            element_id, start_row, start_col, bg_color, fg_color, attrs, text,
+        )
+    }
+
     /// Write word-wrapped text to this viewport, with a newline at the end
     /// `start_col` and `start_row` are coordinates within the viewport
     pub fn println(
@@ -252,6 +418,24 @@ impl Viewport {
             .println(element_id, start_row, start_col, bg_color, fg_color, text)
     }
 
+    /// Write word-wrapped text to this viewport, with text attributes and a newline at the end
+    /// `start_col` and `start_row` are coordinates within the viewport
+    pub fn println_styled(
+        &mut self,
+        element_id: u128,
+        start_row: u64,
+        start_col: u64,
+        bg_color: Color,
+        fg_color: Color,
+        attrs: TextAttrs,
+        text: &str,
+    ) -> (u64, u64) {
+        self.update_element_coord(element_id, start_row, start_col);
+        self.buff.println_styled(
+            element_id, start_row, start_col, bg_color, fg_color, attrs, text,
+        )
+    }
+
     /// Write word-wrapped text to this viewport from a char iterator.
     /// attempts to word-wrap.
     /// `start_col` and `start_row` are coordinates within the viewport
@@ -326,16 +510,7 @@ impl Viewport {
         viewport_abs_row: u64,
         viewport_abs_col: u64,
     ) -> Option<(u128, u64, u64)> {
-        let Some(cell) = self.buff.charcell_at(viewport_abs_row, viewport_abs_col) else {
-            return None;
-        };
-        let CharCell::Fill {
-            value: _value,
-            bg: _bg,
-            fg: _fg,
-            element_id,
-        } = cell
-        else {
+        let Some(element_id) = self.buff.element_at(viewport_abs_row, viewport_abs_col) else {
             return None;
         };
         let Some((ui_row, ui_col)) = self.element_coords.get(&element_id) else {
@@ -355,12 +530,25 @@ impl Viewport {
 
     /// What's the charcell at the given relative coordinates, when taking into account scrolling?
     pub fn charcell_at(&self, rel_row: u64, rel_col: u64) -> Option<CharCell> {
-        if rel_row + self.scroll_offset >= self.num_rows {
+        if self.scrollbar_at(rel_col) {
+            return Some(self.scrollbar_charcell(rel_row));
+        }
+        if rel_row + self.scroll_offset >= self.content_rows() {
             return None;
         }
         self.buff.charcell_at(rel_row + self.scroll_offset, rel_col)
     }
 
+    /// Which UI element is at the given relative coordinates, when taking into account scrolling?
+    /// Return Some((element_id, row, col)) with the (row, col) relative to the UI element.
+    /// Return None otherwise
+    pub fn element_at(&self, rel_row: u64, rel_col: u64) -> Option<(u128, u64, u64)> {
+        if self.scrollbar_at(rel_col) || rel_row + self.scroll_offset >= self.content_rows() {
+            return None;
+        }
+        self.get_ui_coordinate(rel_row + self.scroll_offset, rel_col)
+    }
+
     /// Are we visible?
     pub fn visible(&self) -> bool {
         self.visible
@@ -382,12 +570,15 @@ impl Viewport {
         self.start_row = vp.start_row;
         self.start_col = vp.start_col;
         self.num_rows = vp.num_rows;
+        self.content_rows = vp.content_rows;
+        self.scrollbar = vp.scrollbar;
         self.visible = vp.visible;
         self.buff = CharBuff::new(vp.buff.num_cols);
+        self.set_scroll(vp.scroll_offset);
     }
 
     /// Dump the viewport contents
     pub fn dump_viewport(&self) -> String {
-        CharBuff::dump_charbuff(&self.buff, self.num_rows)
+        CharBuff::dump_charbuff(&self.buff, self.content_rows())
     }
 }
@@ -29,17 +29,26 @@ use std::thread;
 use std::thread::JoinHandle;
 
 use termion::async_stdin;
+use termion::event::Event;
 use termion::event::Key;
+use termion::event::MouseEvent;
+use termion::input::MouseTerminal;
 use termion::input::TermRead;
 use termion::raw::IntoRawMode;
 use termion::screen::IntoAlternateScreen;
 
+use crate::core;
+use crate::core::config::DEFAULT_IDENTITY_NAME;
+use crate::runner::identities::SiteIdentityDB;
+use crate::runner::pins::SitePin;
+use crate::runner::Error as RunnerError;
 use crate::ui::events::WrbEvent;
 use crate::ui::events::WrbFrameData;
 use crate::ui::events::WrbUIEventChannels;
 use crate::ui::forms::WrbFormEvent;
 use crate::ui::root::FrameUpdate;
 use crate::ui::root::Root;
+use crate::ui::scanline::ColorDepth;
 use crate::ui::scanline::Scanline;
 use crate::ui::Error as UIError;
 use crate::ui::Renderer;
@@ -63,6 +72,7 @@ pub enum ViewerFocus {
 #[derive(Clone, Debug)]
 pub enum ViewerEvent {
     Stdin(Key),
+    Mouse(MouseEvent),
     Root(Root),
     Update(FrameUpdate),
     Quit,
@@ -83,12 +93,26 @@ pub struct Viewer {
     focus: ViewerFocus,
     /// whether or not to abort the main loop
     quit: Arc<AtomicBool>,
+    /// name of the wrbsite we're viewing
+    wrbname: String,
+    /// names of all of the user's identities.  The default identity is first.
+    identities: Vec<String>,
+    /// identity the wrbsite will run as the next time it loads
+    site_identity: String,
+    /// path to the DB that remembers which identity each wrbsite runs as
+    site_identities_path: Option<String>,
+    /// how many colors the terminal can draw
+    color_depth: ColorDepth,
+    /// if the wrbsite's publisher changed, this is the new publisher's pin, and the channel to
+    /// signal once the user trusts it
+    pending_site_pin: Option<(SitePin, Sender<()>)>,
 }
 
 #[derive(Debug)]
 pub enum Error {
     IO(IOError),
     UI(UIError),
+    Runner(RunnerError),
     Finished,
 }
 
@@ -104,6 +128,12 @@ impl From<UIError> for Error {
     }
 }
 
+impl From<RunnerError> for Error {
+    fn from(e: RunnerError) -> Self {
+        Self::Runner(e)
+    }
+}
+
 impl Viewer {
     pub fn new(events: WrbUIEventChannels, wrbname: &str) -> Self {
         Self {
@@ -114,7 +144,113 @@ impl Viewer {
             status: ViewerStatus::new(wrbname.to_string(), false),
             focus: ViewerFocus::NoFocus,
             quit: Arc::new(AtomicBool::new(false)),
+            wrbname: wrbname.to_string(),
+            identities: vec![],
+            site_identity: DEFAULT_IDENTITY_NAME.to_string(),
+            site_identities_path: None,
+            color_depth: ColorDepth::TrueColor,
+            pending_site_pin: None,
+        }
+    }
+
+    /// Draw pages with only the colors available at `depth`
+    pub fn with_color_depth(mut self, depth: ColorDepth) -> Self {
+        self.color_depth = depth;
+        self
+    }
+
+    /// Let the user choose which of `identities` this wrbsite runs as.
+    /// `active` is the one it's running as now.
+    pub fn with_identities(
+        mut self,
+        identities: Vec<String>,
+        active: &str,
+        site_identities_path: String,
+    ) -> Self {
+        if identities.len() > 1 {
+            self.status
+                .set_text(format!("{} (as '{}')", &self.wrbname, active));
         }
+        self.identities = identities;
+        self.site_identity = active.to_string();
+        self.site_identities_path = Some(site_identities_path);
+        self
+    }
+
+    /// Don't show the wrbsite until the user trusts its new publisher, `new_pin`.
+    /// `accept_send` is signaled once they do.
+    pub fn with_publisher_changed(
+        mut self,
+        old_pin: &SitePin,
+        new_pin: SitePin,
+        accept_send: Sender<()>,
+    ) -> Self {
+        self.status.set_text(format!(
+            "WARNING: publisher of {} changed from {} (signer {}) to {} (signer {})",
+            &self.wrbname,
+            &old_pin.contract_id,
+            &old_pin.signer,
+            &new_pin.contract_id,
+            &new_pin.signer
+        ));
+        self.status
+            .set_mode_text("(a)ccept new publisher  |  (q)uit".to_string());
+        self.pending_site_pin = Some((new_pin, accept_send));
+        self
+    }
+
+    /// Trust the wrbsite's new publisher, and let it load
+    fn accept_site_pin(&mut self) {
+        let Some((new_pin, accept_send)) = self.pending_site_pin.take() else {
+            return;
+        };
+        if let Err(e) = core::accept_site_pin(&new_pin) {
+            self.status.set_text(e);
+            self.pending_site_pin = Some((new_pin, accept_send));
+            return;
+        }
+        self.status.reset_mode_text();
+        if accept_send.send(()).is_err() {
+            wrb_warn!("Failed to signal that the new publisher was accepted");
+            self.status
+                .set_text(format!("Failed to load {}", &self.wrbname));
+            return;
+        }
+        self.status
+            .set_text(format!("Trusted the new publisher of {}", &self.wrbname));
+    }
+
+    /// Run the wrbsite as the next identity the next time it loads, and remember the choice.
+    fn next_site_identity(&mut self) -> Result<(), RunnerError> {
+        if self.identities.len() < 2 {
+            self.status
+                .set_text("No other identities are configured".to_string());
+            return Ok(());
+        }
+        let Some(site_identities_path) = self.site_identities_path.as_ref() else {
+            return Ok(());
+        };
+        let idx = self
+            .identities
+            .iter()
+            .position(|identity| identity == &self.site_identity)
+            .map(|idx| (idx + 1) % self.identities.len())
+            .unwrap_or(0);
+        let next_identity = self.identities[idx].clone();
+
+        let mut db = SiteIdentityDB::open(site_identities_path)?;
+        if next_identity == DEFAULT_IDENTITY_NAME {
+            db.remove_site_identity(&self.wrbname)?;
+        } else {
+            db.put_site_identity(&self.wrbname, &next_identity)?;
+        }
+
+        self.status.set_text(format!(
+            "{} will run as '{}' the next time it loads",
+            &self.wrbname, &next_identity
+        ));
+        self.site_identity = next_identity;
+        Ok(())
     }
 
     /// cursor goto
@@ -225,7 +361,16 @@ impl Viewer {
         mut frame: Option<&mut Root>,
         stdout: &mut W,
     ) -> Result<bool, Error> {
-        wrb_debug!("Got key in focus {:?}: {:?}", &self.focus, &key);
+        if self.focus == ViewerFocus::Root
+            && frame
+                .as_ref()
+                .map(|frame| frame.focused_is_masked())
+                .unwrap_or(false)
+        {
+            wrb_debug!("Got key in focus {:?}: <masked>", &self.focus);
+        } else {
+            wrb_debug!("Got key in focus {:?}: {:?}", &self.focus, &key);
+        }
 
         // if we have no frame, then focus reverts to the Status widget
         if frame.is_none() {
@@ -258,9 +403,25 @@ impl Viewer {
                     Key::Char('\n') => {
                         self.focus = ViewerFocus::Root;
                     }
+                    Key::Char('i') => {
+                        if let Err(e) = self.next_site_identity() {
+                            wrb_warn!("Failed to change site identity: {:?}", &e);
+                            self.status
+                                .set_text(format!("Failed to change identity: {}", &e));
+                        }
+                    }
+                    Key::Char('a') => {
+                        self.accept_site_pin();
+                    }
                     Key::Char('q') => {
                         ret = false;
                     }
+                    Key::PageUp | Key::PageDown => {
+                        // scroll the viewport last clicked on
+                        if let Some(frame) = frame.as_mut() {
+                            frame.handle_event(WrbFormEvent::Keypress(key))?;
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -318,18 +479,80 @@ impl Viewer {
         Ok(ret)
     }
 
-    /// Keyboard reader thread
+    /// Handle a mouse event we received.
+    /// Only button presses do anything.  Clicking the status bar focuses it, and pressing a
+    /// button over the root passes the press to the root (and focuses the root if a form got
+    /// focus as a result).
+    pub fn dispatch_mouse_event<W: Write>(
+        &mut self,
+        mouse_event: MouseEvent,
+        mut frame: Option<&mut Root>,
+        stdout: &mut W,
+    ) -> Result<(), Error> {
+        wrb_debug!(
+            "Got mouse event in focus {:?}: {:?}",
+            &self.focus,
+            &mouse_event
+        );
+        let MouseEvent::Press(button, term_col, term_row) = mouse_event else {
+            return Ok(());
+        };
+
+        // termion coordinates are 1-indexed
+        let row = u64::from(term_row.saturating_sub(1));
+        let col = u64::from(term_col.saturating_sub(1));
+
+        let status_rows = self.status.num_rows();
+        let (root_rows, _) = self.get_root_size(self.size.0, self.size.1);
+        let (status_start, root_start) = if self.status.at_top() {
+            (0, status_rows)
+        } else {
+            (root_rows, 0)
+        };
+
+        if status_start <= row && row < status_start + status_rows {
+            if row == status_start {
+                // clicked on the progress text
+                self.status
+                    .handle_click(self.focus == ViewerFocus::Status, button, col)?;
+            }
+            if let Some(frame) = frame.as_mut() {
+                frame.clear_focus()?;
+            }
+            self.set_status_focus(stdout)?;
+            return Ok(());
+        }
+
+        let Some(frame) = frame.as_mut() else {
+            return Ok(());
+        };
+        frame.handle_mouse_event(button, row.saturating_sub(root_start), col)?;
+        if frame.focused.is_some() {
+            self.focus = ViewerFocus::Root;
+            self.update_focused_cursor(frame, stdout)?;
+        }
+        Ok(())
+    }
+
+    /// Keyboard and mouse reader thread
     fn start_keyboard_thread(
         quit: Arc<AtomicBool>,
         key_sender: Sender<ViewerEvent>,
     ) -> JoinHandle<()> {
         let stdin = stdin();
         let handle = thread::spawn(move || {
-            for c in stdin.keys() {
-                let Ok(k) = c else {
+            for c in stdin.events() {
+                let Ok(event) = c else {
                     return;
                 };
-                if key_sender.send(ViewerEvent::Stdin(k)).is_err() {
+                let viewer_event = match event {
+                    Event::Key(k) => ViewerEvent::Stdin(k),
+                    Event::Mouse(m) => ViewerEvent::Mouse(m),
+                    Event::Unsupported(_) => {
+                        continue;
+                    }
+                };
+                if key_sender.send(viewer_event).is_err() {
                     return;
                 }
                 if quit.load(Ordering::SeqCst) {
@@ -374,14 +597,17 @@ impl Viewer {
 
     /// Render a frame (`root`). Saves it to self.last_frame
     fn render<W: Write>(&mut self, mut root: Root, screen: &mut W) -> Result<(), Error> {
-        let status_text = self
-            .status
-            .render(self.focus == ViewerFocus::Status, self.size.1);
+        let status_text = self.status.render(
+            self.focus == ViewerFocus::Status,
+            self.size.1,
+            self.color_depth,
+        );
         let (root_rows, _) = self.get_root_size(self.size.0, self.size.1);
         let root_text = {
             wrb_debug!("Render root! Num rows = {}", root_rows);
             let chars = root.render();
-            let scanlines = Scanline::compile_rows(&chars, 0, root_rows);
+            let scanlines =
+                Scanline::compile_rows_with_depth(&chars, 0, root_rows, self.color_depth);
             Renderer::scanlines_into_term_string(scanlines)
         };
 
@@ -427,7 +653,8 @@ impl Viewer {
 
     /// Main event loop
     pub fn main(mut self) -> Result<(), Error> {
-        let mut screen = stdout().lock().into_raw_mode()?.into_alternate_screen()?;
+        let mut screen =
+            MouseTerminal::from(stdout().lock().into_raw_mode()?).into_alternate_screen()?;
 
         self.clear_screen(&mut screen)?;
         self.hide_cursor(&mut screen)?;
@@ -467,6 +694,12 @@ impl Viewer {
         }
         self.size = sz;
 
+        // the page won't load until the user accepts its new publisher, so show the status bar
+        // over a blank page in the meantime
+        if self.pending_site_pin.is_some() {
+            self.render(Root::null(), &mut screen)?;
+        }
+
         let mut timer_thread = None;
 
         while !self.quit.load(Ordering::SeqCst) {
@@ -504,6 +737,15 @@ impl Viewer {
                         self.set_quit(&viewer_send);
                     }
                 }
+                Ok(ViewerEvent::Mouse(mouse_event)) => {
+                    let mut last_frame = self.last_frame.take();
+                    self.dispatch_mouse_event(mouse_event, last_frame.as_mut(), &mut screen)?;
+
+                    if let Some(mut frame) = last_frame {
+                        frame.redraw()?;
+                        self.render(frame, &mut screen)?;
+                    }
+                }
                 Ok(ViewerEvent::Root(root)) => {
                     let frame_delay_opt = root.frame_delay.clone();
 
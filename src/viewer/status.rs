@@ -15,15 +15,22 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-use termion::color;
 use termion::event::Key;
+use termion::event::MouseButton;
 
+use crate::ui::charbuff::Color;
+use crate::ui::charbuff::TextAttrs;
 use crate::ui::forms::TextLine;
 use crate::ui::forms::WrbForm;
 use crate::ui::forms::WrbFormEvent;
+use crate::ui::scanline::ColorDepth;
+use crate::ui::scanline::Scanline;
 use crate::ui::Error;
 use crate::ui::Root;
 
+/// Commands available from the status bar
+const DEFAULT_MODE_TEXT: &str = "(g)oto  |  (i)dentity  |  (q)uit";
+
 pub struct ViewerStatus {
     mode_text: String,
     at_top: bool,
@@ -34,7 +41,7 @@ impl ViewerStatus {
     pub fn new(wrb_name: String, at_top: bool) -> Self {
         Self {
             progress_text: TextLine::new_detached(wrb_name, 2048),
-            mode_text: "(g)oto  |  (q)uit".into(),
+            mode_text: DEFAULT_MODE_TEXT.into(),
             at_top,
         }
     }
@@ -56,29 +63,48 @@ impl ViewerStatus {
         "Goto: "
     }
 
-    pub fn render(&self, focused: bool, num_cols: u64) -> String {
+    /// Terminal codes to set the given colors, at the given color depth
+    fn color_codes(fg: Color, bg: Color, depth: ColorDepth) -> String {
+        Scanline::fg_color(&fg, depth)
+            .into_iter()
+            .chain(Scanline::bg_color(&bg, depth))
+            .map(|cmd| cmd.into_term_code())
+            .collect()
+    }
+
+    pub fn render(&self, focused: bool, num_cols: u64, depth: ColorDepth) -> String {
         let num_cols =
             usize::try_from(num_cols).expect("infallible -- num_cols doesn't fit a usize");
         let bg_color = if focused {
-            color::Bg(color::Rgb(0xff, 0, 0xff))
+            Color::rgb(0xff, 0, 0xff)
         } else {
-            color::Bg(color::Rgb(0xff, 0xff, 0))
+            Color::rgb(0xff, 0xff, 0)
         };
 
         let prefix = if focused { self.focus_prefix() } else { "" };
 
+        // in monochrome, set the progress text apart from the page in reverse video
+        let (progress_attrs, mode_attrs) = if depth == ColorDepth::Mono {
+            (
+                Scanline::Attrs(TextAttrs::REVERSE).into_term_code(),
+                Scanline::Attrs(TextAttrs::NONE).into_term_code(),
+            )
+        } else {
+            ("".to_string(), "".to_string())
+        };
+
         let formatted_progress_text = format!(
             "{}{}{}{}{}",
-            color::Fg(color::Black),
-            bg_color,
+            Self::color_codes(Color::rgb(0, 0, 0), bg_color, depth),
+            &progress_attrs,
             termion::clear::CurrentLine,
             prefix,
             Self::trunc_text(self.progress_text.text(), num_cols)
         );
         let formatted_mode_text = format!(
             "{}{}{}{}",
-            color::Fg(color::White),
-            color::Bg(color::Black),
+            Self::color_codes(Color::rgb(0xff, 0xff, 0xff), Color::rgb(0, 0, 0), depth),
+            &mode_attrs,
             termion::clear::CurrentLine,
             Self::trunc_text(&self.mode_text, num_cols)
         );
@@ -98,10 +124,39 @@ impl ViewerStatus {
         Ok(())
     }
 
+    /// Handle a click on the progress text, at the given column
+    pub fn handle_click(
+        &mut self,
+        focused: bool,
+        button: MouseButton,
+        col: u64,
+    ) -> Result<(), Error> {
+        let prefix_len = if focused {
+            self.focus_prefix().len()
+        } else {
+            0
+        };
+        self.handle_event(WrbFormEvent::Mouse(
+            button,
+            0,
+            col.saturating_sub(u64::try_from(prefix_len).unwrap_or(0)),
+        ))
+    }
+
     pub fn set_text(&mut self, txt: String) {
         self.progress_text.set_text(txt);
     }
 
+    /// Replace the list of commands shown under the progress text
+    pub fn set_mode_text(&mut self, txt: String) {
+        self.mode_text = txt;
+    }
+
+    /// Go back to showing the default list of commands
+    pub fn reset_mode_text(&mut self) {
+        self.mode_text = DEFAULT_MODE_TEXT.into();
+    }
+
     /// where should the cursor column be?
     pub fn cursor_column(&self, focused: bool) -> usize {
         self.progress_text.cursor()
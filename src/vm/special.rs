@@ -23,11 +23,13 @@ use crate::vm::storage::WrbHeadersDB;
 use std::ops::Deref;
 use std::str;
 
+use crate::runner::Error as RunnerError;
 use crate::runner::Runner;
 
 use crate::storage::Wrbpod;
 
 use crate::core::make_runner;
+use crate::core::unlock_identity;
 use crate::core::with_global_config;
 use crate::core::with_globals;
 
@@ -52,7 +54,11 @@ use clarity::vm::types::{
 use clarity::vm::ClarityVersion;
 use clarity::vm::ContractContext;
 
+use stacks_common::address::{
+    C32_ADDRESS_VERSION_MAINNET_SINGLESIG, C32_ADDRESS_VERSION_TESTNET_SINGLESIG,
+};
 use stacks_common::types::chainstate::StacksPrivateKey;
+use stacks_common::types::chainstate::StacksPublicKey;
 use stacks_common::util::hash::{to_hex, Hash160};
 
 use crate::runner::stackerdb::StackerDBSession;
@@ -70,6 +76,8 @@ pub const WRB_ERR_WRBPOD_PUT_SLICE_FAILURE: u128 = 1006;
 pub const WRB_ERR_WRBPOD_SYNC_SLOT_FAILURE: u128 = 1007;
 
 pub const WRB_ERR_READONLY_FAILURE: u128 = 2000;
+pub const WRB_ERR_READONLY_TIMEOUT: u128 = 2001;
+pub const WRB_ERR_READONLY_CONNECTION_REFUSED: u128 = 2002;
 
 pub const WRB_ERR_BUFF_TO_UTF8_FAILURE: u128 = 3000;
 
@@ -176,10 +184,14 @@ fn handle_wrb_call_readonly(
     // carry out the RPC
     let value = match runner.call_readonly(&target_contract_id, &function_name, &args) {
         Ok(value) => Value::okay(Value::buff_from(value.serialize_to_vec()?).unwrap()).unwrap(),
-        Err(e) => err_ascii_512(
-            WRB_ERR_READONLY_FAILURE,
-            &format!("wrb: failed call-readonly: {:?}", &e),
-        ),
+        Err(e) => {
+            let code = match e {
+                RunnerError::Timeout(_) => WRB_ERR_READONLY_TIMEOUT,
+                RunnerError::ConnectionRefused(_) => WRB_ERR_READONLY_CONNECTION_REFUSED,
+                _ => WRB_ERR_READONLY_FAILURE,
+            };
+            err_ascii_512(code, &format!("wrb: failed call-readonly: {:?}", &e))
+        }
     };
 
     env_with_global_context(
@@ -382,6 +394,82 @@ fn handle_load_large_string_utf8(
     Ok(())
 }
 
+/// Trampoline code for contract-call to `.wrb-ll wrb-ll-app-identity`
+pub fn handle_app_identity(
+    global_context: &mut GlobalContext,
+    sender: PrincipalData,
+    sponsor: Option<PrincipalData>,
+    contract_id: &QualifiedContractIdentifier,
+    args: &[Value],
+    wrb_lowlevel_contract: Contract,
+) -> Result<(), Error> {
+    // no args
+    if args.len() != 0 {
+        return Err(InterpreterError::InterpreterError(format!(
+            "Expected 0 arguments, got {}",
+            args.len()
+        ))
+        .into());
+    }
+
+    let (name, namespace) = load_app_name(
+        global_context,
+        sender.clone(),
+        sponsor.clone(),
+        &wrb_lowlevel_contract,
+    );
+    unlock_identity().map_err(InterpreterError::InterpreterError)?;
+    let (privkey, mainnet) =
+        with_global_config(|cfg| (cfg.site_private_key(&name, &namespace), cfg.mainnet())).ok_or(
+            InterpreterError::InterpreterError(format!("System is not initialized")),
+        )?;
+
+    let version = if mainnet {
+        C32_ADDRESS_VERSION_MAINNET_SINGLESIG
+    } else {
+        C32_ADDRESS_VERSION_TESTNET_SINGLESIG
+    };
+    let pubkey = StacksPublicKey::from_private(&privkey);
+    let identity_value = Value::Tuple(
+        TupleData::from_data(vec![
+            (
+                "public-key".into(),
+                Value::buff_from(pubkey.to_bytes_compressed())
+                    .expect("FATAL: could not encode public key"),
+            ),
+            (
+                "principal".into(),
+                Value::Principal(privkey_to_principal(&privkey, version).into()),
+            ),
+        ])
+        .unwrap(),
+    );
+
+    wrb_debug!(
+        "App identity for {}.{} is {}",
+        &name,
+        &namespace,
+        &identity_value
+    );
+
+    env_with_global_context(
+        global_context,
+        sender,
+        sponsor,
+        wrb_lowlevel_contract.contract_context,
+        |env| {
+            env.execute_contract_allow_private(
+                contract_id,
+                "wrb-ll-finish-app-identity",
+                &[SymbolicExpression::atom_value(identity_value)],
+                false,
+            )
+        },
+    )
+    .expect("FATAL: failed to set app identity");
+    Ok(())
+}
+
 /// Trampoline code for contract-call to `.wrb-ll wrbpod-default`
 pub fn handle_wrbpod_default(
     global_context: &mut GlobalContext,
@@ -504,9 +592,10 @@ pub fn handle_wrbpod_open(
         slot: wrbpod_slot_id,
     };
 
-    // is this an owned wrbpod? only true if the client's identity private key matches the target
+    // is this an owned wrbpod? only true if the client's wrbpod private key matches the target
     // contract.
-    let privkey = with_global_config(|cfg| cfg.private_key().clone()).ok_or(
+    unlock_identity().map_err(InterpreterError::InterpreterError)?;
+    let privkey = with_global_config(|cfg| cfg.wrbpod_private_key()).ok_or(
         InterpreterError::InterpreterError(format!("System is not initialized")),
     )?;
 
@@ -591,43 +680,44 @@ pub fn handle_wrbpod_open(
             }
         };
 
-    let replica_stackerdb_client =
-        match runner.get_replica_stackerdb_client(wrbpod_contract_id.clone(), privkey.clone()) {
-            Ok(client) => client,
-            Err(e) => {
-                wrb_warn!(
-                    "Failed to open replica StackerDB client for {}: {:?}",
-                    &wrbpod_contract_id,
-                    &e
-                );
-                let result = err_ascii_512(
-                    WRB_ERR_WRBPOD_OPEN_FAILURE,
-                    &format!(
-                        "wrb: failed to open replica StackerDB client for {}: {:?}",
-                        &wrbpod_contract_id, &e
-                    ),
-                );
-                env_with_global_context(
-                    global_context,
-                    sender,
-                    sponsor,
-                    wrb_lowlevel_contract.contract_context,
-                    |env| {
-                        env.execute_contract_allow_private(
-                            contract_id,
-                            "wrb-ll-finish-wrbpod-open",
-                            &[
-                                SymbolicExpression::atom_value(args[0].clone()),
-                                SymbolicExpression::atom_value(Value::UInt(0)),
-                                SymbolicExpression::atom_value(result),
-                            ],
-                            false,
-                        )
-                    },
-                )?;
-                return Ok(());
-            }
-        };
+    let replica_stackerdb_client = match runner
+        .get_writable_replica_stackerdb_client(wrbpod_contract_id.clone(), privkey.clone())
+    {
+        Ok(client) => client,
+        Err(e) => {
+            wrb_warn!(
+                "Failed to open replica StackerDB client for {}: {:?}",
+                &wrbpod_contract_id,
+                &e
+            );
+            let result = err_ascii_512(
+                WRB_ERR_WRBPOD_OPEN_FAILURE,
+                &format!(
+                    "wrb: failed to open replica StackerDB client for {}: {:?}",
+                    &wrbpod_contract_id, &e
+                ),
+            );
+            env_with_global_context(
+                global_context,
+                sender,
+                sponsor,
+                wrb_lowlevel_contract.contract_context,
+                |env| {
+                    env.execute_contract_allow_private(
+                        contract_id,
+                        "wrb-ll-finish-wrbpod-open",
+                        &[
+                            SymbolicExpression::atom_value(args[0].clone()),
+                            SymbolicExpression::atom_value(Value::UInt(0)),
+                            SymbolicExpression::atom_value(result),
+                        ],
+                        false,
+                    )
+                },
+            )?;
+            return Ok(());
+        }
+    };
 
     let wrbpod_session_result = Wrbpod::open(
         home_stackerdb_client,
@@ -1486,6 +1576,16 @@ pub fn handle_wrb_contract_call_special_cases(
         "wrb-ll-store-large-string-utf8" => {
             handle_store_large_string_utf8(args)?;
         }
+        "wrb-ll-app-identity" => {
+            handle_app_identity(
+                global_context,
+                sender,
+                sponsor,
+                contract_id,
+                args,
+                wrb_lowlevel_contract,
+            )?;
+        }
         "wrb-ll-wrbpod-default" => {
             handle_wrbpod_default(
                 global_context,
@@ -18,6 +18,14 @@
 use crate::vm::ClarityVM;
 use std::fs;
 
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::chainstate::StacksPublicKey;
+use stacks_common::util::hash::to_hex;
+
+use crate::core;
+use crate::core::with_global_config;
+use crate::ui::Renderer;
+
 #[test]
 fn test_vm_bootup() {
     let db_path = "/tmp/wrb-clarity-vm-test-bootup";
@@ -27,3 +35,42 @@ fn test_vm_bootup() {
 
     let _ = ClarityVM::new(db_path, "foo.btc", 0);
 }
+
+#[test]
+fn test_vm_app_identity() {
+    core::init(true, "localhost", 20443);
+
+    let db_path = "/tmp/wrb-clarity-vm-test-app-identity";
+    if fs::metadata(&db_path).is_ok() {
+        fs::remove_dir_all(&db_path).unwrap();
+    }
+
+    // foo.btc gets its own key, not the root identity key
+    let (root_key, site_key) = with_global_config(|cfg| {
+        (
+            cfg.private_key().clone(),
+            cfg.site_private_key("foo", "btc"),
+        )
+    })
+    .unwrap();
+    assert_ne!(root_key, site_key);
+    let public_key = StacksPublicKey::from_private(&site_key);
+
+    let code = format!(
+        r#"
+    (wrb-root u10 u1)
+    (wrb-viewport u0 u0 u0 u10 u1)
+    (wrb-static-print-immediate u0 none u0 u0
+        (if (is-eq (app-identity) {{ public-key: 0x{}, principal: '{} }}) u"same" u"different"))
+    "#,
+        to_hex(&public_key.to_bytes_compressed()),
+        StacksAddress::p2pkh(true, &public_key)
+    );
+
+    let bytes = Renderer::encode_bytes(code.as_bytes()).unwrap();
+
+    let mut vm = ClarityVM::new(db_path, "foo.btc", 1).unwrap();
+    let mut renderer = Renderer::new(1_000_000_000);
+    let s = renderer.eval_to_text(&mut vm, &bytes).unwrap();
+    assert_eq!(s.trim_end(), "same");
+}